@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use crate::modules::audit;
+use crate::modules::admin_user_db::{self, AdminRole, AdminUser, UpdateAdminUser};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateAdminUserRequest {
+    pub username: String,
+    pub password: String,
+    pub role: AdminRole,
+}
+
+// 命令实现 (桌面端视为 owner)
+
+/// 列出管理员
+#[tauri::command]
+pub async fn list_admin_users() -> Result<Vec<AdminUser>, String> {
+    admin_user_db::list_users()
+}
+
+/// 创建管理员
+#[tauri::command]
+pub async fn create_admin_user(request: CreateAdminUserRequest) -> Result<AdminUser, String> {
+    let user = admin_user_db::create_user(&request.username, &request.password, request.role)?;
+    audit::record(
+        "admin_user.create",
+        Some(user.username.clone()),
+        Some(serde_json::json!({ "role": user.role.as_str() })),
+    );
+    Ok(user)
+}
+
+/// 更新管理员 (角色 / 密码 / 禁用)
+#[tauri::command]
+pub async fn update_admin_user(id: String, request: UpdateAdminUser) -> Result<AdminUser, String> {
+    // 密码只记录是否修改
+    let details = serde_json::json!({
+        "role": request.role.map(|r| r.as_str()),
+        "password_changed": request.password.is_some(),
+        "disabled": request.disabled,
+    });
+    let user = admin_user_db::update_user(&id, request)?;
+    audit::record("admin_user.update", Some(user.username.clone()), Some(details));
+    Ok(user)
+}
+
+/// 删除管理员
+#[tauri::command]
+pub async fn delete_admin_user(id: String) -> Result<(), String> {
+    let target = user_target(&id);
+    admin_user_db::delete_user(&id)?;
+    audit::record("admin_user.delete", Some(target), None);
+    Ok(())
+}
+
+/// 重置管理员的二次验证 (设备丢失时)
+#[tauri::command]
+pub async fn reset_admin_user_totp(id: String) -> Result<(), String> {
+    admin_user_db::reset_totp(&id)?;
+    audit::record("admin_user.reset_totp", Some(user_target(&id)), None);
+    Ok(())
+}
+
+/// 审计记录中的管理员标识 (用户名，找不到时为 ID)
+fn user_target(id: &str) -> String {
+    admin_user_db::get_user(id)
+        .ok()
+        .flatten()
+        .map(|u| u.username)
+        .unwrap_or_else(|| id.to_string())
+}
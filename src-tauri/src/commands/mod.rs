@@ -15,6 +15,8 @@ pub mod security;
 pub mod proxy_pool;
 // 导出 user_token 命令
 pub mod user_token;
+// 导出 admin_user 命令 (管理员账号)
+pub mod admin_user;
 
 /// 列出所有账号
 #[tauri::command]
@@ -339,6 +341,9 @@ pub async fn save_config(
     proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
     config: AppConfig,
 ) -> Result<(), String> {
+    // [NEW] 拒绝无效的自定义脱敏规则，避免静默失效
+    crate::proxy::redaction::validate_config(&config.proxy.redaction)?;
+    crate::proxy::dlp::validate_config(&config.proxy.dlp)?;
     modules::save_app_config(&config)?;
 
     // 通知托盘配置已更新
@@ -376,11 +381,37 @@ pub async fn save_config(
         crate::proxy::update_global_system_prompt_config(config.proxy.global_system_prompt.clone());
         // [NEW] 更新全局图像思维模式配置
         crate::proxy::update_image_thinking_mode(config.proxy.image_thinking_mode.clone());
+        // [NEW] 更新幂等去重配置
+        crate::proxy::idempotency::update_idempotency_config(config.proxy.idempotency.clone());
+        // [NEW] 更新响应缓存配置
+        crate::proxy::response_cache::update_response_cache_config(config.proxy.response_cache.clone());
+        // [NEW] 更新指标导出配置
+        crate::proxy::metrics::update_metrics_config(config.proxy.metrics.clone());
+        instance.axum_server.update_metrics_listener().await;
+        // [NEW] 更新 HTTPS 配置 (证书热重载)
+        crate::proxy::tls::update_tls_config(config.proxy.tls.clone());
+        // [NEW] 更新链路追踪导出配置
+        if let Err(e) = crate::proxy::telemetry::apply_telemetry_config(&config.proxy.telemetry) {
+            modules::logger::log_error(&format!("Failed to apply telemetry config: {}", e));
+        }
+        // [NEW] 更新告警配置
+        crate::proxy::alerts::update_alerts_config(config.proxy.alerts.clone());
+        // [NEW] 更新脱敏配置
+        crate::proxy::redaction::update_redaction_config(config.proxy.redaction.clone());
+        // [NEW] 更新 DLP 规则
+        crate::proxy::dlp::update_dlp_config(config.proxy.dlp.clone());
+        // [NEW] 更新价格表
+        crate::proxy::pricing::update_pricing_config(config.proxy.pricing.clone());
         // 更新代理池配置
         instance
             .axum_server
             .update_proxy_pool(config.proxy.proxy_pool.clone())
             .await;
+        // 更新上游端点配置
+        instance
+            .axum_server
+            .update_upstream_endpoints(&config.proxy)
+            .await;
         // 更新熔断配置
         instance
             .token_manager
@@ -1029,3 +1060,22 @@ pub async fn get_token_stats_account_trend_daily(
 ) -> Result<Vec<crate::modules::token_stats::AccountTrendPoint>, String> {
     crate::modules::token_stats::get_account_trend_daily(days)
 }
+
+#[tauri::command]
+pub async fn get_token_cost_breakdown(
+    hours: i64,
+    group_by: crate::modules::token_stats::CostGroupBy,
+) -> Result<Vec<crate::modules::token_stats::CostBreakdown>, String> {
+    crate::modules::token_stats::get_cost_breakdown(hours, group_by)
+}
+
+/// 导出费用明细 CSV
+#[tauri::command]
+pub async fn export_token_cost_csv(
+    hours: i64,
+    group_by: crate::modules::token_stats::CostGroupBy,
+) -> Result<String, String> {
+    let rows = crate::modules::token_stats::get_cost_breakdown(hours, group_by)?;
+    let currency = crate::proxy::pricing::get_pricing_config().currency;
+    Ok(crate::modules::token_stats::cost_breakdown_csv(&rows, group_by, &currency))
+}
@@ -59,13 +59,15 @@ pub async fn start_proxy_service(
     cf_state: State<'_, crate::commands::cloudflared::CloudflaredState>,
     app_handle: tauri::AppHandle,
 ) -> Result<ProxyStatus, String> {
-    internal_start_proxy_service(
+    let status = internal_start_proxy_service(
         config,
         &state,
         crate::modules::integration::SystemManager::Desktop(app_handle),
         Arc::new(cf_state.inner().clone()),
     )
-    .await
+    .await?;
+    crate::modules::audit::record("proxy.start", None, None);
+    Ok(status)
 }
 
 struct StartingGuard(Arc<AtomicBool>);
@@ -194,6 +196,7 @@ pub async fn internal_start_proxy_service(
 
     // [FIX] Ensure the server is logically running
     axum_server.set_running(true).await;
+    axum_server.update_upstream_endpoints(&config).await;
 
     *instance_lock = Some(instance);
 
@@ -239,6 +242,11 @@ pub async fn ensure_admin_server(
     let token_manager = Arc::new(TokenManager::new(app_data_dir));
     // [NEW] 加载账号数据，否则管理界面统计为 0
     let _ = token_manager.load_accounts().await;
+    // [NEW] 恢复上次运行留下的限流/会话/健康分状态
+    token_manager.restore_runtime_state();
+
+    // [NEW] HTTPS 配置需在监听器绑定前就绪
+    crate::proxy::tls::update_tls_config(config.tls.clone());
 
     let (axum_server, server_handle) = match crate::proxy::AxumServer::start(
         config.get_bind_address().to_string(),
@@ -246,7 +254,7 @@ pub async fn ensure_admin_server(
         token_manager,
         config.custom_mapping.clone(),
         config.request_timeout,
-        config.upstream_proxy.clone(),
+        &config,
         config.user_agent_override.clone(),
         crate::proxy::ProxySecurityConfig::from_proxy_config(&config),
         config.zai.clone(),
@@ -274,6 +282,26 @@ pub async fn ensure_admin_server(
     crate::proxy::update_global_system_prompt_config(config.global_system_prompt.clone());
     // [NEW] 初始化全局图像思维模式配置
     crate::proxy::update_image_thinking_mode(config.image_thinking_mode.clone());
+    // [NEW] 初始化幂等去重配置
+    crate::proxy::idempotency::update_idempotency_config(config.idempotency.clone());
+    crate::proxy::response_cache::update_response_cache_config(config.response_cache.clone());
+    // [NEW] 初始化指标导出配置并按需启动独立监听
+    crate::proxy::metrics::update_metrics_config(config.metrics.clone());
+    if let Some(admin) = admin_lock.as_ref() {
+        admin.axum_server.update_metrics_listener().await;
+    }
+    // [NEW] 初始化链路追踪导出
+    if let Err(e) = crate::proxy::telemetry::apply_telemetry_config(&config.telemetry) {
+        tracing::error!("[Telemetry] {}", e);
+    }
+    // [NEW] 初始化告警配置
+    crate::proxy::alerts::update_alerts_config(config.alerts.clone());
+    // [NEW] 初始化脱敏配置
+    crate::proxy::redaction::update_redaction_config(config.redaction.clone());
+    // [NEW] 初始化 DLP 规则
+    crate::proxy::dlp::update_dlp_config(config.dlp.clone());
+    // [NEW] 初始化价格表
+    crate::proxy::pricing::update_pricing_config(config.pricing.clone());
 
     Ok(())
 }
@@ -290,10 +318,14 @@ pub async fn stop_proxy_service(state: State<'_, ProxyServiceState>) -> Result<(
     // 停止 Axum 服务器 (仅逻辑停止，不杀死进程)
     if let Some(instance) = instance_lock.take() {
         instance.token_manager.abort_background_tasks().await;
+        if let Err(e) = instance.token_manager.persist_runtime_state() {
+            tracing::warn!("[RuntimeState] Failed to save snapshot on stop: {}", e);
+        }
         instance.axum_server.set_running(false).await;
         // 已移除 instance.axum_server.stop() 调用，防止杀死 Admin Server
     }
 
+    crate::modules::audit::record("proxy.stop", None, None);
     Ok(())
 }
 
@@ -403,6 +435,66 @@ pub async fn get_proxy_log_detail(log_id: String) -> Result<ProxyRequestLog, Str
     crate::modules::proxy_db::get_log_detail(&log_id)
 }
 
+/// [NEW] 全文搜索日志 (请求 / 响应消息文本)
+#[tauri::command]
+pub async fn search_proxy_logs(
+    request: crate::modules::proxy_db::LogSearchQuery,
+) -> Result<crate::modules::proxy_db::LogSearchResult, String> {
+    tokio::task::spawn_blocking(move || crate::modules::proxy_db::search_logs(&request))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// [NEW] 重放日志中的请求并返回与原始结果的对比
+#[tauri::command]
+pub async fn replay_proxy_log(
+    state: State<'_, ProxyServiceState>,
+    log_id: String,
+    overrides: Option<crate::proxy::replay::ReplayOverrides>,
+) -> Result<crate::proxy::replay::ReplayResult, String> {
+    let (port, api_key) = {
+        let instance_lock = state.instance.read().await;
+        let instance = instance_lock.as_ref().ok_or("服务未运行")?;
+        (instance.config.port, instance.config.api_key.clone())
+    };
+    crate::proxy::replay::replay_log(port, &api_key, &log_id, overrides.unwrap_or_default()).await
+}
+
+/// 获取指定日志的重放记录
+#[tauri::command]
+pub async fn get_proxy_log_replays(log_id: String) -> Result<Vec<ProxyRequestLog>, String> {
+    crate::modules::proxy_db::get_log_replays(&log_id)
+}
+
+/// [NEW] 向告警通道发送测试通知
+#[tauri::command]
+pub async fn test_alert_channel(
+    request: crate::proxy::alerts::AlertChannel,
+) -> Result<(), String> {
+    crate::proxy::alerts::send_test(&request).await
+}
+
+/// 获取告警投递记录
+#[tauri::command]
+pub async fn get_alert_history(
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<crate::modules::alert_db::AlertDelivery>, String> {
+    tokio::task::spawn_blocking(move || {
+        crate::modules::alert_db::get_history(limit.unwrap_or(100), offset.unwrap_or(0))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 清空告警投递记录
+#[tauri::command]
+pub async fn clear_alert_history() -> Result<usize, String> {
+    tokio::task::spawn_blocking(crate::modules::alert_db::clear_history)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
 /// 获取日志总数
 #[tauri::command]
 pub async fn get_proxy_logs_count() -> Result<u64, String> {
@@ -412,15 +504,57 @@ pub async fn get_proxy_logs_count() -> Result<u64, String> {
 /// 导出所有日志到指定文件
 #[tauri::command]
 pub async fn export_proxy_logs(file_path: String) -> Result<usize, String> {
-    let logs = crate::modules::proxy_db::get_all_logs_for_export()?;
-    let count = logs.len();
-
-    let json = serde_json::to_string_pretty(&logs)
-        .map_err(|e| format!("Failed to serialize logs: {}", e))?;
+    // [FIX] 逐行写入，避免大日志库一次性加载到内存
+    tokio::task::spawn_blocking(move || {
+        use std::io::Write;
+        let file =
+            std::fs::File::create(&file_path).map_err(|e| format!("Failed to write file: {}", e))?;
+        let mut writer = std::io::BufWriter::new(file);
+        let io_err = |e: std::io::Error| format!("Failed to write file: {}", e);
+
+        // 保持原有 JSON 数组格式，每行一条
+        writer.write_all(b"[").map_err(io_err)?;
+        let filter = crate::modules::proxy_db::LogFilter::default();
+        let mut separator: &[u8] = b"\n";
+        let count = crate::modules::proxy_db::for_each_log_for_export(&filter, |log| {
+            writer.write_all(separator).map_err(io_err)?;
+            separator = b",\n";
+            serde_json::to_writer(&mut writer, &log)
+                .map_err(|e| format!("Failed to serialize logs: {}", e))?;
+            Ok(())
+        })?;
+        writer.write_all(b"\n]\n").map_err(io_err)?;
+        writer.flush().map_err(io_err)?;
+        Ok(count)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
 
-    std::fs::write(&file_path, json).map_err(|e| format!("Failed to write file: {}", e))?;
+/// [NEW] 按筛选条件流式导出日志 (JSONL / CSV / HAR)
+#[tauri::command]
+pub async fn export_proxy_logs_filtered(
+    file_path: String,
+    filter: crate::modules::proxy_db::LogFilter,
+    format: crate::modules::log_export::LogExportFormat,
+    redact_bodies: Option<bool>,
+) -> Result<usize, String> {
+    let port = crate::modules::config::load_app_config()
+        .map(|c| c.proxy.port)
+        .unwrap_or(8045);
+    let options = crate::modules::log_export::LogExportOptions {
+        format,
+        redact_bodies: redact_bodies.unwrap_or(false),
+        base_url: format!("http://127.0.0.1:{}", port),
+    };
 
-    Ok(count)
+    tokio::task::spawn_blocking(move || {
+        let file =
+            std::fs::File::create(&file_path).map_err(|e| format!("Failed to write file: {}", e))?;
+        crate::modules::log_export::export_logs(std::io::BufWriter::new(file), &filter, &options)
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 /// 导出指定的日志JSON到文件
@@ -789,3 +923,68 @@ pub async fn get_proxy_pool_config(
         Err("服务未运行".to_string())
     }
 }
+
+/// 获取上游端点健康状态
+#[tauri::command]
+pub async fn get_upstream_endpoint_health(
+    state: State<'_, ProxyServiceState>,
+) -> Result<crate::proxy::upstream::endpoint_health::EndpointHealthReport, String> {
+    let admin_lock = state.admin_server.read().await;
+    if let Some(admin) = admin_lock.as_ref() {
+        Ok(admin.axum_server.upstream_endpoint_health().report())
+    } else {
+        Err("服务未运行".to_string())
+    }
+}
+
+/// 获取响应缓存统计
+#[tauri::command]
+pub async fn get_response_cache_stats(
+    state: State<'_, ProxyServiceState>,
+) -> Result<crate::proxy::response_cache::ResponseCacheStats, String> {
+    let admin_lock = state.admin_server.read().await;
+    if let Some(admin) = admin_lock.as_ref() {
+        Ok(admin.axum_server.response_cache.stats())
+    } else {
+        Err("服务未运行".to_string())
+    }
+}
+
+/// 清空响应缓存，返回删除的条目数
+#[tauri::command]
+pub async fn clear_response_cache(
+    state: State<'_, ProxyServiceState>,
+) -> Result<usize, String> {
+    let admin_lock = state.admin_server.read().await;
+    if let Some(admin) = admin_lock.as_ref() {
+        admin.axum_server.response_cache.clear()
+    } else {
+        Err("服务未运行".to_string())
+    }
+}
+
+/// 清空上游端点统计与熔断状态
+#[tauri::command]
+pub async fn reset_upstream_endpoint_health(
+    state: State<'_, ProxyServiceState>,
+) -> Result<(), String> {
+    let admin_lock = state.admin_server.read().await;
+    if let Some(admin) = admin_lock.as_ref() {
+        admin.axum_server.upstream_endpoint_health().reset();
+        Ok(())
+    } else {
+        Err("服务未运行".to_string())
+    }
+}
+
+/// 获取 HTTPS 状态与证书指纹
+#[tauri::command]
+pub async fn get_tls_status() -> Result<crate::proxy::tls::TlsStatus, String> {
+    Ok(crate::proxy::tls::status())
+}
+
+/// 重新加载 HTTPS 证书
+#[tauri::command]
+pub async fn reload_tls_certificate() -> Result<crate::proxy::tls::CertificateInfo, String> {
+    crate::proxy::tls::reload()
+}
@@ -60,3 +60,16 @@ pub async fn get_all_account_bindings(
         Err("Service not running".to_string())
     }
 }
+
+/// Get per-proxy live/total connection counters
+#[tauri::command]
+pub async fn get_proxy_pool_stats(
+    state: State<'_, ProxyServiceState>,
+) -> Result<Vec<crate::proxy::proxy_pool::ProxyConnectionStats>, String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        Ok(instance.axum_server.proxy_pool_manager.get_connection_stats().await)
+    } else {
+        Err("Service not running".to_string())
+    }
+}
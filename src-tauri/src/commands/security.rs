@@ -1,6 +1,6 @@
 use tauri::State;
 use serde::{Deserialize, Serialize};
-use crate::modules::security_db;
+use crate::modules::{audit, audit_db, security_db};
 
 // ==================== 请求/响应结构 ====================
 
@@ -40,6 +40,10 @@ pub struct IpStatsResponse {
     pub unique_ips: usize,
     pub blocked_requests: usize,
     pub top_ips: Vec<security_db::IpRanking>,
+    /// 当前生效的自动封禁数量
+    pub auto_ban_count: usize,
+    /// 最近 24 小时自动封禁次数
+    pub ban_events_24h: usize,
 }
 
 // ==================== IP 访问日志命令 ====================
@@ -75,9 +79,30 @@ pub async fn get_ip_stats() -> Result<IpStatsResponse, String> {
         unique_ips: stats.unique_ips as usize,
         blocked_requests: stats.blocked_count as usize,
         top_ips,
+        auto_ban_count: stats.auto_ban_count as usize,
+        ban_events_24h: stats.ban_events_24h as usize,
     })
 }
 
+/// 获取最近的自动封禁事件
+#[tauri::command]
+pub async fn get_ip_ban_events(limit: Option<usize>) -> Result<Vec<security_db::IpBanEvent>, String> {
+    security_db::get_ban_events(limit.unwrap_or(100))
+}
+
+/// 获取 DLP 命中统计 (默认最近 24 小时)
+#[tauri::command]
+pub async fn get_dlp_stats(hours: Option<i64>) -> Result<security_db::DlpStats, String> {
+    let since = chrono::Utc::now().timestamp() - hours.unwrap_or(24).max(1) * 3600;
+    security_db::get_dlp_stats(since)
+}
+
+/// 清空 DLP 命中统计
+#[tauri::command]
+pub async fn clear_dlp_stats() -> Result<(), String> {
+    security_db::clear_dlp_hits()
+}
+
 /// 清空 IP 访问日志
 #[tauri::command]
 pub async fn clear_ip_access_logs() -> Result<(), String> {
@@ -99,7 +124,7 @@ pub async fn add_ip_to_blacklist(
 ) -> Result<(), String> {
     // 验证 IP 格式
     if !is_valid_ip_pattern(&request.ip_pattern) {
-        return Err("Invalid IP pattern. Use IP address or CIDR notation (e.g., 192.168.1.0/24)".to_string());
+        return Err("Invalid IP pattern. Use an IPv4/IPv6 address or CIDR notation (e.g., 192.168.1.0/24, 2001:db8::/32)".to_string());
     }
     
     security_db::add_to_blacklist(
@@ -108,6 +133,11 @@ pub async fn add_ip_to_blacklist(
         request.expires_at,
         "manual",
     )?;
+    audit::record(
+        "blacklist.add",
+        Some(request.ip_pattern.clone()),
+        Some(serde_json::json!({ "reason": request.reason, "expires_at": request.expires_at })),
+    );
     Ok(())
 }
 
@@ -119,7 +149,9 @@ pub async fn remove_ip_from_blacklist(ip_pattern: String) -> Result<(), String>
     let entry = entries.iter().find(|e| e.ip_pattern == ip_pattern);
     
     if let Some(entry) = entry {
-        security_db::remove_from_blacklist(&entry.id)
+        security_db::remove_from_blacklist(&entry.id)?;
+        audit::record("blacklist.remove", Some(ip_pattern), None);
+        Ok(())
     } else {
         Err(format!("IP pattern {} not found in blacklist", ip_pattern))
     }
@@ -130,9 +162,11 @@ pub async fn remove_ip_from_blacklist(ip_pattern: String) -> Result<(), String>
 pub async fn clear_ip_blacklist() -> Result<(), String> {
     // 获取所有黑名单条目并逐个删除
     let entries = security_db::get_blacklist()?;
+    let count = entries.len();
     for entry in entries {
         security_db::remove_from_blacklist(&entry.ip_pattern)?;
     }
+    audit::record("blacklist.clear", None, Some(serde_json::json!({ "count": count })));
     Ok(())
 }
 
@@ -157,13 +191,18 @@ pub async fn add_ip_to_whitelist(
 ) -> Result<(), String> {
     // 验证 IP 格式
     if !is_valid_ip_pattern(&request.ip_pattern) {
-        return Err("Invalid IP pattern. Use IP address or CIDR notation (e.g., 192.168.1.0/24)".to_string());
+        return Err("Invalid IP pattern. Use an IPv4/IPv6 address or CIDR notation (e.g., 192.168.1.0/24, 2001:db8::/32)".to_string());
     }
     
     security_db::add_to_whitelist(
         &request.ip_pattern,
         request.description.as_deref(),
     )?;
+    audit::record(
+        "whitelist.add",
+        Some(request.ip_pattern.clone()),
+        Some(serde_json::json!({ "description": request.description })),
+    );
     Ok(())
 }
 
@@ -175,7 +214,9 @@ pub async fn remove_ip_from_whitelist(ip_pattern: String) -> Result<(), String>
     let entry = entries.iter().find(|e| e.ip_pattern == ip_pattern);
     
     if let Some(entry) = entry {
-        security_db::remove_from_whitelist(&entry.id)
+        security_db::remove_from_whitelist(&entry.id)?;
+        audit::record("whitelist.remove", Some(ip_pattern), None);
+        Ok(())
     } else {
         Err(format!("IP pattern {} not found in whitelist", ip_pattern))
     }
@@ -186,9 +227,11 @@ pub async fn remove_ip_from_whitelist(ip_pattern: String) -> Result<(), String>
 pub async fn clear_ip_whitelist() -> Result<(), String> {
     // 获取所有白名单条目并逐个删除
     let entries = security_db::get_whitelist()?;
+    let count = entries.len();
     for entry in entries {
         security_db::remove_from_whitelist(&entry.ip_pattern)?;
     }
+    audit::record("whitelist.clear", None, Some(serde_json::json!({ "count": count })));
     Ok(())
 }
 
@@ -265,43 +308,59 @@ pub async fn get_ip_token_stats(
 
 /// 验证 IP 模式格式 (支持单个 IP 和 CIDR)
 fn is_valid_ip_pattern(pattern: &str) -> bool {
-    // 检查是否为 CIDR 格式
-    if pattern.contains('/') {
-        let parts: Vec<&str> = pattern.split('/').collect();
-        if parts.len() != 2 {
-            return false;
-        }
-        
-        // 验证 IP 部分
-        if !is_valid_ip(parts[0]) {
-            return false;
-        }
-        
-        // 验证掩码部分
-        if let Ok(mask) = parts[1].parse::<u8>() {
-            return mask <= 32;
-        }
-        return false;
-    }
-    
-    // 单个 IP 地址
-    is_valid_ip(pattern)
+    // 单个 IPv4/IPv6 地址或 CIDR (IPv4 掩码 <= 32，IPv6 掩码 <= 128)
+    crate::utils::ip::IpNet::parse(pattern).is_some()
 }
 
-/// 验证 IP 地址格式
-fn is_valid_ip(ip: &str) -> bool {
-    let parts: Vec<&str> = ip.split('.').collect();
-    if parts.len() != 4 {
-        return false;
-    }
-    
-    for part in parts {
-        if part.parse::<u8>().is_err() {
-            return false;
-        }
+/// 将已保存的密文重新加密为当前密钥下的 v2 格式 (设置加密口令后调用)
+#[tauri::command]
+pub async fn reencrypt_secrets() -> Result<usize, String> {
+    tokio::task::spawn_blocking(crate::modules::secret_migration::reencrypt_secrets)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn get_account_encryption_status() -> Result<crate::modules::account_crypto::EncryptionStatus, String> {
+    Ok(crate::modules::account_crypto::status())
+}
+
+/// 使用主密码解锁账号令牌，并重新加载反代账号池
+#[tauri::command]
+pub async fn unlock_account_encryption(
+    password: String,
+    app_state: State<'_, crate::commands::proxy::ProxyServiceState>,
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || crate::modules::account_crypto::unlock(&password))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let instance_lock = app_state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        instance.token_manager.load_accounts().await?;
     }
-    
-    true
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn enable_account_encryption(password: String) -> Result<usize, String> {
+    tokio::task::spawn_blocking(move || crate::modules::account_crypto::enable(&password))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn rotate_account_encryption(old_password: String, new_password: String) -> Result<usize, String> {
+    tokio::task::spawn_blocking(move || crate::modules::account_crypto::rotate(&old_password, &new_password))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn disable_account_encryption(password: String) -> Result<usize, String> {
+    tokio::task::spawn_blocking(move || crate::modules::account_crypto::disable(&password))
+        .await
+        .map_err(|e| e.to_string())?
 }
 
 #[cfg(test)]
@@ -315,6 +374,8 @@ mod tests {
         assert!(is_valid_ip_pattern("172.16.0.0/16"));
         assert!(is_valid_ip_pattern("192.168.1.0/24"));
         assert!(is_valid_ip_pattern("8.8.8.8/32"));
+        assert!(is_valid_ip_pattern("2001:db8::/32"));
+        assert!(is_valid_ip_pattern("::1"));
     }
 
     #[test]
@@ -324,5 +385,34 @@ mod tests {
         assert!(!is_valid_ip_pattern("192.168.1.1/33"));
         assert!(!is_valid_ip_pattern("192.168.1.1/"));
         assert!(!is_valid_ip_pattern("invalid"));
+        assert!(!is_valid_ip_pattern("2001:db8::/129"));
     }
 }
+
+// ==================== 审计日志命令 ====================
+
+/// 分页查询审计日志
+#[tauri::command]
+pub async fn get_audit_logs(
+    filter: audit_db::AuditFilter,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<audit_db::AuditPage, String> {
+    audit_db::query(&filter, limit.unwrap_or(50).min(500), offset.unwrap_or(0))
+}
+
+/// 按筛选条件导出审计日志到文件，返回导出条数
+#[tauri::command]
+pub async fn export_audit_logs(
+    file_path: String,
+    filter: audit_db::AuditFilter,
+    format: audit_db::AuditExportFormat,
+) -> Result<usize, String> {
+    tokio::task::spawn_blocking(move || {
+        let (content, count) = audit_db::export(&filter, format)?;
+        std::fs::write(&file_path, content).map_err(|e| format!("Failed to write file: {}", e))?;
+        Ok(count)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
-use crate::modules::user_token_db::{self, UserToken, TokenIpBinding};
+use crate::modules::audit;
+use crate::modules::user_token_db::{self, UserToken, TokenIpBinding, TokenRestrictions};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateTokenRequest {
@@ -10,6 +11,12 @@ pub struct CreateTokenRequest {
     pub curfew_start: Option<String>,
     pub curfew_end: Option<String>,
     pub custom_expires_at: Option<i64>,  // 自定义过期时间戳 (秒)
+    #[serde(default)]
+    pub response_cache: bool,            // 是否启用响应缓存
+    #[serde(default)]
+    pub log_metadata_only: bool,         // 日志仅记录元数据
+    #[serde(flatten)]
+    pub restrictions: TokenRestrictions, // 权限范围与能力限制
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,6 +27,12 @@ pub struct UpdateTokenRequest {
     pub max_ips: Option<i32>,
     pub curfew_start: Option<Option<String>>,
     pub curfew_end: Option<Option<String>>,
+    #[serde(default)]
+    pub response_cache: Option<bool>,
+    #[serde(default)]
+    pub log_metadata_only: Option<bool>,
+    #[serde(default)]
+    pub restrictions: Option<TokenRestrictions>,
 }
 
 // 命令实现
@@ -33,7 +46,7 @@ pub async fn list_user_tokens() -> Result<Vec<UserToken>, String> {
 /// 创建新令牌
 #[tauri::command]
 pub async fn create_user_token(request: CreateTokenRequest) -> Result<UserToken, String> {
-    user_token_db::create_token(
+    let token = user_token_db::create_token(
         request.username,
         request.expires_type,
         request.description,
@@ -41,12 +54,22 @@ pub async fn create_user_token(request: CreateTokenRequest) -> Result<UserToken,
         request.curfew_start,
         request.curfew_end,
         request.custom_expires_at,
-    )
+        request.response_cache,
+        request.log_metadata_only,
+        request.restrictions,
+    )?;
+    audit::record(
+        "user_token.create",
+        Some(token.username.clone()),
+        Some(serde_json::json!({ "token_id": token.id, "expires_type": token.expires_type })),
+    );
+    Ok(token)
 }
 
 /// 更新令牌
 #[tauri::command]
 pub async fn update_user_token(id: String, request: UpdateTokenRequest) -> Result<(), String> {
+    let details = serde_json::to_value(&request).ok();
     user_token_db::update_token(
         &id,
         request.username,
@@ -55,19 +78,42 @@ pub async fn update_user_token(id: String, request: UpdateTokenRequest) -> Resul
         request.max_ips,
         request.curfew_start,
         request.curfew_end,
-    )
+        request.response_cache,
+        request.log_metadata_only,
+        request.restrictions,
+    )?;
+    audit::record("user_token.update", Some(token_target(&id)), details);
+    Ok(())
 }
 
 /// 删除令牌
 #[tauri::command]
 pub async fn delete_user_token(id: String) -> Result<(), String> {
-    user_token_db::delete_token(&id)
+    let target = token_target(&id);
+    user_token_db::delete_token(&id)?;
+    audit::record("user_token.delete", Some(target), None);
+    Ok(())
 }
 
 /// 续期令牌
 #[tauri::command]
 pub async fn renew_user_token(id: String, expires_type: String) -> Result<(), String> {
-    user_token_db::renew_token(&id, &expires_type)
+    user_token_db::renew_token(&id, &expires_type)?;
+    audit::record(
+        "user_token.renew",
+        Some(token_target(&id)),
+        Some(serde_json::json!({ "expires_type": expires_type })),
+    );
+    Ok(())
+}
+
+/// 审计记录中的令牌标识 (用户名，找不到时为 ID)
+fn token_target(id: &str) -> String {
+    user_token_db::get_token_by_id(id)
+        .ok()
+        .flatten()
+        .map(|t| t.username)
+        .unwrap_or_else(|| id.to_string())
 }
 
 /// 获取令牌 IP 绑定
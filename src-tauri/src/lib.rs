@@ -148,6 +148,56 @@ pub fn run() {
         error!("Failed to initialize user token database: {}", e);
     }
 
+    // Initialize admin user database
+    if let Err(e) = modules::admin_user_db::init_db() {
+        error!("Failed to initialize admin user database: {}", e);
+    }
+
+    // Initialize audit log database
+    if let Err(e) = modules::audit_db::init_db() {
+        error!("Failed to initialize audit log database: {}", e);
+    }
+
+    // Initialize response cache database
+    if let Err(e) = modules::response_cache_db::init_db() {
+        error!("Failed to initialize response cache database: {}", e);
+    }
+
+    // Initialize alert history database
+    if let Err(e) = modules::alert_db::init_db() {
+        error!("Failed to initialize alert database: {}", e);
+    }
+
+    // Re-encrypt legacy (fixed-nonce) secrets with the v2 envelope format
+    if let Err(e) = modules::secret_migration::reencrypt_secrets() {
+        error!("Failed to re-encrypt stored secrets: {}", e);
+    }
+
+    // One-shot account encryption management: --account-encryption <enable|rotate|disable>
+    if let Some(pos) = args.iter().position(|arg| arg == "--account-encryption") {
+        let action = args.get(pos + 1).map(String::as_str).unwrap_or_default();
+        match modules::account_crypto::run_cli(action) {
+            Ok(count) => {
+                info!("Account encryption '{}' completed for {} account file(s)", action, count);
+                std::process::exit(0);
+            }
+            Err(e) => {
+                error!("Account encryption '{}' failed: {}", action, e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Unlock encrypted account tokens from ABV_MASTER_PASSWORD / ABV_MASTER_PASSWORD_FILE
+    match modules::account_crypto::unlock_from_env() {
+        Ok(true) => info!("Account tokens unlocked from environment"),
+        Ok(false) if modules::account_crypto::status().enabled => {
+            warn!("Account tokens are encrypted and locked; unlock them with the master password to load accounts");
+        }
+        Ok(false) => {}
+        Err(e) => error!("Failed to unlock account tokens: {}", e),
+    }
+
     if is_headless {
         info!("Starting in HEADLESS mode...");
 
@@ -293,9 +343,34 @@ pub fn run() {
                 }
             }
 
-            // Wait for Ctrl-C
+            // Wait for Ctrl-C (or SIGTERM from `docker stop` / container reschedule)
+            #[cfg(unix)]
+            {
+                let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                    .expect("Failed to install SIGTERM handler");
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+            }
+            #[cfg(not(unix))]
             tokio::signal::ctrl_c().await.ok();
             info!("Headless mode shutting down");
+
+            // Persist scheduling state and stop background tasks before exit
+            let token_manager = proxy_state
+                .instance
+                .read()
+                .await
+                .as_ref()
+                .map(|instance| instance.token_manager.clone());
+            if let Some(token_manager) = token_manager {
+                token_manager
+                    .graceful_shutdown(std::time::Duration::from_secs(2))
+                    .await;
+            }
+            // Flush pending trace spans
+            let _ = tokio::task::spawn_blocking(crate::proxy::telemetry::shutdown).await;
         });
         return;
     }
@@ -537,8 +612,15 @@ pub fn run() {
             commands::proxy::get_proxy_logs,
             commands::proxy::get_proxy_logs_paginated,
             commands::proxy::get_proxy_log_detail,
+            commands::proxy::search_proxy_logs,
+            commands::proxy::replay_proxy_log,
+            commands::proxy::get_proxy_log_replays,
+            commands::proxy::test_alert_channel,
+            commands::proxy::get_alert_history,
+            commands::proxy::clear_alert_history,
             commands::proxy::get_proxy_logs_count,
             commands::proxy::export_proxy_logs,
+            commands::proxy::export_proxy_logs_filtered,
             commands::proxy::export_proxy_logs_json,
             commands::proxy::get_proxy_logs_count_filtered,
             commands::proxy::get_proxy_logs_filtered,
@@ -558,11 +640,18 @@ pub fn run() {
             commands::proxy::clear_proxy_rate_limit,
             commands::proxy::clear_all_proxy_rate_limits,
             commands::proxy::check_proxy_health,
+            commands::proxy::get_upstream_endpoint_health,
+            commands::proxy::reset_upstream_endpoint_health,
+            commands::proxy::get_response_cache_stats,
+            commands::proxy::clear_response_cache,
+            commands::proxy::get_tls_status,
+            commands::proxy::reload_tls_certificate,
             // Proxy Pool Binding commands
             commands::proxy_pool::bind_account_proxy,
             commands::proxy_pool::unbind_account_proxy,
             commands::proxy_pool::get_account_proxy_binding,
             commands::proxy_pool::get_all_account_bindings,
+            commands::proxy_pool::get_proxy_pool_stats,
             // Autostart commands
             commands::autostart::toggle_auto_launch,
             commands::autostart::is_auto_launch_enabled,
@@ -584,6 +673,8 @@ pub fn run() {
             commands::get_token_stats_model_trend_daily,
             commands::get_token_stats_account_trend_hourly,
             commands::get_token_stats_account_trend_daily,
+            commands::get_token_cost_breakdown,
+            commands::export_token_cost_csv,
             proxy::cli_sync::get_cli_sync_status,
             proxy::cli_sync::execute_cli_sync,
             proxy::cli_sync::execute_cli_restore,
@@ -600,6 +691,9 @@ pub fn run() {
             // Security/IP monitoring commands
             commands::security::get_ip_access_logs,
             commands::security::get_ip_stats,
+            commands::security::get_ip_ban_events,
+            commands::security::get_dlp_stats,
+            commands::security::clear_dlp_stats,
             commands::security::get_ip_token_stats,
             commands::security::clear_ip_access_logs,
             commands::security::get_ip_blacklist,
@@ -612,8 +706,16 @@ pub fn run() {
             commands::security::remove_ip_from_whitelist,
             commands::security::clear_ip_whitelist,
             commands::security::check_ip_in_whitelist,
+            commands::security::get_audit_logs,
+            commands::security::export_audit_logs,
             commands::security::get_security_config,
             commands::security::update_security_config,
+            commands::security::reencrypt_secrets,
+            commands::security::get_account_encryption_status,
+            commands::security::unlock_account_encryption,
+            commands::security::enable_account_encryption,
+            commands::security::rotate_account_encryption,
+            commands::security::disable_account_encryption,
             // Cloudflared commands
             commands::cloudflared::cloudflared_check,
             commands::cloudflared::cloudflared_install,
@@ -634,6 +736,12 @@ pub fn run() {
             commands::user_token::renew_user_token,
             commands::user_token::get_token_ip_bindings,
             commands::user_token::get_user_token_summary,
+            // Admin User commands
+            commands::admin_user::list_admin_users,
+            commands::admin_user::create_admin_user,
+            commands::admin_user::update_admin_user,
+            commands::admin_user::delete_admin_user,
+            commands::admin_user::reset_admin_user_totp,
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
@@ -663,6 +771,7 @@ pub fn run() {
                             }
                         });
                     }
+                    crate::proxy::telemetry::shutdown();
                 }
                 // Handle macOS dock icon click to reopen window
                 #[cfg(target_os = "macos")]
@@ -648,7 +648,10 @@ fn atomic_replace_file(src: &PathBuf, dst: &PathBuf) -> Result<(), String> {
 pub fn load_account(account_id: &str) -> Result<Account, String> {
     let accounts_dir = get_accounts_dir()?;
     let account_path = accounts_dir.join(format!("{}.json", account_id));
-    load_account_at_path(&account_path)
+    let mut account = load_account_at_path(&account_path)?;
+    // [NEW] 启用静态加密时透明解密令牌
+    crate::modules::account_crypto::decrypt_token(&mut account.token)?;
+    Ok(account)
 }
 
 /// Save account data
@@ -659,7 +662,13 @@ pub fn save_account(account: &Account) -> Result<(), String> {
     let temp_filename = format!("{}.tmp.{}", account.id, Uuid::new_v4());
     let temp_path = accounts_dir.join(&temp_filename);
 
-    let content = serde_json::to_string_pretty(account)
+    // [NEW] 启用静态加密时写入加密后的令牌 (持有写入锁直到落盘，避免与密钥轮换交错)
+    let _guard = crate::modules::account_crypto::write_lock();
+    let stored = Account {
+        token: crate::modules::account_crypto::encrypt_token(&account.token)?,
+        ..account.clone()
+    };
+    let content = serde_json::to_string_pretty(&stored)
         .map_err(|e| format!("failed_to_serialize_account_data: {}", e))?;
 
     if let Err(e) = std::fs::write(&temp_path, content) {
@@ -892,7 +901,13 @@ pub fn delete_accounts(account_ids: &[String]) -> Result<(), String> {
         index.current_account_id = index.accounts.first().map(|s| s.id.clone());
     }
 
-    save_account_index(&index)
+    save_account_index(&index)?;
+    crate::modules::audit::record(
+        "account.delete_batch",
+        Some(format!("{} accounts", account_ids.len())),
+        Some(serde_json::json!({ "account_ids": account_ids })),
+    );
+    Ok(())
 }
 
 /// Reorder account list
@@ -1471,6 +1486,15 @@ pub fn update_account_quota(account_id: &str, quota: QuotaData) -> Result<(), St
     // This ensures in-memory protected_models are updated
     crate::proxy::server::trigger_account_reload(account_id);
 
+    // [NEW] Push to admin event stream
+    crate::proxy::event_stream::publish_account(
+        account_id,
+        Some(&account.email),
+        crate::proxy::event_stream::AccountState::QuotaRefreshed,
+        None,
+        None,
+    );
+
     Ok(())
 }
 
@@ -1555,6 +1579,13 @@ pub fn mark_account_forbidden(account_id: &str, reason: &str) -> Result<(), Stri
 
     // 4. Notify frontend to refresh account list
     crate::modules::log_bridge::emit_accounts_refreshed();
+    crate::proxy::event_stream::publish_account(
+        account_id,
+        Some(&account.email),
+        crate::proxy::event_stream::AccountState::Forbidden,
+        None,
+        Some(reason.to_string()),
+    );
 
     Ok(())
 }
@@ -0,0 +1,526 @@
+//! Account Token Encryption Module
+//! 账号令牌静态加密：启用后账号文件中的 access_token / refresh_token 使用主密码派生的密钥加密。
+//! 主密码只用于派生密钥，不落盘；磁盘上仅保存 salt 与校验密文。
+
+use base64::{engine::general_purpose, Engine as _};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, RwLock};
+
+use crate::models::TokenData;
+use crate::utils::crypto;
+
+const META_FILE: &str = "account_encryption.json";
+const VERIFIER_PLAINTEXT: &str = "antigravity-account-encryption";
+const TOKEN_FIELDS: [&str; 2] = ["access_token", "refresh_token"];
+const MIN_PASSWORD_LEN: usize = 8;
+
+/// 加密元数据 (salt 与校验密文)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EncryptionMeta {
+    version: u32,
+    salt: String,
+    verifier: String,
+    /// 轮换进行中: 用新密钥加密的旧密钥，全部账号文件迁移完成后删除
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    previous_key: Option<String>,
+}
+
+/// 加密状态
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct EncryptionStatus {
+    pub enabled: bool,
+    pub unlocked: bool,
+}
+
+#[derive(Default)]
+struct State {
+    loaded: bool,
+    meta: Option<EncryptionMeta>,
+    key: Option<[u8; 32]>,
+    /// 轮换期间仍可用于解密的旧密钥
+    previous_key: Option<[u8; 32]>,
+}
+
+static STATE: Lazy<RwLock<State>> = Lazy::new(|| RwLock::new(State::default()));
+
+/// 令牌写入锁：加密令牌并写入账号文件的流程与启用 / 轮换 / 关闭互斥，
+/// 避免轮换过程中有写入者使用过期密钥
+static WRITE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// 获取令牌写入锁 (需覆盖 encrypt_field 到文件落盘的整个过程)
+pub fn write_lock() -> MutexGuard<'static, ()> {
+    WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn meta_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(META_FILE)
+}
+
+fn read_meta(data_dir: &Path) -> Result<Option<EncryptionMeta>, String> {
+    let path = meta_path(data_dir);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("failed_to_read_encryption_meta: {}", e))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("failed_to_parse_encryption_meta: {}", e))
+}
+
+/// 先写临时文件再重命名，避免中断时留下半截文件
+fn write_atomic(path: &Path, content: &str) -> Result<(), String> {
+    let temp_path = path.with_extension(format!("json.tmp.{}", uuid::Uuid::new_v4()));
+    fs::write(&temp_path, content).map_err(|e| format!("{}: {}", temp_path.display(), e))?;
+    if let Err(e) = fs::rename(&temp_path, path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(format!("{}: {}", path.display(), e));
+    }
+    Ok(())
+}
+
+fn write_meta(data_dir: &Path, meta: &EncryptionMeta) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(meta).map_err(|e| e.to_string())?;
+    write_atomic(&meta_path(data_dir), &content).map_err(|e| format!("failed_to_write_encryption_meta: {}", e))
+}
+
+/// 首次访问时从磁盘加载元数据
+fn ensure_loaded() {
+    if STATE.read().map(|s| s.loaded).unwrap_or(true) {
+        return;
+    }
+    let meta = crate::modules::account::get_data_dir()
+        .and_then(|dir| read_meta(&dir))
+        .unwrap_or_else(|e| {
+            tracing::error!("[AccountCrypto] {}", e);
+            None
+        });
+    if let Ok(mut state) = STATE.write() {
+        if !state.loaded {
+            state.meta = meta;
+            state.loaded = true;
+        }
+    }
+}
+
+fn set_state(meta: Option<EncryptionMeta>, key: Option<[u8; 32]>) {
+    set_rotating_state(meta, key, None);
+}
+
+fn set_rotating_state(meta: Option<EncryptionMeta>, key: Option<[u8; 32]>, previous_key: Option<[u8; 32]>) {
+    if let Ok(mut state) = STATE.write() {
+        *state = State { loaded: true, meta, key, previous_key };
+    }
+}
+
+fn current_meta() -> Option<EncryptionMeta> {
+    ensure_loaded();
+    STATE.read().ok().and_then(|s| s.meta.clone())
+}
+
+fn current_key() -> Option<[u8; 32]> {
+    ensure_loaded();
+    STATE.read().ok().and_then(|s| s.key)
+}
+
+fn previous_key() -> Option<[u8; 32]> {
+    STATE.read().ok().and_then(|s| s.previous_key)
+}
+
+pub fn status() -> EncryptionStatus {
+    ensure_loaded();
+    let state = STATE.read();
+    EncryptionStatus {
+        enabled: state.as_ref().map(|s| s.meta.is_some()).unwrap_or(false),
+        unlocked: state.as_ref().map(|s| s.key.is_some()).unwrap_or(false),
+    }
+}
+
+fn new_meta(password: &str) -> Result<(EncryptionMeta, [u8; 32]), String> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(format!("master_password_too_short (min {})", MIN_PASSWORD_LEN));
+    }
+    let salt: [u8; 16] = rand::random();
+    let key = crypto::derive_passphrase_key(password, &salt)?;
+    let meta = EncryptionMeta {
+        version: 1,
+        salt: general_purpose::STANDARD.encode(salt),
+        verifier: crypto::encrypt_with_master_key(VERIFIER_PLAINTEXT, &key)?,
+        previous_key: None,
+    };
+    Ok((meta, key))
+}
+
+fn verify(meta: &EncryptionMeta, password: &str) -> Result<[u8; 32], String> {
+    let salt = general_purpose::STANDARD
+        .decode(&meta.salt)
+        .map_err(|e| format!("invalid_encryption_salt: {}", e))?;
+    let key = crypto::derive_passphrase_key(password, &salt)?;
+    match crypto::decrypt_with_master_key(&meta.verifier, &key) {
+        Ok(text) if text == VERIFIER_PLAINTEXT => Ok(key),
+        _ => Err("invalid_master_password".to_string()),
+    }
+}
+
+/// 取出未完成轮换遗留的旧密钥 (以当前密钥加密保存)
+fn recover_previous_key(meta: &EncryptionMeta, key: &[u8; 32]) -> Result<Option<[u8; 32]>, String> {
+    let Some(sealed) = &meta.previous_key else {
+        return Ok(None);
+    };
+    let encoded = crypto::decrypt_with_master_key(sealed, key)?;
+    let bytes = general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("invalid_previous_key: {}", e))?;
+    bytes
+        .try_into()
+        .map(Some)
+        .map_err(|_| "invalid_previous_key".to_string())
+}
+
+/// 使用主密码解锁；若上次轮换被中断，继续把剩余账号文件迁移到当前密钥
+pub fn unlock(password: &str) -> Result<(), String> {
+    let _guard = write_lock();
+    let data_dir = crate::modules::account::get_data_dir()?;
+    let meta = read_meta(&data_dir)?.ok_or("account_encryption_not_enabled")?;
+    let key = verify(&meta, password)?;
+    let meta = match recover_previous_key(&meta, &key)? {
+        Some(old_key) => {
+            set_rotating_state(Some(meta.clone()), Some(key), Some(old_key));
+            let count = finish_rotation(&data_dir, meta, &key, &old_key)?;
+            tracing::warn!("[AccountCrypto] Resumed interrupted rotation for {} account file(s)", count);
+            read_meta(&data_dir)?.ok_or("account_encryption_not_enabled")?
+        }
+        None => meta,
+    };
+    set_state(Some(meta), Some(key));
+    tracing::info!("[AccountCrypto] Account tokens unlocked");
+    Ok(())
+}
+
+/// 从环境变量或密钥文件解锁 (Headless 模式)，返回是否已解锁
+pub fn unlock_from_env() -> Result<bool, String> {
+    if !status().enabled {
+        return Ok(false);
+    }
+    match password_from_env("ABV_MASTER_PASSWORD", "MASTER_PASSWORD", "ABV_MASTER_PASSWORD_FILE")? {
+        Some(password) => {
+            unlock(&password)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// 从环境变量或密码文件读取密码
+fn password_from_env(var: &str, fallback: &str, file_var: &str) -> Result<Option<String>, String> {
+    match std::env::var(var).or_else(|_| std::env::var(fallback)) {
+        Ok(password) if !password.is_empty() => Ok(Some(password)),
+        _ => match std::env::var(file_var) {
+            Ok(path) if !path.trim().is_empty() => fs::read_to_string(path.trim())
+                .map(|content| Some(content.trim_end_matches(['\r', '\n']).to_string()))
+                .map_err(|e| format!("failed_to_read_master_password_file: {}", e)),
+            _ => Ok(None),
+        },
+    }
+}
+
+/// 命令行管理入口: `--account-encryption <enable|rotate|disable>`
+/// 当前密码取自 ABV_MASTER_PASSWORD(_FILE)，轮换时新密码取自 ABV_NEW_MASTER_PASSWORD(_FILE)
+pub fn run_cli(action: &str) -> Result<usize, String> {
+    let password = password_from_env("ABV_MASTER_PASSWORD", "MASTER_PASSWORD", "ABV_MASTER_PASSWORD_FILE")?
+        .ok_or("ABV_MASTER_PASSWORD is not set")?;
+    match action {
+        "enable" => enable(&password),
+        "disable" => disable(&password),
+        "rotate" => {
+            let new_password =
+                password_from_env("ABV_NEW_MASTER_PASSWORD", "NEW_MASTER_PASSWORD", "ABV_NEW_MASTER_PASSWORD_FILE")?
+                    .ok_or("ABV_NEW_MASTER_PASSWORD is not set")?;
+            rotate(&password, &new_password)
+        }
+        other => Err(format!("unknown account encryption action: {}", other)),
+    }
+}
+
+/// 解密单个令牌字段；未加密的值原样返回，轮换期间回退到旧密钥
+pub fn decrypt_field(value: &str) -> Result<String, String> {
+    if !crypto::is_master_key_encrypted(value) {
+        return Ok(value.to_string());
+    }
+    let key = current_key().ok_or("account_encryption_locked")?;
+    crypto::decrypt_with_master_key(value, &key).or_else(|e| match previous_key() {
+        Some(old_key) => crypto::decrypt_with_master_key(value, &old_key).map_err(|_| e),
+        None => Err(e),
+    })
+}
+
+/// 按当前设置加密单个令牌字段；未启用加密时原样返回
+pub fn encrypt_field(value: &str) -> Result<String, String> {
+    if current_meta().is_none() || crypto::is_master_key_encrypted(value) {
+        return Ok(value.to_string());
+    }
+    let key = current_key().ok_or("account_encryption_locked")?;
+    crypto::encrypt_with_master_key(value, &key)
+}
+
+/// 读取账号文件后解密令牌
+pub fn decrypt_token(token: &mut TokenData) -> Result<(), String> {
+    token.access_token = decrypt_field(&token.access_token)?;
+    token.refresh_token = decrypt_field(&token.refresh_token)?;
+    Ok(())
+}
+
+/// 生成写入账号文件用的令牌副本
+pub fn encrypt_token(token: &TokenData) -> Result<TokenData, String> {
+    let mut stored = token.clone();
+    stored.access_token = encrypt_field(&token.access_token)?;
+    stored.refresh_token = encrypt_field(&token.refresh_token)?;
+    Ok(stored)
+}
+
+/// 逐个改写账号文件中的令牌字段，返回改写的文件数
+fn rewrite_account_files<F>(accounts_dir: &Path, transform: F) -> Result<usize, String>
+where
+    F: Fn(&str) -> Result<String, String>,
+{
+    let entries = match fs::read_dir(accounts_dir) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(0),
+    };
+    let mut rewritten = 0;
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let content = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut account: Value = serde_json::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let Some(token) = account.get_mut("token").and_then(|t| t.as_object_mut()) else {
+            continue;
+        };
+
+        let mut changed = false;
+        for field in TOKEN_FIELDS {
+            if let Some(Value::String(value)) = token.get_mut(field) {
+                let updated = transform(value).map_err(|e| format!("{}: {}", path.display(), e))?;
+                if updated != *value {
+                    *value = updated;
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            let content = serde_json::to_string_pretty(&account).map_err(|e| e.to_string())?;
+            write_atomic(&path, &content)?;
+            rewritten += 1;
+        }
+    }
+    Ok(rewritten)
+}
+
+fn decrypt_all(accounts_dir: &Path, key: &[u8; 32]) -> Result<usize, String> {
+    rewrite_account_files(accounts_dir, |value| {
+        if crypto::is_master_key_encrypted(value) {
+            crypto::decrypt_with_master_key(value, key)
+        } else {
+            Ok(value.to_string())
+        }
+    })
+}
+
+fn encrypt_all(accounts_dir: &Path, key: &[u8; 32]) -> Result<usize, String> {
+    rewrite_account_files(accounts_dir, |value| {
+        if crypto::is_master_key_encrypted(value) {
+            Ok(value.to_string())
+        } else {
+            crypto::encrypt_with_master_key(value, key)
+        }
+    })
+}
+
+/// 把旧密钥的密文直接转为新密钥加密 (逐个文件原子写入，不经过明文落盘)
+fn reencrypt_all(accounts_dir: &Path, old_key: &[u8; 32], new_key: &[u8; 32]) -> Result<usize, String> {
+    rewrite_account_files(accounts_dir, |value| {
+        if !crypto::is_master_key_encrypted(value) {
+            return crypto::encrypt_with_master_key(value, new_key);
+        }
+        if crypto::decrypt_with_master_key(value, new_key).is_ok() {
+            return Ok(value.to_string());
+        }
+        let plain = crypto::decrypt_with_master_key(value, old_key)?;
+        crypto::encrypt_with_master_key(&plain, new_key)
+    })
+}
+
+/// 完成迁移后从元数据中删除旧密钥
+fn finish_rotation(
+    data_dir: &Path,
+    mut meta: EncryptionMeta,
+    key: &[u8; 32],
+    old_key: &[u8; 32],
+) -> Result<usize, String> {
+    let count = reencrypt_all(&data_dir.join("accounts"), old_key, key)?;
+    meta.previous_key = None;
+    write_meta(data_dir, &meta)?;
+    Ok(count)
+}
+
+fn enable_in(data_dir: &Path, password: &str) -> Result<(EncryptionMeta, [u8; 32], usize), String> {
+    if read_meta(data_dir)?.is_some() {
+        return Err("account_encryption_already_enabled".to_string());
+    }
+    let (meta, key) = new_meta(password)?;
+    // 先写元数据：中途失败时已加密与未加密的文件都能正常读取
+    write_meta(data_dir, &meta)?;
+    let count = encrypt_all(&data_dir.join("accounts"), &key)?;
+    Ok((meta, key, count))
+}
+
+fn disable_in(data_dir: &Path, password: &str) -> Result<usize, String> {
+    let meta = read_meta(data_dir)?.ok_or("account_encryption_not_enabled")?;
+    let key = verify(&meta, password)?;
+    // 全部解密后再删除元数据
+    let count = decrypt_all(&data_dir.join("accounts"), &key)?;
+    fs::remove_file(meta_path(data_dir)).map_err(|e| format!("failed_to_remove_encryption_meta: {}", e))?;
+    Ok(count)
+}
+
+/// 轮换主密钥。on_switch 在新元数据落盘后、迁移账号文件前调用，用于切换内存中的密钥
+fn rotate_in<F>(
+    data_dir: &Path,
+    old_password: &str,
+    new_password: &str,
+    on_switch: F,
+) -> Result<(EncryptionMeta, [u8; 32], usize), String>
+where
+    F: FnOnce(&EncryptionMeta, [u8; 32], [u8; 32]),
+{
+    let meta = read_meta(data_dir)?.ok_or("account_encryption_not_enabled")?;
+    let old_key = verify(&meta, old_password)?;
+    // 先完成上一次被中断的轮换，避免更早的密钥丢失
+    if let Some(older_key) = recover_previous_key(&meta, &old_key)? {
+        finish_rotation(data_dir, meta.clone(), &old_key, &older_key)?;
+    }
+    let (mut new_meta, new_key) = new_meta(new_password)?;
+    // 1. 新元数据保存用新密钥加密的旧密钥：中途中断后用新密码解锁即可继续迁移
+    new_meta.previous_key = Some(crypto::encrypt_with_master_key(
+        &general_purpose::STANDARD.encode(old_key),
+        &new_key,
+    )?);
+    write_meta(data_dir, &new_meta)?;
+    on_switch(&new_meta, new_key, old_key);
+    // 2. 逐个文件从旧密钥直接转为新密钥，3. 完成后删除旧密钥
+    let count = finish_rotation(data_dir, new_meta.clone(), &new_key, &old_key)?;
+    new_meta.previous_key = None;
+    Ok((new_meta, new_key, count))
+}
+
+/// 启用账号令牌加密，返回加密的账号文件数
+pub fn enable(password: &str) -> Result<usize, String> {
+    let _guard = write_lock();
+    let data_dir = crate::modules::account::get_data_dir()?;
+    let (meta, key, count) = enable_in(&data_dir, password)?;
+    set_state(Some(meta), Some(key));
+    tracing::info!("[AccountCrypto] Encryption enabled for {} account file(s)", count);
+    Ok(count)
+}
+
+/// 关闭账号令牌加密，返回解密的账号文件数
+pub fn disable(password: &str) -> Result<usize, String> {
+    let _guard = write_lock();
+    let data_dir = crate::modules::account::get_data_dir()?;
+    let count = disable_in(&data_dir, password)?;
+    set_state(None, None);
+    tracing::info!("[AccountCrypto] Encryption disabled for {} account file(s)", count);
+    Ok(count)
+}
+
+/// 更换主密码，返回重新加密的账号文件数
+pub fn rotate(old_password: &str, new_password: &str) -> Result<usize, String> {
+    let _guard = write_lock();
+    let data_dir = crate::modules::account::get_data_dir()?;
+    // 新元数据落盘后立即切换密钥 (旧密钥仅用于解密)，写入锁阻止其他写入者使用过期密钥
+    let (meta, key, count) = rotate_in(&data_dir, old_password, new_password, |meta, key, old_key| {
+        set_rotating_state(Some(meta.clone()), Some(key), Some(old_key));
+    })?;
+    set_state(Some(meta), Some(key));
+    tracing::info!("[AccountCrypto] Master password rotated for {} account file(s)", count);
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_token(dir: &Path, field: &str) -> String {
+        let content = fs::read_to_string(dir.join("accounts").join("a1.json")).unwrap();
+        let json: Value = serde_json::from_str(&content).unwrap();
+        json["token"][field].as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_enable_rotate_disable_cycle() {
+        let dir = std::env::temp_dir().join(format!("ag_account_crypto_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("accounts")).unwrap();
+        let account = serde_json::json!({
+            "id": "a1",
+            "email": "a@example.com",
+            "token": { "access_token": "atk", "refresh_token": "rtk", "expires_in": 3600 }
+        });
+        fs::write(dir.join("accounts").join("a1.json"), account.to_string()).unwrap();
+
+        assert!(enable_in(&dir, "short").is_err());
+        let (_, key, count) = enable_in(&dir, "first-password").unwrap();
+        assert_eq!(count, 1);
+        let stored = read_token(&dir, "refresh_token");
+        assert!(crypto::is_master_key_encrypted(&stored));
+        assert_eq!(crypto::decrypt_with_master_key(&stored, &key).unwrap(), "rtk");
+        assert!(enable_in(&dir, "first-password").is_err());
+
+        assert!(rotate_in(&dir, "wrong-password", "second-password", |_, _, _| {}).is_err());
+        let (_, new_key, _) = rotate_in(&dir, "first-password", "second-password", |_, _, _| {}).unwrap();
+        let stored = read_token(&dir, "access_token");
+        assert!(crypto::decrypt_with_master_key(&stored, &key).is_err());
+        assert_eq!(crypto::decrypt_with_master_key(&stored, &new_key).unwrap(), "atk");
+        assert!(read_meta(&dir).unwrap().unwrap().previous_key.is_none());
+
+        assert!(disable_in(&dir, "first-password").is_err());
+        assert_eq!(disable_in(&dir, "second-password").unwrap(), 1);
+        assert_eq!(read_token(&dir, "refresh_token"), "rtk");
+        assert!(read_meta(&dir).unwrap().is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_resume_interrupted_rotation() {
+        let dir = std::env::temp_dir().join(format!("ag_account_crypto_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("accounts")).unwrap();
+        let account = serde_json::json!({
+            "id": "a1",
+            "token": { "access_token": "atk", "refresh_token": "rtk", "expires_in": 3600 }
+        });
+        fs::write(dir.join("accounts").join("a1.json"), account.to_string()).unwrap();
+        let (_, old_key, _) = enable_in(&dir, "first-password").unwrap();
+
+        // 模拟新元数据已落盘、账号文件尚未迁移时中断
+        let (mut meta, new_key) = new_meta("second-password").unwrap();
+        meta.previous_key =
+            Some(crypto::encrypt_with_master_key(&general_purpose::STANDARD.encode(old_key), &new_key).unwrap());
+        write_meta(&dir, &meta).unwrap();
+
+        let meta = read_meta(&dir).unwrap().unwrap();
+        let key = verify(&meta, "second-password").unwrap();
+        let recovered = recover_previous_key(&meta, &key).unwrap().unwrap();
+        assert_eq!(recovered, old_key);
+        assert_eq!(finish_rotation(&dir, meta, &key, &recovered).unwrap(), 1);
+
+        let stored = read_token(&dir, "refresh_token");
+        assert_eq!(crypto::decrypt_with_master_key(&stored, &new_key).unwrap(), "rtk");
+        assert!(read_meta(&dir).unwrap().unwrap().previous_key.is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -77,19 +77,33 @@ impl AccountService {
             "[Service] Added/Updated account: {}",
             account.email
         ));
+        modules::audit::record("account.add", Some(account.email.clone()), None);
         Ok(account)
     }
 
     /// 删除账号逻辑
     pub fn delete_account(&self, account_id: &str) -> Result<(), String> {
+        let email = modules::load_account(account_id).ok().map(|a| a.email);
         modules::delete_account(account_id)?;
         self.integration.update_tray();
+        modules::audit::record(
+            "account.delete",
+            Some(email.unwrap_or_else(|| account_id.to_string())),
+            Some(serde_json::json!({ "account_id": account_id })),
+        );
         Ok(())
     }
 
     /// 切换账号逻辑
     pub async fn switch_account(&self, account_id: &str) -> Result<(), String> {
-        modules::account::switch_account(account_id, &self.integration).await
+        modules::account::switch_account(account_id, &self.integration).await?;
+        let email = modules::load_account(account_id).ok().map(|a| a.email);
+        modules::audit::record(
+            "account.switch",
+            Some(email.unwrap_or_else(|| account_id.to_string())),
+            Some(serde_json::json!({ "account_id": account_id })),
+        );
+        Ok(())
     }
 
     /// 列表获取
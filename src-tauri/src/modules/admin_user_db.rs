@@ -0,0 +1,640 @@
+//! 管理员账号与会话 (admin_users.db)
+//!
+//! - 密码使用 Argon2id (PHC 字符串) 存储
+//! - 会话令牌仅保存 SHA-256 哈希，登录时返回一次明文
+//! - 尚未创建任何管理员时，沿用旧版 admin_password / api_key 登录 (视为 owner)
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use base64::{engine::general_purpose, Engine as _};
+use once_cell::sync::Lazy;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI8, Ordering};
+
+use crate::utils::totp;
+
+/// 会话令牌前缀 (与旧版管理密码区分)
+pub const SESSION_PREFIX: &str = "abvs_";
+/// 会话有效期 (秒)
+pub const SESSION_TTL_SECS: i64 = 12 * 3600;
+/// 密码最短长度
+const MIN_PASSWORD_LEN: usize = 8;
+/// 认证器 App 中显示的发行方
+const TOTP_ISSUER: &str = "Antigravity Tools";
+
+/// 管理员角色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminRole {
+    /// 全部权限，包括配置与管理员管理
+    Owner,
+    /// 日常运维: 账号、代理服务、令牌管理，可查看日志
+    Operator,
+    /// 只读: 账号、统计与状态
+    Viewer,
+    /// 只读 + 请求日志、访问日志等审计记录
+    Auditor,
+}
+
+impl AdminRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminRole::Owner => "owner",
+            AdminRole::Operator => "operator",
+            AdminRole::Viewer => "viewer",
+            AdminRole::Auditor => "auditor",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "owner" => Some(AdminRole::Owner),
+            "operator" => Some(AdminRole::Operator),
+            "viewer" => Some(AdminRole::Viewer),
+            "auditor" => Some(AdminRole::Auditor),
+            _ => None,
+        }
+    }
+}
+
+/// 管理员账号
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminUser {
+    pub id: String,
+    pub username: String,
+    pub role: AdminRole,
+    pub disabled: bool,
+    pub totp_enabled: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub last_login_at: Option<i64>,
+}
+
+/// 已认证的管理身份 (注入到请求 extensions)
+#[derive(Debug, Clone, Serialize)]
+pub struct AdminIdentity {
+    /// 旧版管理密码 / 桌面端为 None
+    pub user_id: Option<String>,
+    pub username: String,
+    pub role: AdminRole,
+    pub totp_enabled: bool,
+    #[serde(skip)]
+    pub session_id: Option<String>,
+}
+
+impl AdminIdentity {
+    /// 旧版管理密码身份
+    pub fn legacy() -> Self {
+        Self {
+            user_id: None,
+            username: "admin".to_string(),
+            role: AdminRole::Owner,
+            totp_enabled: false,
+            session_id: None,
+        }
+    }
+}
+
+/// 登录结果 (令牌明文仅在此返回一次)
+#[derive(Debug, Clone, Serialize)]
+pub struct AdminSession {
+    pub token: String,
+    pub expires_at: i64,
+    pub identity: AdminIdentity,
+}
+
+/// 更新管理员请求
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpdateAdminUser {
+    pub role: Option<AdminRole>,
+    pub password: Option<String>,
+    pub disabled: Option<bool>,
+}
+
+/// 登录失败原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoginError {
+    InvalidCredentials,
+    TotpRequired,
+    InvalidTotp,
+    Internal(String),
+}
+
+impl LoginError {
+    /// 返回给客户端的错误码 (内部错误不暴露细节)
+    pub fn code(&self) -> &str {
+        match self {
+            LoginError::InvalidCredentials => "invalid_credentials",
+            LoginError::TotpRequired => "totp_required",
+            LoginError::InvalidTotp => "invalid_totp",
+            LoginError::Internal(_) => "internal_error",
+        }
+    }
+}
+
+impl From<String> for LoginError {
+    fn from(e: String) -> Self {
+        LoginError::Internal(e)
+    }
+}
+
+/// 是否存在管理员账号的缓存 (-1 未知, 0 否, 1 是)
+static HAS_USERS: AtomicI8 = AtomicI8::new(-1);
+
+/// 获取数据库路径
+pub fn get_db_path() -> Result<PathBuf, String> {
+    Ok(crate::modules::account::get_data_dir()?.join("admin_users.db"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let conn = Connection::open(get_db_path()?).map_err(|e| format!("Failed to open database: {}", e))?;
+    conn.execute_batch("PRAGMA foreign_keys = ON;")
+        .map_err(|e| format!("Failed to enable foreign keys: {}", e))?;
+    Ok(conn)
+}
+
+/// 初始化数据库
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS admin_users (
+            id TEXT PRIMARY KEY,
+            username TEXT UNIQUE NOT NULL COLLATE NOCASE,
+            password_hash TEXT NOT NULL,
+            role TEXT NOT NULL,
+            disabled INTEGER NOT NULL DEFAULT 0,
+            totp_secret TEXT,
+            totp_enabled INTEGER NOT NULL DEFAULT 0,
+            totp_last_counter INTEGER,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            last_login_at INTEGER
+        );
+        CREATE TABLE IF NOT EXISTS admin_sessions (
+            id TEXT PRIMARY KEY,
+            token_hash TEXT UNIQUE NOT NULL,
+            user_id TEXT REFERENCES admin_users(id) ON DELETE CASCADE,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL,
+            last_seen_at INTEGER NOT NULL,
+            client_ip TEXT,
+            user_agent TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_admin_sessions_expires ON admin_sessions (expires_at);",
+    )
+    .map_err(|e| format!("Failed to create admin tables: {}", e))?;
+    HAS_USERS.store(-1, Ordering::SeqCst);
+    Ok(())
+}
+
+/// 是否已创建管理员账号 (数据库异常时按已创建处理，拒绝旧版密码)
+pub fn has_users() -> bool {
+    match HAS_USERS.load(Ordering::SeqCst) {
+        0 => return false,
+        1 => return true,
+        _ => {}
+    }
+    let result = connect_db().and_then(|conn| {
+        conn.query_row("SELECT EXISTS(SELECT 1 FROM admin_users)", [], |row| row.get::<_, bool>(0))
+            .map_err(|e| e.to_string())
+    });
+    match result {
+        Ok(exists) => {
+            HAS_USERS.store(exists as i8, Ordering::SeqCst);
+            exists
+        }
+        Err(e) => {
+            tracing::error!("[AdminAuth] Failed to query admin users: {}", e);
+            true
+        }
+    }
+}
+
+fn hash_password(password: &str) -> Result<String, String> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(format!("Password must be at least {} characters", MIN_PASSWORD_LEN));
+    }
+    let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>())
+        .map_err(|e| format!("Failed to generate salt: {}", e))?;
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("Failed to hash password: {}", e))
+}
+
+/// 用户不存在时用于校验的占位哈希，使响应时间与用户存在时一致
+static DUMMY_PASSWORD_HASH: Lazy<String> = Lazy::new(|| {
+    let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>()).expect("16-byte salt encodes");
+    Argon2::default()
+        .hash_password(uuid::Uuid::new_v4().to_string().as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .unwrap_or_default()
+});
+
+fn verify_password(hash: &str, password: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+        .unwrap_or(false)
+}
+
+fn hash_session_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn row_to_user(row: &rusqlite::Row) -> rusqlite::Result<AdminUser> {
+    let role: String = row.get("role")?;
+    Ok(AdminUser {
+        id: row.get("id")?,
+        username: row.get("username")?,
+        role: AdminRole::parse(&role).unwrap_or(AdminRole::Viewer),
+        disabled: row.get("disabled")?,
+        totp_enabled: row.get("totp_enabled")?,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+        last_login_at: row.get("last_login_at")?,
+    })
+}
+
+const USER_COLUMNS: &str =
+    "id, username, role, disabled, totp_enabled, created_at, updated_at, last_login_at";
+
+/// 列出管理员
+pub fn list_users() -> Result<Vec<AdminUser>, String> {
+    let conn = connect_db()?;
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM admin_users ORDER BY created_at", USER_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let users = stmt
+        .query_map([], row_to_user)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(users)
+}
+
+pub fn get_user(id: &str) -> Result<Option<AdminUser>, String> {
+    let conn = connect_db()?;
+    conn.query_row(
+        &format!("SELECT {} FROM admin_users WHERE id = ?1", USER_COLUMNS),
+        params![id],
+        row_to_user,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// 除 exclude_id 外仍可用的 owner 数量
+fn count_active_owners(conn: &Connection, exclude_id: &str) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM admin_users WHERE role = 'owner' AND disabled = 0 AND id != ?1",
+        params![exclude_id],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// 创建管理员 (第一个管理员必须是 owner)
+pub fn create_user(username: &str, password: &str, role: AdminRole) -> Result<AdminUser, String> {
+    let username = username.trim();
+    if username.is_empty() {
+        return Err("Username is required".to_string());
+    }
+    let password_hash = hash_password(password)?;
+    let now = chrono::Utc::now().timestamp();
+    let id = uuid::Uuid::new_v4().to_string();
+
+    // 首个管理员检查与插入在同一写事务中完成，避免并发创建时绕过检查
+    let mut conn = connect_db()?;
+    let tx = conn
+        .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+        .map_err(|e| e.to_string())?;
+    let has_any: bool = tx
+        .query_row("SELECT EXISTS(SELECT 1 FROM admin_users)", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if !has_any && role != AdminRole::Owner {
+        return Err("The first admin user must be an owner".to_string());
+    }
+    tx.execute(
+        "INSERT INTO admin_users (id, username, password_hash, role, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+        params![id, username, password_hash, role.as_str(), now],
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            format!("Admin user '{}' already exists", username)
+        }
+        e => format!("Failed to create admin user: {}", e),
+    })?;
+    tx.commit().map_err(|e| e.to_string())?;
+    HAS_USERS.store(1, Ordering::SeqCst);
+
+    get_user(&id)?.ok_or_else(|| "Admin user not found".to_string())
+}
+
+/// 更新管理员；修改角色 / 密码 / 禁用时注销其全部会话
+pub fn update_user(id: &str, update: UpdateAdminUser) -> Result<AdminUser, String> {
+    let mut conn = connect_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let current: String = tx
+        .query_row("SELECT role FROM admin_users WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Admin user not found".to_string())?;
+
+    let loses_owner = current == "owner"
+        && (update.role.is_some_and(|r| r != AdminRole::Owner) || update.disabled == Some(true));
+    if loses_owner && count_active_owners(&tx, id)? == 0 {
+        return Err("At least one active owner is required".to_string());
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    if let Some(role) = update.role {
+        tx.execute(
+            "UPDATE admin_users SET role = ?1, updated_at = ?2 WHERE id = ?3",
+            params![role.as_str(), now, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    if let Some(password) = update.password.as_deref() {
+        tx.execute(
+            "UPDATE admin_users SET password_hash = ?1, updated_at = ?2 WHERE id = ?3",
+            params![hash_password(password)?, now, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    if let Some(disabled) = update.disabled {
+        tx.execute(
+            "UPDATE admin_users SET disabled = ?1, updated_at = ?2 WHERE id = ?3",
+            params![disabled, now, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    if update.role.is_some() || update.password.is_some() || update.disabled == Some(true) {
+        tx.execute("DELETE FROM admin_sessions WHERE user_id = ?1", params![id])
+            .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_user(id)?.ok_or_else(|| "Admin user not found".to_string())
+}
+
+/// 删除管理员 (不能删除最后一个 owner)
+pub fn delete_user(id: &str) -> Result<(), String> {
+    let mut conn = connect_db()?;
+    // 会话与用户在同一事务中删除，避免残留孤立会话
+    let tx = conn
+        .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+        .map_err(|e| e.to_string())?;
+    let role: Option<String> = tx
+        .query_row("SELECT role FROM admin_users WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match role.as_deref() {
+        None => return Err("Admin user not found".to_string()),
+        Some("owner") if count_active_owners(&tx, id)? == 0 => {
+            return Err("At least one active owner is required".to_string())
+        }
+        _ => {}
+    }
+    tx.execute("DELETE FROM admin_sessions WHERE user_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM admin_users WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    HAS_USERS.store(-1, Ordering::SeqCst);
+    Ok(())
+}
+
+fn decrypt_totp_secret(stored: &str) -> Result<String, String> {
+    crate::utils::crypto::decrypt_string(stored)
+}
+
+/// 校验 TOTP 并记录已使用的计数器 (同一验证码不可重复使用)
+fn check_totp(conn: &Connection, user_id: &str, code: &str) -> Result<bool, String> {
+    let (secret, last_counter): (Option<String>, Option<i64>) = conn
+        .query_row(
+            "SELECT totp_secret, totp_last_counter FROM admin_users WHERE id = ?1",
+            params![user_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    let Some(secret) = secret else {
+        return Ok(false);
+    };
+    let secret = decrypt_totp_secret(&secret)?;
+    match totp::verify(&secret, code, totp::current_counter()) {
+        Some(counter) if last_counter.is_none_or(|last| counter as i64 > last) => {
+            conn.execute(
+                "UPDATE admin_users SET totp_last_counter = ?1 WHERE id = ?2",
+                params![counter as i64, user_id],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// 校验用户名、密码与 TOTP
+pub fn authenticate(username: &str, password: &str, totp_code: Option<&str>) -> Result<AdminUser, LoginError> {
+    let conn = connect_db()?;
+    let found: Option<(String, String)> = conn
+        .query_row(
+            "SELECT id, password_hash FROM admin_users WHERE username = ?1 AND disabled = 0",
+            params![username.trim()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some((id, password_hash)) = found else {
+        // 仍执行一次 Argon2 校验，避免通过响应时间探测用户名是否存在
+        let _ = verify_password(&DUMMY_PASSWORD_HASH, password);
+        return Err(LoginError::InvalidCredentials);
+    };
+    if !verify_password(&password_hash, password) {
+        return Err(LoginError::InvalidCredentials);
+    }
+
+    let user = get_user(&id)?.ok_or(LoginError::InvalidCredentials)?;
+    if user.totp_enabled {
+        let code = totp_code.filter(|c| !c.trim().is_empty()).ok_or(LoginError::TotpRequired)?;
+        if !check_totp(&conn, &id, code)? {
+            return Err(LoginError::InvalidTotp);
+        }
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    conn.execute("UPDATE admin_users SET last_login_at = ?1 WHERE id = ?2", params![now, id])
+        .map_err(|e| e.to_string())?;
+    Ok(user)
+}
+
+/// 创建会话 (user 为 None 表示旧版管理密码登录)
+pub fn create_session(user: Option<&AdminUser>, client_ip: Option<&str>, user_agent: Option<&str>) -> Result<AdminSession, String> {
+    let conn = connect_db()?;
+    let now = chrono::Utc::now().timestamp();
+    conn.execute("DELETE FROM admin_sessions WHERE expires_at <= ?1", params![now])
+        .map_err(|e| e.to_string())?;
+
+    let token = format!(
+        "{}{}",
+        SESSION_PREFIX,
+        general_purpose::URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>())
+    );
+    let id = uuid::Uuid::new_v4().to_string();
+    let expires_at = now + SESSION_TTL_SECS;
+    conn.execute(
+        "INSERT INTO admin_sessions (id, token_hash, user_id, created_at, expires_at, last_seen_at, client_ip, user_agent)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?4, ?6, ?7)",
+        params![id, hash_session_token(&token), user.map(|u| u.id.as_str()), now, expires_at, client_ip, user_agent],
+    )
+    .map_err(|e| format!("Failed to create session: {}", e))?;
+
+    let identity = match user {
+        Some(user) => AdminIdentity {
+            user_id: Some(user.id.clone()),
+            username: user.username.clone(),
+            role: user.role,
+            totp_enabled: user.totp_enabled,
+            session_id: Some(id),
+        },
+        None => AdminIdentity {
+            session_id: Some(id),
+            ..AdminIdentity::legacy()
+        },
+    };
+    Ok(AdminSession { token, expires_at, identity })
+}
+
+/// 根据会话令牌解析身份
+pub fn resolve_session(token: &str) -> Result<Option<AdminIdentity>, String> {
+    if !token.starts_with(SESSION_PREFIX) {
+        return Ok(None);
+    }
+    let conn = connect_db()?;
+    let now = chrono::Utc::now().timestamp();
+    let session: Option<(String, Option<String>)> = conn
+        .query_row(
+            "SELECT id, user_id FROM admin_sessions WHERE token_hash = ?1 AND expires_at > ?2",
+            params![hash_session_token(token), now],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some((session_id, user_id)) = session else {
+        return Ok(None);
+    };
+
+    let identity = match user_id {
+        // 旧版密码会话在创建管理员后失效
+        None if has_users() => return Ok(None),
+        None => AdminIdentity::legacy(),
+        Some(user_id) => match get_user(&user_id)? {
+            Some(user) if !user.disabled => AdminIdentity {
+                user_id: Some(user.id),
+                username: user.username,
+                role: user.role,
+                totp_enabled: user.totp_enabled,
+                session_id: None,
+            },
+            _ => return Ok(None),
+        },
+    };
+
+    conn.execute(
+        "UPDATE admin_sessions SET last_seen_at = ?1 WHERE id = ?2",
+        params![now, session_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(Some(AdminIdentity {
+        session_id: Some(session_id),
+        ..identity
+    }))
+}
+
+/// 注销会话
+pub fn delete_session(session_id: &str) -> Result<(), String> {
+    let conn = connect_db()?;
+    conn.execute("DELETE FROM admin_sessions WHERE id = ?1", params![session_id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 开始绑定 TOTP: 生成新密钥 (确认前不生效)，返回 (密钥, otpauth 链接)
+pub fn begin_totp_setup(user_id: &str) -> Result<(String, String), String> {
+    let user = get_user(user_id)?.ok_or_else(|| "Admin user not found".to_string())?;
+    if user.totp_enabled {
+        return Err("TOTP is already enabled".to_string());
+    }
+    let secret = totp::generate_secret();
+    let conn = connect_db()?;
+    conn.execute(
+        "UPDATE admin_users SET totp_secret = ?1, totp_last_counter = NULL WHERE id = ?2",
+        params![crate::utils::crypto::encrypt_string(&secret)?, user_id],
+    )
+    .map_err(|e| e.to_string())?;
+    let url = totp::otpauth_url(TOTP_ISSUER, &user.username, &secret);
+    Ok((secret, url))
+}
+
+/// 输入验证码确认启用 / 关闭 TOTP
+pub fn set_totp_enabled(user_id: &str, code: &str, enabled: bool) -> Result<(), String> {
+    let conn = connect_db()?;
+    if !check_totp(&conn, user_id, code)? {
+        return Err("invalid_totp".to_string());
+    }
+    let now = chrono::Utc::now().timestamp();
+    if enabled {
+        conn.execute(
+            "UPDATE admin_users SET totp_enabled = 1, updated_at = ?1 WHERE id = ?2",
+            params![now, user_id],
+        )
+    } else {
+        conn.execute(
+            "UPDATE admin_users SET totp_enabled = 0, totp_secret = NULL, totp_last_counter = NULL, updated_at = ?1 WHERE id = ?2",
+            params![now, user_id],
+        )
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 管理员重置他人的 TOTP (丢失设备时)
+pub fn reset_totp(user_id: &str) -> Result<(), String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "UPDATE admin_users SET totp_enabled = 0, totp_secret = NULL, totp_last_counter = NULL, updated_at = ?1 WHERE id = ?2",
+        params![chrono::Utc::now().timestamp(), user_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_hashing() {
+        assert!(hash_password("short").is_err());
+        let hash = hash_password("correct horse battery").unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert!(verify_password(&hash, "correct horse battery"));
+        assert!(!verify_password(&hash, "wrong password"));
+        assert!(!verify_password("not-a-hash", "correct horse battery"));
+    }
+
+    #[test]
+    fn test_role_round_trip() {
+        for role in [AdminRole::Owner, AdminRole::Operator, AdminRole::Viewer, AdminRole::Auditor] {
+            assert_eq!(AdminRole::parse(role.as_str()), Some(role));
+        }
+        assert_eq!(AdminRole::parse("root"), None);
+    }
+}
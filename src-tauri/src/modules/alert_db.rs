@@ -0,0 +1,180 @@
+//! Alert Database Module
+//! 告警投递记录
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// 保留的投递记录条数上限
+const MAX_HISTORY_ROWS: i64 = 1000;
+
+/// 单次投递记录
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AlertDelivery {
+    pub id: i64,
+    pub timestamp: i64,
+    /// 触发的规则 ID (测试发送为 None)
+    pub rule_id: Option<String>,
+    pub kind: String,
+    pub subject: String,
+    pub message: String,
+    pub channel_id: String,
+    pub channel_name: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+pub fn get_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("alerts.db"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let conn = Connection::open(get_db_path()?).map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "journal_mode", "WAL").map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000).map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL").map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+fn create_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS alert_deliveries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            rule_id TEXT,
+            kind TEXT NOT NULL,
+            subject TEXT NOT NULL,
+            message TEXT NOT NULL,
+            channel_id TEXT NOT NULL,
+            channel_name TEXT NOT NULL,
+            success INTEGER NOT NULL,
+            error TEXT
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_alert_deliveries_timestamp ON alert_deliveries (timestamp DESC)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+    create_schema(&conn)
+}
+
+fn record_with(conn: &Connection, delivery: &AlertDelivery) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO alert_deliveries (timestamp, rule_id, kind, subject, message, channel_id, channel_name, success, error)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            delivery.timestamp,
+            delivery.rule_id,
+            delivery.kind,
+            delivery.subject,
+            delivery.message,
+            delivery.channel_id,
+            delivery.channel_name,
+            delivery.success,
+            delivery.error,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // 只保留最近的记录
+    conn.execute(
+        "DELETE FROM alert_deliveries WHERE id <= (SELECT MAX(id) FROM alert_deliveries) - ?1",
+        [MAX_HISTORY_ROWS],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn history_with(conn: &Connection, limit: usize, offset: usize) -> Result<Vec<AlertDelivery>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, timestamp, rule_id, kind, subject, message, channel_id, channel_name, success, error
+             FROM alert_deliveries
+             ORDER BY id DESC
+             LIMIT ?1 OFFSET ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![limit as i64, offset as i64], |row| {
+            Ok(AlertDelivery {
+                id: row.get(0)?,
+                timestamp: row.get(1)?,
+                rule_id: row.get(2)?,
+                kind: row.get(3)?,
+                subject: row.get(4)?,
+                message: row.get(5)?,
+                channel_id: row.get(6)?,
+                channel_name: row.get(7)?,
+                success: row.get(8)?,
+                error: row.get(9)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// 写入一条投递记录
+pub fn record_delivery(delivery: &AlertDelivery) -> Result<(), String> {
+    let conn = connect_db()?;
+    record_with(&conn, delivery)
+}
+
+/// 按时间倒序获取投递记录
+pub fn get_history(limit: usize, offset: usize) -> Result<Vec<AlertDelivery>, String> {
+    let conn = connect_db()?;
+    history_with(&conn, limit, offset)
+}
+
+pub fn clear_history() -> Result<usize, String> {
+    let conn = connect_db()?;
+    conn.execute("DELETE FROM alert_deliveries", [])
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_trim_history() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_schema(&conn).unwrap();
+
+        let delivery = |n: i64| AlertDelivery {
+            id: 0,
+            timestamp: n,
+            rule_id: Some("rule".to_string()),
+            kind: "account_disabled".to_string(),
+            subject: format!("user{}@example.com", n),
+            message: "disabled".to_string(),
+            channel_id: "ch".to_string(),
+            channel_name: "Slack".to_string(),
+            success: n % 2 == 0,
+            error: (n % 2 != 0).then(|| "timeout".to_string()),
+        };
+        for n in 0..(MAX_HISTORY_ROWS + 5) {
+            record_with(&conn, &delivery(n)).unwrap();
+        }
+
+        let history = history_with(&conn, 2, 0).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].timestamp, MAX_HISTORY_ROWS + 4);
+        assert!(history[0].success);
+        assert!(!history[1].success);
+        assert_eq!(history[1].error.as_deref(), Some("timeout"));
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM alert_deliveries", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, MAX_HISTORY_ROWS);
+    }
+}
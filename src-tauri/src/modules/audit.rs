@@ -0,0 +1,219 @@
+//! 管理操作审计
+//!
+//! 业务代码在操作成功后调用 [`record`]。操作者由调用上下文决定：
+//! - 管理接口请求在 [`web_scope`] 内执行，记录暂存到请求上下文，
+//!   由审计中间件在响应后连同管理员身份、IP 与状态码一起写入
+//! - 其余调用 (Tauri 命令、本机后台任务) 直接以 desktop 身份写入
+
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+use crate::modules::audit_db::{self, AuditEntry};
+
+/// 单次配置保存最多记录的变更项
+const MAX_CONFIG_CHANGES: usize = 200;
+/// 自动清理的最小间隔 (秒)
+const PRUNE_INTERVAL_SECS: i64 = 24 * 3600;
+const REDACTED: &str = "[REDACTED]";
+
+/// 业务代码记录的待写入事件
+#[derive(Debug, Clone)]
+pub struct PendingEvent {
+    pub action: String,
+    pub target: Option<String>,
+    pub details: Option<Value>,
+}
+
+tokio::task_local! {
+    static WEB_EVENTS: Arc<Mutex<Vec<PendingEvent>>>;
+}
+
+static LAST_PRUNE: AtomicI64 = AtomicI64::new(0);
+
+/// 在管理接口请求上下文中执行，返回期间记录的事件
+pub async fn web_scope<F: Future>(fut: F) -> (F::Output, Vec<PendingEvent>) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let output = WEB_EVENTS.scope(events.clone(), fut).await;
+    let events = std::mem::take(&mut *events.lock());
+    (output, events)
+}
+
+/// 记录一次成功的管理操作
+pub fn record(action: &str, target: Option<String>, details: Option<Value>) {
+    let mut event = Some(PendingEvent {
+        action: action.to_string(),
+        target,
+        details,
+    });
+    let in_web = WEB_EVENTS
+        .try_with(|events| {
+            if let Some(event) = event.take() {
+                events.lock().push(event);
+            }
+        })
+        .is_ok();
+    if in_web {
+        return;
+    }
+
+    let Some(event) = event else { return };
+    write(AuditEntry {
+        id: 0,
+        timestamp: chrono::Utc::now().timestamp(),
+        source: "desktop".to_string(),
+        actor: "desktop".to_string(),
+        actor_role: None,
+        client_ip: None,
+        action: event.action,
+        target: event.target,
+        details: event.details,
+        success: true,
+        status: None,
+    });
+}
+
+/// 写入记录 (失败仅记日志，不影响业务)，并按保留期定期清理
+/// [FIX] 在异步运行时中调用时 (如管理接口中间件) 转入阻塞线程池，避免 SQLite 写入与清理阻塞异步任务
+pub fn write(entry: AuditEntry) {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => {
+            handle.spawn_blocking(move || write_blocking(entry));
+        }
+        Err(_) => write_blocking(entry),
+    }
+}
+
+fn write_blocking(entry: AuditEntry) {
+    if let Err(e) = audit_db::insert(&entry) {
+        tracing::error!("[Audit] Failed to record '{}': {}", entry.action, e);
+    }
+    maybe_prune(entry.timestamp);
+}
+
+fn maybe_prune(now: i64) {
+    let last = LAST_PRUNE.load(Ordering::Relaxed);
+    if now - last < PRUNE_INTERVAL_SECS
+        || LAST_PRUNE
+            .compare_exchange(last, now, Ordering::AcqRel, Ordering::Relaxed)
+            .is_err()
+    {
+        return;
+    }
+    let retention_days = crate::modules::config::load_app_config()
+        .map(|c| c.proxy.security_monitor.audit_log.retention_days)
+        .unwrap_or(0);
+    match audit_db::prune(retention_days) {
+        Ok(deleted) if deleted > 0 => {
+            tracing::info!("[Audit] Removed {} entries older than {} days", deleted, retention_days)
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("[Audit] Failed to prune audit log: {}", e),
+    }
+}
+
+/// 配置文件保存后记录变更 (无变化时不记录)
+pub fn record_config_change(before: &Value, after: &Value) {
+    let mut changes = Vec::new();
+    diff_values("", "", before, after, &mut changes);
+    if changes.is_empty() {
+        return;
+    }
+    let truncated = changes.len() > MAX_CONFIG_CHANGES;
+    changes.truncate(MAX_CONFIG_CHANGES);
+    record(
+        "config.save",
+        None,
+        Some(json!({ "changes": changes, "truncated": truncated })),
+    );
+}
+
+/// 是否为敏感字段 (变更只记录 "已修改"，不记录取值)
+fn is_sensitive_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key.contains("password")
+        || key.contains("secret")
+        || key.contains("webhook")
+        || key.ends_with("api_key")
+        || key.ends_with("token")
+        || key == "headers"
+}
+
+/// 敏感字段可能是每次保存都重新加密的密文，比较前先解密
+fn normalize_secret(value: &Value) -> Value {
+    match value {
+        Value::String(s) => Value::String(crate::utils::crypto::decrypt_string(s).unwrap_or_else(|_| s.clone())),
+        other => other.clone(),
+    }
+}
+
+fn diff_values(path: &str, key: &str, before: &Value, after: &Value, changes: &mut Vec<Value>) {
+    if !key.is_empty() && is_sensitive_key(key) {
+        if normalize_secret(before) != normalize_secret(after) {
+            changes.push(json!({ "path": path, "before": REDACTED, "after": REDACTED }));
+        }
+        return;
+    }
+
+    match (before, after) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys().filter(|k| !a.contains_key(*k))).collect();
+            keys.sort();
+            for k in keys {
+                let child = if path.is_empty() { k.clone() } else { format!("{}.{}", path, k) };
+                let null = Value::Null;
+                diff_values(
+                    &child,
+                    k,
+                    a.get(k).unwrap_or(&null),
+                    b.get(k).unwrap_or(&null),
+                    changes,
+                );
+            }
+        }
+        (a, b) if a != b => {
+            changes.push(json!({ "path": path, "before": a, "after": b }));
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_diff_redacts_secrets() {
+        let before = json!({
+            "proxy": {
+                "port": 8045,
+                "api_key": "sk-old",
+                "custom_mapping": { "gpt-4": "gemini-2.5-pro" },
+                "upstream_proxy": { "url": "", "password": "a" }
+            },
+            "language": "en"
+        });
+        let after = json!({
+            "proxy": {
+                "port": 8046,
+                "api_key": "sk-new",
+                "custom_mapping": { "gpt-4": "gemini-2.5-pro", "gpt-5": "gemini-3-pro" },
+                "upstream_proxy": { "url": "", "password": "a" }
+            },
+            "language": "en"
+        });
+        let mut changes = Vec::new();
+        diff_values("", "", &before, &after, &mut changes);
+
+        assert_eq!(
+            changes,
+            vec![
+                json!({ "path": "proxy.api_key", "before": REDACTED, "after": REDACTED }),
+                json!({ "path": "proxy.custom_mapping.gpt-5", "before": null, "after": "gemini-3-pro" }),
+                json!({ "path": "proxy.port", "before": 8045, "after": 8046 }),
+            ]
+        );
+    }
+}
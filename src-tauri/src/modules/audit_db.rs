@@ -0,0 +1,350 @@
+//! Audit Database Module
+//! 管理操作审计记录 (audit.db)，只追加：禁止修改，仅按保留期清理
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 单次导出的最大条数
+const MAX_EXPORT_ROWS: usize = 100_000;
+
+/// 审计记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditEntry {
+    pub id: i64,
+    /// 秒级时间戳
+    pub timestamp: i64,
+    /// 来源: web (管理接口) / desktop (桌面端命令与本机任务)
+    pub source: String,
+    /// 操作者用户名 (桌面端为 "desktop")
+    pub actor: String,
+    pub actor_role: Option<String>,
+    pub client_ip: Option<String>,
+    /// 动作 (如 account.delete、config.save)
+    pub action: String,
+    /// 操作对象 (账号邮箱、IP、令牌用户名等)
+    pub target: Option<String>,
+    /// 附加信息 (配置变更 diff 等)
+    pub details: Option<Value>,
+    pub success: bool,
+    /// Web 请求的 HTTP 状态码
+    pub status: Option<u16>,
+}
+
+/// 查询条件
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditFilter {
+    /// 操作者 (精确匹配)
+    pub actor: Option<String>,
+    /// 动作前缀 (如 account. 匹配全部账号操作)
+    pub action: Option<String>,
+    pub source: Option<String>,
+    /// 匹配目标、动作或 IP 的关键字
+    pub search: Option<String>,
+    /// 起始时间 (秒，含)
+    pub start_time: Option<i64>,
+    /// 结束时间 (秒，含)
+    pub end_time: Option<i64>,
+}
+
+impl AuditFilter {
+    /// 生成筛选条件与对应参数 (参数按 ?1, ?2... 编号)
+    fn conditions(&self) -> (String, Vec<rusqlite::types::Value>) {
+        use rusqlite::types::Value;
+        let mut clauses: Vec<String> = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+        let mut push = |clause: &str, value: Value| {
+            values.push(value);
+            clauses.push(clause.replace('?', &format!("?{}", values.len())));
+        };
+
+        if let Some(actor) = self.actor.as_deref().filter(|s| !s.is_empty()) {
+            push("actor = ?", Value::Text(actor.to_string()));
+        }
+        if let Some(action) = self.action.as_deref().filter(|s| !s.is_empty()) {
+            push("action LIKE ?", Value::Text(format!("{}%", action)));
+        }
+        if let Some(source) = self.source.as_deref().filter(|s| !s.is_empty()) {
+            push("source = ?", Value::Text(source.to_string()));
+        }
+        if let Some(search) = self.search.as_deref().filter(|s| !s.is_empty()) {
+            push(
+                "(target LIKE ? OR action LIKE ? OR client_ip LIKE ?)",
+                Value::Text(format!("%{}%", search)),
+            );
+        }
+        if let Some(start) = self.start_time {
+            push("timestamp >= ?", Value::Integer(start));
+        }
+        if let Some(end) = self.end_time {
+            push("timestamp <= ?", Value::Integer(end));
+        }
+
+        let where_sql = if clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", clauses.join(" AND "))
+        };
+        (where_sql, values)
+    }
+}
+
+/// 分页查询结果
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditPage {
+    pub total: i64,
+    pub entries: Vec<AuditEntry>,
+}
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditExportFormat {
+    #[default]
+    Jsonl,
+    Csv,
+}
+
+impl AuditExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Jsonl => "application/x-ndjson",
+            Self::Csv => "text/csv; charset=utf-8",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Jsonl => "jsonl",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+static SCHEMA_READY: AtomicBool = AtomicBool::new(false);
+
+pub fn get_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("audit.db"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let conn = Connection::open(get_db_path()?).map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "journal_mode", "WAL").map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000).map_err(|e| e.to_string())?;
+    if !SCHEMA_READY.load(Ordering::Acquire) {
+        create_schema(&conn)?;
+        SCHEMA_READY.store(true, Ordering::Release);
+    }
+    Ok(conn)
+}
+
+fn create_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            source TEXT NOT NULL,
+            actor TEXT NOT NULL,
+            actor_role TEXT,
+            client_ip TEXT,
+            action TEXT NOT NULL,
+            target TEXT,
+            details TEXT,
+            success INTEGER NOT NULL,
+            status INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_audit_log_timestamp ON audit_log (timestamp DESC);
+        CREATE INDEX IF NOT EXISTS idx_audit_log_action ON audit_log (action);
+        -- 只追加：拒绝修改已有记录
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, 'audit log is append-only');
+        END;",
+    )
+    .map_err(|e| e.to_string())
+}
+
+pub fn init_db() -> Result<(), String> {
+    connect_db().map(|_| ())
+}
+
+/// 追加一条记录 (id 由数据库生成)
+pub fn insert(entry: &AuditEntry) -> Result<i64, String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "INSERT INTO audit_log (timestamp, source, actor, actor_role, client_ip, action, target, details, success, status)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            entry.timestamp,
+            entry.source,
+            entry.actor,
+            entry.actor_role,
+            entry.client_ip,
+            entry.action,
+            entry.target,
+            entry.details.as_ref().map(|d| d.to_string()),
+            entry.success,
+            entry.status,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+fn row_to_entry(row: &rusqlite::Row) -> rusqlite::Result<AuditEntry> {
+    let details: Option<String> = row.get(8)?;
+    Ok(AuditEntry {
+        id: row.get(0)?,
+        timestamp: row.get(1)?,
+        source: row.get(2)?,
+        actor: row.get(3)?,
+        actor_role: row.get(4)?,
+        client_ip: row.get(5)?,
+        action: row.get(6)?,
+        target: row.get(7)?,
+        details: details.and_then(|d| serde_json::from_str(&d).ok()),
+        success: row.get(9)?,
+        status: row.get(10)?,
+    })
+}
+
+const SELECT_COLUMNS: &str =
+    "id, timestamp, source, actor, actor_role, client_ip, action, target, details, success, status";
+
+/// 分页查询 (按时间倒序)
+pub fn query(filter: &AuditFilter, limit: usize, offset: usize) -> Result<AuditPage, String> {
+    let conn = connect_db()?;
+    let (where_sql, values) = filter.conditions();
+
+    let total: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM audit_log {}", where_sql),
+            rusqlite::params_from_iter(values.iter()),
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM audit_log {} ORDER BY id DESC LIMIT {} OFFSET {}",
+            SELECT_COLUMNS, where_sql, limit, offset
+        ))
+        .map_err(|e| e.to_string())?;
+    let entries = stmt
+        .query_map(rusqlite::params_from_iter(values.iter()), row_to_entry)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(AuditPage { total, entries })
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 按筛选条件导出 (按时间正序)，返回 (内容, 条数)
+pub fn export(filter: &AuditFilter, format: AuditExportFormat) -> Result<(String, usize), String> {
+    let conn = connect_db()?;
+    let (where_sql, values) = filter.conditions();
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM audit_log {} ORDER BY id ASC LIMIT {}",
+            SELECT_COLUMNS, where_sql, MAX_EXPORT_ROWS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(values.iter()), row_to_entry)
+        .map_err(|e| e.to_string())?;
+
+    let mut out = String::new();
+    if format == AuditExportFormat::Csv {
+        out.push_str("id,time,source,actor,actor_role,client_ip,action,target,success,status,details\n");
+    }
+    let mut count = 0;
+    for row in rows {
+        let entry = row.map_err(|e| e.to_string())?;
+        match format {
+            AuditExportFormat::Jsonl => {
+                out.push_str(&serde_json::to_string(&entry).map_err(|e| e.to_string())?);
+            }
+            AuditExportFormat::Csv => {
+                let time = chrono::DateTime::from_timestamp(entry.timestamp, 0)
+                    .map(|t| t.to_rfc3339())
+                    .unwrap_or_default();
+                let fields = [
+                    entry.id.to_string(),
+                    time,
+                    entry.source,
+                    entry.actor,
+                    entry.actor_role.unwrap_or_default(),
+                    entry.client_ip.unwrap_or_default(),
+                    entry.action,
+                    entry.target.unwrap_or_default(),
+                    entry.success.to_string(),
+                    entry.status.map(|s| s.to_string()).unwrap_or_default(),
+                    entry.details.map(|d| d.to_string()).unwrap_or_default(),
+                ];
+                out.push_str(&fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","));
+            }
+        }
+        out.push('\n');
+        count += 1;
+    }
+    Ok((out, count))
+}
+
+/// 删除超过保留期的记录 (审计记录唯一的删除途径)
+pub fn prune(retention_days: u32) -> Result<usize, String> {
+    if retention_days == 0 {
+        return Ok(0);
+    }
+    let conn = connect_db()?;
+    let cutoff = chrono::Utc::now().timestamp() - retention_days as i64 * 24 * 3600;
+    conn.execute("DELETE FROM audit_log WHERE timestamp < ?1", [cutoff])
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_and_append_only() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_schema(&conn).unwrap();
+        for (actor, action) in [("alice", "account.delete"), ("bob", "config.save"), ("alice", "account.add")] {
+            conn.execute(
+                "INSERT INTO audit_log (timestamp, source, actor, action, success) VALUES (100, 'web', ?1, ?2, 1)",
+                params![actor, action],
+            )
+            .unwrap();
+        }
+
+        let filter = AuditFilter {
+            actor: Some("alice".to_string()),
+            action: Some("account.".to_string()),
+            ..Default::default()
+        };
+        let (where_sql, values) = filter.conditions();
+        assert_eq!(where_sql, "WHERE actor = ?1 AND action LIKE ?2");
+        let count: i64 = conn
+            .query_row(
+                &format!("SELECT COUNT(*) FROM audit_log {}", where_sql),
+                rusqlite::params_from_iter(values.iter()),
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 2);
+
+        // 已有记录不可修改
+        assert!(conn.execute("UPDATE audit_log SET actor = 'mallory'", []).is_err());
+    }
+}
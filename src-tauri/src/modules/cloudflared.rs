@@ -234,7 +234,7 @@ impl CloudflaredManager {
             return Err("Cloudflared not installed".to_string());
         }
 
-        let local_url = format!("http://localhost:{}", config.port);
+        let local_url = crate::proxy::tls::local_base_url(config.port);
         info!("[cloudflared] Starting tunnel to: {}", local_url);
 
         let mut cmd = Command::new(&self.bin_path);
@@ -251,6 +251,11 @@ impl CloudflaredManager {
                 cmd.arg("tunnel")
                     .arg("--url")
                     .arg(&local_url);
+
+                // 主端口仅 HTTPS 时源站为自签名证书
+                if local_url.starts_with("https://") {
+                    cmd.arg("--no-tls-verify");
+                }
                 
                 // 注意：--no-autoupdate 参数在较新版本的 cloudflared 中已不被支持，会导致进程立即退出
                 // cmd.arg("--no-autoupdate");
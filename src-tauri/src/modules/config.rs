@@ -93,7 +93,17 @@ pub fn save_app_config(config: &AppConfig) -> Result<(), String> {
     
     let content = serde_json::to_string_pretty(config)
         .map_err(|e| format!("failed_to_serialize_config: {}", e))?;
+
+    // [NEW] 保存前读取旧配置，用于审计日志中的变更对比
+    let previous: Option<serde_json::Value> = fs::read_to_string(&config_path)
+        .ok()
+        .and_then(|old| serde_json::from_str(&old).ok());
     
-    fs::write(&config_path, content)
-        .map_err(|e| format!("failed_to_save_config: {}", e))
+    fs::write(&config_path, &content)
+        .map_err(|e| format!("failed_to_save_config: {}", e))?;
+
+    if let (Some(before), Ok(after)) = (previous, serde_json::from_str(&content)) {
+        super::audit::record_config_change(&before, &after);
+    }
+    Ok(())
 }
@@ -164,8 +164,10 @@ where
     S: Subscriber,
 {
     fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
-        // [FIX] 如果调试控制台未启用，直接跳过所有处理，避免性能损耗
-        if !LOG_BRIDGE_ENABLED.load(Ordering::Relaxed) {
+        // [FIX] 如果调试控制台未启用且没有事件流订阅日志，直接跳过所有处理，避免性能损耗
+        let console_enabled = LOG_BRIDGE_ENABLED.load(Ordering::Relaxed);
+        let stream_enabled = crate::proxy::event_stream::wants_logs();
+        if !console_enabled && !stream_enabled {
             return;
         }
 
@@ -201,6 +203,16 @@ where
             fields: visitor.fields,
         };
 
+        // Push to admin event stream (headless / web)
+        if stream_enabled {
+            crate::proxy::event_stream::publish(crate::proxy::event_stream::AdminEvent::Log(
+                entry.clone(),
+            ));
+        }
+        if !console_enabled {
+            return;
+        }
+
         // Add to buffer
         {
             let mut buffer = get_log_buffer().write();
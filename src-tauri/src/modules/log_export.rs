@@ -0,0 +1,381 @@
+// 请求日志导出 (JSONL / CSV / HAR)
+//
+// 逐行读取 request_logs 并直接写入目标 Writer，导出大小不受内存限制。
+
+use std::io::Write;
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::modules::proxy_db::{self, LogFilter};
+use crate::proxy::monitor::ProxyRequestLog;
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogExportFormat {
+    #[default]
+    Jsonl,
+    Csv,
+    Har,
+}
+
+impl LogExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Jsonl => "application/x-ndjson",
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Har => "application/json",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Jsonl => "jsonl",
+            Self::Csv => "csv",
+            Self::Har => "har",
+        }
+    }
+}
+
+/// 导出选项
+#[derive(Debug, Clone)]
+pub struct LogExportOptions {
+    pub format: LogExportFormat,
+    /// 以占位符替换请求 / 响应体
+    pub redact_bodies: bool,
+    /// HAR 条目中拼接完整 URL 的前缀 (日志只保存路径)
+    pub base_url: String,
+}
+
+const CSV_HEADER: [&str; 27] = [
+    "id",
+    "time",
+    "method",
+    "url",
+    "status",
+    "duration_ms",
+    "model",
+    "mapped_model",
+    "account_email",
+    "client_ip",
+    "username",
+    "protocol",
+    "input_tokens",
+    "output_tokens",
+    "cached_tokens",
+    "thinking_tokens",
+    "cost",
+    "ttft_ms",
+    "token_wait_ms",
+    "queue_wait_ms",
+    "upstream_ms",
+    "attempts",
+    "upstream_endpoint",
+    "output_tokens_per_sec",
+    "error",
+    "request_body",
+    "response_body",
+];
+
+/// 按筛选条件导出日志，返回导出条数
+pub fn export_logs<W: Write>(
+    mut writer: W,
+    filter: &LogFilter,
+    options: &LogExportOptions,
+) -> Result<usize, String> {
+    let io_err = |e: std::io::Error| format!("Failed to write export: {}", e);
+
+    match options.format {
+        LogExportFormat::Csv => {
+            writeln!(writer, "{}", CSV_HEADER.join(",")).map_err(io_err)?;
+        }
+        LogExportFormat::Har => {
+            let creator = json!({
+                "name": "Antigravity Tools",
+                "version": env!("CARGO_PKG_VERSION"),
+            });
+            write!(
+                writer,
+                "{{\"log\":{{\"version\":\"1.2\",\"creator\":{},\"entries\":[",
+                creator
+            )
+            .map_err(io_err)?;
+        }
+        LogExportFormat::Jsonl => {}
+    }
+
+    let mut first = true;
+    let count = proxy_db::for_each_log_for_export(filter, |mut log| {
+        if options.redact_bodies {
+            log.request_body = redact(log.request_body.take());
+            log.response_body = redact(log.response_body.take());
+        }
+        match options.format {
+            LogExportFormat::Jsonl => {
+                serde_json::to_writer(&mut writer, &log).map_err(|e| e.to_string())?;
+                writer.write_all(b"\n").map_err(io_err)?;
+            }
+            LogExportFormat::Csv => {
+                writeln!(writer, "{}", csv_row(&log)).map_err(io_err)?;
+            }
+            LogExportFormat::Har => {
+                if !first {
+                    writer.write_all(b",").map_err(io_err)?;
+                }
+                serde_json::to_writer(&mut writer, &har_entry(&log, &options.base_url))
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        first = false;
+        Ok(())
+    })?;
+
+    if options.format == LogExportFormat::Har {
+        writer.write_all(b"]}}").map_err(io_err)?;
+    }
+    writer.flush().map_err(io_err)?;
+    Ok(count)
+}
+
+fn redact(body: Option<String>) -> Option<String> {
+    body.map(|b| format!("[REDACTED {} bytes]", b.len()))
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_row(log: &ProxyRequestLog) -> String {
+    fn opt<T: ToString>(v: Option<T>) -> String {
+        v.map(|v| v.to_string()).unwrap_or_default()
+    }
+    let latency = log.latency.as_ref();
+    let fields = [
+        log.id.clone(),
+        format_time(log.timestamp),
+        log.method.clone(),
+        log.url.clone(),
+        log.status.to_string(),
+        log.duration.to_string(),
+        opt(log.model.as_ref()),
+        opt(log.mapped_model.as_ref()),
+        opt(log.account_email.as_ref()),
+        opt(log.client_ip.as_ref()),
+        opt(log.username.as_ref()),
+        opt(log.protocol.as_ref()),
+        opt(log.input_tokens),
+        opt(log.output_tokens),
+        opt(log.cached_tokens),
+        opt(log.thinking_tokens),
+        opt(log.cost),
+        opt(latency.and_then(|l| l.ttft_ms)),
+        opt(latency.map(|l| l.token_wait_ms)),
+        opt(latency.map(|l| l.queue_wait_ms)),
+        opt(latency.map(|l| l.upstream_ms)),
+        opt(latency.map(|l| l.attempts)),
+        opt(latency.and_then(|l| l.upstream_endpoint.as_ref())),
+        opt(latency.and_then(|l| l.output_tokens_per_sec)),
+        opt(log.error.as_ref()),
+        opt(log.request_body.as_ref()),
+        opt(log.response_body.as_ref()),
+    ];
+    fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(",")
+}
+
+fn format_time(timestamp_ms: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(timestamp_ms)
+        .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+        .unwrap_or_default()
+}
+
+fn har_entry(log: &ProxyRequestLog, base_url: &str) -> Value {
+    let request_size = log.request_body.as_ref().map(|b| b.len() as i64).unwrap_or(0);
+    let response_size = log.response_body.as_ref().map(|b| b.len() as i64).unwrap_or(-1);
+    let mime_type = |body: &Option<String>| {
+        match body.as_deref().map(str::trim_start) {
+            Some(b) if b.starts_with('{') || b.starts_with('[') => "application/json",
+            _ => "text/plain",
+        }
+    };
+    // 等待时间取首 Token 时间，其余计为接收
+    let wait = log
+        .latency
+        .as_ref()
+        .and_then(|l| l.ttft_ms)
+        .unwrap_or(log.duration)
+        .min(log.duration);
+
+    let mut request = json!({
+        "method": log.method,
+        "url": format!("{}{}", base_url.trim_end_matches('/'), log.url),
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": [],
+        "queryString": [],
+        "headersSize": -1,
+        "bodySize": request_size,
+    });
+    if let Some(body) = &log.request_body {
+        request["postData"] = json!({
+            "mimeType": mime_type(&log.request_body),
+            "text": body,
+        });
+    }
+
+    json!({
+        "startedDateTime": format_time(log.timestamp),
+        "time": log.duration,
+        "request": request,
+        "response": {
+            "status": log.status,
+            "statusText": "",
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": [],
+            "content": {
+                "size": response_size.max(0),
+                "mimeType": mime_type(&log.response_body),
+                "text": log.response_body.clone().unwrap_or_default(),
+            },
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": response_size,
+        },
+        "cache": {},
+        "timings": {
+            "send": 0,
+            "wait": wait,
+            "receive": log.duration - wait,
+        },
+        "_id": log.id,
+        "_model": log.model,
+        "_mappedModel": log.mapped_model,
+        "_account": log.account_email,
+        "_clientIp": log.client_ip,
+        "_username": log.username,
+        "_protocol": log.protocol,
+        "_inputTokens": log.input_tokens,
+        "_outputTokens": log.output_tokens,
+        "_cost": log.cost,
+        "_latency": log.latency,
+        "_error": log.error,
+    })
+}
+
+/// 将导出内容分块发送到 channel 的 Writer，供 HTTP 流式响应使用
+pub struct ChannelWriter {
+    tx: tokio::sync::mpsc::Sender<Result<Bytes, std::io::Error>>,
+    buf: Vec<u8>,
+}
+
+const CHANNEL_CHUNK_SIZE: usize = 64 * 1024;
+
+impl ChannelWriter {
+    pub fn new(tx: tokio::sync::mpsc::Sender<Result<Bytes, std::io::Error>>) -> Self {
+        Self {
+            tx,
+            buf: Vec::with_capacity(CHANNEL_CHUNK_SIZE),
+        }
+    }
+
+    fn send_buffer(&mut self) -> std::io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let chunk = Bytes::from(std::mem::replace(
+            &mut self.buf,
+            Vec::with_capacity(CHANNEL_CHUNK_SIZE),
+        ));
+        // 客户端断开时终止导出
+        self.tx
+            .blocking_send(Ok(chunk))
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "client disconnected"))
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= CHANNEL_CHUNK_SIZE {
+            self.send_buffer()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.send_buffer()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::request_timing::LatencyBreakdown;
+
+    fn sample_log() -> ProxyRequestLog {
+        ProxyRequestLog {
+            id: "log-1".to_string(),
+            timestamp: 1_700_000_000_000,
+            method: "POST".to_string(),
+            url: "/v1/messages".to_string(),
+            status: 200,
+            duration: 1500,
+            model: Some("claude-sonnet-4-5".to_string()),
+            mapped_model: Some("claude-sonnet-4-6".to_string()),
+            account_email: Some("a@example.com".to_string()),
+            client_ip: Some("127.0.0.1".to_string()),
+            error: None,
+            request_body: Some("{\"messages\":[]}".to_string()),
+            response_body: Some("line1\nline \"2\", done".to_string()),
+            input_tokens: Some(10),
+            output_tokens: Some(20),
+            protocol: Some("anthropic".to_string()),
+            username: None,
+            latency: Some(LatencyBreakdown {
+                ttft_ms: Some(400),
+                attempts: 1,
+                ..Default::default()
+            }),
+            replay_of: None,
+            cached_tokens: None,
+            thinking_tokens: None,
+            cost: None,
+            provider: None,
+        }
+    }
+
+    #[test]
+    fn test_csv_row_escapes_fields() {
+        let row = csv_row(&sample_log());
+        assert!(row.starts_with("log-1,2023-11-14T22:13:20.000Z,POST,/v1/messages,200,1500,"));
+        assert!(row.contains("\"{\"\"messages\"\":[]}\""));
+        assert!(row.ends_with("\"line1\nline \"\"2\"\", done\""));
+        assert_eq!(csv_field("plain"), "plain");
+    }
+
+    #[test]
+    fn test_har_entry_shape() {
+        let entry = har_entry(&sample_log(), "http://127.0.0.1:8045/");
+        assert_eq!(entry["request"]["url"], "http://127.0.0.1:8045/v1/messages");
+        assert_eq!(entry["request"]["postData"]["mimeType"], "application/json");
+        assert_eq!(entry["response"]["content"]["mimeType"], "text/plain");
+        assert_eq!(entry["timings"]["wait"], 400);
+        assert_eq!(entry["timings"]["receive"], 1100);
+        assert_eq!(entry["_account"], "a@example.com");
+    }
+
+    #[test]
+    fn test_redact_keeps_size_only() {
+        assert_eq!(
+            redact(Some("secret".to_string())).as_deref(),
+            Some("[REDACTED 6 bytes]")
+        );
+        assert_eq!(redact(None), None);
+    }
+}
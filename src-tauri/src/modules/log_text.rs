@@ -0,0 +1,176 @@
+// 从请求 / 响应体中提取消息文本
+//
+// 用于全文索引与重放结果对比，兼容 OpenAI / Claude / Gemini 协议及流式汇总格式。
+
+use serde_json::Value;
+
+/// 提取请求中的消息文本 (system 指令、消息内容、prompt)
+/// 无法识别时返回原始内容
+pub fn extract_request_text(body: &str) -> String {
+    let Ok(json) = serde_json::from_str::<Value>(body) else {
+        return body.to_string();
+    };
+    let json = json.get("request").filter(|r| r.is_object()).unwrap_or(&json);
+    let mut parts: Vec<String> = Vec::new();
+
+    // Claude system / Gemini systemInstruction
+    if let Some(system) = json.get("system") {
+        collect_text(system, &mut parts);
+    }
+    if let Some(instruction) = json.get("systemInstruction").and_then(|s| s.get("parts")) {
+        collect_text(instruction, &mut parts);
+    }
+    // OpenAI / Claude messages
+    for message in json.get("messages").and_then(|m| m.as_array()).into_iter().flatten() {
+        if let Some(content) = message.get("content") {
+            collect_text(content, &mut parts);
+        }
+    }
+    // Gemini contents
+    for content in json.get("contents").and_then(|c| c.as_array()).into_iter().flatten() {
+        if let Some(content_parts) = content.get("parts") {
+            collect_text(content_parts, &mut parts);
+        }
+    }
+    // Completions / Images / Responses API
+    for key in ["prompt", "input"] {
+        if let Some(value) = json.get(key) {
+            collect_text(value, &mut parts);
+        }
+    }
+
+    if parts.is_empty() {
+        body.to_string()
+    } else {
+        parts.join("\n")
+    }
+}
+
+/// 递归收集内容中的文本：字符串、text 字段、嵌套的 content / parts (如 tool_result)
+fn collect_text(value: &Value, parts: &mut Vec<String>) {
+    match value {
+        Value::String(text) => parts.push(text.clone()),
+        Value::Array(items) => {
+            for item in items {
+                collect_text(item, parts);
+            }
+        }
+        Value::Object(obj) => {
+            if let Some(text) = obj.get("text").and_then(|t| t.as_str()) {
+                parts.push(text.to_string());
+            }
+            for key in ["content", "parts"] {
+                if let Some(nested) = obj.get(key) {
+                    collect_text(nested, parts);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 提取响应中的模型输出文本 (不含思考内容，工具调用以 JSON 形式保留)
+/// 无法识别时返回原始内容
+pub fn extract_response_text(body: &str) -> String {
+    let Ok(json) = serde_json::from_str::<Value>(body) else {
+        return body.to_string();
+    };
+    let json = json.get("response").filter(|r| r.is_object()).unwrap_or(&json);
+    let mut parts: Vec<String> = Vec::new();
+    let push_tool = |parts: &mut Vec<String>, v: &Value| {
+        parts.push(serde_json::to_string(v).unwrap_or_default());
+    };
+
+    match json.get("content") {
+        // 流式汇总格式
+        Some(Value::String(text)) => parts.push(text.clone()),
+        // Claude content blocks
+        Some(Value::Array(blocks)) => {
+            for block in blocks {
+                match block.get("type").and_then(|t| t.as_str()) {
+                    Some("text") => {
+                        if let Some(text) = block.get("text").and_then(|t| t.as_str()) {
+                            parts.push(text.to_string());
+                        }
+                    }
+                    Some("tool_use") => push_tool(&mut parts, block),
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+    // OpenAI choices
+    for choice in json.get("choices").and_then(|c| c.as_array()).into_iter().flatten() {
+        let message = choice.get("message").unwrap_or(choice);
+        if let Some(text) = message.get("content").and_then(|t| t.as_str()) {
+            parts.push(text.to_string());
+        }
+        for call in message.get("tool_calls").and_then(|t| t.as_array()).into_iter().flatten() {
+            push_tool(&mut parts, call);
+        }
+    }
+    // Gemini candidates
+    for candidate in json.get("candidates").and_then(|c| c.as_array()).into_iter().flatten() {
+        let candidate_parts = candidate
+            .get("content")
+            .and_then(|c| c.get("parts"))
+            .and_then(|p| p.as_array());
+        for part in candidate_parts.into_iter().flatten() {
+            if part.get("thought").and_then(|t| t.as_bool()) == Some(true) {
+                continue;
+            }
+            if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                parts.push(text.to_string());
+            } else if let Some(call) = part.get("functionCall") {
+                push_tool(&mut parts, call);
+            }
+        }
+    }
+    // 流式汇总格式中的工具调用
+    for call in json.get("tool_calls").and_then(|t| t.as_array()).into_iter().flatten() {
+        push_tool(&mut parts, call);
+    }
+
+    if parts.is_empty() {
+        body.to_string()
+    } else {
+        parts.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_request_text_formats() {
+        let claude = r#"{"system":[{"type":"text","text":"Be brief"}],"messages":[{"role":"user","content":[{"type":"text","text":"Hello"},{"type":"tool_result","tool_use_id":"t1","content":[{"type":"text","text":"42"}]}]}]}"#;
+        assert_eq!(extract_request_text(claude), "Be brief\nHello\n42");
+
+        let openai = r#"{"model":"gpt-4o","messages":[{"role":"user","content":"Hi there"}]}"#;
+        assert_eq!(extract_request_text(openai), "Hi there");
+
+        let gemini = r#"{"systemInstruction":{"parts":[{"text":"sys"}]},"contents":[{"role":"user","parts":[{"text":"Yo"},{"inlineData":{"data":"AAAA"}}]}]}"#;
+        assert_eq!(extract_request_text(gemini), "sys\nYo");
+
+        assert_eq!(extract_request_text("[Binary Request Data]"), "[Binary Request Data]");
+    }
+
+    #[test]
+    fn test_extract_response_text_formats() {
+        let claude = r#"{"content":[{"type":"thinking","thinking":"hmm"},{"type":"text","text":"Hello"}]}"#;
+        assert_eq!(extract_response_text(claude), "Hello");
+
+        let openai = r#"{"choices":[{"message":{"role":"assistant","content":"Hi"}}]}"#;
+        assert_eq!(extract_response_text(openai), "Hi");
+
+        let gemini = r#"{"response":{"candidates":[{"content":{"parts":[{"text":"x","thought":true},{"text":"Yo"}]}}]}}"#;
+        assert_eq!(extract_response_text(gemini), "Yo");
+
+        let consolidated = r#"{"thinking":"t","content":"Streamed"}"#;
+        assert_eq!(extract_response_text(consolidated), "Streamed");
+
+        assert_eq!(extract_response_text("plain error"), "plain error");
+    }
+}
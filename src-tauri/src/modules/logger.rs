@@ -65,8 +65,12 @@ pub fn init_logger() {
     // 6. Log bridge layer
     let bridge_layer = crate::modules::log_bridge::TauriLogBridgeLayer::new();
 
+    // 7. OpenTelemetry layer (exporter is attached later from proxy config)
+    let telemetry_layer = crate::proxy::telemetry::layer();
+
     // 5. Initialize global subscriber (use try_init to avoid crash on repeated initialization)
     let _ = tracing_subscriber::registry()
+        .with(telemetry_layer)
         .with(filter_layer)
         .with(console_layer)
         .with(file_layer)
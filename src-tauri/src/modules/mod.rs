@@ -1,4 +1,5 @@
 pub mod account;
+pub mod account_crypto;
 pub mod quota;
 pub mod config;
 pub mod logger;
@@ -10,6 +11,8 @@ pub mod migration;
 pub mod tray;
 pub mod i18n;
 pub mod proxy_db;
+pub mod log_export;
+pub mod log_text;
 pub mod device;
 pub mod update_checker;
 pub mod scheduler;
@@ -23,6 +26,12 @@ pub mod cache;
 pub mod log_bridge;
 pub mod security_db;
 pub mod user_token_db;
+pub mod admin_user_db;
+pub mod audit;
+pub mod audit_db;
+pub mod response_cache_db;
+pub mod alert_db;
+pub mod secret_migration;
 pub mod version;
 
 use crate::models;
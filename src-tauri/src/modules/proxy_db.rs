@@ -1,6 +1,18 @@
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use std::path::PathBuf;
-use crate::proxy::monitor::ProxyRequestLog;
+use crate::proxy::monitor::{LatencyStats, Percentiles, ProxyRequestLog};
+use crate::proxy::request_timing::LatencyBreakdown;
+
+/// 统计 API 中延迟分位数的时间窗口
+const LATENCY_STATS_WINDOW_HOURS: i64 = 24;
+/// 全文索引补建时每批处理的日志数
+const SEARCH_BACKFILL_BATCH: i64 = 500;
+/// 搜索结果摘要中的高亮标记
+const SNIPPET_OPEN: &str = "<mark>";
+const SNIPPET_CLOSE: &str = "</mark>";
+/// snippet() 使用的占位标记 (私有区字符)，转义正文后再替换为高亮标记
+const SNIPPET_OPEN_SENTINEL: char = '\u{E000}';
+const SNIPPET_CLOSE_SENTINEL: char = '\u{E001}';
 
 pub fn get_proxy_db_path() -> Result<PathBuf, String> {
     let data_dir = crate::modules::account::get_data_dir()?;
@@ -26,7 +38,19 @@ fn connect_db() -> Result<Connection, String> {
 pub fn init_db() -> Result<(), String> {
     // connect_db will initialize WAL mode and other pragmas
     let conn = connect_db()?;
-    
+    create_schema(&conn)?;
+
+    // [NEW] 为升级前的历史日志补建全文索引 (后台执行，不阻塞启动)
+    std::thread::spawn(|| match backfill_search_index() {
+        Ok(0) => {}
+        Ok(indexed) => tracing::info!("[ProxyDB] Indexed {} existing logs for full-text search", indexed),
+        Err(e) => tracing::warn!("[ProxyDB] Failed to backfill search index: {}", e),
+    });
+
+    Ok(())
+}
+
+fn create_schema(conn: &Connection) -> Result<(), String> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS request_logs (
             id TEXT PRIMARY KEY,
@@ -51,6 +75,21 @@ pub fn init_db() -> Result<(), String> {
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN protocol TEXT", []);
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN client_ip TEXT", []);
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN username TEXT", []);
+    // [NEW] 延迟分解
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN queue_wait_ms INTEGER", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN token_wait_ms INTEGER", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN upstream_ms INTEGER", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN ttft_ms INTEGER", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN attempts INTEGER", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN accounts_tried TEXT", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN upstream_endpoint TEXT", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN output_tps REAL", []);
+    // [NEW] 请求重放：关联原始日志
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN replay_of TEXT", []);
+    // [NEW] 费用核算
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN cached_tokens INTEGER", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN thinking_tokens INTEGER", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN cost REAL", []);
 
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_timestamp ON request_logs (timestamp DESC)",
@@ -63,15 +102,49 @@ pub fn init_db() -> Result<(), String> {
         [],
     ).map_err(|e| e.to_string())?;
 
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_replay_of ON request_logs (replay_of)",
+        [],
+    ).map_err(|e| e.to_string())?;
+
+    // [NEW] 全文索引：保存从请求 / 响应体中提取的消息文本，而非原始 JSON
+    let fts_exists = conn.query_row(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'request_logs_fts'",
+        [],
+        |_| Ok(()),
+    ).optional().map_err(|e| e.to_string())?.is_some();
+    if !fts_exists {
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE request_logs_fts USING fts5(
+                log_id UNINDEXED,
+                request_text,
+                response_text,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+            CREATE TABLE IF NOT EXISTS search_index_state (key TEXT PRIMARY KEY, value INTEGER);"
+        ).map_err(|e| e.to_string())?;
+        // 此前写入的日志由 backfill_search_index 补建索引
+        conn.execute(
+            "INSERT OR REPLACE INTO search_index_state (key, value) VALUES ('backfill_before', ?1)",
+            [chrono::Utc::now().timestamp_millis()],
+        ).map_err(|e| e.to_string())?;
+    }
+
     Ok(())
 }
 
 pub fn save_log(log: &ProxyRequestLog) -> Result<(), String> {
     let conn = connect_db()?;
+    insert_log(&conn, log)
+}
+
+fn insert_log(conn: &Connection, log: &ProxyRequestLog) -> Result<(), String> {
+    let latency = log.latency.as_ref();
 
     conn.execute(
-        "INSERT INTO request_logs (id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        "INSERT INTO request_logs (id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username,
+                                   queue_wait_ms, token_wait_ms, upstream_ms, ttft_ms, attempts, accounts_tried, upstream_endpoint, output_tps, replay_of, cached_tokens, thinking_tokens, cost)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29)",
         params![
             log.id,
             log.timestamp,
@@ -90,12 +163,170 @@ pub fn save_log(log: &ProxyRequestLog) -> Result<(), String> {
             log.protocol,
             log.client_ip,
             log.username,
+            latency.map(|l| l.queue_wait_ms),
+            latency.map(|l| l.token_wait_ms),
+            latency.map(|l| l.upstream_ms),
+            latency.and_then(|l| l.ttft_ms),
+            latency.map(|l| l.attempts),
+            latency.and_then(|l| serde_json::to_string(&l.accounts_tried).ok()),
+            latency.and_then(|l| l.upstream_endpoint.clone()),
+            latency.and_then(|l| l.output_tokens_per_sec),
+            log.replay_of,
+            log.cached_tokens,
+            log.thinking_tokens,
+            log.cost,
         ],
     ).map_err(|e| e.to_string())?;
 
+    if let Err(e) = index_log_text(conn, &log.id, log.request_body.as_deref(), log.response_body.as_deref()) {
+        tracing::debug!("Failed to index proxy log text: {}", e);
+    }
+
     Ok(())
 }
 
+/// 写入单条日志的全文索引
+fn index_log_text(
+    conn: &Connection,
+    log_id: &str,
+    request_body: Option<&str>,
+    response_body: Option<&str>,
+) -> rusqlite::Result<()> {
+    use crate::modules::log_text::{extract_request_text, extract_response_text};
+    let request_text = request_body.map(extract_request_text).unwrap_or_default();
+    let response_text = response_body.map(extract_response_text).unwrap_or_default();
+    if request_text.is_empty() && response_text.is_empty() {
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT INTO request_logs_fts (log_id, request_text, response_text) VALUES (?1, ?2, ?3)",
+        params![log_id, request_text, response_text],
+    )?;
+    Ok(())
+}
+
+/// 按时间倒序分批为历史日志补建全文索引，进度记录在 search_index_state 中，中断后可继续
+fn backfill_search_index() -> Result<usize, String> {
+    let mut conn = connect_db()?;
+    let mut indexed = 0;
+
+    loop {
+        let cursor: Option<i64> = conn.query_row(
+            "SELECT value FROM search_index_state WHERE key = 'backfill_before'",
+            [],
+            |row| row.get(0),
+        ).optional().map_err(|e| e.to_string())?;
+        let Some(cursor) = cursor else {
+            return Ok(indexed);
+        };
+
+        // 本批次的下界；与下界时间戳相同的记录一并处理，避免跨批次遗漏
+        let floor: Option<i64> = conn.query_row(
+            "SELECT timestamp FROM request_logs WHERE timestamp < ?1 ORDER BY timestamp DESC LIMIT 1 OFFSET ?2",
+            params![cursor, SEARCH_BACKFILL_BATCH - 1],
+            |row| row.get(0),
+        ).optional().map_err(|e| e.to_string())?;
+
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        {
+            let mut stmt = tx.prepare(
+                "SELECT id, request_body, response_body FROM request_logs WHERE timestamp < ?1 AND timestamp >= ?2"
+            ).map_err(|e| e.to_string())?;
+            let mut rows = stmt
+                .query(params![cursor, floor.unwrap_or(i64::MIN)])
+                .map_err(|e| e.to_string())?;
+            while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+                let id: String = row.get(0).map_err(|e| e.to_string())?;
+                let request_body: Option<String> = row.get(1).unwrap_or(None);
+                let response_body: Option<String> = row.get(2).unwrap_or(None);
+                index_log_text(&tx, &id, request_body.as_deref(), response_body.as_deref())
+                    .map_err(|e| e.to_string())?;
+                indexed += 1;
+            }
+        }
+        match floor {
+            Some(floor) => tx.execute(
+                "UPDATE search_index_state SET value = ?1 WHERE key = 'backfill_before'",
+                [floor],
+            ),
+            None => tx.execute("DELETE FROM search_index_state WHERE key = 'backfill_before'", []),
+        }.map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+}
+
+/// 从查询结果中读取延迟分解 (start 为 queue_wait_ms 列下标，其后依次为
+/// token_wait_ms, upstream_ms, ttft_ms, attempts, accounts_tried, upstream_endpoint, output_tps)
+/// 旧记录或未发生上游调度的请求返回 None
+fn latency_from_row(row: &rusqlite::Row, start: usize) -> Option<LatencyBreakdown> {
+    let attempts: u32 = row.get::<_, Option<u32>>(start + 4).ok().flatten()?;
+    let ms = |idx: usize| row.get::<_, Option<u64>>(start + idx).ok().flatten();
+    Some(LatencyBreakdown {
+        queue_wait_ms: ms(0).unwrap_or(0),
+        token_wait_ms: ms(1).unwrap_or(0),
+        upstream_ms: ms(2).unwrap_or(0),
+        ttft_ms: ms(3),
+        attempts,
+        accounts_tried: row
+            .get::<_, Option<String>>(start + 5)
+            .ok()
+            .flatten()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        upstream_endpoint: row.get(start + 6).unwrap_or(None),
+        output_tokens_per_sec: row.get(start + 7).unwrap_or(None),
+    })
+}
+
+/// 计算指定列在时间窗口内的 p50 / p90 / p99
+fn column_percentiles(conn: &Connection, column: &str, since: i64) -> Result<Percentiles, String> {
+    let count: i64 = conn.query_row(
+        &format!("SELECT COUNT({col}) FROM request_logs WHERE timestamp >= ?1 AND {col} IS NOT NULL", col = column),
+        [since],
+        |row| row.get(0),
+    ).map_err(|e| e.to_string())?;
+    if count == 0 {
+        return Ok(Percentiles::default());
+    }
+
+    let sql = format!(
+        "SELECT CAST({col} AS REAL) FROM request_logs WHERE timestamp >= ?1 AND {col} IS NOT NULL ORDER BY {col} LIMIT 1 OFFSET ?2",
+        col = column
+    );
+    let at = |p: f64| -> Result<Option<f64>, String> {
+        let offset = ((count - 1) as f64 * p).round() as i64;
+        conn.query_row(&sql, params![since, offset], |row| row.get(0))
+            .map(Some)
+            .map_err(|e| e.to_string())
+    };
+    Ok(Percentiles {
+        p50: at(0.5)?,
+        p90: at(0.9)?,
+        p99: at(0.99)?,
+    })
+}
+
+/// 最近窗口内的延迟分位数 (timestamp 以毫秒存储)
+fn get_latency_stats(conn: &Connection, window_hours: i64) -> Result<LatencyStats, String> {
+    let since = chrono::Utc::now().timestamp_millis() - window_hours * 3600 * 1000;
+    let avg_attempts: Option<f64> = conn.query_row(
+        "SELECT AVG(attempts) FROM request_logs WHERE timestamp >= ?1 AND attempts IS NOT NULL",
+        [since],
+        |row| row.get(0),
+    ).map_err(|e| e.to_string())?;
+
+    Ok(LatencyStats {
+        window_hours,
+        duration_ms: column_percentiles(conn, "duration", since)?,
+        ttft_ms: column_percentiles(conn, "ttft_ms", since)?,
+        token_wait_ms: column_percentiles(conn, "token_wait_ms", since)?,
+        queue_wait_ms: column_percentiles(conn, "queue_wait_ms", since)?,
+        upstream_ms: column_percentiles(conn, "upstream_ms", since)?,
+        output_tokens_per_sec: column_percentiles(conn, "output_tps", since)?,
+        avg_attempts: avg_attempts.map(|v| (v * 100.0).round() / 100.0),
+    })
+}
+
 /// Get logs summary (without large request_body and response_body fields) with pagination
 pub fn get_logs_summary(limit: usize, offset: usize) -> Result<Vec<ProxyRequestLog>, String> {
     let conn = connect_db()?;
@@ -128,6 +359,12 @@ pub fn get_logs_summary(limit: usize, offset: usize) -> Result<Vec<ProxyRequestL
             protocol: row.get(14).unwrap_or(None),
             client_ip: row.get(15).unwrap_or(None),
             username: row.get(16).unwrap_or(None),
+            latency: None,
+            replay_of: None,
+            cached_tokens: None,
+            thinking_tokens: None,
+            cost: None,
+            provider: None,
         })
 
     }).map_err(|e| e.to_string())?;
@@ -159,10 +396,13 @@ pub fn get_stats() -> Result<crate::proxy::monitor::ProxyStats, String> {
         |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
     ).map_err(|e| e.to_string())?;
 
+    let latency = get_latency_stats(&conn, LATENCY_STATS_WINDOW_HOURS)?;
+
     Ok(crate::proxy::monitor::ProxyStats {
         total_requests,
         success_count,
         error_count,
+        latency,
     })
 }
 
@@ -173,32 +413,38 @@ pub fn get_log_detail(log_id: &str) -> Result<ProxyRequestLog, String> {
     let mut stmt = conn.prepare(
         "SELECT id, timestamp, method, url, status, duration, model, error,
                 request_body, response_body, input_tokens, output_tokens,
-                account_email, mapped_model, protocol, client_ip, username
+                account_email, mapped_model, protocol, client_ip, username,
+                queue_wait_ms, token_wait_ms, upstream_ms, ttft_ms, attempts, accounts_tried, upstream_endpoint, output_tps, replay_of,
+                cached_tokens, thinking_tokens, cost
          FROM request_logs
          WHERE id = ?1"
     ).map_err(|e| e.to_string())?;
 
-    stmt.query_row([log_id], |row| {
-        Ok(ProxyRequestLog {
-            id: row.get(0)?,
-            timestamp: row.get(1)?,
-            method: row.get(2)?,
-            url: row.get(3)?,
-            status: row.get(4)?,
-            duration: row.get(5)?,
-            model: row.get(6)?,
-            mapped_model: row.get(13).unwrap_or(None),
-            account_email: row.get(12).unwrap_or(None),
-            error: row.get(7)?,
-            request_body: row.get(8).unwrap_or(None),
-            response_body: row.get(9).unwrap_or(None),
-            input_tokens: row.get(10).unwrap_or(None),
-            output_tokens: row.get(11).unwrap_or(None),
-            protocol: row.get(14).unwrap_or(None),
-            client_ip: row.get(15).unwrap_or(None),
-            username: row.get(16).unwrap_or(None),
-        })
-    }).map_err(|e| e.to_string())
+    stmt.query_row([log_id], full_log_from_row).map_err(|e| e.to_string())
+}
+
+/// 获取指定日志的全部重放记录 (按时间顺序，含完整请求 / 响应体)
+pub fn get_log_replays(log_id: &str) -> Result<Vec<ProxyRequestLog>, String> {
+    let conn = connect_db()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, timestamp, method, url, status, duration, model, error,
+                request_body, response_body, input_tokens, output_tokens,
+                account_email, mapped_model, protocol, client_ip, username,
+                queue_wait_ms, token_wait_ms, upstream_ms, ttft_ms, attempts, accounts_tried, upstream_endpoint, output_tps, replay_of,
+                cached_tokens, thinking_tokens, cost
+         FROM request_logs
+         WHERE replay_of = ?1
+         ORDER BY timestamp ASC"
+    ).map_err(|e| e.to_string())?;
+
+    let logs_iter = stmt.query_map([log_id], full_log_from_row).map_err(|e| e.to_string())?;
+
+    let mut logs = Vec::new();
+    for log in logs_iter {
+        logs.push(log.map_err(|e| e.to_string())?);
+    }
+    Ok(logs)
 }
 
 /// Cleanup old logs (keep last N days)
@@ -207,6 +453,10 @@ pub fn cleanup_old_logs(days: i64) -> Result<usize, String> {
     
     let cutoff_timestamp = chrono::Utc::now().timestamp() - (days * 24 * 3600);
     
+    conn.execute(
+        "DELETE FROM request_logs_fts WHERE log_id IN (SELECT id FROM request_logs WHERE timestamp < ?1)",
+        [cutoff_timestamp],
+    ).map_err(|e| e.to_string())?;
     let deleted = conn.execute(
         "DELETE FROM request_logs WHERE timestamp < ?1",
         [cutoff_timestamp],
@@ -223,6 +473,12 @@ pub fn cleanup_old_logs(days: i64) -> Result<usize, String> {
 pub fn limit_max_logs(max_count: usize) -> Result<usize, String> {
     let conn = connect_db()?;
     
+    conn.execute(
+        "DELETE FROM request_logs_fts WHERE log_id NOT IN (
+            SELECT id FROM request_logs ORDER BY timestamp DESC LIMIT ?1
+        )",
+        [max_count],
+    ).map_err(|e| e.to_string())?;
     let deleted = conn.execute(
         "DELETE FROM request_logs WHERE id NOT IN (
             SELECT id FROM request_logs ORDER BY timestamp DESC LIMIT ?1
@@ -237,7 +493,9 @@ pub fn limit_max_logs(max_count: usize) -> Result<usize, String> {
 
 pub fn clear_logs() -> Result<(), String> {
     let conn = connect_db()?;
+    conn.execute("DELETE FROM request_logs_fts", []).map_err(|e| e.to_string())?;
     conn.execute("DELETE FROM request_logs", []).map_err(|e| e.to_string())?;
+    let _ = conn.execute("DELETE FROM search_index_state", []);
     Ok(())
 }
 
@@ -336,6 +594,12 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
                 protocol: row.get(14).unwrap_or(None),
                 client_ip: row.get(15).unwrap_or(None),
                 username: row.get(16).unwrap_or(None),
+                latency: None,
+                replay_of: None,
+                cached_tokens: None,
+                thinking_tokens: None,
+                cost: None,
+                provider: None,
             })
 
         }).map_err(|e| e.to_string())?;
@@ -361,6 +625,12 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
                 protocol: row.get(14).unwrap_or(None),
                 client_ip: row.get(15).unwrap_or(None),
                 username: row.get(16).unwrap_or(None),
+                latency: None,
+                replay_of: None,
+                cached_tokens: None,
+                thinking_tokens: None,
+                cost: None,
+                provider: None,
             })
 
         }).map_err(|e| e.to_string())?;
@@ -386,6 +656,12 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
                 protocol: row.get(14).unwrap_or(None),
                 client_ip: row.get(15).unwrap_or(None),
                 username: row.get(16).unwrap_or(None),
+                latency: None,
+                replay_of: None,
+                cached_tokens: None,
+                thinking_tokens: None,
+                cost: None,
+                provider: None,
             })
 
         }).map_err(|e| e.to_string())?;
@@ -395,46 +671,271 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
     Ok(logs)
 }
 
-/// Get all logs with full details for export
-pub fn get_all_logs_for_export() -> Result<Vec<ProxyRequestLog>, String> {
+/// 日志筛选条件 (导出 / 全文搜索共用)
+/// filter / errors_only 与 get_logs_filtered 含义一致，其余条件为空时不生效
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LogFilter {
+    pub filter: String,
+    pub errors_only: bool,
+    /// 起始时间 (毫秒时间戳，含)
+    pub start_time: Option<i64>,
+    /// 结束时间 (毫秒时间戳，含)
+    pub end_time: Option<i64>,
+    /// 匹配请求模型或映射后模型
+    pub model: Option<String>,
+    pub account: Option<String>,
+    /// User Token 用户名
+    pub user: Option<String>,
+    pub status: Option<u16>,
+}
+
+impl LogFilter {
+    /// 生成筛选条件与对应参数 (参数按 ?1, ?2... 编号)
+    fn conditions(&self) -> (Vec<String>, Vec<rusqlite::types::Value>) {
+        use rusqlite::types::Value;
+        let mut clauses: Vec<String> = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+        let mut push = |clause: &str, value: Value| {
+            values.push(value);
+            clauses.push(clause.replace('?', &format!("?{}", values.len())));
+        };
+
+        if !self.filter.is_empty() {
+            push(
+                "(url LIKE ? OR method LIKE ? OR model LIKE ? OR CAST(status AS TEXT) LIKE ? OR account_email LIKE ? OR client_ip LIKE ?)",
+                Value::Text(format!("%{}%", self.filter)),
+            );
+        }
+        if let Some(start) = self.start_time {
+            push("timestamp >= ?", Value::Integer(start));
+        }
+        if let Some(end) = self.end_time {
+            push("timestamp <= ?", Value::Integer(end));
+        }
+        if let Some(model) = self.model.as_deref().filter(|m| !m.is_empty()) {
+            push("(model = ? OR mapped_model = ?)", Value::Text(model.to_string()));
+        }
+        if let Some(account) = self.account.as_deref().filter(|a| !a.is_empty()) {
+            push("account_email = ?", Value::Text(account.to_string()));
+        }
+        if let Some(user) = self.user.as_deref().filter(|u| !u.is_empty()) {
+            push("username = ?", Value::Text(user.to_string()));
+        }
+        if let Some(status) = self.status {
+            push("status = ?", Value::Integer(status as i64));
+        }
+        if self.errors_only {
+            clauses.push("(status < 200 OR status >= 400)".to_string());
+        }
+
+        (clauses, values)
+    }
+}
+
+fn where_sql(clauses: &[String]) -> String {
+    if clauses.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", clauses.join(" AND "))
+    }
+}
+
+fn full_log_from_row(row: &rusqlite::Row) -> rusqlite::Result<ProxyRequestLog> {
+    Ok(ProxyRequestLog {
+        id: row.get(0)?,
+        timestamp: row.get(1)?,
+        method: row.get(2)?,
+        url: row.get(3)?,
+        status: row.get(4)?,
+        duration: row.get(5)?,
+        model: row.get(6)?,
+        mapped_model: row.get(13).unwrap_or(None),
+        account_email: row.get(12).unwrap_or(None),
+        error: row.get(7)?,
+        request_body: row.get(8).unwrap_or(None),
+        response_body: row.get(9).unwrap_or(None),
+        input_tokens: row.get(10).unwrap_or(None),
+        output_tokens: row.get(11).unwrap_or(None),
+        protocol: row.get(14).unwrap_or(None),
+        client_ip: row.get(15).unwrap_or(None),
+        username: row.get(16).unwrap_or(None),
+        latency: latency_from_row(row, 17),
+        replay_of: row.get(25).unwrap_or(None),
+        cached_tokens: row.get(26).unwrap_or(None),
+        thinking_tokens: row.get(27).unwrap_or(None),
+        cost: row.get(28).unwrap_or(None),
+        provider: None,
+    })
+}
+
+/// 按时间顺序逐行遍历符合条件的完整日志 (含请求 / 响应体)，不会一次性加载到内存
+/// 回调返回 Err 时中止遍历；返回已处理的条数
+pub fn for_each_log_for_export<F>(filter: &LogFilter, mut f: F) -> Result<usize, String>
+where
+    F: FnMut(ProxyRequestLog) -> Result<(), String>,
+{
     let conn = connect_db()?;
+    let (clauses, values) = filter.conditions();
+    let where_clause = where_sql(&clauses);
 
-    let mut stmt = conn.prepare(
+    let mut stmt = conn.prepare(&format!(
         "SELECT id, timestamp, method, url, status, duration, model, error,
                 request_body, response_body, input_tokens, output_tokens,
-                account_email, mapped_model, protocol, client_ip, username
+                account_email, mapped_model, protocol, client_ip, username,
+                queue_wait_ms, token_wait_ms, upstream_ms, ttft_ms, attempts, accounts_tried, upstream_endpoint, output_tps, replay_of,
+                cached_tokens, thinking_tokens, cost
          FROM request_logs
-         ORDER BY timestamp DESC"
-    ).map_err(|e| e.to_string())?;
+         {}
+         ORDER BY timestamp ASC",
+        where_clause
+    )).map_err(|e| e.to_string())?;
 
-    let logs_iter = stmt.query_map([], |row| {
-        Ok(ProxyRequestLog {
-            id: row.get(0)?,
-            timestamp: row.get(1)?,
-            method: row.get(2)?,
-            url: row.get(3)?,
-            status: row.get(4)?,
-            duration: row.get(5)?,
-            model: row.get(6)?,
-            mapped_model: row.get(13).unwrap_or(None),
-            account_email: row.get(12).unwrap_or(None),
-            error: row.get(7)?,
-            request_body: row.get(8).unwrap_or(None),
-            response_body: row.get(9).unwrap_or(None),
-            input_tokens: row.get(10).unwrap_or(None),
-            output_tokens: row.get(11).unwrap_or(None),
-            protocol: row.get(14).unwrap_or(None),
-            client_ip: row.get(15).unwrap_or(None),
-            username: row.get(16).unwrap_or(None),
-        })
+    let mut rows = stmt
+        .query(rusqlite::params_from_iter(values))
+        .map_err(|e| e.to_string())?;
+    let mut count = 0;
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        f(full_log_from_row(row).map_err(|e| e.to_string())?)?;
+        count += 1;
+    }
+    Ok(count)
+}
 
-    }).map_err(|e| e.to_string())?;
+/// 全文搜索请求
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LogSearchQuery {
+    /// FTS5 查询语法：关键词、"短语"、AND / OR / NOT、前缀匹配 (foo*)
+    pub query: String,
+    /// 与日志列表相同的附加筛选条件
+    pub filter: LogFilter,
+    /// 按时间倒序 (默认按相关度)
+    pub newest_first: bool,
+    pub limit: usize,
+    pub offset: usize,
+}
 
-    let mut logs = Vec::new();
-    for log in logs_iter {
-        logs.push(log.map_err(|e| e.to_string())?);
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogSearchHit {
+    /// 日志摘要 (不含请求 / 响应体)
+    pub log: ProxyRequestLog,
+    /// 命中片段，关键词以 <mark></mark> 包裹
+    pub request_snippet: String,
+    pub response_snippet: String,
+    /// 相关度 (越大越相关)
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LogSearchResult {
+    pub total: u64,
+    pub hits: Vec<LogSearchHit>,
+}
+
+/// 在请求 / 响应消息文本中全文搜索日志
+pub fn search_logs(query: &LogSearchQuery) -> Result<LogSearchResult, String> {
+    let conn = connect_db()?;
+    search_logs_in(&conn, query)
+}
+
+/// 转义摘要中的 HTML，仅保留高亮标记
+fn render_snippet(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    for c in raw.chars() {
+        match c {
+            SNIPPET_OPEN_SENTINEL => out.push_str(SNIPPET_OPEN),
+            SNIPPET_CLOSE_SENTINEL => out.push_str(SNIPPET_CLOSE),
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
     }
-    Ok(logs)
+    out
+}
+
+fn search_logs_in(conn: &Connection, query: &LogSearchQuery) -> Result<LogSearchResult, String> {
+    use rusqlite::types::Value;
+    let match_query = query.query.trim();
+    if match_query.is_empty() {
+        return Err("Search query is empty".to_string());
+    }
+    let search_err = |e: rusqlite::Error| format!("Invalid search query: {}", e);
+
+    let (mut clauses, mut values) = query.filter.conditions();
+    values.push(Value::Text(match_query.to_string()));
+    clauses.push(format!("request_logs_fts MATCH ?{}", values.len()));
+    let where_clause = where_sql(&clauses);
+    let from = "FROM request_logs_fts JOIN request_logs l ON l.id = request_logs_fts.log_id";
+
+    let total: u64 = conn.query_row(
+        &format!("SELECT COUNT(*) {} {}", from, where_clause),
+        rusqlite::params_from_iter(values.iter()),
+        |row| row.get(0),
+    ).map_err(search_err)?;
+
+    let limit = if query.limit == 0 { 50 } else { query.limit.min(500) };
+    values.push(Value::Integer(limit as i64));
+    values.push(Value::Integer(query.offset as i64));
+    let order = if query.newest_first { "l.timestamp DESC" } else { "bm25(request_logs_fts)" };
+    let sql = format!(
+        "SELECT l.id, l.timestamp, l.method, l.url, l.status, l.duration, l.model, l.error,
+                l.input_tokens, l.output_tokens, l.account_email, l.mapped_model, l.protocol, l.client_ip, l.username, l.replay_of,
+                snippet(request_logs_fts, 1, '{open}', '{close}', '…', 16),
+                snippet(request_logs_fts, 2, '{open}', '{close}', '…', 16),
+                bm25(request_logs_fts)
+         {from} {where_clause}
+         ORDER BY {order}
+         LIMIT ?{limit_idx} OFFSET ?{offset_idx}",
+        open = SNIPPET_OPEN_SENTINEL,
+        close = SNIPPET_CLOSE_SENTINEL,
+        limit_idx = values.len() - 1,
+        offset_idx = values.len(),
+    );
+
+    let mut stmt = conn.prepare(&sql).map_err(search_err)?;
+    let hits = stmt.query_map(rusqlite::params_from_iter(values.iter()), |row| {
+        Ok(LogSearchHit {
+            log: ProxyRequestLog {
+                id: row.get(0)?,
+                timestamp: row.get(1)?,
+                method: row.get(2)?,
+                url: row.get(3)?,
+                status: row.get(4)?,
+                duration: row.get(5)?,
+                model: row.get(6)?,
+                mapped_model: row.get(11).unwrap_or(None),
+                account_email: row.get(10).unwrap_or(None),
+                error: row.get(7)?,
+                request_body: None,
+                response_body: None,
+                input_tokens: row.get(8).unwrap_or(None),
+                output_tokens: row.get(9).unwrap_or(None),
+                protocol: row.get(12).unwrap_or(None),
+                client_ip: row.get(13).unwrap_or(None),
+                username: row.get(14).unwrap_or(None),
+                latency: None,
+                replay_of: row.get(15).unwrap_or(None),
+                cached_tokens: None,
+                thinking_tokens: None,
+                cost: None,
+                provider: None,
+            },
+            request_snippet: render_snippet(&row.get::<_, String>(16)?),
+            response_snippet: render_snippet(&row.get::<_, String>(17)?),
+            // bm25 越小越相关，取反便于展示
+            score: -row.get::<_, f64>(18)?,
+        })
+    }).map_err(search_err)?;
+
+    Ok(LogSearchResult {
+        total,
+        hits: hits.collect::<Result<Vec<_>, _>>().map_err(search_err)?,
+    })
 }
 
 // ... existing code ...
@@ -504,3 +1005,95 @@ pub fn get_token_usage_by_ip(limit: usize, hours: i64) -> Result<Vec<IpTokenStat
     Ok(stats)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(id: &str, timestamp: i64, model: &str, request: &str, response: &str) -> ProxyRequestLog {
+        ProxyRequestLog {
+            id: id.to_string(),
+            timestamp,
+            method: "POST".to_string(),
+            url: "/v1/messages".to_string(),
+            status: 200,
+            duration: 100,
+            model: Some(model.to_string()),
+            mapped_model: None,
+            account_email: None,
+            client_ip: None,
+            error: None,
+            request_body: Some(request.to_string()),
+            response_body: Some(response.to_string()),
+            input_tokens: None,
+            output_tokens: None,
+            protocol: None,
+            username: None,
+            latency: None,
+            replay_of: None,
+            cached_tokens: None,
+            thinking_tokens: None,
+            cost: None,
+            provider: None,
+        }
+    }
+
+    fn search(conn: &Connection, query: &str, filter: LogFilter) -> Result<LogSearchResult, String> {
+        search_logs_in(
+            conn,
+            &LogSearchQuery {
+                query: query.to_string(),
+                filter,
+                newest_first: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_full_text_search() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_schema(&conn).unwrap();
+        let request = |text: &str| format!(r#"{{"messages":[{{"role":"user","content":"{}"}}]}}"#, text);
+        let response = |text: &str| format!(r#"{{"content":[{{"type":"text","text":"{}"}}]}}"#, text);
+        insert_log(&conn, &log("a", 1, "claude-sonnet-4-5", &request("how do I reverse a linked list"), &response("Use three pointers"))).unwrap();
+        insert_log(&conn, &log("b", 2, "gemini-3-flash", &request("reverse the list order"), &response("Call reverse()"))).unwrap();
+
+        let result = search(&conn, "reverse", LogFilter::default()).unwrap();
+        assert_eq!(result.total, 2);
+        assert_eq!(result.hits[0].log.id, "b");
+
+        // 短语查询只匹配相邻词，且不会命中 JSON 键名
+        let phrase = search(&conn, "\"linked list\"", LogFilter::default()).unwrap();
+        assert_eq!(phrase.total, 1);
+        assert_eq!(phrase.hits[0].request_snippet, "how do I reverse a <mark>linked list</mark>");
+        assert_eq!(search(&conn, "messages", LogFilter::default()).unwrap().total, 0);
+
+        // 与普通筛选条件组合
+        let filtered = search(
+            &conn,
+            "reverse",
+            LogFilter {
+                model: Some("claude-sonnet-4-5".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(filtered.total, 1);
+        assert_eq!(filtered.hits[0].log.id, "a");
+
+        assert!(search(&conn, "\"unterminated", LogFilter::default()).is_err());
+    }
+
+    #[test]
+    fn test_search_snippet_escapes_html() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_schema(&conn).unwrap();
+        let request = r#"{"messages":[{"role":"user","content":"<img src=x onerror=alert(1)> payload & more"}]}"#;
+        insert_log(&conn, &log("a", 1, "gemini-3-flash", request, "{}")).unwrap();
+
+        let result = search(&conn, "payload", LogFilter::default()).unwrap();
+        let snippet = &result.hits[0].request_snippet;
+        assert!(!snippet.contains("<img"));
+        assert!(snippet.contains("&lt;img src=x onerror=alert(1)&gt; <mark>payload</mark> &amp; more"));
+    }
+}
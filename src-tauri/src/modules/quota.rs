@@ -376,7 +376,7 @@ pub async fn warmup_model_directly(
         .map(|c| c.proxy.port)
         .unwrap_or(8045);
 
-    let warmup_url = format!("http://127.0.0.1:{}/internal/warmup", port);
+    let warmup_url = format!("{}/internal/warmup", crate::proxy::tls::local_base_url(port));
     let body = json!({
         "email": email,
         "model": model_name,
@@ -386,9 +386,9 @@ pub async fn warmup_model_directly(
 
     // Use a no-proxy client for local loopback requests
     // This prevents Docker environments from routing localhost through external proxies
-    let client = rquest::Client::builder()
+    // (and skips certificate checks when the main port is HTTPS-only)
+    let client = crate::proxy::tls::local_client_builder()
         .timeout(std::time::Duration::from_secs(60))
-        .no_proxy()
         .build()
         .unwrap_or_else(|_| rquest::Client::new());
     let resp = client
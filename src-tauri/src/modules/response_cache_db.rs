@@ -0,0 +1,231 @@
+//! Response Cache Database Module
+//! 响应缓存存储 (精确匹配，带 TTL 与条目上限)
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// 缓存的上游响应
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub status: u16,
+    pub content_type: String,
+    pub body: Vec<u8>,
+    pub created_at: i64,
+}
+
+/// 缓存库统计
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CacheDbStats {
+    pub entries: u64,
+    pub total_bytes: u64,
+    pub total_hits: u64,
+    pub oldest_entry_at: Option<i64>,
+}
+
+pub fn get_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("response_cache.db"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let conn = Connection::open(get_db_path()?).map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "journal_mode", "WAL").map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000).map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL").map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+fn create_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS response_cache (
+            cache_key TEXT PRIMARY KEY,
+            model TEXT,
+            status INTEGER NOT NULL,
+            content_type TEXT NOT NULL,
+            body BLOB NOT NULL,
+            size INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL,
+            hit_count INTEGER NOT NULL DEFAULT 0,
+            last_hit_at INTEGER
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_response_cache_expires ON response_cache (expires_at)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_response_cache_created ON response_cache (created_at)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+    create_schema(&conn)
+}
+
+fn lookup_with(conn: &Connection, key: &str, now: i64) -> Result<Option<CachedResponse>, String> {
+    let cached = conn
+        .query_row(
+            "SELECT status, content_type, body, created_at FROM response_cache
+             WHERE cache_key = ?1 AND expires_at > ?2",
+            params![key, now],
+            |row| {
+                Ok(CachedResponse {
+                    status: row.get::<_, i64>(0)? as u16,
+                    content_type: row.get(1)?,
+                    body: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    if cached.is_some() {
+        let _ = conn.execute(
+            "UPDATE response_cache SET hit_count = hit_count + 1, last_hit_at = ?1 WHERE cache_key = ?2",
+            params![now, key],
+        );
+    }
+    Ok(cached)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn store_with(
+    conn: &Connection,
+    key: &str,
+    model: Option<&str>,
+    status: u16,
+    content_type: &str,
+    body: &[u8],
+    now: i64,
+    ttl_secs: u64,
+    max_entries: usize,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO response_cache
+            (cache_key, model, status, content_type, body, size, created_at, expires_at, hit_count)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 0)",
+        params![
+            key,
+            model,
+            status as i64,
+            content_type,
+            body,
+            body.len() as i64,
+            now,
+            now + ttl_secs as i64
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // 清理过期条目，并按创建时间淘汰超出上限的旧条目
+    conn.execute("DELETE FROM response_cache WHERE expires_at <= ?1", params![now])
+        .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM response_cache WHERE cache_key IN (
+            SELECT cache_key FROM response_cache ORDER BY created_at DESC LIMIT -1 OFFSET ?1
+        )",
+        params![max_entries as i64],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn stats_with(conn: &Connection, now: i64) -> Result<CacheDbStats, String> {
+    conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(size), 0), COALESCE(SUM(hit_count), 0), MIN(created_at)
+         FROM response_cache WHERE expires_at > ?1",
+        params![now],
+        |row| {
+            Ok(CacheDbStats {
+                entries: row.get::<_, i64>(0)? as u64,
+                total_bytes: row.get::<_, i64>(1)? as u64,
+                total_hits: row.get::<_, i64>(2)? as u64,
+                oldest_entry_at: row.get(3)?,
+            })
+        },
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// 查找未过期的缓存条目 (命中时累加命中计数)
+pub fn lookup(key: &str) -> Result<Option<CachedResponse>, String> {
+    let conn = connect_db()?;
+    lookup_with(&conn, key, chrono::Utc::now().timestamp())
+}
+
+/// 写入缓存条目
+pub fn store(
+    key: &str,
+    model: Option<&str>,
+    status: u16,
+    content_type: &str,
+    body: &[u8],
+    ttl_secs: u64,
+    max_entries: usize,
+) -> Result<(), String> {
+    let conn = connect_db()?;
+    store_with(
+        &conn,
+        key,
+        model,
+        status,
+        content_type,
+        body,
+        chrono::Utc::now().timestamp(),
+        ttl_secs,
+        max_entries,
+    )
+}
+
+/// 缓存库统计
+pub fn get_stats() -> Result<CacheDbStats, String> {
+    let conn = connect_db()?;
+    stats_with(&conn, chrono::Utc::now().timestamp())
+}
+
+/// 清空缓存，返回删除的条目数
+pub fn clear() -> Result<usize, String> {
+    let conn = connect_db()?;
+    conn.execute("DELETE FROM response_cache", [])
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_lookup_expire_and_evict() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_schema(&conn).unwrap();
+
+        store_with(&conn, "a", Some("m"), 200, "application/json", b"{}", 100, 60, 2).unwrap();
+        let hit = lookup_with(&conn, "a", 110).unwrap().unwrap();
+        assert_eq!(hit.body, b"{}");
+        assert_eq!(hit.created_at, 100);
+
+        // 过期后不再命中
+        assert!(lookup_with(&conn, "a", 161).unwrap().is_none());
+
+        // 超出条目上限时淘汰最旧的条目
+        store_with(&conn, "b", None, 200, "text/event-stream", b"data: 1\n\n", 120, 60, 2).unwrap();
+        store_with(&conn, "c", None, 200, "text/event-stream", b"data: 2\n\n", 130, 60, 2).unwrap();
+        assert!(lookup_with(&conn, "a", 131).unwrap().is_none());
+        assert!(lookup_with(&conn, "b", 131).unwrap().is_some());
+
+        let stats = stats_with(&conn, 131).unwrap();
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.total_hits, 1);
+    }
+}
@@ -0,0 +1,172 @@
+//! Secret Re-encryption Module
+//! 将 gui_config.json 与账号文件中的旧版密文 (固定 nonce) 重新加密为 v2 信封格式
+
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+use crate::utils::crypto;
+
+/// 旧版 (#1738 之前) 不带前缀的密文只出现在这些字段中
+const LEGACY_UNPREFIXED_FIELDS: [&str; 1] = ["password"];
+
+/// 递归处理 JSON，返回重新加密的字段数
+fn reencrypt_value(value: &mut Value, field: Option<&str>) -> usize {
+    match value {
+        Value::String(s) => {
+            let unprefixed_legacy = field.is_some_and(|f| LEGACY_UNPREFIXED_FIELDS.contains(&f))
+                && !s.is_empty()
+                && !s.starts_with("ag_enc_");
+            if !crypto::needs_reencryption(s) && !unprefixed_legacy {
+                return 0;
+            }
+            // 无法解密的值 (密钥变更 / 普通明文) 保持原样，避免数据丢失
+            match crypto::reencrypt_string(s) {
+                Ok(encrypted) => {
+                    *s = encrypted;
+                    1
+                }
+                Err(_) => 0,
+            }
+        }
+        Value::Array(items) => items.iter_mut().map(|v| reencrypt_value(v, field)).sum(),
+        Value::Object(map) => map
+            .iter_mut()
+            .map(|(key, v)| reencrypt_value(v, Some(key.as_str())))
+            .sum(),
+        _ => 0,
+    }
+}
+
+/// 加密仍为明文的值，返回加密的字段数
+fn encrypt_plaintext(value: &mut Value) -> usize {
+    match value {
+        Value::String(s) if !s.is_empty() && !s.starts_with("ag_enc_") => match crypto::encrypt_string(s) {
+            Ok(encrypted) => {
+                *s = encrypted;
+                1
+            }
+            Err(_) => 0,
+        },
+        _ => 0,
+    }
+}
+
+/// 告警通道中以明文保存的密钥 (SMTP 密码、ntfy / Gotify Token、Webhook 请求头)
+fn encrypt_alert_secrets(config: &mut Value) -> usize {
+    let Some(channels) = config.pointer_mut("/proxy/alerts/channels").and_then(Value::as_array_mut) else {
+        return 0;
+    };
+    let mut changed = 0;
+    for channel in channels.iter_mut() {
+        for field in ["password", "token"] {
+            if let Some(value) = channel.get_mut(field) {
+                changed += encrypt_plaintext(value);
+            }
+        }
+        if let Some(Value::Object(headers)) = channel.get_mut("headers") {
+            changed += headers.values_mut().map(encrypt_plaintext).sum::<usize>();
+        }
+    }
+    changed
+}
+
+fn reencrypt_json_file(path: &Path) -> Result<usize, String> {
+    if !path.exists() {
+        return Ok(0);
+    }
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut json: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+
+    let changed = reencrypt_value(&mut json, None) + encrypt_alert_secrets(&mut json);
+    if changed > 0 {
+        let content = serde_json::to_string_pretty(&json).map_err(|e| e.to_string())?;
+        // 先写临时文件再重命名，中途崩溃不会留下损坏的配置或账号文件
+        let temp_path = path.with_extension(format!("json.tmp.{}", uuid::Uuid::new_v4()));
+        fs::write(&temp_path, content).map_err(|e| format!("Failed to write {}: {}", temp_path.display(), e))?;
+        if let Err(e) = fs::rename(&temp_path, path) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(format!("Failed to replace {}: {}", path.display(), e));
+        }
+    }
+    Ok(changed)
+}
+
+/// 重新加密所有需要迁移的密文 (旧格式或非当前密钥)，返回更新的字段数
+/// 无需迁移时不会改写文件，可在每次启动时调用
+pub fn reencrypt_secrets() -> Result<usize, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    let mut total = reencrypt_json_file(&data_dir.join("gui_config.json"))?;
+
+    let accounts_dir = crate::modules::account::get_accounts_dir()?;
+    // 与账号令牌的加密写入互斥
+    let _guard = crate::modules::account_crypto::write_lock();
+    if let Ok(entries) = fs::read_dir(&accounts_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match reencrypt_json_file(&path) {
+                Ok(count) => total += count,
+                Err(e) => tracing::warn!("[Crypto] Skipping {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    if total > 0 {
+        tracing::info!("[Crypto] Re-encrypted {} secret(s) with the v2 envelope format", total);
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reencrypt_value_only_touches_legacy_secrets() {
+        let v2 = crypto::encrypt_string("already").unwrap();
+        let mut json = serde_json::json!({
+            "proxy": {
+                "proxy_pool": {
+                    "proxies": [{ "auth": { "username": "ag_enc_user", "password": "plain text" } }]
+                },
+                "api_key": "sk-123"
+            },
+            "secret": v2.clone()
+        });
+
+        assert_eq!(reencrypt_value(&mut json, None), 0);
+        assert_eq!(json["secret"], Value::String(v2));
+        assert_eq!(json["proxy"]["proxy_pool"]["proxies"][0]["auth"]["password"], "plain text");
+    }
+
+    #[test]
+    fn test_encrypt_alert_secrets() {
+        let mut json = serde_json::json!({
+            "proxy": {
+                "alerts": {
+                    "channels": [
+                        { "type": "smtp", "host": "smtp.example.com", "password": "hunter2", "from": "a@b", "to": [] },
+                        { "type": "gotify", "server": "https://gotify", "token": "tok" },
+                        { "type": "ntfy", "topic": "t", "token": null },
+                        { "type": "webhook", "url": "https://hook", "headers": { "Authorization": "Bearer x", "X-Empty": "" } }
+                    ]
+                }
+            }
+        });
+
+        assert_eq!(encrypt_alert_secrets(&mut json), 3);
+        let channels = &json["proxy"]["alerts"]["channels"];
+        assert_eq!(crypto::decrypt_string(channels[0]["password"].as_str().unwrap()).unwrap(), "hunter2");
+        assert_eq!(crypto::decrypt_string(channels[1]["token"].as_str().unwrap()).unwrap(), "tok");
+        assert!(channels[2]["token"].is_null());
+        assert!(channels[3]["headers"]["Authorization"].as_str().unwrap().starts_with("ag_enc_"));
+        assert_eq!(channels[3]["headers"]["X-Empty"], "");
+
+        // 已加密的值不会重复处理
+        assert_eq!(encrypt_alert_secrets(&mut json), 0);
+    }
+}
@@ -1,9 +1,15 @@
 //! Security Database Module
 //! 安全监控相关的数据库操作
 
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::utils::ip::IpPrefixIndex;
 
 /// IP 访问日志
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +59,70 @@ pub struct IpStats {
     pub today_requests: u64,
     pub blacklist_count: u64,
     pub whitelist_count: u64,
+    /// 当前生效的自动封禁数
+    pub auto_ban_count: u64,
+    /// 最近 24 小时的自动封禁事件数
+    pub ban_events_24h: u64,
+}
+
+/// 自动封禁事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpBanEvent {
+    pub id: String,
+    pub client_ip: String,
+    /// 触发规则: auth_failures / invalid_tokens / request_rate
+    pub rule: String,
+    /// 窗口内的违规次数
+    pub offense_count: i64,
+    pub duration_secs: i64,
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+/// DLP 规则命中记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DlpHitRecord {
+    pub rule: String,
+    /// 处理动作: block / mask / log
+    pub action: String,
+    /// 请求中命中的次数
+    pub matches: i64,
+    pub protocol: String,
+    pub username: Option<String>,
+    pub created_at: i64,
+}
+
+/// 单条 DLP 规则的命中统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DlpRuleStats {
+    pub rule: String,
+    pub action: String,
+    /// 命中的请求数
+    pub requests: u64,
+    /// 命中的内容片段数
+    pub matches: u64,
+    pub last_hit_at: i64,
+}
+
+/// 按用户令牌汇总的 DLP 命中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DlpUserStats {
+    /// 未使用用户令牌的请求为 None
+    pub username: Option<String>,
+    pub requests: u64,
+    pub blocked: u64,
+}
+
+/// DLP 命中统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DlpStats {
+    pub since: i64,
+    pub total_hits: u64,
+    pub blocked: u64,
+    pub masked: u64,
+    pub logged: u64,
+    pub by_rule: Vec<DlpRuleStats>,
+    pub by_user: Vec<DlpUserStats>,
 }
 
 /// IP 访问排行
@@ -167,6 +237,48 @@ pub fn init_db() -> Result<(), String> {
     // Migration: Add username column to ip_access_logs
     let _ = conn.execute("ALTER TABLE ip_access_logs ADD COLUMN username TEXT", []);
 
+    // 自动封禁事件表
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS ip_ban_events (
+            id TEXT PRIMARY KEY,
+            client_ip TEXT NOT NULL,
+            rule TEXT NOT NULL,
+            offense_count INTEGER NOT NULL,
+            duration_secs INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_ban_events_ip ON ip_ban_events (client_ip, created_at)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // DLP 规则命中表
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS dlp_hits (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            rule TEXT NOT NULL,
+            action TEXT NOT NULL,
+            matches INTEGER NOT NULL,
+            protocol TEXT NOT NULL,
+            username TEXT,
+            created_at INTEGER NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_dlp_hits_created ON dlp_hits (created_at)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
     Ok(())
 }
 
@@ -309,6 +421,23 @@ pub fn get_ip_stats() -> Result<IpStats, String> {
         .query_row("SELECT COUNT(*) FROM ip_whitelist", [], |row| row.get(0))
         .map_err(|e| e.to_string())?;
 
+    let now = chrono::Utc::now().timestamp();
+    let auto_ban_count: u64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM ip_blacklist WHERE created_by = 'auto' AND (expires_at IS NULL OR expires_at >= ?1)",
+            [now],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let ban_events_24h: u64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM ip_ban_events WHERE created_at >= ?1",
+            [now - 86400],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
     Ok(IpStats {
         total_requests,
         unique_ips,
@@ -316,6 +445,8 @@ pub fn get_ip_stats() -> Result<IpStats, String> {
         today_requests,
         blacklist_count,
         whitelist_count,
+        auto_ban_count,
+        ban_events_24h,
     })
 }
 
@@ -399,6 +530,7 @@ pub fn add_to_blacklist(
         params![id, ip_pattern, reason, now, expires_at, created_by],
     )
     .map_err(|e| e.to_string())?;
+    invalidate_ip_indexes();
 
     Ok(IpBlacklistEntry {
         id,
@@ -417,6 +549,7 @@ pub fn remove_from_blacklist(id: &str) -> Result<(), String> {
 
     conn.execute("DELETE FROM ip_blacklist WHERE id = ?1", [id])
         .map_err(|e| e.to_string())?;
+    invalidate_ip_indexes();
 
     Ok(())
 }
@@ -459,98 +592,93 @@ pub fn is_ip_in_blacklist(ip: &str) -> Result<bool, String> {
     get_blacklist_entry_for_ip(ip).map(|entry| entry.is_some())
 }
 
-/// 获取 IP 对应的黑名单条目（如果存在）
-pub fn get_blacklist_entry_for_ip(ip: &str) -> Result<Option<IpBlacklistEntry>, String> {
-    let conn = connect_db()?;
-    let now = chrono::Utc::now().timestamp();
+/// 黑白名单内存索引，名单变更时失效并在下次查询时重建
+struct IpListIndexes {
+    blacklist: IpPrefixIndex<IpBlacklistEntry>,
+    /// 仅自动封禁条目 (created_by = auto)
+    auto_blacklist: IpPrefixIndex<IpBlacklistEntry>,
+    whitelist: IpPrefixIndex<()>,
+}
 
-    // 清理过期的黑名单条目
-    let _ = conn.execute(
-        "DELETE FROM ip_blacklist WHERE expires_at IS NOT NULL AND expires_at < ?1",
-        [now],
-    );
+static IP_LIST_INDEXES: Lazy<RwLock<Option<Arc<IpListIndexes>>>> = Lazy::new(|| RwLock::new(None));
+/// 名单版本号，避免并发重建时写入过期索引
+static IP_LIST_GENERATION: AtomicU64 = AtomicU64::new(0);
 
-    // 精确匹配
-    let entry_result = conn.query_row(
-        "SELECT id, ip_pattern, reason, created_at, expires_at, created_by, hit_count
-         FROM ip_blacklist WHERE ip_pattern = ?1",
-        [ip],
-        |row| {
-            Ok(IpBlacklistEntry {
-                id: row.get(0)?,
-                ip_pattern: row.get(1)?,
-                reason: row.get(2)?,
-                created_at: row.get(3)?,
-                expires_at: row.get(4)?,
-                created_by: row.get(5)?,
-                hit_count: row.get(6)?,
-            })
-        },
-    );
+/// 使黑白名单索引失效
+fn invalidate_ip_indexes() {
+    IP_LIST_GENERATION.fetch_add(1, Ordering::SeqCst);
+    *IP_LIST_INDEXES.write() = None;
+}
 
-    if let Ok(entry) = entry_result {
-        // 增加命中计数
-        let _ = conn.execute(
-            "UPDATE ip_blacklist SET hit_count = hit_count + 1 WHERE ip_pattern = ?1",
-            [ip],
-        );
-        return Ok(Some(entry));
+fn ip_indexes() -> Result<Arc<IpListIndexes>, String> {
+    if let Some(indexes) = IP_LIST_INDEXES.read().as_ref() {
+        return Ok(indexes.clone());
     }
 
-    // CIDR 匹配
-    let entries = get_blacklist()?;
-    for entry in entries {
-        if entry.ip_pattern.contains('/') {
-            if cidr_match(ip, &entry.ip_pattern) {
-                // 增加命中计数
-                let _ = conn.execute(
-                    "UPDATE ip_blacklist SET hit_count = hit_count + 1 WHERE id = ?1",
-                    [&entry.id],
-                );
-                return Ok(Some(entry));
-            }
+    let generation = IP_LIST_GENERATION.load(Ordering::SeqCst);
+    let mut indexes = IpListIndexes {
+        blacklist: IpPrefixIndex::default(),
+        auto_blacklist: IpPrefixIndex::default(),
+        whitelist: IpPrefixIndex::default(),
+    };
+    for entry in get_blacklist()? {
+        let pattern = entry.ip_pattern.clone();
+        if entry.created_by == "auto" {
+            indexes.auto_blacklist.insert(&pattern, entry.clone());
         }
+        indexes.blacklist.insert(&pattern, entry);
+    }
+    for entry in get_whitelist()? {
+        indexes.whitelist.insert(&entry.ip_pattern, ());
     }
 
-    Ok(None)
+    let indexes = Arc::new(indexes);
+    let mut slot = IP_LIST_INDEXES.write();
+    if IP_LIST_GENERATION.load(Ordering::SeqCst) == generation {
+        *slot = Some(indexes.clone());
+    }
+    Ok(indexes)
 }
 
-/// 简单的 CIDR 匹配
-fn cidr_match(ip: &str, cidr: &str) -> bool {
-    let parts: Vec<&str> = cidr.split('/').collect();
-    if parts.len() != 2 {
-        return false;
-    }
+/// 获取 IP 对应的黑名单条目（如果存在）
+/// 精确 IP、IPv4/IPv6 CIDR 均通过内存前缀索引匹配，最长前缀优先
+pub fn get_blacklist_entry_for_ip(ip: &str) -> Result<Option<IpBlacklistEntry>, String> {
+    lookup_blacklist_entry(ip, false)
+}
 
-    let network = parts[0];
-    let prefix_len: u8 = match parts[1].parse() {
-        Ok(p) => p,
-        Err(_) => return false,
-    };
+/// 获取 IP 对应的自动封禁条目 (仅启用自动封禁、未启用手动黑名单时使用)
+pub fn get_auto_ban_entry_for_ip(ip: &str) -> Result<Option<IpBlacklistEntry>, String> {
+    lookup_blacklist_entry(ip, true)
+}
 
-    let ip_parts: Vec<u8> = ip
-        .split('.')
-        .filter_map(|s| s.parse().ok())
-        .collect();
-    let net_parts: Vec<u8> = network
-        .split('.')
-        .filter_map(|s| s.parse().ok())
-        .collect();
-
-    if ip_parts.len() != 4 || net_parts.len() != 4 {
-        return false;
-    }
+fn lookup_blacklist_entry(ip: &str, auto_only: bool) -> Result<Option<IpBlacklistEntry>, String> {
+    let index = |indexes: &IpListIndexes| -> Option<IpBlacklistEntry> {
+        let list = if auto_only { &indexes.auto_blacklist } else { &indexes.blacklist };
+        list.lookup(ip).cloned()
+    };
+    let Some(entry) = index(&*ip_indexes()?) else {
+        return Ok(None);
+    };
 
-    let ip_u32 = u32::from_be_bytes([ip_parts[0], ip_parts[1], ip_parts[2], ip_parts[3]]);
-    let net_u32 = u32::from_be_bytes([net_parts[0], net_parts[1], net_parts[2], net_parts[3]]);
+    let conn = connect_db()?;
+    let now = chrono::Utc::now().timestamp();
 
-    let mask = if prefix_len == 0 {
-        0
-    } else {
-        !0u32 << (32 - prefix_len)
-    };
+    // 命中已过期条目时清理过期的黑名单条目并重新匹配
+    if entry.expires_at.is_some_and(|expires_at| expires_at < now) {
+        let _ = conn.execute(
+            "DELETE FROM ip_blacklist WHERE expires_at IS NOT NULL AND expires_at < ?1",
+            [now],
+        );
+        invalidate_ip_indexes();
+        return Ok(index(&*ip_indexes()?).filter(|e| e.expires_at.is_none_or(|expires_at| expires_at >= now)));
+    }
 
-    (ip_u32 & mask) == (net_u32 & mask)
+    // 增加命中计数
+    let _ = conn.execute(
+        "UPDATE ip_blacklist SET hit_count = hit_count + 1 WHERE id = ?1",
+        [&entry.id],
+    );
+    Ok(Some(entry))
 }
 
 // ============================================================================
@@ -570,6 +698,7 @@ pub fn add_to_whitelist(ip_pattern: &str, description: Option<&str>) -> Result<I
         params![id, ip_pattern, description, now],
     )
     .map_err(|e| e.to_string())?;
+    invalidate_ip_indexes();
 
     Ok(IpWhitelistEntry {
         id,
@@ -585,6 +714,7 @@ pub fn remove_from_whitelist(id: &str) -> Result<(), String> {
 
     conn.execute("DELETE FROM ip_whitelist WHERE id = ?1", [id])
         .map_err(|e| e.to_string())?;
+    invalidate_ip_indexes();
 
     Ok(())
 }
@@ -621,32 +751,170 @@ pub fn get_whitelist() -> Result<Vec<IpWhitelistEntry>, String> {
 
 /// 检查 IP 是否在白名单中
 pub fn is_ip_in_whitelist(ip: &str) -> Result<bool, String> {
+    Ok(ip_indexes()?.whitelist.lookup(ip).is_some())
+}
+
+// ============================================================================
+// 自动封禁事件
+// ============================================================================
+
+/// 保存自动封禁事件
+pub fn save_ban_event(event: &IpBanEvent) -> Result<(), String> {
     let conn = connect_db()?;
+    conn.execute(
+        "INSERT INTO ip_ban_events (id, client_ip, rule, offense_count, duration_secs, created_at, expires_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            event.id,
+            event.client_ip,
+            event.rule,
+            event.offense_count,
+            event.duration_secs,
+            event.created_at,
+            event.expires_at,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    // 精确匹配
-    let count: i64 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM ip_whitelist WHERE ip_pattern = ?1",
-            [ip],
-            |row| row.get(0),
+/// 统计 IP 自 since 以来的封禁次数 (用于递增封禁时长)
+pub fn count_ban_events(ip: &str, since: i64) -> Result<u32, String> {
+    let conn = connect_db()?;
+    conn.query_row(
+        "SELECT COUNT(*) FROM ip_ban_events WHERE client_ip = ?1 AND created_at >= ?2",
+        params![ip, since],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// 获取最近的自动封禁事件
+pub fn get_ban_events(limit: usize) -> Result<Vec<IpBanEvent>, String> {
+    let conn = connect_db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, client_ip, rule, offense_count, duration_secs, created_at, expires_at
+             FROM ip_ban_events ORDER BY created_at DESC LIMIT ?1",
         )
         .map_err(|e| e.to_string())?;
 
-    if count > 0 {
-        return Ok(true);
+    let events = stmt
+        .query_map([limit as i64], |row| {
+            Ok(IpBanEvent {
+                id: row.get(0)?,
+                client_ip: row.get(1)?,
+                rule: row.get(2)?,
+                offense_count: row.get(3)?,
+                duration_secs: row.get(4)?,
+                created_at: row.get(5)?,
+                expires_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut result = Vec::new();
+    for event in events {
+        result.push(event.map_err(|e| e.to_string())?);
     }
+    Ok(result)
+}
 
-    // CIDR 匹配
-    let entries = get_whitelist()?;
-    for entry in entries {
-        if entry.ip_pattern.contains('/') {
-            if cidr_match(ip, &entry.ip_pattern) {
-                return Ok(true);
-            }
-        }
+// ============================================================================
+// DLP 命中统计
+// ============================================================================
+
+/// 保存一次请求的 DLP 命中记录
+pub fn save_dlp_hits(hits: &[DlpHitRecord]) -> Result<(), String> {
+    if hits.is_empty() {
+        return Ok(());
+    }
+    let mut conn = connect_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for hit in hits {
+        tx.execute(
+            "INSERT INTO dlp_hits (rule, action, matches, protocol, username, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![hit.rule, hit.action, hit.matches, hit.protocol, hit.username, hit.created_at],
+        )
+        .map_err(|e| e.to_string())?;
     }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// 统计 since 以来的 DLP 命中
+pub fn get_dlp_stats(since: i64) -> Result<DlpStats, String> {
+    let conn = connect_db()?;
+
+    let (total_hits, blocked, masked, logged): (u64, u64, u64, u64) = conn
+        .query_row(
+            "SELECT COUNT(*),
+                    COALESCE(SUM(action = 'block'), 0),
+                    COALESCE(SUM(action = 'mask'), 0),
+                    COALESCE(SUM(action = 'log'), 0)
+             FROM dlp_hits WHERE created_at >= ?1",
+            [since],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|e| e.to_string())?;
 
-    Ok(false)
+    let mut stmt = conn
+        .prepare(
+            "SELECT rule, action, COUNT(*), SUM(matches), MAX(created_at)
+             FROM dlp_hits WHERE created_at >= ?1
+             GROUP BY rule, action ORDER BY COUNT(*) DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let by_rule = stmt
+        .query_map([since], |row| {
+            Ok(DlpRuleStats {
+                rule: row.get(0)?,
+                action: row.get(1)?,
+                requests: row.get(2)?,
+                matches: row.get(3)?,
+                last_hit_at: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT username, COUNT(*), COALESCE(SUM(action = 'block'), 0)
+             FROM dlp_hits WHERE created_at >= ?1
+             GROUP BY username ORDER BY COUNT(*) DESC LIMIT 50",
+        )
+        .map_err(|e| e.to_string())?;
+    let by_user = stmt
+        .query_map([since], |row| {
+            Ok(DlpUserStats {
+                username: row.get(0)?,
+                requests: row.get(1)?,
+                blocked: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(DlpStats {
+        since,
+        total_hits,
+        blocked,
+        masked,
+        logged,
+        by_rule,
+        by_user,
+    })
+}
+
+/// 清空 DLP 命中记录
+pub fn clear_dlp_hits() -> Result<(), String> {
+    let conn = connect_db()?;
+    conn.execute("DELETE FROM dlp_hits", [])
+        .map_err(|e| e.to_string())?;
+    Ok(())
 }
 
 /// 清空所有 IP 访问日志
@@ -10,6 +10,8 @@ pub struct TokenStatsAggregated {
     pub total_output_tokens: u64,
     pub total_tokens: u64,
     pub request_count: u64,
+    #[serde(default)]
+    pub total_cost: f64,
 }
 
 /// Per-account token statistics
@@ -20,6 +22,8 @@ pub struct AccountTokenStats {
     pub total_output_tokens: u64,
     pub total_tokens: u64,
     pub request_count: u64,
+    #[serde(default)]
+    pub total_cost: f64,
 }
 
 /// Summary statistics
@@ -30,6 +34,8 @@ pub struct TokenStatsSummary {
     pub total_tokens: u64,
     pub total_requests: u64,
     pub unique_accounts: u64,
+    #[serde(default)]
+    pub total_cost: f64,
 }
 
 /// Per-model token statistics
@@ -40,6 +46,8 @@ pub struct ModelTokenStats {
     pub total_output_tokens: u64,
     pub total_tokens: u64,
     pub request_count: u64,
+    #[serde(default)]
+    pub total_cost: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,7 +86,10 @@ fn connect_db() -> Result<Connection, String> {
 /// Initialize the token stats database
 pub fn init_db() -> Result<(), String> {
     let conn = connect_db()?;
+    create_schema(&conn)
+}
 
+fn create_schema(conn: &Connection) -> Result<(), String> {
     // Create main usage table
     conn.execute(
         "CREATE TABLE IF NOT EXISTS token_usage (
@@ -122,37 +133,74 @@ pub fn init_db() -> Result<(), String> {
     )
     .map_err(|e| e.to_string())?;
 
+    // [NEW] 费用核算：用户令牌、缓存 / 思考 Token 与费用
+    let _ = conn.execute("ALTER TABLE token_usage ADD COLUMN username TEXT", []);
+    let _ = conn.execute("ALTER TABLE token_usage ADD COLUMN cached_tokens INTEGER NOT NULL DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE token_usage ADD COLUMN thinking_tokens INTEGER NOT NULL DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE token_usage ADD COLUMN cost REAL NOT NULL DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE token_stats_hourly ADD COLUMN total_cost REAL NOT NULL DEFAULT 0", []);
+
     Ok(())
 }
 
+/// Single request usage to record
+#[derive(Debug, Clone, Default)]
+pub struct UsageRecord {
+    pub account_email: String,
+    pub model: String,
+    /// User token username (None for admin / anonymous requests)
+    pub username: Option<String>,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub cached_tokens: u32,
+    pub thinking_tokens: u32,
+    /// Cost computed from the price table at record time
+    pub cost: f64,
+}
+
 /// Record token usage from a request
-pub fn record_usage(
-    account_email: &str,
-    model: &str,
-    input_tokens: u32,
-    output_tokens: u32,
-) -> Result<(), String> {
+pub fn record_usage(record: &UsageRecord) -> Result<(), String> {
     let conn = connect_db()?;
-    let timestamp = chrono::Local::now().timestamp();
-    let total_tokens = input_tokens + output_tokens;
+    insert_usage(&conn, record, chrono::Local::now())
+}
+
+fn insert_usage(
+    conn: &Connection,
+    record: &UsageRecord,
+    now: chrono::DateTime<chrono::Local>,
+) -> Result<(), String> {
+    let total_tokens = record.input_tokens + record.output_tokens;
 
     // Insert into raw usage table
     conn.execute(
-        "INSERT INTO token_usage (timestamp, account_email, model, input_tokens, output_tokens, total_tokens)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        params![timestamp, account_email, model, input_tokens, output_tokens, total_tokens],
+        "INSERT INTO token_usage (timestamp, account_email, model, input_tokens, output_tokens, total_tokens,
+                                  username, cached_tokens, thinking_tokens, cost)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            now.timestamp(),
+            record.account_email,
+            record.model,
+            record.input_tokens,
+            record.output_tokens,
+            total_tokens,
+            record.username,
+            record.cached_tokens,
+            record.thinking_tokens,
+            record.cost,
+        ],
     ).map_err(|e| e.to_string())?;
 
-    let hour_bucket = chrono::Local::now().format("%Y-%m-%d %H:00").to_string();
+    let hour_bucket = now.format("%Y-%m-%d %H:00").to_string();
     conn.execute(
-        "INSERT INTO token_stats_hourly (hour_bucket, account_email, total_input_tokens, total_output_tokens, total_tokens, request_count)
-         VALUES (?1, ?2, ?3, ?4, ?5, 1)
+        "INSERT INTO token_stats_hourly (hour_bucket, account_email, total_input_tokens, total_output_tokens, total_tokens, request_count, total_cost)
+         VALUES (?1, ?2, ?3, ?4, ?5, 1, ?6)
          ON CONFLICT(hour_bucket, account_email) DO UPDATE SET
             total_input_tokens = total_input_tokens + ?3,
             total_output_tokens = total_output_tokens + ?4,
             total_tokens = total_tokens + ?5,
-            request_count = request_count + 1",
-        params![hour_bucket, account_email, input_tokens, output_tokens, total_tokens],
+            request_count = request_count + 1,
+            total_cost = total_cost + ?6",
+        params![hour_bucket, record.account_email, record.input_tokens, record.output_tokens, total_tokens, record.cost],
     ).map_err(|e| e.to_string())?;
 
     Ok(())
@@ -170,7 +218,8 @@ pub fn get_hourly_stats(hours: i64) -> Result<Vec<TokenStatsAggregated>, String>
                 SUM(total_input_tokens) as input, 
                 SUM(total_output_tokens) as output,
                 SUM(total_tokens) as total,
-                SUM(request_count) as count
+                SUM(request_count) as count,
+                SUM(total_cost) as cost
          FROM token_stats_hourly 
          WHERE hour_bucket >= ?1
          GROUP BY hour_bucket
@@ -186,6 +235,7 @@ pub fn get_hourly_stats(hours: i64) -> Result<Vec<TokenStatsAggregated>, String>
                 total_output_tokens: row.get(2)?,
                 total_tokens: row.get(3)?,
                 request_count: row.get(4)?,
+                total_cost: row.get(5)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -209,7 +259,8 @@ pub fn get_daily_stats(days: i64) -> Result<Vec<TokenStatsAggregated>, String> {
                 SUM(total_input_tokens) as input, 
                 SUM(total_output_tokens) as output,
                 SUM(total_tokens) as total,
-                SUM(request_count) as count
+                SUM(request_count) as count,
+                SUM(total_cost) as cost
          FROM token_stats_hourly 
          WHERE substr(hour_bucket, 1, 10) >= ?1
          GROUP BY day_bucket
@@ -225,6 +276,7 @@ pub fn get_daily_stats(days: i64) -> Result<Vec<TokenStatsAggregated>, String> {
                 total_output_tokens: row.get(2)?,
                 total_tokens: row.get(3)?,
                 request_count: row.get(4)?,
+                total_cost: row.get(5)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -248,7 +300,8 @@ pub fn get_weekly_stats(weeks: i64) -> Result<Vec<TokenStatsAggregated>, String>
                 SUM(input_tokens) as input, 
                 SUM(output_tokens) as output,
                 SUM(total_tokens) as total,
-                COUNT(*) as count
+                COUNT(*) as count,
+                SUM(cost) as cost
          FROM token_usage 
          WHERE timestamp >= ?1
          GROUP BY week_bucket
@@ -264,6 +317,7 @@ pub fn get_weekly_stats(weeks: i64) -> Result<Vec<TokenStatsAggregated>, String>
                 total_output_tokens: row.get(2)?,
                 total_tokens: row.get(3)?,
                 request_count: row.get(4)?,
+                total_cost: row.get(5)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -287,7 +341,8 @@ pub fn get_account_stats(hours: i64) -> Result<Vec<AccountTokenStats>, String> {
                 SUM(total_input_tokens) as input, 
                 SUM(total_output_tokens) as output,
                 SUM(total_tokens) as total,
-                SUM(request_count) as count
+                SUM(request_count) as count,
+                SUM(total_cost) as cost
          FROM token_stats_hourly 
          WHERE hour_bucket >= ?1
          GROUP BY account_email
@@ -303,6 +358,7 @@ pub fn get_account_stats(hours: i64) -> Result<Vec<AccountTokenStats>, String> {
                 total_output_tokens: row.get(2)?,
                 total_tokens: row.get(3)?,
                 request_count: row.get(4)?,
+                total_cost: row.get(5)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -320,16 +376,17 @@ pub fn get_summary_stats(hours: i64) -> Result<TokenStatsSummary, String> {
     let cutoff = chrono::Local::now() - chrono::Duration::hours(hours);
     let cutoff_bucket = cutoff.format("%Y-%m-%d %H:00").to_string();
 
-    let (total_input, total_output, total, requests): (u64, u64, u64, u64) = conn
+    let (total_input, total_output, total, requests, total_cost): (u64, u64, u64, u64, f64) = conn
         .query_row(
             "SELECT COALESCE(SUM(total_input_tokens), 0),
                 COALESCE(SUM(total_output_tokens), 0),
                 COALESCE(SUM(total_tokens), 0),
-                COALESCE(SUM(request_count), 0)
+                COALESCE(SUM(request_count), 0),
+                COALESCE(SUM(total_cost), 0)
          FROM token_stats_hourly 
          WHERE hour_bucket >= ?1",
             [&cutoff_bucket],
-            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
         )
         .map_err(|e| e.to_string())?;
 
@@ -347,6 +404,7 @@ pub fn get_summary_stats(hours: i64) -> Result<TokenStatsSummary, String> {
         total_tokens: total,
         total_requests: requests,
         unique_accounts,
+        total_cost,
     })
 }
 
@@ -360,7 +418,8 @@ pub fn get_model_stats(hours: i64) -> Result<Vec<ModelTokenStats>, String> {
                 SUM(input_tokens) as input,
                 SUM(output_tokens) as output,
                 SUM(total_tokens) as total,
-                COUNT(*) as count
+                COUNT(*) as count,
+                SUM(cost) as cost
          FROM token_usage
          WHERE timestamp >= ?1
          GROUP BY model
@@ -376,6 +435,7 @@ pub fn get_model_stats(hours: i64) -> Result<Vec<ModelTokenStats>, String> {
                 total_output_tokens: row.get(2)?,
                 total_tokens: row.get(3)?,
                 request_count: row.get(4)?,
+                total_cost: row.get(5)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -553,10 +613,172 @@ pub fn get_account_trend_daily(days: i64) -> Result<Vec<AccountTrendPoint>, Stri
         .collect())
 }
 
+/// Cost breakdown dimension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CostGroupBy {
+    /// User token username
+    User,
+    Account,
+    #[default]
+    Model,
+    Day,
+}
+
+impl CostGroupBy {
+    fn key_sql(self) -> &'static str {
+        match self {
+            Self::User => "COALESCE(username, '')",
+            Self::Account => "account_email",
+            Self::Model => "model",
+            Self::Day => "strftime('%Y-%m-%d', datetime(timestamp, 'unixepoch', 'localtime'))",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Account => "account",
+            Self::Model => "model",
+            Self::Day => "day",
+        }
+    }
+}
+
+/// Cost breakdown row
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CostBreakdown {
+    /// Group key (username / account email / model / day); empty username means no user token
+    pub key: String,
+    pub request_count: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cached_tokens: u64,
+    pub thinking_tokens: u64,
+    pub total_cost: f64,
+}
+
+fn cost_breakdown_with(
+    conn: &Connection,
+    cutoff: i64,
+    group_by: CostGroupBy,
+) -> Result<Vec<CostBreakdown>, String> {
+    let order = if group_by == CostGroupBy::Day { "key ASC" } else { "cost DESC, key ASC" };
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} as key,
+                COUNT(*) as count,
+                SUM(input_tokens),
+                SUM(output_tokens),
+                SUM(cached_tokens),
+                SUM(thinking_tokens),
+                SUM(cost) as cost
+         FROM token_usage
+         WHERE timestamp >= ?1
+         GROUP BY key
+         ORDER BY {}",
+            group_by.key_sql(),
+            order
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([cutoff], |row| {
+            Ok(CostBreakdown {
+                key: row.get(0)?,
+                request_count: row.get(1)?,
+                input_tokens: row.get(2)?,
+                output_tokens: row.get(3)?,
+                cached_tokens: row.get(4)?,
+                thinking_tokens: row.get(5)?,
+                total_cost: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Get cost breakdown grouped by user token, account, model or day
+pub fn get_cost_breakdown(hours: i64, group_by: CostGroupBy) -> Result<Vec<CostBreakdown>, String> {
+    let conn = connect_db()?;
+    let cutoff = chrono::Local::now().timestamp() - (hours * 3600);
+    cost_breakdown_with(&conn, cutoff, group_by)
+}
+
+/// Render a cost breakdown as CSV (for internal chargeback)
+pub fn cost_breakdown_csv(rows: &[CostBreakdown], group_by: CostGroupBy, currency: &str) -> String {
+    let escape = |value: &str| {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    };
+    let mut out = format!(
+        "{},requests,input_tokens,output_tokens,cached_tokens,thinking_tokens,cost_{}\n",
+        group_by.label(),
+        currency.to_ascii_lowercase()
+    );
+    for row in rows {
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{:.6}\n",
+            escape(&row.key),
+            row.request_count,
+            row.input_tokens,
+            row.output_tokens,
+            row.cached_tokens,
+            row.thinking_tokens,
+            row.total_cost
+        ));
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_cost_breakdown_groups_and_csv() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_schema(&conn).unwrap();
+        let now = chrono::Local::now();
+        let record = |account: &str, model: &str, user: Option<&str>, cost: f64| UsageRecord {
+            account_email: account.to_string(),
+            model: model.to_string(),
+            username: user.map(str::to_string),
+            input_tokens: 100,
+            output_tokens: 50,
+            cached_tokens: 10,
+            thinking_tokens: 5,
+            cost,
+        };
+        insert_usage(&conn, &record("a@x.com", "gemini-2.5-pro", Some("team,a"), 1.5), now).unwrap();
+        insert_usage(&conn, &record("a@x.com", "gemini-2.5-flash", Some("team,a"), 0.5), now).unwrap();
+        insert_usage(&conn, &record("b@x.com", "gemini-2.5-pro", None, 3.0), now).unwrap();
+
+        let by_user = cost_breakdown_with(&conn, 0, CostGroupBy::User).unwrap();
+        assert_eq!(by_user.len(), 2);
+        assert_eq!(by_user[0].key, "");
+        assert!((by_user[0].total_cost - 3.0).abs() < 1e-9);
+        assert_eq!(by_user[1].request_count, 2);
+        assert_eq!(by_user[1].cached_tokens, 20);
+
+        let by_model = cost_breakdown_with(&conn, 0, CostGroupBy::Model).unwrap();
+        assert_eq!(by_model[0].key, "gemini-2.5-pro");
+        assert!((by_model[0].total_cost - 4.5).abs() < 1e-9);
+
+        let hourly_cost: f64 = conn
+            .query_row("SELECT SUM(total_cost) FROM token_stats_hourly", [], |row| row.get(0))
+            .unwrap();
+        assert!((hourly_cost - 5.0).abs() < 1e-9);
+
+        let csv = cost_breakdown_csv(&by_user, CostGroupBy::User, "USD");
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "user,requests,input_tokens,output_tokens,cached_tokens,thinking_tokens,cost_usd");
+        assert_eq!(lines[2], "\"team,a\",2,200,100,20,10,2.000000");
+    }
+
     #[test]
     fn test_record_and_query() {
         // This would need a test database setup
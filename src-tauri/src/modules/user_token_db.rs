@@ -4,16 +4,36 @@
 #![allow(dead_code)]
 // 用户令牌存储，部分接口留作后续扩展
 
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use sha2::Sha256;
+use std::collections::HashSet;
+use std::num::NonZeroUsize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use uuid::Uuid;
 use chrono::{Utc, Local, Timelike, FixedOffset};
 
+/// 数据库中令牌哈希的前缀 (未带前缀的值为旧版明文令牌)
+const TOKEN_HASH_PREFIX: &str = "hmac-sha256:";
+/// 保留的可见前缀长度 (`sk-` + 8 位)，用于在界面中识别令牌
+const VISIBLE_PREFIX_LEN: usize = 11;
+/// 令牌不存在时的拒绝原因
+pub const INVALID_TOKEN_REASON: &str = "Invalid token. Please check your API key.";
+/// 已验证令牌的内存缓存容量
+const TOKEN_CACHE_SIZE: usize = 1024;
+
 /// 用户令牌结构体
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserToken {
     pub id: String,
+    /// 完整令牌仅在创建时返回一次，其余情况下为可见前缀 (如 `sk-1a2b3c4d`)
     pub token: String,
     pub username: String,
     pub description: Option<String>,
@@ -28,6 +48,47 @@ pub struct UserToken {
     pub last_used_at: Option<i64>,
     pub total_requests: i64,
     pub total_tokens_used: i64,
+    #[serde(default)]
+    pub response_cache: bool,         // 是否启用响应缓存
+    #[serde(default)]
+    pub log_metadata_only: bool,      // 日志仅记录元数据 (不保存请求/响应体)
+    #[serde(flatten)]
+    pub restrictions: TokenRestrictions,
+}
+
+/// 令牌权限范围与能力限制
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TokenRestrictions {
+    /// 允许的权限范围 (如 `openai:chat`、`gemini:*`、`images`)，为空表示不限制
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// 禁止工具调用
+    #[serde(default)]
+    pub deny_tools: bool,
+    /// 禁止图像生成
+    #[serde(default)]
+    pub deny_image_generation: bool,
+    /// 禁止思考 (thinking / reasoning)
+    #[serde(default)]
+    pub deny_thinking: bool,
+}
+
+impl TokenRestrictions {
+    fn from_row(row: &rusqlite::Row) -> Self {
+        let scopes: Option<String> = row.get("scopes").unwrap_or(None);
+        Self {
+            scopes: scopes
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            deny_tools: row.get("deny_tools").unwrap_or(false),
+            deny_image_generation: row.get("deny_image_generation").unwrap_or(false),
+            deny_thinking: row.get("deny_thinking").unwrap_or(false),
+        }
+    }
+
+    fn scopes_json(&self) -> String {
+        serde_json::to_string(&self.scopes).unwrap_or_else(|_| "[]".to_string())
+    }
 }
 
 /// 令牌 IP 绑定结构体
@@ -55,6 +116,104 @@ pub struct TokenUsageLog {
     pub status: u16,
 }
 
+/// 已验证令牌缓存项
+struct CachedToken {
+    token: UserToken,
+    /// 已确认绑定的 IP，命中时无需再查询 token_ip_bindings
+    bound_ips: HashSet<String>,
+}
+
+/// 以令牌哈希为键的 LRU 缓存，避免每次请求都查询 SQLite
+static VALIDATED_TOKENS: Lazy<Mutex<LruCache<String, CachedToken>>> =
+    Lazy::new(|| Mutex::new(LruCache::new(NonZeroUsize::new(TOKEN_CACHE_SIZE).unwrap())));
+
+static TOKEN_HASH_KEY: OnceLock<Vec<u8>> = OnceLock::new();
+/// 串行化首次加载，避免并发生成两把不同的密钥
+static TOKEN_HASH_KEY_INIT: Mutex<()> = Mutex::new(());
+
+/// 原子写入新生成的密钥文件 (临时文件 + 重命名，Unix 下权限 0600)
+fn write_key_file(path: &Path, content: &str) -> Result<(), String> {
+    let temp_path = path.with_extension(format!("key.tmp.{}", Uuid::new_v4()));
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let result = options
+        .open(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(content.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|_| std::fs::rename(&temp_path, path));
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(format!("Failed to write token hash key: {}", e));
+    }
+    Ok(())
+}
+
+/// 获取令牌哈希密钥: 优先使用 ABV_TOKEN_HASH_KEY，否则读取/生成数据目录下的 user_token_hash.key
+/// 仅在密钥文件不存在时生成新密钥；其他读取错误直接返回，避免覆盖后所有令牌哈希失效
+fn token_hash_key() -> Result<&'static [u8], String> {
+    if let Some(key) = TOKEN_HASH_KEY.get() {
+        return Ok(key);
+    }
+    let _init = TOKEN_HASH_KEY_INIT.lock();
+    if let Some(key) = TOKEN_HASH_KEY.get() {
+        return Ok(key);
+    }
+
+    let key = match std::env::var("ABV_TOKEN_HASH_KEY").or_else(|_| std::env::var("TOKEN_HASH_KEY")) {
+        Ok(key) if !key.is_empty() => key.into_bytes(),
+        _ => {
+            let path = crate::modules::account::get_data_dir()?.join("user_token_hash.key");
+            match std::fs::read_to_string(&path) {
+                Ok(content) => general_purpose::STANDARD
+                    .decode(content.trim())
+                    .map_err(|e| format!("Invalid token hash key: {}", e))?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    let key: [u8; 32] = rand::random();
+                    write_key_file(&path, &general_purpose::STANDARD.encode(key))?;
+                    key.to_vec()
+                }
+                Err(e) => return Err(format!("Failed to read token hash key {}: {}", path.display(), e)),
+            }
+        }
+    };
+
+    Ok(TOKEN_HASH_KEY.get_or_init(|| key))
+}
+
+fn hash_token_with_key(key: &[u8], token: &str) -> String {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(token.as_bytes());
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("{}{}", TOKEN_HASH_PREFIX, digest)
+}
+
+/// 计算令牌的 keyed hash (HMAC-SHA256)
+pub fn hash_token(token: &str) -> Result<String, String> {
+    Ok(hash_token_with_key(token_hash_key()?, token))
+}
+
+/// 令牌的可见前缀
+fn visible_prefix(token: &str) -> String {
+    token.chars().take(VISIBLE_PREFIX_LEN).collect()
+}
+
+/// 清空已验证令牌缓存 (令牌被修改/删除/续期后调用)
+fn invalidate_token_cache() {
+    VALIDATED_TOKENS.lock().clear();
+}
+
 /// 获取数据库路径
 pub fn get_db_path() -> Result<PathBuf, String> {
     let mut path = crate::modules::account::get_data_dir()?;
@@ -105,6 +264,16 @@ pub fn init_db() -> Result<(), String> {
     let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN last_used_at INTEGER", []);
     let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN curfew_start TEXT", []);
     let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN curfew_end TEXT", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN response_cache INTEGER DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN log_metadata_only INTEGER DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN token_prefix TEXT", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN scopes TEXT", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN deny_tools INTEGER DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN deny_image_generation INTEGER DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN deny_thinking INTEGER DEFAULT 0", []);
+
+    // 将旧版明文令牌迁移为哈希存储
+    migrate_plaintext_tokens(&conn)?;
 
     // 创建 token_ip_bindings 表
     conn.execute(
@@ -153,7 +322,33 @@ pub fn init_db() -> Result<(), String> {
     Ok(())
 }
 
+/// 将明文存储的令牌替换为 keyed hash，并保留可见前缀，返回迁移的行数
+fn migrate_plaintext_tokens(conn: &Connection) -> Result<usize, String> {
+    let plaintext: Vec<(String, String)> = {
+        let mut stmt = conn
+            .prepare("SELECT id, token FROM user_tokens WHERE token NOT LIKE 'hmac-sha256:%'")
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("Failed to query tokens: {}", e))?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+
+    for (id, token) in &plaintext {
+        conn.execute(
+            "UPDATE user_tokens SET token = ?1, token_prefix = ?2 WHERE id = ?3",
+            params![hash_token(token)?, visible_prefix(token), id],
+        ).map_err(|e| format!("Failed to migrate user token: {}", e))?;
+    }
+
+    if !plaintext.is_empty() {
+        tracing::info!("[UserToken] Migrated {} plaintext token(s) to hashed storage", plaintext.len());
+    }
+    Ok(plaintext.len())
+}
+
 /// 创建新令牌
+#[allow(clippy::too_many_arguments)]
 pub fn create_token(
     username: String,
     expires_type: String,
@@ -161,7 +356,10 @@ pub fn create_token(
     max_ips: i32,
     curfew_start: Option<String>,
     curfew_end: Option<String>,
-    custom_expires_at: Option<i64>  // 自定义过期时间戳 (秒)
+    custom_expires_at: Option<i64>,  // 自定义过期时间戳 (秒)
+    response_cache: bool,
+    log_metadata_only: bool,
+    restrictions: TokenRestrictions,
 ) -> Result<UserToken, String> {
     let conn = connect_db()?;
     let id = Uuid::new_v4().to_string();
@@ -192,17 +390,22 @@ pub fn create_token(
         last_used_at: None,
         total_requests: 0,
         total_tokens_used: 0,
+        response_cache,
+        log_metadata_only,
+        restrictions,
     };
 
+    // 数据库仅保存哈希与可见前缀，完整令牌只在本次返回
     conn.execute(
         "INSERT INTO user_tokens (
             id, token, username, description, enabled, expires_type, expires_at, max_ips,
             curfew_start, curfew_end,
-            created_at, updated_at, total_requests, total_tokens_used
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            created_at, updated_at, total_requests, total_tokens_used, response_cache, log_metadata_only,
+            token_prefix, scopes, deny_tools, deny_image_generation, deny_thinking
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
         params![
             user_token.id,
-            user_token.token,
+            hash_token(&token)?,
             user_token.username,
             user_token.description,
             user_token.enabled,
@@ -215,6 +418,13 @@ pub fn create_token(
             user_token.updated_at,
             user_token.total_requests,
             user_token.total_tokens_used,
+            user_token.response_cache,
+            user_token.log_metadata_only,
+            visible_prefix(&token),
+            user_token.restrictions.scopes_json(),
+            user_token.restrictions.deny_tools,
+            user_token.restrictions.deny_image_generation,
+            user_token.restrictions.deny_thinking,
         ],
     ).map_err(|e| format!("Failed to insert user token: {}", e))?;
 
@@ -230,7 +440,7 @@ pub fn list_tokens() -> Result<Vec<UserToken>, String> {
     let token_iter = stmt.query_map([], |row| {
         Ok(UserToken {
             id: row.get("id")?,
-            token: row.get("token")?,
+            token: row.get::<_, Option<String>>("token_prefix")?.unwrap_or_default(),
             username: row.get("username")?,
             description: row.get("description")?,
             enabled: row.get("enabled").unwrap_or(true), // 防御性默认值
@@ -244,6 +454,9 @@ pub fn list_tokens() -> Result<Vec<UserToken>, String> {
             last_used_at: row.get("last_used_at").unwrap_or(None),
             total_requests: row.get("total_requests").unwrap_or(0),
             total_tokens_used: row.get("total_tokens_used").unwrap_or(0),
+            response_cache: row.get("response_cache").unwrap_or(false),
+            log_metadata_only: row.get("log_metadata_only").unwrap_or(false),
+            restrictions: TokenRestrictions::from_row(row),
         })
     }).map_err(|e| format!("Failed to query tokens: {}", e))?;
 
@@ -264,7 +477,7 @@ pub fn get_token_by_id(id: &str) -> Result<Option<UserToken>, String> {
     let token = stmt.query_row(params![id], |row| {
         Ok(UserToken {
             id: row.get("id")?,
-            token: row.get("token")?,
+            token: row.get::<_, Option<String>>("token_prefix")?.unwrap_or_default(),
             username: row.get("username")?,
             description: row.get("description")?,
             enabled: row.get("enabled")?,
@@ -278,22 +491,44 @@ pub fn get_token_by_id(id: &str) -> Result<Option<UserToken>, String> {
             last_used_at: row.get("last_used_at")?,
             total_requests: row.get("total_requests")?,
             total_tokens_used: row.get("total_tokens_used")?,
+            response_cache: row.get("response_cache").unwrap_or(false),
+            log_metadata_only: row.get("log_metadata_only").unwrap_or(false),
+            restrictions: TokenRestrictions::from_row(row),
         })
     }).optional().map_err(|e| format!("Failed to query token: {}", e))?;
     
     Ok(token)
 }
 
-/// 根据 Token 值获取令牌信息
+/// 根据 Token 值获取令牌信息 (优先命中已验证令牌缓存)
 pub fn get_token_by_value(token: &str) -> Result<Option<UserToken>, String> {
+    get_token_by_hash(&hash_token(token)?)
+}
+
+fn get_token_by_hash(hash: &str) -> Result<Option<UserToken>, String> {
+    if let Some(cached) = VALIDATED_TOKENS.lock().get(hash) {
+        return Ok(Some(cached.token.clone()));
+    }
+
+    let token = query_token_by_hash(hash)?;
+    if let Some(found) = &token {
+        VALIDATED_TOKENS.lock().put(
+            hash.to_string(),
+            CachedToken { token: found.clone(), bound_ips: HashSet::new() },
+        );
+    }
+    Ok(token)
+}
+
+fn query_token_by_hash(hash: &str) -> Result<Option<UserToken>, String> {
     let conn = connect_db()?;
     let mut stmt = conn.prepare("SELECT * FROM user_tokens WHERE token = ?1")
         .map_err(|e| format!("Failed to prepare query: {}", e))?;
     
-    let token = stmt.query_row(params![token], |row| {
+    let token = stmt.query_row(params![hash], |row| {
         Ok(UserToken {
             id: row.get("id")?,
-            token: row.get("token")?,
+            token: row.get::<_, Option<String>>("token_prefix")?.unwrap_or_default(),
             username: row.get("username")?,
             description: row.get("description")?,
             enabled: row.get("enabled")?,
@@ -307,6 +542,9 @@ pub fn get_token_by_value(token: &str) -> Result<Option<UserToken>, String> {
             last_used_at: row.get("last_used_at")?,
             total_requests: row.get("total_requests")?,
             total_tokens_used: row.get("total_tokens_used")?,
+            response_cache: row.get("response_cache").unwrap_or(false),
+            log_metadata_only: row.get("log_metadata_only").unwrap_or(false),
+            restrictions: TokenRestrictions::from_row(row),
         })
     }).optional().map_err(|e| format!("Failed to query token: {}", e))?;
     
@@ -314,6 +552,7 @@ pub fn get_token_by_value(token: &str) -> Result<Option<UserToken>, String> {
 }
 
 /// 更新令牌状态/备注等
+#[allow(clippy::too_many_arguments)]
 pub fn update_token(
     id: &str,
     username: Option<String>,
@@ -321,7 +560,10 @@ pub fn update_token(
     enabled: Option<bool>,
     max_ips: Option<i32>,
     curfew_start: Option<Option<String>>,
-    curfew_end: Option<Option<String>>
+    curfew_end: Option<Option<String>>,
+    response_cache: Option<bool>,
+    log_metadata_only: Option<bool>,
+    restrictions: Option<TokenRestrictions>,
 ) -> Result<(), String> {
     let conn = connect_db()?;
     let now = Utc::now().timestamp();
@@ -366,6 +608,33 @@ pub fn update_token(
         param_idx += 1;
     }
 
+    if let Some(cache) = response_cache {
+        query.push_str(&format!(", response_cache = ?{}", param_idx));
+        params_vec.push(Box::new(cache));
+        param_idx += 1;
+    }
+
+    if let Some(metadata_only) = log_metadata_only {
+        query.push_str(&format!(", log_metadata_only = ?{}", param_idx));
+        params_vec.push(Box::new(metadata_only));
+        param_idx += 1;
+    }
+
+    if let Some(restrictions) = restrictions {
+        query.push_str(&format!(
+            ", scopes = ?{}, deny_tools = ?{}, deny_image_generation = ?{}, deny_thinking = ?{}",
+            param_idx,
+            param_idx + 1,
+            param_idx + 2,
+            param_idx + 3
+        ));
+        params_vec.push(Box::new(restrictions.scopes_json()));
+        params_vec.push(Box::new(restrictions.deny_tools));
+        params_vec.push(Box::new(restrictions.deny_image_generation));
+        params_vec.push(Box::new(restrictions.deny_thinking));
+        param_idx += 4;
+    }
+
     query.push_str(&format!(" WHERE id = ?{}", param_idx));
     params_vec.push(Box::new(id.to_string()));
 
@@ -374,6 +643,7 @@ pub fn update_token(
 
     conn.execute(&query, params_refs.as_slice())
         .map_err(|e| format!("Failed to update user token: {}", e))?;
+    invalidate_token_cache();
 
     Ok(())
 }
@@ -394,6 +664,7 @@ pub fn renew_token(id: &str, expires_type: &str) -> Result<(), String> {
         "UPDATE user_tokens SET expires_type = ?1, expires_at = ?2, updated_at = ?3, enabled = 1 WHERE id = ?4",
         params![expires_type, expires_at, now, id],
     ).map_err(|e| format!("Failed to renew token: {}", e))?;
+    invalidate_token_cache();
     
     Ok(())
 }
@@ -403,6 +674,7 @@ pub fn delete_token(id: &str) -> Result<(), String> {
     let conn = connect_db()?;
     conn.execute("DELETE FROM user_tokens WHERE id = ?1", params![id])
         .map_err(|e| format!("Failed to delete token: {}", e))?;
+    invalidate_token_cache();
     Ok(())
 }
 
@@ -501,7 +773,8 @@ pub fn record_token_usage_and_ip(
 /// 检查 Token 是否有效 (包含过期时间检查和 IP 限制检查)
 /// 返回: (是否有效, 拒绝原因)
 pub fn validate_token(token_str: &str, ip: &str) -> Result<(bool, Option<String>), String> {
-    let token_opt = get_token_by_value(token_str)?;
+    let hash = hash_token(token_str)?;
+    let token_opt = get_token_by_hash(&hash)?;
 
     if let Some(token) = token_opt {
         // 1. 检查过期时间
@@ -512,7 +785,11 @@ pub fn validate_token(token_str: &str, ip: &str) -> Result<(bool, Option<String>
         }
 
         // 2. 检查 IP 限制
-        if token.max_ips > 0 {
+        let cached_bound = VALIDATED_TOKENS
+            .lock()
+            .peek(&hash)
+            .is_some_and(|cached| cached.bound_ips.contains(ip));
+        if token.max_ips > 0 && !cached_bound {
             let conn = connect_db()?;
 
             // 检查当前 IP 是否已绑定
@@ -522,7 +799,11 @@ pub fn validate_token(token_str: &str, ip: &str) -> Result<(bool, Option<String>
                 |row| row.get(0)
             ).unwrap_or(false);
 
-            if !is_bound {
+            if is_bound {
+                if let Some(cached) = VALIDATED_TOKENS.lock().get_mut(&hash) {
+                    cached.bound_ips.insert(ip.to_string());
+                }
+            } else {
                 // 如果未绑定，检查是否达到上限
                 let current_ip_count: i32 = conn.query_row(
                     "SELECT COUNT(*) FROM token_ip_bindings WHERE token_id = ?1",
@@ -563,7 +844,7 @@ pub fn validate_token(token_str: &str, ip: &str) -> Result<(bool, Option<String>
         // 一切正常，Token 有效
         Ok((true, None))
     } else {
-        Ok((false, Some("Invalid token. Please check your API key.".to_string())))
+        Ok((false, Some(INVALID_TOKEN_REASON.to_string())))
     }
 }
 
@@ -595,7 +876,7 @@ mod tests {
         
         // Use a random username to avoid collisions in existing DB runs during dev
         let username = format!("TestUser_{}", Uuid::new_v4());
-        let token_res = create_token(username.clone(), "day".to_string(), Some("Test token".to_string()), 0, None, None, None);
+        let token_res = create_token(username.clone(), "day".to_string(), Some("Test token".to_string()), 0, None, None, None, false, false, TokenRestrictions::default());
         assert!(token_res.is_ok());
 
         let token = token_res.unwrap();
@@ -604,6 +885,44 @@ mod tests {
         
         let fetched = get_token_by_id(&token.id);
         assert!(fetched.is_ok());
-        assert_eq!(fetched.unwrap().unwrap().username, username);
+        let fetched = fetched.unwrap().unwrap();
+        assert_eq!(fetched.username, username);
+        // 完整令牌不再可读，只保留可见前缀
+        assert_eq!(fetched.token, visible_prefix(&token.token));
+
+        let by_value = get_token_by_value(&token.token).unwrap().unwrap();
+        assert_eq!(by_value.id, token.id);
+        assert!(get_token_by_value("sk-not-a-real-token").unwrap().is_none());
+
+        delete_token(&token.id).unwrap();
+        assert!(get_token_by_value(&token.token).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_write_key_file_replaces_atomically() {
+        let dir = std::env::temp_dir().join(format!("ag_token_key_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("user_token_hash.key");
+        write_key_file(&path, "first").unwrap();
+        write_key_file(&path, "second").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+        // 不残留临时文件
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_hash_token_is_keyed() {
+        let a = hash_token_with_key(b"key-a", "sk-123");
+        assert!(a.starts_with(TOKEN_HASH_PREFIX));
+        assert_eq!(a, hash_token_with_key(b"key-a", "sk-123"));
+        assert_ne!(a, hash_token_with_key(b"key-b", "sk-123"));
+        assert_ne!(a, hash_token_with_key(b"key-a", "sk-124"));
     }
 }
@@ -0,0 +1,620 @@
+// 告警规则与通知投递
+//
+// 账号被禁用、403 验证拦截、配额保护、模型无可用账号、代理池节点不健康等事件
+// 按规则的阈值 / 时间窗口 / 冷却时间判定后，投递到 Webhook (通用 / Slack / Discord / 飞书)、
+// ntfy、Gotify 或 SMTP 邮件通道，每次投递结果记入 alerts.db。
+
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::Duration;
+
+use crate::modules::alert_db::{self, AlertDelivery};
+
+/// 单次投递超时
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 告警事件类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    /// 账号因 invalid_grant 被禁用
+    AccountDisabled,
+    /// 账号被上游 403 验证拦截
+    ValidationBlocked,
+    /// 账号触发配额保护
+    QuotaProtection,
+    /// 某模型所有账号均不可用
+    AccountsExhausted,
+    /// 代理池节点健康检查失败
+    ProxyUnhealthy,
+}
+
+impl AlertKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::AccountDisabled => "account_disabled",
+            Self::ValidationBlocked => "validation_blocked",
+            Self::QuotaProtection => "quota_protection",
+            Self::AccountsExhausted => "accounts_exhausted",
+            Self::ProxyUnhealthy => "proxy_unhealthy",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Self::AccountDisabled => "Account disabled",
+            Self::ValidationBlocked => "Account validation blocked",
+            Self::QuotaProtection => "Quota protection triggered",
+            Self::AccountsExhausted => "All accounts exhausted",
+            Self::ProxyUnhealthy => "Proxy node unhealthy",
+        }
+    }
+}
+
+/// Webhook 消息模板
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookTemplate {
+    #[default]
+    Generic,
+    Slack,
+    Discord,
+    Feishu,
+}
+
+/// SMTP 连接加密方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    #[default]
+    Starttls,
+    Tls,
+    None,
+}
+
+/// 通道目标
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ChannelTarget {
+    Webhook {
+        url: String,
+        #[serde(default)]
+        template: WebhookTemplate,
+        /// 附加请求头 (如鉴权)，值加密保存
+        #[serde(
+            default,
+            serialize_with = "crate::utils::crypto::serialize_password_map",
+            deserialize_with = "crate::utils::crypto::deserialize_password_map"
+        )]
+        headers: HashMap<String, String>,
+    },
+    Ntfy {
+        #[serde(default = "default_ntfy_server")]
+        server: String,
+        topic: String,
+        #[serde(
+            default,
+            serialize_with = "crate::utils::crypto::serialize_optional_password",
+            deserialize_with = "crate::utils::crypto::deserialize_optional_password"
+        )]
+        token: Option<String>,
+    },
+    Gotify {
+        server: String,
+        #[serde(
+            serialize_with = "crate::utils::crypto::serialize_password",
+            deserialize_with = "crate::utils::crypto::deserialize_password"
+        )]
+        token: String,
+    },
+    Smtp {
+        host: String,
+        #[serde(default = "default_smtp_port")]
+        port: u16,
+        #[serde(default)]
+        security: SmtpSecurity,
+        #[serde(default)]
+        username: Option<String>,
+        #[serde(
+            default,
+            serialize_with = "crate::utils::crypto::serialize_optional_password",
+            deserialize_with = "crate::utils::crypto::deserialize_optional_password"
+        )]
+        password: Option<String>,
+        from: String,
+        to: Vec<String>,
+    },
+}
+
+/// 通知通道
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AlertChannel {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub target: ChannelTarget,
+}
+
+/// 告警规则：同一对象在 window_secs 内发生 threshold 次事件时触发，
+/// 触发后 cooldown_secs 内不再重复通知
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AlertRule {
+    pub id: String,
+    pub kind: AlertKind,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_threshold")]
+    pub threshold: u32,
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+    /// 投递的通道 ID，为空时投递到所有启用的通道
+    #[serde(default)]
+    pub channels: Vec<String>,
+}
+
+/// 告警配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AlertsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub channels: Vec<AlertChannel>,
+    #[serde(default = "default_rules")]
+    pub rules: Vec<AlertRule>,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            channels: Vec::new(),
+            rules: default_rules(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_ntfy_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+fn default_threshold() -> u32 {
+    1
+}
+
+fn default_window_secs() -> u64 {
+    300
+}
+
+fn default_cooldown_secs() -> u64 {
+    1800
+}
+
+fn default_rules() -> Vec<AlertRule> {
+    let rule = |kind: AlertKind, threshold: u32| AlertRule {
+        id: kind.as_str().to_string(),
+        kind,
+        enabled: true,
+        threshold,
+        window_secs: default_window_secs(),
+        cooldown_secs: default_cooldown_secs(),
+        channels: Vec::new(),
+    };
+    vec![
+        rule(AlertKind::AccountDisabled, 1),
+        rule(AlertKind::ValidationBlocked, 1),
+        rule(AlertKind::QuotaProtection, 1),
+        // 偶发的无可用账号通常会随限流恢复，连续出现才通知
+        rule(AlertKind::AccountsExhausted, 3),
+        rule(AlertKind::ProxyUnhealthy, 1),
+    ]
+}
+
+static GLOBAL_ALERTS_CONFIG: OnceLock<RwLock<AlertsConfig>> = OnceLock::new();
+
+/// 获取当前告警配置
+pub fn get_alerts_config() -> AlertsConfig {
+    GLOBAL_ALERTS_CONFIG
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+/// 更新全局告警配置
+pub fn update_alerts_config(config: AlertsConfig) {
+    if let Some(lock) = GLOBAL_ALERTS_CONFIG.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = config.clone();
+        }
+    } else {
+        let _ = GLOBAL_ALERTS_CONFIG.set(RwLock::new(config.clone()));
+    }
+    tracing::info!(
+        "[Alerts] Config updated: enabled={}, channels={}, rules={}",
+        config.enabled,
+        config.channels.len(),
+        config.rules.len()
+    );
+}
+
+/// 规则在某个对象上的触发状态
+#[derive(Debug, Default)]
+struct RuleState {
+    events: VecDeque<i64>,
+    last_fired: Option<i64>,
+}
+
+impl RuleState {
+    /// 记录一次事件 (时间单位：秒)，返回是否应当触发通知
+    fn record(&mut self, rule: &AlertRule, now: i64) -> bool {
+        let window = rule.window_secs as i64;
+        while self.events.front().is_some_and(|t| now - t >= window) {
+            self.events.pop_front();
+        }
+        self.events.push_back(now);
+
+        if (self.events.len() as u32) < rule.threshold.max(1) {
+            return false;
+        }
+        if self
+            .last_fired
+            .is_some_and(|t| now - t < rule.cooldown_secs as i64)
+        {
+            return false;
+        }
+        self.last_fired = Some(now);
+        self.events.clear();
+        true
+    }
+}
+
+static RULE_STATES: Lazy<Mutex<HashMap<(String, String), RuleState>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 一次待投递的告警
+#[derive(Debug, Clone)]
+struct Alert {
+    rule_id: Option<String>,
+    kind: AlertKind,
+    subject: String,
+    message: String,
+    timestamp: i64,
+}
+
+impl Alert {
+    fn title(&self) -> String {
+        format!("[Antigravity] {}: {}", self.kind.title(), self.subject)
+    }
+}
+
+/// 上报一次事件。subject 为事件对象 (账号邮箱 / 模型名 / 代理名)。
+/// 同步调用，命中规则时在后台异步投递
+pub fn emit(kind: AlertKind, subject: &str, message: &str) {
+    let config = get_alerts_config();
+    if !config.enabled || config.channels.is_empty() {
+        return;
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let fired: Vec<AlertRule> = {
+        let Ok(mut states) = RULE_STATES.lock() else {
+            return;
+        };
+        config
+            .rules
+            .iter()
+            .filter(|rule| rule.enabled && rule.kind == kind)
+            .filter(|rule| {
+                states
+                    .entry((rule.id.clone(), subject.to_string()))
+                    .or_default()
+                    .record(rule, now)
+            })
+            .cloned()
+            .collect()
+    };
+    if fired.is_empty() {
+        return;
+    }
+
+    let Ok(handle) = tokio::runtime::Handle::try_current() else {
+        tracing::warn!("[Alerts] No async runtime, dropping alert {}: {}", kind.as_str(), subject);
+        return;
+    };
+
+    for rule in fired {
+        let channels: Vec<AlertChannel> = config
+            .channels
+            .iter()
+            .filter(|c| c.enabled && (rule.channels.is_empty() || rule.channels.contains(&c.id)))
+            .cloned()
+            .collect();
+        let alert = Alert {
+            rule_id: Some(rule.id.clone()),
+            kind,
+            subject: subject.to_string(),
+            message: message.to_string(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        };
+        tracing::info!(
+            "[Alerts] Rule {} fired for {} ({} channels)",
+            rule.id,
+            subject,
+            channels.len()
+        );
+        handle.spawn(async move {
+            for channel in channels {
+                let _ = deliver_and_record(&channel, &alert).await;
+            }
+        });
+    }
+}
+
+/// 向指定通道发送测试通知 (不经过规则判定)
+pub async fn send_test(channel: &AlertChannel) -> Result<(), String> {
+    let alert = Alert {
+        rule_id: None,
+        kind: AlertKind::AccountDisabled,
+        subject: "test@example.com".to_string(),
+        message: "This is a test notification from Antigravity Tools.".to_string(),
+        timestamp: chrono::Utc::now().timestamp_millis(),
+    };
+    deliver_and_record(channel, &alert).await
+}
+
+async fn deliver_and_record(channel: &AlertChannel, alert: &Alert) -> Result<(), String> {
+    let result = deliver(channel, alert).await;
+    if let Err(e) = &result {
+        tracing::warn!("[Alerts] Delivery to {} failed: {}", channel.name, e);
+    }
+
+    let delivery = AlertDelivery {
+        id: 0,
+        timestamp: alert.timestamp,
+        rule_id: alert.rule_id.clone(),
+        kind: alert.kind.as_str().to_string(),
+        subject: alert.subject.clone(),
+        message: alert.message.clone(),
+        channel_id: channel.id.clone(),
+        channel_name: channel.name.clone(),
+        success: result.is_ok(),
+        error: result.as_ref().err().cloned(),
+    };
+    let _ = tokio::task::spawn_blocking(move || {
+        if let Err(e) = alert_db::record_delivery(&delivery) {
+            tracing::warn!("[Alerts] Failed to record delivery: {}", e);
+        }
+    })
+    .await;
+    result
+}
+
+async fn deliver(channel: &AlertChannel, alert: &Alert) -> Result<(), String> {
+    match &channel.target {
+        ChannelTarget::Webhook { url, template, headers } => {
+            let mut request = crate::utils::http::get_standard_client()
+                .post(url)
+                .timeout(DELIVERY_TIMEOUT)
+                .json(&webhook_payload(*template, alert));
+            for (name, value) in headers {
+                request = request.header(name.as_str(), value.as_str());
+            }
+            check_response(request.send().await.map_err(|e| e.to_string())?).await
+        }
+        ChannelTarget::Ntfy { server, topic, token } => {
+            let url = format!("{}/{}", server.trim_end_matches('/'), topic);
+            let mut request = crate::utils::http::get_standard_client()
+                .post(url)
+                .timeout(DELIVERY_TIMEOUT)
+                .header("Title", alert.title())
+                .header("Tags", alert.kind.as_str())
+                .body(alert.message.clone());
+            if let Some(token) = token.as_deref().filter(|t| !t.is_empty()) {
+                request = request.bearer_auth(token);
+            }
+            check_response(request.send().await.map_err(|e| e.to_string())?).await
+        }
+        ChannelTarget::Gotify { server, token } => {
+            let url = format!("{}/message", server.trim_end_matches('/'));
+            let request = crate::utils::http::get_standard_client()
+                .post(url)
+                .timeout(DELIVERY_TIMEOUT)
+                .header("X-Gotify-Key", token.as_str())
+                .json(&json!({
+                    "title": alert.title(),
+                    "message": alert.message,
+                    "priority": 8,
+                }));
+            check_response(request.send().await.map_err(|e| e.to_string())?).await
+        }
+        ChannelTarget::Smtp {
+            host,
+            port,
+            security,
+            username,
+            password,
+            from,
+            to,
+        } => {
+            let from: Mailbox = from.parse().map_err(|e| format!("Invalid sender: {}", e))?;
+            let mut builder = Message::builder().from(from).subject(alert.title());
+            for recipient in to {
+                let mailbox: Mailbox = recipient
+                    .parse()
+                    .map_err(|e| format!("Invalid recipient {}: {}", recipient, e))?;
+                builder = builder.to(mailbox);
+            }
+            let email = builder
+                .body(alert.message.clone())
+                .map_err(|e| e.to_string())?;
+
+            let transport = match security {
+                SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host),
+                SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host),
+                SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)),
+            }
+            .map_err(|e| e.to_string())?;
+            let mut transport = transport.port(*port).timeout(Some(DELIVERY_TIMEOUT));
+            if let Some(username) = username.as_deref().filter(|u| !u.is_empty()) {
+                transport = transport.credentials(Credentials::new(
+                    username.to_string(),
+                    password.clone().unwrap_or_default(),
+                ));
+            }
+            transport
+                .build()
+                .send(email)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+async fn check_response(response: rquest::Response) -> Result<(), String> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(format!(
+        "HTTP {}: {}",
+        status.as_u16(),
+        body.chars().take(200).collect::<String>()
+    ))
+}
+
+fn webhook_payload(template: WebhookTemplate, alert: &Alert) -> serde_json::Value {
+    let title = alert.title();
+    match template {
+        WebhookTemplate::Generic => json!({
+            "title": title,
+            "message": alert.message,
+            "kind": alert.kind,
+            "subject": alert.subject,
+            "rule_id": alert.rule_id,
+            "timestamp": alert.timestamp,
+        }),
+        WebhookTemplate::Slack => json!({ "text": format!("*{}*\n{}", title, alert.message) }),
+        WebhookTemplate::Discord => json!({ "content": format!("**{}**\n{}", title, alert.message) }),
+        WebhookTemplate::Feishu => json!({
+            "msg_type": "text",
+            "content": { "text": format!("{}\n{}", title, alert.message) },
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_threshold_window_and_cooldown() {
+        let rule = AlertRule {
+            id: "r".to_string(),
+            kind: AlertKind::AccountsExhausted,
+            enabled: true,
+            threshold: 3,
+            window_secs: 60,
+            cooldown_secs: 600,
+            channels: Vec::new(),
+        };
+        let mut state = RuleState::default();
+
+        // 窗口外的事件不计数
+        assert!(!state.record(&rule, 0));
+        assert!(!state.record(&rule, 10));
+        assert!(!state.record(&rule, 70));
+        assert!(!state.record(&rule, 80));
+        assert!(state.record(&rule, 90));
+
+        // 冷却期内即使达到阈值也不再触发
+        for t in 100..110 {
+            assert!(!state.record(&rule, t));
+        }
+        assert!(!state.record(&rule, 680));
+        assert!(!state.record(&rule, 685));
+        assert!(state.record(&rule, 690));
+    }
+
+    #[test]
+    fn test_channel_secrets_encrypted_on_save() {
+        let channel = AlertChannel {
+            id: "c2".to_string(),
+            name: "mail".to_string(),
+            enabled: true,
+            target: ChannelTarget::Smtp {
+                host: "smtp.example.com".to_string(),
+                port: 587,
+                security: SmtpSecurity::Starttls,
+                username: Some("ops".to_string()),
+                password: Some("hunter2".to_string()),
+                from: "ops@example.com".to_string(),
+                to: vec!["oncall@example.com".to_string()],
+            },
+        };
+        let saved = serde_json::to_value(&channel).unwrap();
+        assert!(saved["password"].as_str().unwrap().starts_with("ag_enc_"));
+        assert_eq!(serde_json::from_value::<AlertChannel>(saved).unwrap(), channel);
+
+        let webhook: AlertChannel = serde_json::from_str(
+            r#"{"id":"c3","name":"hook","type":"webhook","url":"https://hook","headers":{"Authorization":"Bearer x"}}"#,
+        )
+        .unwrap();
+        let saved = serde_json::to_value(&webhook).unwrap();
+        assert_ne!(saved["headers"]["Authorization"], "Bearer x");
+        assert_eq!(serde_json::from_value::<AlertChannel>(saved).unwrap(), webhook);
+    }
+
+    #[test]
+    fn test_channel_config_and_payloads() {
+        let channel: AlertChannel = serde_json::from_str(
+            r#"{"id":"c1","name":"ops","type":"ntfy","topic":"antigravity"}"#,
+        )
+        .unwrap();
+        assert!(channel.enabled);
+        assert_eq!(
+            channel.target,
+            ChannelTarget::Ntfy {
+                server: "https://ntfy.sh".to_string(),
+                topic: "antigravity".to_string(),
+                token: None,
+            }
+        );
+
+        let alert = Alert {
+            rule_id: Some("account_disabled".to_string()),
+            kind: AlertKind::AccountDisabled,
+            subject: "a@example.com".to_string(),
+            message: "invalid_grant".to_string(),
+            timestamp: 0,
+        };
+        let generic = webhook_payload(WebhookTemplate::Generic, &alert);
+        assert_eq!(generic["kind"], "account_disabled");
+        assert_eq!(generic["title"], "[Antigravity] Account disabled: a@example.com");
+        let feishu = webhook_payload(WebhookTemplate::Feishu, &alert);
+        assert_eq!(feishu["msg_type"], "text");
+        assert!(feishu["content"]["text"].as_str().unwrap().ends_with("\ninvalid_grant"));
+
+        let config: AlertsConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config.rules.len(), 5);
+    }
+}
@@ -0,0 +1,222 @@
+// 自动临时封禁 (fail2ban 风格)
+//
+// 违规计数保存在内存滑动窗口中，达到阈值后写入带过期时间的 ip_blacklist 条目 (created_by = auto)，
+// 并记录 ip_ban_events。历史封禁次数越多，封禁时长按 escalation_factor 倍数递增，直至上限。
+// 白名单 IP 与本机回环地址不会被自动封禁。
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::modules::security_db;
+use crate::proxy::config::{AutoBanConfig, AutoBanRule};
+
+/// 违规类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Offense {
+    /// API Key / 管理密码错误或缺失 (含既非 API Key 也非已有令牌的凭证)
+    AuthFailure,
+    /// 使用不存在的用户令牌
+    InvalidToken,
+    /// 普通请求 (用于频率限制)
+    Request,
+}
+
+impl Offense {
+    fn rule_name(&self) -> &'static str {
+        match self {
+            Offense::AuthFailure => "auth_failures",
+            Offense::InvalidToken => "invalid_tokens",
+            Offense::Request => "request_rate",
+        }
+    }
+
+    fn rule(&self, config: &AutoBanConfig) -> AutoBanRule {
+        match self {
+            Offense::AuthFailure => config.auth_failures,
+            Offense::InvalidToken => config.invalid_tokens,
+            Offense::Request => config.request_rate,
+        }
+    }
+}
+
+/// 内存中最多跟踪的 (IP, 规则) 数量，超过后清理过期窗口
+const MAX_TRACKED: usize = 10_000;
+
+/// (IP, 违规类型) -> 窗口内的事件时间
+type OffenseWindows = HashMap<(String, Offense), VecDeque<Instant>>;
+
+static WINDOWS: Lazy<Mutex<OffenseWindows>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 所有规则中最长的窗口，清理时据此判断，避免删除其他规则仍需要的记录
+fn max_window(config: &AutoBanConfig) -> Duration {
+    let secs = [config.auth_failures, config.invalid_tokens, config.request_rate]
+        .iter()
+        .map(|rule| rule.window_secs)
+        .max()
+        .unwrap_or(0);
+    Duration::from_secs(secs.max(1))
+}
+
+/// 记录一次事件，返回窗口内的计数 (达到阈值时返回 Some 并重置窗口)
+fn hit(ip: &str, offense: Offense, rule: AutoBanRule, max_window: Duration, now: Instant) -> Option<u32> {
+    let window = Duration::from_secs(rule.window_secs.max(1));
+    let mut windows = WINDOWS.lock();
+
+    if windows.len() > MAX_TRACKED {
+        windows.retain(|_, hits| hits.back().is_some_and(|last| now.duration_since(*last) < max_window));
+    }
+
+    let hits = windows.entry((ip.to_string(), offense)).or_default();
+    hits.push_back(now);
+    while hits.front().is_some_and(|first| now.duration_since(*first) >= window) {
+        hits.pop_front();
+    }
+
+    if hits.len() as u32 >= rule.threshold {
+        let count = hits.len() as u32;
+        windows.remove(&(ip.to_string(), offense));
+        Some(count)
+    } else {
+        None
+    }
+}
+
+/// 根据历史封禁次数计算封禁时长
+pub fn ban_duration(config: &AutoBanConfig, prior_bans: u32) -> i64 {
+    let factor = config.escalation_factor.max(1) as i64;
+    let mut duration = config.ban_duration_secs.max(1);
+    for _ in 0..prior_bans {
+        duration = duration.saturating_mul(factor);
+        if duration >= config.max_ban_duration_secs {
+            break;
+        }
+    }
+    duration.min(config.max_ban_duration_secs.max(1))
+}
+
+fn is_exempt(ip: &str) -> bool {
+    if ip.parse::<std::net::IpAddr>().is_ok_and(|addr| addr.is_loopback()) {
+        return true;
+    }
+    security_db::is_ip_in_whitelist(ip).unwrap_or(false)
+}
+
+fn ban(config: &AutoBanConfig, ip: &str, offense: Offense, count: u32) -> Result<(), String> {
+    if is_exempt(ip) {
+        tracing::debug!("[AutoBan] {} reached {} threshold but is exempt", ip, offense.rule_name());
+        return Ok(());
+    }
+    if security_db::get_blacklist_entry_for_ip(ip)?.is_some() {
+        return Ok(());
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let prior = security_db::count_ban_events(ip, now - config.history_secs)?;
+    let duration = ban_duration(config, prior);
+    let expires_at = now + duration;
+    let reason = format!(
+        "Auto ban: {} ({} in {}s)",
+        offense.rule_name(),
+        count,
+        offense.rule(config).window_secs
+    );
+
+    security_db::add_to_blacklist(ip, Some(&reason), Some(expires_at), "auto")?;
+    security_db::save_ban_event(&security_db::IpBanEvent {
+        id: uuid::Uuid::new_v4().to_string(),
+        client_ip: ip.to_string(),
+        rule: offense.rule_name().to_string(),
+        offense_count: count as i64,
+        duration_secs: duration,
+        created_at: now,
+        expires_at,
+    })?;
+
+    tracing::warn!(
+        "[AutoBan] Banned {} for {}s ({}, previous bans: {})",
+        ip,
+        duration,
+        reason,
+        prior
+    );
+    Ok(())
+}
+
+/// 记录一次违规，达到规则阈值时自动封禁该 IP
+pub fn record(config: &AutoBanConfig, ip: &str, offense: Offense) {
+    if !config.enabled {
+        return;
+    }
+    let rule = offense.rule(config);
+    if rule.threshold == 0 {
+        return;
+    }
+    if let Some(count) = hit(ip, offense, rule, max_window(config), Instant::now()) {
+        if let Err(e) = ban(config, ip, offense, count) {
+            tracing::error!("[AutoBan] Failed to ban {}: {}", ip, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sliding_window_threshold() {
+        let rule = AutoBanRule { threshold: 3, window_secs: 10 };
+        let window = Duration::from_secs(10);
+        let ip = "198.51.100.77";
+        let start = Instant::now();
+
+        assert_eq!(hit(ip, Offense::AuthFailure, rule, window, start), None);
+        assert_eq!(hit(ip, Offense::AuthFailure, rule, window, start + Duration::from_secs(1)), None);
+        // 前两次事件已滑出窗口
+        assert_eq!(hit(ip, Offense::AuthFailure, rule, window, start + Duration::from_secs(11)), None);
+        assert_eq!(hit(ip, Offense::AuthFailure, rule, window, start + Duration::from_secs(12)), None);
+        assert_eq!(hit(ip, Offense::AuthFailure, rule, window, start + Duration::from_secs(13)), Some(3));
+        // 达到阈值后窗口重置
+        assert_eq!(hit(ip, Offense::AuthFailure, rule, window, start + Duration::from_secs(14)), None);
+    }
+
+    #[test]
+    fn test_pruning_keeps_hits_for_longer_windows() {
+        let config = AutoBanConfig {
+            auth_failures: AutoBanRule { threshold: 3, window_secs: 600 },
+            request_rate: AutoBanRule { threshold: 1_000_000, window_secs: 10 },
+            ..Default::default()
+        };
+        let max = max_window(&config);
+        assert_eq!(max, Duration::from_secs(600));
+
+        let ip = "198.51.100.78";
+        let start = Instant::now();
+        assert_eq!(hit(ip, Offense::AuthFailure, config.auth_failures, max, start), None);
+        assert_eq!(hit(ip, Offense::AuthFailure, config.auth_failures, max, start + Duration::from_secs(1)), None);
+
+        // 大量短窗口规则的记录触发清理，不应删除长窗口规则仍在窗口内的记录
+        let later = start + Duration::from_secs(100);
+        for i in 0..=MAX_TRACKED {
+            let other = format!("10.{}.{}.{}", i / 65536, (i / 256) % 256, i % 256);
+            hit(&other, Offense::Request, config.request_rate, max, later);
+        }
+        assert_eq!(hit(ip, Offense::AuthFailure, config.auth_failures, max, later), Some(3));
+    }
+
+    #[test]
+    fn test_ban_duration_escalates_and_caps() {
+        let config = AutoBanConfig {
+            ban_duration_secs: 60,
+            escalation_factor: 4,
+            max_ban_duration_secs: 3600,
+            ..Default::default()
+        };
+        assert_eq!(ban_duration(&config, 0), 60);
+        assert_eq!(ban_duration(&config, 1), 240);
+        assert_eq!(ban_duration(&config, 2), 960);
+        assert_eq!(ban_duration(&config, 3), 3600);
+        assert_eq!(ban_duration(&config, 30), 3600);
+    }
+}
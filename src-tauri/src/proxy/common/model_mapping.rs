@@ -253,6 +253,21 @@ fn wildcard_match(pattern: &str, text: &str) -> bool {
 pub fn resolve_model_route(
     original_model: &str,
     custom_mapping: &std::collections::HashMap<String, String>,
+) -> String {
+    let span = tracing::info_span!(
+        "model_routing",
+        model.requested = original_model,
+        model.resolved = tracing::field::Empty
+    );
+    let _enter = span.enter();
+    let resolved = route_model(original_model, custom_mapping);
+    span.record("model.resolved", resolved.as_str());
+    resolved
+}
+
+fn route_model(
+    original_model: &str,
+    custom_mapping: &std::collections::HashMap<String, String>,
 ) -> String {
     // 0. API 热更新废弃模型转发 (最高物理优先级，强制纠正)
     // 如果用户非要用已经被移除的模型，并且官方下发了 fallback path，我们在此拦截并纠正
@@ -443,6 +443,132 @@ pub struct SecurityMonitorConfig {
     /// IP 白名单配置
     #[serde(default)]
     pub whitelist: IpWhitelistConfig,
+
+    /// 受信任的反向代理 (IP 或 CIDR，支持 IPv6)
+    /// 仅当 TCP 对端属于这些地址时才采信 X-Forwarded-For / X-Real-IP
+    #[serde(default = "default_trusted_proxies")]
+    pub trusted_proxies: Vec<String>,
+
+    /// 采信本机 cloudflared 隧道传递的 CF-Connecting-IP (仅对端为回环地址时生效)
+    #[serde(default = "default_true")]
+    pub trust_cloudflared: bool,
+
+    /// 自动临时封禁 (fail2ban 风格)
+    #[serde(default)]
+    pub auto_ban: AutoBanConfig,
+
+    /// 管理操作审计日志
+    #[serde(default)]
+    pub audit_log: AuditLogConfig,
+}
+
+/// 审计日志配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogConfig {
+    /// 保留天数，0 表示永久保留
+    #[serde(default = "default_audit_retention_days")]
+    pub retention_days: u32,
+}
+
+fn default_audit_retention_days() -> u32 {
+    365
+}
+
+impl Default for AuditLogConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: default_audit_retention_days(),
+        }
+    }
+}
+
+/// 自动封禁规则: 在 window_secs 秒内触发 threshold 次即封禁，threshold 为 0 时禁用
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct AutoBanRule {
+    pub threshold: u32,
+    pub window_secs: u64,
+}
+
+/// 自动封禁配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoBanConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// 鉴权失败 (API Key / 管理密码错误或缺失)
+    #[serde(default = "default_auth_failure_rule")]
+    pub auth_failures: AutoBanRule,
+
+    /// 使用不存在的用户令牌
+    #[serde(default = "default_invalid_token_rule")]
+    pub invalid_tokens: AutoBanRule,
+
+    /// 单 IP 请求频率 (默认关闭)
+    #[serde(default = "default_request_rate_rule")]
+    pub request_rate: AutoBanRule,
+
+    /// 首次封禁时长 (秒)
+    #[serde(default = "default_ban_duration_secs")]
+    pub ban_duration_secs: i64,
+
+    /// 重复违规时的封禁时长倍数
+    #[serde(default = "default_escalation_factor")]
+    pub escalation_factor: u32,
+
+    /// 封禁时长上限 (秒)
+    #[serde(default = "default_max_ban_duration_secs")]
+    pub max_ban_duration_secs: i64,
+
+    /// 统计历史封禁次数的时间范围 (秒)
+    #[serde(default = "default_ban_history_secs")]
+    pub history_secs: i64,
+}
+
+fn default_auth_failure_rule() -> AutoBanRule {
+    AutoBanRule { threshold: 10, window_secs: 300 }
+}
+
+fn default_invalid_token_rule() -> AutoBanRule {
+    AutoBanRule { threshold: 20, window_secs: 600 }
+}
+
+fn default_request_rate_rule() -> AutoBanRule {
+    AutoBanRule { threshold: 0, window_secs: 1 }
+}
+
+fn default_ban_duration_secs() -> i64 {
+    15 * 60
+}
+
+fn default_escalation_factor() -> u32 {
+    4
+}
+
+fn default_max_ban_duration_secs() -> i64 {
+    7 * 24 * 3600
+}
+
+fn default_ban_history_secs() -> i64 {
+    7 * 24 * 3600
+}
+
+impl Default for AutoBanConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            auth_failures: default_auth_failure_rule(),
+            invalid_tokens: default_invalid_token_rule(),
+            request_rate: default_request_rate_rule(),
+            ban_duration_secs: default_ban_duration_secs(),
+            escalation_factor: default_escalation_factor(),
+            max_ban_duration_secs: default_max_ban_duration_secs(),
+            history_secs: default_ban_history_secs(),
+        }
+    }
+}
+
+fn default_trusted_proxies() -> Vec<String> {
+    vec!["127.0.0.1/8".to_string(), "::1/128".to_string()]
 }
 
 impl Default for SecurityMonitorConfig {
@@ -450,6 +576,10 @@ impl Default for SecurityMonitorConfig {
         Self {
             blacklist: IpBlacklistConfig::default(),
             whitelist: IpWhitelistConfig::default(),
+            trusted_proxies: default_trusted_proxies(),
+            trust_cloudflared: true,
+            auto_ban: AutoBanConfig::default(),
+            audit_log: AuditLogConfig::default(),
         }
     }
 }
@@ -555,6 +685,46 @@ pub struct ProxyConfig {
     /// 代理池配置
     #[serde(default)]
     pub proxy_pool: ProxyPoolConfig,
+
+    /// 上游 v1internal 端点健康跟踪与降级顺序配置
+    #[serde(default)]
+    pub upstream_endpoints: UpstreamEndpointConfig,
+
+    /// 幂等请求去重配置 (Idempotency-Key)
+    #[serde(default)]
+    pub idempotency: crate::proxy::idempotency::IdempotencyConfig,
+
+    /// 精确匹配响应缓存配置
+    #[serde(default)]
+    pub response_cache: crate::proxy::response_cache::ResponseCacheConfig,
+
+    /// Prometheus 指标导出配置
+    #[serde(default)]
+    pub metrics: crate::proxy::metrics::MetricsConfig,
+
+    /// 监听端 HTTPS 配置
+    #[serde(default)]
+    pub tls: crate::proxy::tls::TlsConfig,
+
+    /// OpenTelemetry 链路追踪导出配置
+    #[serde(default)]
+    pub telemetry: crate::proxy::telemetry::TelemetryConfig,
+
+    /// 告警规则与通知通道配置
+    #[serde(default)]
+    pub alerts: crate::proxy::alerts::AlertsConfig,
+
+    /// 日志与调试载荷脱敏配置
+    #[serde(default)]
+    pub redaction: crate::proxy::redaction::RedactionConfig,
+
+    /// 出站数据防泄漏 (DLP) 规则
+    #[serde(default)]
+    pub dlp: crate::proxy::dlp::DlpConfig,
+
+    /// 模型价格表 (费用核算)
+    #[serde(default)]
+    pub pricing: crate::proxy::pricing::PricingConfig,
 }
 
 /// 上游代理配置
@@ -566,6 +736,50 @@ pub struct UpstreamProxyConfig {
     pub url: String,
 }
 
+/// 上游端点 (Sandbox / Daily / Prod) 健康跟踪配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamEndpointConfig {
+    /// 是否根据观测到的健康度调整降级顺序 (关闭时固定为 Sandbox → Daily → Prod)
+    #[serde(default = "default_true")]
+    pub adaptive_ordering: bool,
+    /// 手动固定的端点顺序 (名称 sandbox/daily/prod 或完整 URL)，非空时优先于自适应排序
+    #[serde(default)]
+    pub pinned_order: Vec<String>,
+    /// 连续失败多少次后熔断该端点
+    #[serde(default = "default_endpoint_failure_threshold")]
+    pub failure_threshold: u32,
+    /// 首次熔断时长 (秒)，重复熔断时指数增长
+    #[serde(default = "default_endpoint_open_duration_secs")]
+    pub open_duration_secs: u64,
+    /// 轻量探测间隔 (秒)，0 表示关闭探测
+    #[serde(default = "default_endpoint_probe_interval_secs")]
+    pub probe_interval_secs: u64,
+}
+
+impl Default for UpstreamEndpointConfig {
+    fn default() -> Self {
+        Self {
+            adaptive_ordering: true,
+            pinned_order: Vec::new(),
+            failure_threshold: default_endpoint_failure_threshold(),
+            open_duration_secs: default_endpoint_open_duration_secs(),
+            probe_interval_secs: default_endpoint_probe_interval_secs(),
+        }
+    }
+}
+
+fn default_endpoint_failure_threshold() -> u32 {
+    3
+}
+
+fn default_endpoint_open_duration_secs() -> u64 {
+    30
+}
+
+fn default_endpoint_probe_interval_secs() -> u64 {
+    60
+}
+
 impl Default for ProxyConfig {
     fn default() -> Self {
         Self {
@@ -592,6 +806,16 @@ impl Default for ProxyConfig {
             global_system_prompt: GlobalSystemPromptConfig::default(),
             proxy_pool: ProxyPoolConfig::default(),
             image_thinking_mode: None,
+            upstream_endpoints: UpstreamEndpointConfig::default(),
+            idempotency: crate::proxy::idempotency::IdempotencyConfig::default(),
+            response_cache: crate::proxy::response_cache::ResponseCacheConfig::default(),
+            metrics: crate::proxy::metrics::MetricsConfig::default(),
+            tls: crate::proxy::tls::TlsConfig::default(),
+            telemetry: crate::proxy::telemetry::TelemetryConfig::default(),
+            alerts: crate::proxy::alerts::AlertsConfig::default(),
+            redaction: crate::proxy::redaction::RedactionConfig::default(),
+            dlp: crate::proxy::dlp::DlpConfig::default(),
+            pricing: crate::proxy::pricing::PricingConfig::default(),
         }
     }
 }
@@ -655,6 +879,12 @@ pub struct ProxyEntry {
     pub last_check_time: Option<i64>,     // 上次检查时间
     pub is_healthy: bool,                 // 健康状态
     pub latency: Option<u64>,             // 延迟 (毫秒) [NEW]
+    #[serde(default = "default_proxy_weight")]
+    pub weight: u32,                      // 权重 (WeightedRoundRobin 策略使用) [NEW]
+}
+
+fn default_proxy_weight() -> u32 {
+    1
 }
 
 /// 代理池配置
@@ -47,6 +47,18 @@ pub async fn write_debug_payload(
     let filename = build_filename(prefix, trace_id);
     let path = output_dir.join(filename);
 
+    // [NEW] 落盘前脱敏
+    let redactor = crate::proxy::redaction::redactor();
+    let redacted;
+    let payload = if redactor.is_enabled() {
+        let mut copy = payload.clone();
+        redactor.redact_json(&mut copy);
+        redacted = copy;
+        &redacted
+    } else {
+        payload
+    };
+
     match serde_json::to_vec_pretty(payload) {
         Ok(bytes) => {
             if let Err(e) = fs::write(&path, bytes).await {
@@ -0,0 +1,864 @@
+// 出站数据防泄漏 (DLP)
+//
+// 请求被映射为上游 v1internal 请求体之后、发送之前，检查 contents / systemInstruction 中的文本
+// (图片生成 / 编辑接口在构造请求体前检查提示词，打码后的占位符无法在图片结果中还原)：
+//   block  命中即拒绝请求，按客户端协议返回 400
+//   mask   替换为占位符 [DLP:<规则>:<序号>]，可选在响应中还原为原文
+//   log    仅记录命中
+// 内置检测器与日志脱敏 (redaction) 共用正则。规则可限定到指定用户令牌 (令牌 ID 或用户名)，
+// 命中记录写入 security.db (dlp_hits)，通过安全接口查询统计。
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use bytes::Bytes;
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+
+use crate::modules::security_db::{self, DlpHitRecord};
+use crate::proxy::middleware::auth::UserTokenIdentity;
+use crate::proxy::redaction::{luhn_valid, API_KEY_RE, BEARER_RE, CARD_RE, EMAIL_RE, PRIVATE_KEY_RE};
+
+/// 检测器
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DlpDetector {
+    /// 常见服务商 API Key
+    ApiKey,
+    /// Authorization: Bearer 令牌
+    BearerToken,
+    /// 邮箱地址
+    Email,
+    /// 信用卡号 (Luhn 校验)
+    CreditCard,
+    /// PEM 私钥
+    PrivateKey,
+    /// 自定义正则 (pattern)
+    Regex,
+}
+
+/// 命中后的处理动作
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DlpAction {
+    Block,
+    Mask,
+    Log,
+}
+
+impl DlpAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DlpAction::Block => "block",
+            DlpAction::Mask => "mask",
+            DlpAction::Log => "log",
+        }
+    }
+}
+
+/// DLP 规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DlpRule {
+    /// 规则名称 (唯一，用于占位符与命中统计)
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub detector: DlpDetector,
+    /// detector 为 regex 时使用的正则表达式
+    #[serde(default)]
+    pub pattern: String,
+    pub action: DlpAction,
+    /// mask 动作: 在响应中把占位符还原为原文
+    #[serde(default)]
+    pub restore_response: bool,
+    /// 仅对这些用户令牌生效 (令牌 ID 或用户名)，为空表示所有请求
+    #[serde(default)]
+    pub user_tokens: Vec<String>,
+}
+
+/// DLP 配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct DlpConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub rules: Vec<DlpRule>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+type ByteStream<E> = Pin<Box<dyn futures::Stream<Item = Result<Bytes, E>> + Send>>;
+
+/// 上游请求体中不检查的字段 (二进制数据、签名等)
+const SKIP_KEYS: &[&str] = &["inlineData", "fileData", "thoughtSignature", "thought_signature", "role"];
+/// 占位符最大长度，流式还原时据此判断片段末尾是否为被截断的占位符
+const MAX_PLACEHOLDER_LEN: usize = 96;
+
+static PLACEHOLDER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\[DLP:[A-Za-z0-9_\-]+:\d+\]").expect("valid placeholder regex"));
+static PARTIAL_PLACEHOLDER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\[(?:D(?:L(?:P(?::[A-Za-z0-9_\-]*(?::\d*)?)?)?)?)?$").expect("valid partial placeholder regex")
+});
+
+fn ranges(re: &Regex, text: &str) -> Vec<(usize, usize)> {
+    re.find_iter(text).map(|m| (m.start(), m.end())).collect()
+}
+
+enum Matcher {
+    Builtin(DlpDetector),
+    Custom(Regex),
+}
+
+impl Matcher {
+    /// 命中片段的字节区间 (按位置排列，互不重叠)
+    fn find(&self, text: &str) -> Vec<(usize, usize)> {
+        match self {
+            Matcher::Custom(re) => ranges(re, text),
+            Matcher::Builtin(DlpDetector::ApiKey) => ranges(&API_KEY_RE, text),
+            // 只处理令牌本身，保留 "Bearer " 前缀
+            Matcher::Builtin(DlpDetector::BearerToken) => BEARER_RE
+                .captures_iter(text)
+                .filter_map(|caps| Some((caps.get(1)?.end(), caps.get(0)?.end())))
+                .collect(),
+            Matcher::Builtin(DlpDetector::Email) => ranges(&EMAIL_RE, text),
+            Matcher::Builtin(DlpDetector::CreditCard) => CARD_RE
+                .find_iter(text)
+                .filter(|m| luhn_valid(m.as_str()))
+                .map(|m| (m.start(), m.end()))
+                .collect(),
+            Matcher::Builtin(DlpDetector::PrivateKey) => ranges(&PRIVATE_KEY_RE, text),
+            Matcher::Builtin(DlpDetector::Regex) => Vec::new(),
+        }
+    }
+}
+
+struct CompiledRule {
+    name: String,
+    /// 占位符中使用的规则标识
+    label: String,
+    action: DlpAction,
+    restore: bool,
+    matcher: Matcher,
+    user_tokens: Vec<String>,
+}
+
+impl CompiledRule {
+    fn applies_to(&self, identity: Option<(&str, &str)>) -> bool {
+        self.user_tokens.is_empty()
+            || identity.is_some_and(|(token_id, username)| {
+                self.user_tokens.iter().any(|t| t == token_id || t == username)
+            })
+    }
+
+    fn count(&self, target: &mut Value) -> usize {
+        let mut matches = 0;
+        for_each_text(target, &mut |text| matches += self.matcher.find(text).len());
+        matches
+    }
+}
+
+fn placeholder_label(name: &str) -> String {
+    let label: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .take(48)
+        .collect();
+    if label.is_empty() { "rule".to_string() } else { label }
+}
+
+/// 遍历上游请求体中需要检查的文本
+fn for_each_text(root: &mut Value, f: &mut dyn FnMut(&mut String)) {
+    for key in ["contents", "systemInstruction"] {
+        if let Some(value) = root.get_mut(key) {
+            walk_text(value, f);
+        }
+    }
+}
+
+fn walk_text(value: &mut Value, f: &mut dyn FnMut(&mut String)) {
+    match value {
+        Value::String(s) => f(s),
+        Value::Array(items) => items.iter_mut().for_each(|v| walk_text(v, f)),
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if !SKIP_KEYS.contains(&key.as_str()) {
+                    walk_text(v, f);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 打码占位符与原文的对应关系
+#[derive(Debug, Clone, Default)]
+pub struct MaskVault {
+    entries: Vec<VaultEntry>,
+    counters: HashMap<String, usize>,
+}
+
+#[derive(Debug, Clone)]
+struct VaultEntry {
+    label: String,
+    placeholder: String,
+    original: String,
+    restore: bool,
+}
+
+impl MaskVault {
+    /// 同一规则下相同原文使用同一占位符 (重试时结果稳定)
+    fn placeholder(&mut self, label: &str, original: &str, restore: bool) -> String {
+        if let Some(entry) = self.entries.iter().find(|e| e.label == label && e.original == original) {
+            return entry.placeholder.clone();
+        }
+        let counter = self.counters.entry(label.to_string()).or_insert(0);
+        *counter += 1;
+        let placeholder = format!("[DLP:{}:{}]", label, counter);
+        self.entries.push(VaultEntry {
+            label: label.to_string(),
+            placeholder: placeholder.clone(),
+            original: original.to_string(),
+            restore,
+        });
+        placeholder
+    }
+
+    /// 是否有需要在响应中还原的占位符
+    pub fn is_restorable(&self) -> bool {
+        self.entries.iter().any(|e| e.restore)
+    }
+
+    /// 还原文本中的占位符，未命中时不分配新字符串
+    pub fn restore_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if !self.is_restorable() || !text.contains("[DLP:") {
+            return Cow::Borrowed(text);
+        }
+        PLACEHOLDER_RE.replace_all(text, |caps: &regex::Captures| {
+            self.entries
+                .iter()
+                .find(|e| e.restore && e.placeholder == caps[0])
+                .map(|e| e.original.clone())
+                .unwrap_or_else(|| caps[0].to_string())
+        })
+    }
+
+    /// 递归还原 JSON 中的所有字符串值
+    pub fn restore_json(&self, value: &mut Value) {
+        match value {
+            Value::String(s) => {
+                if let Cow::Owned(restored) = self.restore_text(s) {
+                    *s = restored;
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|v| self.restore_json(v)),
+            Value::Object(map) => map.values_mut().for_each(|v| self.restore_json(v)),
+            _ => {}
+        }
+    }
+}
+
+/// 单条规则在一次请求中的命中
+#[derive(Debug, Clone, PartialEq)]
+pub struct DlpHit {
+    pub rule: String,
+    pub action: DlpAction,
+    pub matches: usize,
+}
+
+/// 检查结果
+#[derive(Debug, Default)]
+pub struct DlpOutcome {
+    /// 触发拦截的规则
+    pub blocked_by: Option<String>,
+    pub hits: Vec<DlpHit>,
+    pub vault: MaskVault,
+}
+
+/// 按配置编译好的 DLP 规则集
+#[derive(Default)]
+pub struct DlpEngine {
+    enabled: bool,
+    rules: Vec<CompiledRule>,
+}
+
+impl DlpEngine {
+    /// 编译配置，返回规则集与无效规则的错误
+    pub fn compile(config: &DlpConfig) -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+        let mut names: Vec<&str> = Vec::new();
+        let mut rules = Vec::new();
+
+        for rule in &config.rules {
+            let name = rule.name.trim();
+            if name.is_empty() {
+                errors.push("rule name must not be empty".to_string());
+                continue;
+            }
+            if names.contains(&name) {
+                errors.push(format!("{}: duplicate rule name", name));
+                continue;
+            }
+            names.push(name);
+
+            let matcher = match rule.detector {
+                DlpDetector::Regex if rule.pattern.is_empty() => {
+                    errors.push(format!("{}: regex detector requires a pattern", name));
+                    continue;
+                }
+                DlpDetector::Regex => match Regex::new(&rule.pattern) {
+                    Ok(re) => Matcher::Custom(re),
+                    Err(e) => {
+                        errors.push(format!("{}: {}", name, e));
+                        continue;
+                    }
+                },
+                detector => Matcher::Builtin(detector),
+            };
+            if !rule.enabled {
+                continue;
+            }
+            rules.push(CompiledRule {
+                name: name.to_string(),
+                label: placeholder_label(name),
+                action: rule.action,
+                restore: rule.action == DlpAction::Mask && rule.restore_response,
+                matcher,
+                user_tokens: rule.user_tokens.clone(),
+            });
+        }
+
+        (
+            Self {
+                enabled: config.enabled,
+                rules,
+            },
+            errors,
+        )
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled && !self.rules.is_empty()
+    }
+
+    /// 检查 (并按 mask 规则改写) 上游请求体。identity 为 (令牌 ID, 用户名)
+    pub fn scan(&self, body: &mut Value, identity: Option<(&str, &str)>) -> DlpOutcome {
+        let mut outcome = DlpOutcome::default();
+        if !self.enabled {
+            return outcome;
+        }
+        let rules: Vec<&CompiledRule> = self.rules.iter().filter(|r| r.applies_to(identity)).collect();
+        if rules.is_empty() {
+            return outcome;
+        }
+        // v1internal 请求体的实际内容位于 request 字段
+        let target = if body.get("request").is_some_and(Value::is_object) {
+            &mut body["request"]
+        } else {
+            body
+        };
+
+        // 1. 拦截规则优先，命中即返回，不再改写请求
+        for rule in rules.iter().filter(|r| r.action == DlpAction::Block) {
+            let matches = rule.count(target);
+            if matches > 0 {
+                outcome.hits.push(DlpHit {
+                    rule: rule.name.clone(),
+                    action: DlpAction::Block,
+                    matches,
+                });
+                outcome.blocked_by = Some(rule.name.clone());
+                return outcome;
+            }
+        }
+
+        // 2. 仅记录
+        for rule in rules.iter().filter(|r| r.action == DlpAction::Log) {
+            let matches = rule.count(target);
+            if matches > 0 {
+                outcome.hits.push(DlpHit {
+                    rule: rule.name.clone(),
+                    action: DlpAction::Log,
+                    matches,
+                });
+            }
+        }
+
+        // 3. 打码
+        for rule in rules.iter().filter(|r| r.action == DlpAction::Mask) {
+            let mut matches = 0;
+            let vault = &mut outcome.vault;
+            for_each_text(target, &mut |text| {
+                let found = rule.matcher.find(text);
+                if found.is_empty() {
+                    return;
+                }
+                matches += found.len();
+                let mut masked = String::with_capacity(text.len());
+                let mut last = 0;
+                for (start, end) in found {
+                    masked.push_str(&text[last..start]);
+                    masked.push_str(&vault.placeholder(&rule.label, &text[start..end], rule.restore));
+                    last = end;
+                }
+                masked.push_str(&text[last..]);
+                *text = masked;
+            });
+            if matches > 0 {
+                outcome.hits.push(DlpHit {
+                    rule: rule.name.clone(),
+                    action: DlpAction::Mask,
+                    matches,
+                });
+            }
+        }
+        outcome
+    }
+}
+
+/// 片段末尾被截断的占位符长度 (需暂存到下一个片段)
+fn partial_placeholder_len(text: &str) -> usize {
+    match text.rfind('[') {
+        Some(idx) if text.len() - idx <= MAX_PLACEHOLDER_LEN && PARTIAL_PLACEHOLDER_RE.is_match(&text[idx..]) => {
+            text.len() - idx
+        }
+        _ => 0,
+    }
+}
+
+/// 流式响应 (上游 SSE) 的占位符还原
+#[derive(Default)]
+struct StreamRestorer {
+    /// 暂存的文本尾部，按是否为思考内容区分
+    pending: HashMap<bool, String>,
+}
+
+impl StreamRestorer {
+    fn restore_part(&mut self, vault: &MaskVault, text: &str, thought: bool, flush: bool) -> String {
+        let mut combined = self.pending.remove(&thought).unwrap_or_default();
+        combined.push_str(text);
+        let keep = if flush { 0 } else { partial_placeholder_len(&combined) };
+        let tail = combined.split_off(combined.len() - keep);
+        if !tail.is_empty() {
+            self.pending.insert(thought, tail);
+        }
+        vault.restore_text(&combined).into_owned()
+    }
+
+    fn take_pending(&mut self, vault: &MaskVault) -> Vec<Value> {
+        let mut parts = Vec::new();
+        for thought in [true, false] {
+            if let Some(text) = self.pending.remove(&thought).filter(|t| !t.is_empty()) {
+                let mut part = json!({ "text": vault.restore_text(&text) });
+                if thought {
+                    part["thought"] = json!(true);
+                }
+                parts.push(part);
+            }
+        }
+        parts
+    }
+
+    fn restore_chunk(&mut self, vault: &MaskVault, chunk: &mut Value) {
+        let inner = if chunk.get("response").is_some_and(Value::is_object) {
+            &mut chunk["response"]
+        } else {
+            chunk
+        };
+        let Some(candidates) = inner.get_mut("candidates").and_then(Value::as_array_mut) else {
+            return;
+        };
+        for candidate in candidates {
+            let finished = candidate.get("finishReason").is_some();
+            if let Some(parts) = candidate.pointer_mut("/content/parts").and_then(Value::as_array_mut) {
+                for part in parts.iter_mut() {
+                    let thought = part.get("thought").and_then(Value::as_bool).unwrap_or(false);
+                    match part.get_mut("text") {
+                        Some(Value::String(text)) => *text = self.restore_part(vault, text, thought, finished),
+                        _ => vault.restore_json(part),
+                    }
+                }
+            }
+            if finished {
+                let leftover = self.take_pending(vault);
+                if !leftover.is_empty() {
+                    let parts = &mut candidate["content"]["parts"];
+                    if !parts.is_array() {
+                        *parts = json!([]);
+                    }
+                    if let Some(parts) = parts.as_array_mut() {
+                        parts.extend(leftover);
+                    }
+                }
+            }
+        }
+    }
+
+    fn process_line(&mut self, vault: &MaskVault, line: &[u8]) -> Vec<u8> {
+        let line = String::from_utf8_lossy(line);
+        let content = line.trim_end_matches(['\r', '\n']);
+        if let Some(data) = content.strip_prefix("data:") {
+            if let Ok(mut chunk) = serde_json::from_str::<Value>(data.trim_start()) {
+                self.restore_chunk(vault, &mut chunk);
+                return format!("data: {}{}", chunk, &line[content.len()..]).into_bytes();
+            }
+        }
+        vault.restore_text(&line).into_owned().into_bytes()
+    }
+
+    /// 流结束时输出仍暂存的文本
+    fn finish(&mut self, vault: &MaskVault) -> Vec<u8> {
+        let parts = self.take_pending(vault);
+        if parts.is_empty() {
+            return Vec::new();
+        }
+        let chunk = json!({ "response": { "candidates": [{ "content": { "role": "model", "parts": parts } }] } });
+        format!("data: {}\n\n", chunk).into_bytes()
+    }
+}
+
+/// 包装上游 SSE 流，把响应中的占位符还原为原文 (无需还原时原样返回)
+pub fn wrap_stream<E>(stream: ByteStream<E>, vault: Arc<MaskVault>) -> ByteStream<E>
+where
+    E: Send + 'static,
+{
+    if !vault.is_restorable() {
+        return stream;
+    }
+
+    Box::pin(async_stream::stream! {
+        let mut inner = stream;
+        let mut line_buf: Vec<u8> = Vec::new();
+        let mut restorer = StreamRestorer::default();
+        while let Some(item) = inner.next().await {
+            match item {
+                Ok(bytes) => {
+                    line_buf.extend_from_slice(&bytes);
+                    let mut out = Vec::new();
+                    while let Some(pos) = line_buf.iter().position(|b| *b == b'\n') {
+                        let line: Vec<u8> = line_buf.drain(..=pos).collect();
+                        out.extend(restorer.process_line(&vault, &line));
+                    }
+                    if !out.is_empty() {
+                        yield Ok(Bytes::from(out));
+                    }
+                }
+                Err(e) => yield Err(e),
+            }
+        }
+        let mut out = Vec::new();
+        if !line_buf.is_empty() {
+            out.extend(restorer.process_line(&vault, &line_buf));
+        }
+        out.extend(restorer.finish(&vault));
+        if !out.is_empty() {
+            yield Ok(Bytes::from(out));
+        }
+    })
+}
+
+/// 客户端协议，决定拦截时的错误格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DlpProtocol {
+    OpenAI,
+    Anthropic,
+    Gemini,
+}
+
+impl DlpProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DlpProtocol::OpenAI => "openai",
+            DlpProtocol::Anthropic => "anthropic",
+            DlpProtocol::Gemini => "gemini",
+        }
+    }
+}
+
+/// 按协议格式构造拦截响应 (400)
+pub fn blocked_response(protocol: DlpProtocol, rule: &str) -> Response {
+    let message = format!("Request blocked by data loss prevention rule '{}'.", rule);
+    let body = match protocol {
+        DlpProtocol::Anthropic => json!({
+            "type": "error",
+            "error": { "type": "invalid_request_error", "message": message }
+        }),
+        DlpProtocol::Gemini => json!({
+            "error": { "code": 400, "message": message, "status": "INVALID_ARGUMENT" }
+        }),
+        DlpProtocol::OpenAI => json!({
+            "error": { "message": message, "type": "invalid_request_error", "code": "dlp_blocked" }
+        }),
+    };
+    (StatusCode::BAD_REQUEST, Json(body)).into_response()
+}
+
+/// 单个请求的 DLP 状态 (账号重试时复用，命中只统计一次)
+pub struct DlpGuard {
+    protocol: DlpProtocol,
+    /// (令牌 ID, 用户名)
+    identity: Option<(String, String)>,
+    vault: Arc<MaskVault>,
+    recorded: bool,
+}
+
+impl DlpGuard {
+    pub fn new(protocol: DlpProtocol, identity: Option<&UserTokenIdentity>) -> Self {
+        Self {
+            protocol,
+            identity: identity.map(|i| (i.token_id.clone(), i.username.clone())),
+            vault: Arc::new(MaskVault::default()),
+            recorded: false,
+        }
+    }
+
+    /// 检查映射后的上游请求体，命中拦截规则时返回协议格式的 400 响应
+    pub fn apply(&mut self, body: &mut Value, trace_id: &str) -> Option<Response> {
+        let engine = engine();
+        if !engine.is_enabled() {
+            return None;
+        }
+        let identity = self.identity.as_ref().map(|(id, name)| (id.as_str(), name.as_str()));
+        let outcome = engine.scan(body, identity);
+        if !self.recorded && !outcome.hits.is_empty() {
+            self.recorded = true;
+            self.record(&outcome.hits, trace_id);
+        }
+        self.vault = Arc::new(outcome.vault);
+        outcome.blocked_by.map(|rule| blocked_response(self.protocol, &rule))
+    }
+
+    /// 检查单段文本 (图片接口的提示词)，打码结果写回原文本，命中拦截规则时返回 400 响应
+    pub fn apply_text(&mut self, text: &mut String, trace_id: &str) -> Option<Response> {
+        let mut body = json!({ "contents": [{ "role": "user", "parts": [{ "text": text.as_str() }] }] });
+        let blocked = self.apply(&mut body, trace_id);
+        if let Some(masked) = body["contents"][0]["parts"][0]["text"].as_str() {
+            *text = masked.to_string();
+        }
+        blocked
+    }
+
+    fn record(&self, hits: &[DlpHit], trace_id: &str) {
+        let username = self.identity.as_ref().map(|(_, name)| name.clone());
+        let now = chrono::Utc::now().timestamp();
+        for hit in hits {
+            tracing::warn!(
+                "[{}] DLP rule '{}' matched {} time(s), action: {} (user: {})",
+                trace_id,
+                hit.rule,
+                hit.matches,
+                hit.action.as_str(),
+                username.as_deref().unwrap_or("-")
+            );
+        }
+        let records: Vec<DlpHitRecord> = hits
+            .iter()
+            .map(|hit| DlpHitRecord {
+                rule: hit.rule.clone(),
+                action: hit.action.as_str().to_string(),
+                matches: hit.matches as i64,
+                protocol: self.protocol.as_str().to_string(),
+                username: username.clone(),
+                created_at: now,
+            })
+            .collect();
+        if let Err(e) = security_db::save_dlp_hits(&records) {
+            tracing::error!("[DLP] Failed to save hit statistics: {}", e);
+        }
+    }
+
+    /// 包装上游 SSE 流以还原占位符
+    pub fn wrap_stream<E: Send + 'static>(&self, stream: ByteStream<E>) -> ByteStream<E> {
+        wrap_stream(stream, self.vault.clone())
+    }
+
+    /// 还原非流式上游响应中的占位符
+    pub fn restore_json(&self, value: &mut Value) {
+        if self.vault.is_restorable() {
+            self.vault.restore_json(value);
+        }
+    }
+}
+
+static GLOBAL_DLP: Lazy<RwLock<Arc<DlpEngine>>> = Lazy::new(|| RwLock::new(Arc::new(DlpEngine::default())));
+
+/// 更新全局 DLP 配置，无效规则会被跳过并记录警告
+pub fn update_dlp_config(config: DlpConfig) {
+    let (engine, errors) = DlpEngine::compile(&config);
+    for error in &errors {
+        tracing::warn!("[DLP] Invalid rule skipped: {}", error);
+    }
+    let active = engine.rules.len();
+    if let Ok(mut current) = GLOBAL_DLP.write() {
+        *current = Arc::new(engine);
+    }
+    tracing::info!("[DLP] Config updated: enabled={}, active_rules={}", config.enabled, active);
+}
+
+/// 获取当前规则集
+pub fn engine() -> Arc<DlpEngine> {
+    GLOBAL_DLP
+        .read()
+        .map(|e| e.clone())
+        .unwrap_or_else(|_| Arc::new(DlpEngine::default()))
+}
+
+/// 校验规则，返回无效规则的错误信息
+pub fn validate_config(config: &DlpConfig) -> Result<(), String> {
+    let (_, errors) = DlpEngine::compile(config);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Invalid DLP rules: {}", errors.join("; ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, detector: DlpDetector, action: DlpAction) -> DlpRule {
+        DlpRule {
+            name: name.to_string(),
+            enabled: true,
+            detector,
+            pattern: String::new(),
+            action,
+            restore_response: false,
+            user_tokens: Vec::new(),
+        }
+    }
+
+    fn request(text: &str) -> Value {
+        json!({
+            "project": "p",
+            "request": {
+                "contents": [{ "role": "user", "parts": [{ "text": text }, { "inlineData": { "data": "jane@example.com" } }] }],
+                "systemInstruction": { "parts": [{ "text": "be nice" }] }
+            }
+        })
+    }
+
+    #[test]
+    fn test_block_mask_log_and_scope() {
+        let mut employee = rule("employee id", DlpDetector::Regex, DlpAction::Mask);
+        employee.pattern = r"EMP-\d{6}".to_string();
+        employee.restore_response = true;
+        let mut key_for_ci = rule("api_keys", DlpDetector::ApiKey, DlpAction::Block);
+        key_for_ci.user_tokens = vec!["ci-bot".to_string()];
+        let config = DlpConfig {
+            enabled: true,
+            rules: vec![
+                key_for_ci,
+                employee,
+                rule("emails", DlpDetector::Email, DlpAction::Log),
+                rule("cards", DlpDetector::CreditCard, DlpAction::Mask),
+            ],
+        };
+        let (engine, errors) = DlpEngine::compile(&config);
+        assert!(errors.is_empty());
+
+        let text = "EMP-123456 and EMP-123456, EMP-654321 mail jane@example.com card 4111 1111 1111 1111 key sk-ant-REDACTED";
+
+        // 拦截规则只对 ci-bot 令牌生效
+        let mut body = request(text);
+        let outcome = engine.scan(&mut body, Some(("tok-1", "ci-bot")));
+        assert_eq!(outcome.blocked_by.as_deref(), Some("api_keys"));
+        assert_eq!(body, request(text));
+
+        let mut body = request(text);
+        let outcome = engine.scan(&mut body, None);
+        assert!(outcome.blocked_by.is_none());
+        assert_eq!(
+            body["request"]["contents"][0]["parts"][0]["text"],
+            "[DLP:employee_id:1] and [DLP:employee_id:1], [DLP:employee_id:2] mail jane@example.com card [DLP:cards:1] key sk-ant-REDACTED"
+        );
+        // 二进制数据不检查
+        assert_eq!(body["request"]["contents"][0]["parts"][1]["inlineData"]["data"], "jane@example.com");
+        assert_eq!(
+            outcome.hits,
+            vec![
+                DlpHit { rule: "emails".to_string(), action: DlpAction::Log, matches: 1 },
+                DlpHit { rule: "employee id".to_string(), action: DlpAction::Mask, matches: 3 },
+                DlpHit { rule: "cards".to_string(), action: DlpAction::Mask, matches: 1 },
+            ]
+        );
+
+        // 只还原开启 restore_response 的规则
+        assert_eq!(
+            outcome.vault.restore_text("ok [DLP:employee_id:2] [DLP:cards:1]"),
+            "ok EMP-654321 [DLP:cards:1]"
+        );
+
+        let mut invalid = config.clone();
+        invalid.rules.push(rule("emails", DlpDetector::Regex, DlpAction::Log));
+        assert!(validate_config(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_apply_text_masks_and_blocks_image_prompt() {
+        // 规则限定到测试专用令牌，避免影响其他测试
+        let mut mask = rule("employee id", DlpDetector::Regex, DlpAction::Mask);
+        mask.pattern = r"EMP-\d{6}".to_string();
+        mask.user_tokens = vec!["image-dlp-test".to_string()];
+        let mut block = rule("api_keys", DlpDetector::ApiKey, DlpAction::Block);
+        block.user_tokens = vec!["image-dlp-test".to_string()];
+        update_dlp_config(DlpConfig { enabled: true, rules: vec![mask, block] });
+
+        let guard = || DlpGuard {
+            protocol: DlpProtocol::OpenAI,
+            identity: Some(("tok-image".to_string(), "image-dlp-test".to_string())),
+            vault: Arc::new(MaskVault::default()),
+            recorded: true,
+        };
+
+        let mut prompt = "badge for EMP-123456".to_string();
+        assert!(guard().apply_text(&mut prompt, "test").is_none());
+        assert_eq!(prompt, "badge for [DLP:employee_id:1]");
+
+        let mut prompt = "poster with sk-ant-REDACTED".to_string();
+        let blocked = guard().apply_text(&mut prompt, "test");
+        assert_eq!(blocked.map(|r| r.status()), Some(StatusCode::BAD_REQUEST));
+
+        update_dlp_config(DlpConfig::default());
+    }
+
+    #[tokio::test]
+    async fn test_stream_restores_split_placeholder() {
+        let mut vault = MaskVault::default();
+        let placeholder = vault.placeholder("secret", "hunter2", true);
+        assert_eq!(placeholder, "[DLP:secret:1]");
+
+        let chunks = vec![
+            "data: {\"response\":{\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"pass is [DLP:se\"}]}}]}}\n\n",
+            "data: {\"response\":{\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"cret:1] ok\"}]}}]}}\n",
+            "\ndata: {\"response\":{\"candidates\":[{\"content\":{\"parts\":[{\"text\":\" [DLP\"}]},\"finishReason\":\"STOP\"}]}}\n\n",
+        ];
+        let upstream = futures::stream::iter(chunks.into_iter().map(|c| Ok::<_, String>(Bytes::from(c))));
+        let output: Vec<u8> = wrap_stream(Box::pin(upstream), Arc::new(vault))
+            .map(|item| item.unwrap().to_vec())
+            .concat()
+            .await;
+
+        let texts: Vec<String> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .filter_map(|l| l.strip_prefix("data: "))
+            .map(|d| {
+                let v: Value = serde_json::from_str(d).unwrap();
+                v["response"]["candidates"][0]["content"]["parts"][0]["text"].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(texts, vec!["pass is ", "hunter2 ok", " [DLP"]);
+    }
+}
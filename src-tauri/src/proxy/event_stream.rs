@@ -0,0 +1,401 @@
+// 管理端实时事件流
+//
+// 桌面端通过 Tauri 事件接收请求日志 / 账号状态 / 调试日志，Headless 与 Web 模式无法收到这些事件。
+// 这里用一个全局广播通道汇总同样的事件，由 /api/events (SSE) 与 /api/events/ws (WebSocket)
+// 按订阅方的筛选条件推送。没有订阅方时发布操作几乎没有开销。
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+
+use crate::modules::log_bridge::LogEntry;
+use crate::proxy::monitor::ProxyRequestLog;
+
+/// 广播通道容量，订阅方落后超过该数量时丢弃旧事件并收到 lagged 通知
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// 事件流接口路径 (浏览器 EventSource / WebSocket 无法自定义请求头，允许通过 ?token= 鉴权)
+const STREAM_PATHS: [&str; 2] = ["/events", "/events/ws"];
+
+/// 账号状态变化类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountState {
+    RateLimited,
+    Disabled,
+    ValidationBlocked,
+    Forbidden,
+    QuotaRefreshed,
+}
+
+/// 账号状态变化事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountEvent {
+    pub account_id: String,
+    pub email: Option<String>,
+    pub state: AccountState,
+    /// 模型级限流 / 保护对应的模型
+    pub model: Option<String>,
+    pub detail: Option<String>,
+    pub timestamp: i64,
+}
+
+/// 推送给管理端的事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum AdminEvent {
+    /// 新的请求日志 (不含请求 / 响应体)
+    Request(Box<ProxyRequestLog>),
+    Account(AccountEvent),
+    /// 调试控制台日志
+    Log(LogEntry),
+    /// 订阅方处理过慢，跳过了 skipped 条事件
+    Lagged { skipped: u64 },
+}
+
+impl AdminEvent {
+    fn topic(&self) -> Option<EventTopic> {
+        match self {
+            Self::Request(_) => Some(EventTopic::Request),
+            Self::Account(_) => Some(EventTopic::Account),
+            Self::Log(_) => Some(EventTopic::Log),
+            Self::Lagged { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EventTopic {
+    Request,
+    Account,
+    Log,
+}
+
+static CHANNEL: OnceLock<broadcast::Sender<AdminEvent>> = OnceLock::new();
+
+/// 订阅了调试日志的连接数，为 0 且调试控制台关闭时日志桥接直接跳过
+static LOG_SUBSCRIBERS: AtomicUsize = AtomicUsize::new(0);
+
+fn channel() -> &'static broadcast::Sender<AdminEvent> {
+    CHANNEL.get_or_init(|| broadcast::channel(CHANNEL_CAPACITY).0)
+}
+
+/// 是否有事件流订阅方
+pub fn has_subscribers() -> bool {
+    CHANNEL.get().is_some_and(|tx| tx.receiver_count() > 0)
+}
+
+/// 是否有订阅方需要调试日志
+pub fn wants_logs() -> bool {
+    LOG_SUBSCRIBERS.load(Ordering::Relaxed) > 0
+}
+
+/// 发布事件 (无订阅方时直接丢弃)
+pub fn publish(event: AdminEvent) {
+    if has_subscribers() {
+        let _ = channel().send(event);
+    }
+}
+
+/// 发布账号状态变化
+pub fn publish_account(
+    account_id: &str,
+    email: Option<&str>,
+    state: AccountState,
+    model: Option<&str>,
+    detail: Option<String>,
+) {
+    if !has_subscribers() {
+        return;
+    }
+    publish(AdminEvent::Account(AccountEvent {
+        account_id: account_id.to_string(),
+        email: email.map(|s| s.to_string()),
+        state,
+        model: model.map(|s| s.to_string()),
+        detail,
+        timestamp: chrono::Utc::now().timestamp_millis(),
+    }));
+}
+
+/// 是否为事件流接口 (兼容 nest 前后的路径)
+pub fn is_stream_path(path: &str) -> bool {
+    let path = path.strip_prefix("/api").unwrap_or(path);
+    STREAM_PATHS.contains(&path)
+}
+
+/// 订阅方的服务端筛选条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventStreamFilter {
+    /// 订阅的事件类型，逗号分隔 (request,account,log)，为空时订阅全部
+    #[serde(default)]
+    pub types: Option<String>,
+    /// 仅推送失败的请求
+    #[serde(default)]
+    pub errors_only: bool,
+    /// 请求模型 (包含匹配，同时匹配映射后的模型)
+    #[serde(default)]
+    pub model: Option<String>,
+    /// 账号 ID 或邮箱 (包含匹配)
+    #[serde(default)]
+    pub account: Option<String>,
+    /// 调试日志最低级别 (TRACE / DEBUG / INFO / WARN / ERROR)
+    #[serde(default)]
+    pub min_level: Option<String>,
+    /// 关键词 (请求 URL / 模型 / 账号 / IP / 用户名，日志内容 / target)
+    #[serde(default)]
+    pub filter: Option<String>,
+}
+
+fn level_rank(level: &str) -> u8 {
+    match level.to_ascii_uppercase().as_str() {
+        "TRACE" => 0,
+        "DEBUG" => 1,
+        "INFO" => 2,
+        "WARN" => 3,
+        "ERROR" => 4,
+        _ => 0,
+    }
+}
+
+impl EventStreamFilter {
+    fn topics(&self) -> Vec<EventTopic> {
+        let all = vec![EventTopic::Request, EventTopic::Account, EventTopic::Log];
+        let Some(types) = self.types.as_deref().filter(|t| !t.trim().is_empty()) else {
+            return all;
+        };
+        types
+            .split(',')
+            .filter_map(|t| match t.trim().to_ascii_lowercase().as_str() {
+                "request" | "requests" => Some(EventTopic::Request),
+                "account" | "accounts" => Some(EventTopic::Account),
+                "log" | "logs" => Some(EventTopic::Log),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn subscribes_logs(&self) -> bool {
+        self.topics().contains(&EventTopic::Log)
+    }
+
+    pub fn matches(&self, event: &AdminEvent) -> bool {
+        let Some(topic) = event.topic() else {
+            return true;
+        };
+        if !self.topics().contains(&topic) {
+            return false;
+        }
+
+        let contains = |value: Option<&str>, needle: &str| {
+            value.is_some_and(|v| v.to_lowercase().contains(&needle.to_lowercase()))
+        };
+        let model = self.model.as_deref().filter(|s| !s.is_empty());
+        let account = self.account.as_deref().filter(|s| !s.is_empty());
+        let keyword = self.filter.as_deref().filter(|s| !s.is_empty());
+
+        match event {
+            AdminEvent::Request(log) => {
+                if self.errors_only && log.status < 400 && log.error.is_none() {
+                    return false;
+                }
+                if let Some(m) = model {
+                    if !contains(log.model.as_deref(), m) && !contains(log.mapped_model.as_deref(), m) {
+                        return false;
+                    }
+                }
+                if let Some(a) = account {
+                    if !contains(log.account_email.as_deref(), a) {
+                        return false;
+                    }
+                }
+                keyword.is_none_or(|k| {
+                    [
+                        Some(log.url.as_str()),
+                        log.model.as_deref(),
+                        log.mapped_model.as_deref(),
+                        log.account_email.as_deref(),
+                        log.client_ip.as_deref(),
+                        log.username.as_deref(),
+                    ]
+                    .into_iter()
+                    .any(|v| contains(v, k))
+                })
+            }
+            AdminEvent::Account(event) => account.is_none_or(|a| {
+                contains(Some(&event.account_id), a) || contains(event.email.as_deref(), a)
+            }),
+            AdminEvent::Log(entry) => {
+                if let Some(min) = self.min_level.as_deref() {
+                    if level_rank(&entry.level) < level_rank(min) {
+                        return false;
+                    }
+                }
+                keyword.is_none_or(|k| {
+                    contains(Some(&entry.message), k) || contains(Some(&entry.target), k)
+                })
+            }
+            AdminEvent::Lagged { .. } => true,
+        }
+    }
+}
+
+/// 一个事件流订阅
+pub struct Subscription {
+    rx: broadcast::Receiver<AdminEvent>,
+    filter: EventStreamFilter,
+    counts_logs: bool,
+}
+
+impl Subscription {
+    pub fn new(filter: EventStreamFilter) -> Self {
+        let rx = channel().subscribe();
+        let mut sub = Self {
+            rx,
+            filter: EventStreamFilter::default(),
+            counts_logs: false,
+        };
+        sub.set_filter(filter);
+        sub
+    }
+
+    /// 替换筛选条件 (WebSocket 客户端可在连接后发送新的筛选条件)
+    pub fn set_filter(&mut self, filter: EventStreamFilter) {
+        let wants_logs = filter.subscribes_logs();
+        if wants_logs && !self.counts_logs {
+            LOG_SUBSCRIBERS.fetch_add(1, Ordering::Relaxed);
+        } else if !wants_logs && self.counts_logs {
+            LOG_SUBSCRIBERS.fetch_sub(1, Ordering::Relaxed);
+        }
+        self.counts_logs = wants_logs;
+        self.filter = filter;
+    }
+
+    /// 等待下一条符合筛选条件的事件，通道关闭时返回 None
+    pub async fn next(&mut self) -> Option<AdminEvent> {
+        loop {
+            match self.rx.recv().await {
+                Ok(event) if self.filter.matches(&event) => return Some(event),
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    return Some(AdminEvent::Lagged { skipped })
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if self.counts_logs {
+            LOG_SUBSCRIBERS.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_log(status: u16, model: &str, account: &str) -> ProxyRequestLog {
+        ProxyRequestLog {
+            id: "1".to_string(),
+            timestamp: 0,
+            method: "POST".to_string(),
+            url: "/v1/messages".to_string(),
+            status,
+            duration: 10,
+            model: Some(model.to_string()),
+            mapped_model: None,
+            account_email: Some(account.to_string()),
+            client_ip: None,
+            error: None,
+            request_body: None,
+            response_body: None,
+            input_tokens: None,
+            output_tokens: None,
+            protocol: None,
+            username: None,
+            latency: None,
+            replay_of: None,
+            cached_tokens: None,
+            thinking_tokens: None,
+            cost: None,
+            provider: None,
+        }
+    }
+
+    fn log_entry(level: &str, message: &str) -> LogEntry {
+        LogEntry {
+            id: 0,
+            timestamp: 0,
+            level: level.to_string(),
+            target: "antigravity".to_string(),
+            message: message.to_string(),
+            fields: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_filter_matching() {
+        let filter = EventStreamFilter {
+            types: Some("request, log".to_string()),
+            errors_only: true,
+            model: Some("Sonnet".to_string()),
+            min_level: Some("warn".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&AdminEvent::Request(Box::new(request_log(429, "claude-sonnet-4-5", "a@x.com")))));
+        assert!(!filter.matches(&AdminEvent::Request(Box::new(request_log(200, "claude-sonnet-4-5", "a@x.com")))));
+        assert!(!filter.matches(&AdminEvent::Request(Box::new(request_log(500, "gemini-3-pro", "a@x.com")))));
+        assert!(filter.matches(&AdminEvent::Log(log_entry("ERROR", "boom"))));
+        assert!(!filter.matches(&AdminEvent::Log(log_entry("INFO", "hello"))));
+        assert!(filter.matches(&AdminEvent::Lagged { skipped: 3 }));
+
+        let account_event = AdminEvent::Account(AccountEvent {
+            account_id: "acc-1".to_string(),
+            email: Some("b@x.com".to_string()),
+            state: AccountState::RateLimited,
+            model: None,
+            detail: None,
+            timestamp: 0,
+        });
+        assert!(!filter.matches(&account_event));
+        let by_account = EventStreamFilter {
+            account: Some("B@X".to_string()),
+            ..Default::default()
+        };
+        assert!(by_account.matches(&account_event));
+        assert!(!by_account.matches(&AdminEvent::Request(Box::new(request_log(200, "m", "a@x.com")))));
+    }
+
+    #[tokio::test]
+    async fn test_subscription_receives_filtered_events() {
+        let before = LOG_SUBSCRIBERS.load(Ordering::Relaxed);
+        let mut sub = Subscription::new(EventStreamFilter {
+            types: Some("log".to_string()),
+            filter: Some("needle".to_string()),
+            ..Default::default()
+        });
+        assert!(wants_logs());
+
+        publish(AdminEvent::Request(Box::new(request_log(200, "m", "a@x.com"))));
+        publish(AdminEvent::Log(log_entry("INFO", "haystack")));
+        publish(AdminEvent::Log(log_entry("INFO", "found the needle")));
+
+        match sub.next().await {
+            Some(AdminEvent::Log(entry)) => assert_eq!(entry.message, "found the needle"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        drop(sub);
+        assert_eq!(LOG_SUBSCRIBERS.load(Ordering::Relaxed), before);
+        assert!(is_stream_path("/api/events/ws"));
+        assert!(is_stream_path("/events"));
+        assert!(!is_stream_path("/api/logs"));
+    }
+}
@@ -10,7 +10,7 @@ use bytes::Bytes;
 use futures::StreamExt;
 use serde_json::{json, Value};
 use tokio::time::Duration;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, Instrument};
 
 use crate::proxy::mappers::claude::{
     transform_claude_request_in, transform_response, create_claude_sse_stream, ClaudeRequest,
@@ -22,6 +22,8 @@ use crate::proxy::server::AppState;
 use crate::proxy::mappers::context_manager::ContextManager;
 use crate::proxy::mappers::estimation_calibrator::get_calibrator;
 use crate::proxy::debug_logger;
+use crate::proxy::dlp::{DlpGuard, DlpProtocol};
+use crate::proxy::middleware::auth::UserTokenIdentity;
 use crate::proxy::upstream::client::mask_email;
 use crate::proxy::common::client_adapter::CLIENT_ADAPTERS; // [NEW] Import Adapter Registry
 use axum::http::HeaderMap;
@@ -235,7 +237,7 @@ The structure MUST be as follows:
 
 // ===== 统一退避策略模块 =====
 // 移除本地重复定义，使用 common 中的统一实现
-use super::common::{determine_retry_strategy, apply_retry_strategy, should_rotate_account, RetryStrategy};
+use super::common::{determine_retry_strategy, apply_retry_strategy, should_rotate_account, signature_recovery_span, RetryStrategy};
 
 // ===== 退避策略模块结束 =====
 
@@ -245,6 +247,7 @@ use super::common::{determine_retry_strategy, apply_retry_strategy, should_rotat
 pub async fn handle_messages(
     State(state): State<AppState>,
     headers: HeaderMap,
+    identity: Option<axum::Extension<UserTokenIdentity>>,
     Json(body): Json<Value>,
 ) -> Response {
     // [FIX] 保存原始请求体的完整副本，用于日志记录
@@ -528,6 +531,10 @@ pub async fn handle_messages(
     let mut last_email: Option<String> = None;
     let mut last_mapped_model: Option<String> = None;
     let mut last_status = StatusCode::SERVICE_UNAVAILABLE; // Default to 503 if no response reached
+    // [NEW] 签名修复重试的 span，由下一次上游调用继承
+    let mut recovery_span: Option<tracing::Span> = None;
+    // [NEW] 出站 DLP 检查 (命中统计只记录一次)
+    let mut dlp = DlpGuard::new(DlpProtocol::Anthropic, identity.as_deref());
     
     for attempt in 0..max_attempts {
         // 2. 模型路由解析
@@ -665,6 +672,7 @@ pub async fn handle_messages(
             // Borrowed from Practical-Guide-to-Context-Engineering
             // Advantage: Completely cache-friendly (only removes messages, doesn't modify content)
             if usage_ratio > threshold_l1 && !compression_applied {
+                let _span = tracing::info_span!("context_compression", layer = 1, usage_ratio = usage_ratio as f64).entered();
                 if ContextManager::trim_tool_messages(&mut request_with_mapped.messages, 5) {
                     info!(
                         "[{}] [Layer-1] Tool trimming triggered (usage: {:.1}%, threshold: {:.1}%)",
@@ -699,6 +707,7 @@ pub async fn handle_messages(
             // NEW: Preserve signatures while compressing thinking text
             // This prevents signature chain breakage (Issue #902)
             if usage_ratio > threshold_l2 && !compression_applied {
+                let _span = tracing::info_span!("context_compression", layer = 2, usage_ratio = usage_ratio as f64).entered();
                 info!(
                     "[{}] [Layer-2] Thinking compression triggered (usage: {:.1}%, threshold: {:.1}%)",
                     trace_id, usage_ratio * 100.0, threshold_l2 * 100.0
@@ -737,7 +746,11 @@ pub async fn handle_messages(
                 // Clone token_manager Arc to avoid borrow issues
                 let token_manager_clone = token_manager.clone();
                 
-                match try_compress_with_summary(&request_with_mapped, &trace_id, &token_manager_clone).await {
+                let layer3_span = tracing::info_span!("context_compression", layer = 3, usage_ratio = usage_ratio as f64);
+                match try_compress_with_summary(&request_with_mapped, &trace_id, &token_manager_clone)
+                    .instrument(layer3_span)
+                    .await
+                {
                     Ok(forked_request) => {
                         info!(
                             "[{}] [Layer-3] Fork successful: {} → {} messages",
@@ -796,7 +809,7 @@ pub async fn handle_messages(
         // let _trace_id = format!("req_{}", chrono::Utc::now().timestamp_subsec_millis());
 
         let token_obj = token_manager.get_token_by_id(&account_id);
-        let gemini_body = match transform_claude_request_in(&request_with_mapped, &project_id, retried_without_thinking, Some(account_id.as_str()), &session_id_str, token_obj.as_ref()) {
+        let mut gemini_body = match transform_claude_request_in(&request_with_mapped, &project_id, retried_without_thinking, Some(account_id.as_str()), &session_id_str, token_obj.as_ref()) {
             Ok(b) => {
                 debug!("[{}] Transformed Gemini Body: {}", trace_id, serde_json::to_string_pretty(&b).unwrap_or_default());
                 b
@@ -819,6 +832,9 @@ pub async fn handle_messages(
                 ).into_response();
             }
         };
+        if let Some(blocked) = dlp.apply(&mut gemini_body, &trace_id) {
+            return blocked;
+        }
 
         if debug_logger::is_enabled(&debug_cfg) {
             let payload = json!({
@@ -871,6 +887,7 @@ pub async fn handle_messages(
 
         let call_result = match upstream
             .call_v1_internal_with_headers(method, &access_token, gemini_body, query, extra_headers.clone(), Some(account_id.as_str()))
+            .instrument(recovery_span.take().unwrap_or_else(tracing::Span::current))
             .await {
             Ok(r) => r,
             Err(e) => {
@@ -935,6 +952,7 @@ pub async fn handle_messages(
                     "upstream_response",
                     meta,
                 );
+                let gemini_stream = dlp.wrap_stream(gemini_stream);
 
                 let current_message_count = request_with_mapped.messages.len();
 
@@ -1179,6 +1197,8 @@ pub async fn handle_messages(
         {
             // Existing logic for thinking signature...\n            retried_without_thinking = true;
             
+            recovery_span = Some(signature_recovery_span(attempt, &email));
+
             // 使用 WARN 级别,因为这不应该经常发生(已经主动过滤过)
             tracing::warn!(
                 "[{}] Unexpected thinking signature error (should have been filtered). \
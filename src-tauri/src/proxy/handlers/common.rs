@@ -75,6 +75,12 @@ pub fn determine_retry_strategy(
     }
 }
 
+/// 退避等待，计入请求延迟分解的排队时间
+async fn backoff(duration: Duration) {
+    sleep(duration).await;
+    crate::proxy::request_timing::record_queue_wait(duration);
+}
+
 /// 执行退避策略并返回是否应该继续重试
 pub async fn apply_retry_strategy(
     strategy: RetryStrategy,
@@ -99,7 +105,7 @@ pub async fn apply_retry_strategy(
                 max_attempts,
                 base_ms
             );
-            sleep(duration).await;
+            backoff(duration).await;
             true
         }
 
@@ -113,7 +119,7 @@ pub async fn apply_retry_strategy(
                 max_attempts,
                 calculated_ms
             );
-            sleep(Duration::from_millis(calculated_ms)).await;
+            backoff(Duration::from_millis(calculated_ms)).await;
             true
         }
 
@@ -127,7 +133,7 @@ pub async fn apply_retry_strategy(
                 max_attempts,
                 calculated_ms
             );
-            sleep(Duration::from_millis(calculated_ms)).await;
+            backoff(Duration::from_millis(calculated_ms)).await;
             true
         }
     }
@@ -145,6 +151,15 @@ pub fn should_rotate_account(status_code: u16) -> bool {
     }
 }
 
+/// 签名修复重试的链路 span，覆盖紧随其后的那次上游调用
+pub fn signature_recovery_span(attempt: usize, account: &str) -> tracing::Span {
+    tracing::info_span!(
+        "signature_recovery",
+        retry.attempt = attempt + 1,
+        account = account
+    )
+}
+
 /// Detects model capabilities and configuration
 /// POST /v1/models/detect
 pub async fn handle_detect_model(
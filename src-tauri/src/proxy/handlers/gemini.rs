@@ -6,14 +6,17 @@ use axum::{
     response::IntoResponse,
 };
 use serde_json::{json, Value};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, Instrument};
 
 use crate::proxy::common::client_adapter::CLIENT_ADAPTERS;
 use crate::proxy::debug_logger;
+use crate::proxy::dlp::{DlpGuard, DlpProtocol};
 use crate::proxy::handlers::common::{
     apply_retry_strategy, determine_retry_strategy, should_rotate_account,
+    signature_recovery_span,
 };
 use crate::proxy::mappers::gemini::{unwrap_response, wrap_request};
+use crate::proxy::middleware::auth::UserTokenIdentity;
 use crate::proxy::server::AppState;
 use crate::proxy::session_manager::SessionManager;
 use crate::proxy::upstream::client::mask_email;
@@ -27,6 +30,7 @@ pub async fn handle_generate(
     State(state): State<AppState>,
     Path(model_action): Path<String>,
     headers: HeaderMap,          // [NEW] Extract headers for adapter detection
+    identity: Option<axum::Extension<UserTokenIdentity>>,
     Json(mut body): Json<Value>, // 改为 mut 以支持修复提示词注入
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
     // 解析 model:method
@@ -93,6 +97,10 @@ pub async fn handle_generate(
 
     let mut last_error = String::new();
     let mut last_email: Option<String> = None;
+    // [NEW] 签名修复重试的 span，由下一次上游调用继承
+    let mut recovery_span: Option<tracing::Span> = None;
+    // [NEW] 出站 DLP 检查 (命中统计只记录一次)
+    let mut dlp = DlpGuard::new(DlpProtocol::Gemini, identity.as_deref());
 
     for attempt in 0..max_attempts {
         // 3. 模型路由解析
@@ -161,7 +169,10 @@ pub async fn handle_generate(
         // [FIX #765] Pass session_id to wrap_request for signature injection
         // [NEW] 获取完整 Token 对象以注入动态规格 (dynamic > static default > 65535)
         let token_obj = token_manager.get_token_by_id(&account_id);
-        let wrapped_body = wrap_request(&body, &project_id, &mapped_model, Some(account_id.as_str()), Some(&session_id), token_obj.as_ref());
+        let mut wrapped_body = wrap_request(&body, &project_id, &mapped_model, Some(account_id.as_str()), Some(&session_id), token_obj.as_ref());
+        if let Some(blocked) = dlp.apply(&mut wrapped_body, &trace_id) {
+            return Ok(blocked);
+        }
 
         if debug_logger::is_enabled(&debug_cfg) {
             let payload = json!({
@@ -210,6 +221,7 @@ pub async fn handle_generate(
                 extra_headers.clone(),
                 Some(account_id.as_str()),
             )
+            .instrument(recovery_span.take().unwrap_or_else(tracing::Span::current))
             .await
         {
             Ok(r) => r,
@@ -279,13 +291,14 @@ pub async fn handle_generate(
                     "status": status.as_u16(),
                     "upstream_url": upstream_url,
                 });
-                let mut response_stream = debug_logger::wrap_stream_with_debug(
+                let response_stream = debug_logger::wrap_stream_with_debug(
                     Box::pin(response.bytes_stream()),
                     debug_cfg.clone(),
                     trace_id.clone(),
                     "upstream_response",
                     meta,
                 );
+                let mut response_stream = dlp.wrap_stream(response_stream);
                 let mut buffer = BytesMut::new();
                 let s_id = session_id.clone(); // Clone for stream closure
 
@@ -481,6 +494,7 @@ pub async fn handle_generate(
                 .json()
                 .await
                 .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Parse error: {}", e)))?;
+            dlp.restore_json(&mut gemini_resp);
 
             // [FIX #1522] Inject Tool ID into Non-streaming Response
             crate::proxy::mappers::gemini::wrapper::inject_ids_to_response(
@@ -600,6 +614,7 @@ pub async fn handle_generate(
                 "[Gemini] Signature error detected on account {}, retrying without thinking",
                 email
             );
+            recovery_span = Some(signature_recovery_span(attempt, &email));
 
             // 追加修复提示词到请求体的最后一条内容
             if let Some(contents) = body.get_mut("contents").and_then(|v| v.as_array_mut()) {
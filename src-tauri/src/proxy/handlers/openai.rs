@@ -5,19 +5,22 @@ use axum::{
 use base64::Engine as _;
 use bytes::Bytes;
 use serde_json::{json, Value};
-use tracing::{debug, error, info}; // Import Engine trait for encode method
+use tracing::{debug, error, info, Instrument}; // Import Engine trait for encode method
 
 use crate::proxy::mappers::openai::{
     transform_openai_request, transform_openai_response, OpenAIRequest,
 };
 // use crate::proxy::upstream::client::UpstreamClient; // 通过 state 获取
 use crate::proxy::debug_logger;
+use crate::proxy::dlp::{DlpGuard, DlpProtocol};
+use crate::proxy::middleware::auth::UserTokenIdentity;
 use crate::proxy::server::AppState;
 use crate::proxy::upstream::client::mask_email;
 
 const MAX_RETRY_ATTEMPTS: usize = 3;
 use super::common::{
-    apply_retry_strategy, determine_retry_strategy, should_rotate_account, RetryStrategy,
+    apply_retry_strategy, determine_retry_strategy, should_rotate_account,
+    signature_recovery_span, RetryStrategy,
 };
 use crate::proxy::common::client_adapter::CLIENT_ADAPTERS; // [NEW] Adapter Registry
 use crate::proxy::session_manager::SessionManager;
@@ -28,13 +31,14 @@ use crate::modules::account;
 pub async fn handle_chat_completions(
     State(state): State<AppState>,
     headers: HeaderMap, // [CHANGED] Extract headers
+    identity: Option<axum::Extension<UserTokenIdentity>>,
     Json(mut body): Json<Value>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
     // [NEW] Check for Image Model Redirection
     let model_name = body.get("model").and_then(|v| v.as_str()).unwrap_or("").to_lowercase();
     if model_name.contains("image") || model_name.contains("dall-e") || model_name.contains("midjourney") {
         tracing::info!("[ChatRedirection] Redirecting model {} to image generations", model_name);
-        return intercept_chat_to_image(state, body, &model_name).await;
+        return intercept_chat_to_image(state, body, &model_name, identity.as_deref()).await;
     }
 
     // [FIX] 保存原始请求体的完整副本，用于日志记录
@@ -161,6 +165,10 @@ pub async fn handle_chat_completions(
         &openai_req.model,
         &*state.custom_mapping.read().await,
     );
+    // [NEW] 签名修复重试的 span，由下一次上游调用继承
+    let mut recovery_span: Option<tracing::Span> = None;
+    // [NEW] 出站 DLP 检查 (命中统计只记录一次)
+    let mut dlp = DlpGuard::new(DlpProtocol::OpenAI, identity.as_deref());
 
     for attempt in 0..max_attempts {
         // 将 OpenAI 工具转为 Value 数组以便探测联网
@@ -215,8 +223,11 @@ pub async fn handle_chat_completions(
         info!("✓ Using account: {} (type: {})", email, config.request_type);
 
         // 4. 转换请求 (返回内容包含 session_id 和 message_count)
-        let (gemini_body, session_id, message_count) =
+        let (mut gemini_body, session_id, message_count) =
             transform_openai_request(&openai_req, &project_id, &mapped_model, proxy_token.as_ref());
+        if let Some(blocked) = dlp.apply(&mut gemini_body, &trace_id) {
+            return Ok(blocked);
+        }
 
         if debug_logger::is_enabled(&debug_cfg) {
             let payload = json!({
@@ -284,6 +295,7 @@ pub async fn handle_chat_completions(
                 extra_headers.clone(),
                 Some(account_id.as_str()),
             )
+            .instrument(recovery_span.take().unwrap_or_else(tracing::Span::current))
             .await
         {
             Ok(r) => r,
@@ -359,6 +371,7 @@ pub async fn handle_chat_completions(
                     "upstream_response",
                     meta,
                 );
+                let gemini_stream = dlp.wrap_stream(gemini_stream);
 
                 // [P1 FIX] Enhanced Peek logic to handle heartbeats and slow start
                 // Pre-read until we find meaningful content, skip heartbeats
@@ -484,10 +497,11 @@ pub async fn handle_chat_completions(
                 }
             }
 
-            let gemini_resp: Value = response
+            let mut gemini_resp: Value = response
                 .json()
                 .await
                 .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Parse error: {}", e)))?;
+            dlp.restore_json(&mut gemini_resp);
 
             let openai_response =
                 transform_openai_response(&gemini_resp, Some(&session_id), message_count);
@@ -624,6 +638,7 @@ pub async fn handle_chat_completions(
                 "[OpenAI] Signature error detected on account {}, retrying without thinking",
                 email
             );
+            recovery_span = Some(signature_recovery_span(attempt, &email));
 
             // 追加修复提示词到最后一条用户消息
             if let Some(last_msg) = openai_req.messages.last_mut() {
@@ -756,6 +771,7 @@ pub async fn handle_chat_completions(
 /// 将 Prompt 转换为 Chat Message 格式，复用 handle_chat_completions
 pub async fn handle_completions(
     State(state): State<AppState>,
+    identity: Option<axum::Extension<UserTokenIdentity>>,
     Json(mut body): Json<Value>,
 ) -> Response {
     debug!(
@@ -1145,6 +1161,8 @@ pub async fn handle_completions(
         &*state.custom_mapping.read().await,
     );
     let trace_id = format!("req_{}", chrono::Utc::now().timestamp_subsec_millis());
+    // [NEW] 出站 DLP 检查 (命中统计只记录一次)
+    let mut dlp = DlpGuard::new(DlpProtocol::OpenAI, identity.as_deref());
 
     for attempt in 0..max_attempts {
         // 3. 模型配置解析
@@ -1200,8 +1218,11 @@ pub async fn handle_completions(
         info!("✓ Using account: {} (type: {})", email, config.request_type);
 
         let proxy_token = token_manager.get_token_by_id(&account_id);
-        let (gemini_body, session_id, message_count) =
+        let (mut gemini_body, session_id, message_count) =
             transform_openai_request(&openai_req, &project_id, &mapped_model, proxy_token.as_ref());
+        if let Some(blocked) = dlp.apply(&mut gemini_body, &trace_id) {
+            return blocked;
+        }
 
         // [New] 打印转换后的报文 (Gemini Body) 供调试 (Codex 路径) ———— 缩减为 simple debug
         debug!(
@@ -1258,7 +1279,7 @@ pub async fn handle_completions(
                 use axum::response::Response;
                 use futures::StreamExt;
 
-                let gemini_stream = response.bytes_stream();
+                let gemini_stream = dlp.wrap_stream(Box::pin(response.bytes_stream()));
 
                 // DECISION: Which stream to create?
                 // If client wants stream: give them what they asked (Legacy/Codex SSE).
@@ -1269,7 +1290,7 @@ pub async fn handle_completions(
                     let mut openai_stream = if is_codex_style {
                         use crate::proxy::mappers::openai::streaming::create_codex_sse_stream;
                         create_codex_sse_stream(
-                            Box::pin(gemini_stream),
+                            gemini_stream,
                             openai_req.model.clone(),
                             session_id,
                             message_count,
@@ -1277,7 +1298,7 @@ pub async fn handle_completions(
                     } else {
                         use crate::proxy::mappers::openai::streaming::create_legacy_sse_stream;
                         create_legacy_sse_stream(
-                            Box::pin(gemini_stream),
+                            gemini_stream,
                             openai_req.model.clone(),
                             session_id,
                             message_count,
@@ -1356,7 +1377,7 @@ pub async fn handle_completions(
                     // Note: We use create_openai_sse_stream regardless of is_codex_style here,
                     // because we just want the content aggregation which chat stream does well.
                     let mut openai_stream = create_openai_sse_stream(
-                        Box::pin(gemini_stream),
+                        gemini_stream,
                         openai_req.model.clone(),
                         session_id,
                         message_count,
@@ -1463,7 +1484,7 @@ pub async fn handle_completions(
                 }
             }
 
-            let gemini_resp: Value = match response.json().await {
+            let mut gemini_resp: Value = match response.json().await {
                 Ok(json) => json,
                 Err(e) => {
                     return (
@@ -1474,6 +1495,7 @@ pub async fn handle_completions(
                         .into_response();
                 }
             };
+            dlp.restore_json(&mut gemini_resp);
 
             let chat_resp = transform_openai_response(&gemini_resp, Some("session-123"), 1);
 
@@ -1608,15 +1630,17 @@ pub async fn handle_list_models(State(state): State<AppState>) -> impl IntoRespo
 pub async fn handle_chat_redirection(
     State(state): State<AppState>,
     headers: HeaderMap,
+    identity: Option<axum::Extension<UserTokenIdentity>>,
     Json(body): Json<Value>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    handle_chat_completions(State(state), headers, Json(body)).await
+    handle_chat_completions(State(state), headers, identity, Json(body)).await
 }
 
 async fn intercept_chat_to_image(
     state: AppState,
     body: Value,
     model_name: &str,
+    identity: Option<&UserTokenIdentity>,
 ) -> Result<Response, (StatusCode, String)> {
     // 1. Extract prompt from messages
     let mut prompt = String::new();
@@ -1642,6 +1666,12 @@ async fn intercept_chat_to_image(
         prompt = "A beautiful painting".to_string(); // fallback
     }
 
+    // [NEW] 提示词同样发往上游，执行 DLP 检查
+    let trace_id = format!("img_{}", chrono::Utc::now().timestamp_subsec_millis());
+    if let Some(blocked) = DlpGuard::new(DlpProtocol::OpenAI, identity).apply_text(&mut prompt, &trace_id) {
+        return Ok(blocked);
+    }
+
     let is_stream = body.get("stream").and_then(|v| v.as_bool()).unwrap_or(false);
 
     // 2. Call internal image generator
@@ -1740,8 +1770,17 @@ async fn intercept_chat_to_image(
 
 pub async fn handle_images_generations(
     State(state): State<AppState>,
-    Json(body): Json<Value>,
+    identity: Option<axum::Extension<UserTokenIdentity>>,
+    Json(mut body): Json<Value>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
+    // [NEW] 提示词同样发往上游，执行 DLP 检查
+    if let Some(Value::String(prompt)) = body.get_mut("prompt") {
+        let trace_id = format!("img_{}", chrono::Utc::now().timestamp_subsec_millis());
+        let mut dlp = DlpGuard::new(DlpProtocol::OpenAI, identity.as_deref());
+        if let Some(blocked) = dlp.apply_text(prompt, &trace_id) {
+            return Ok(blocked);
+        }
+    }
     match handle_images_generations_internal(state, body).await {
         Ok((email_header, openai_response)) => Ok((
             StatusCode::OK,
@@ -2057,6 +2096,7 @@ pub async fn handle_images_generations_internal(
 
 pub async fn handle_images_edits(
     State(state): State<AppState>,
+    identity: Option<axum::Extension<UserTokenIdentity>>,
     mut multipart: axum::extract::Multipart,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
     tracing::info!("[Images] Received edit request");
@@ -2188,6 +2228,12 @@ pub async fn handle_images_edits(
     if let Some(s) = style {
         final_prompt.push_str(&format!(", style: {}", s));
     }
+    // [NEW] 提示词同样发往上游，执行 DLP 检查
+    let trace_id = format!("img_{}", chrono::Utc::now().timestamp_subsec_millis());
+    let mut dlp = DlpGuard::new(DlpProtocol::OpenAI, identity.as_deref());
+    if let Some(blocked) = dlp.apply_text(&mut final_prompt, &trace_id) {
+        return Ok(blocked);
+    }
     contents_parts.push(json!({
         "text": final_prompt
     }));
@@ -258,6 +258,12 @@ pub async fn handle_warmup(
                 output_tokens: Some(0),
                 protocol: Some("warmup".to_string()),
                 username: None,
+                latency: None,
+                replay_of: None,
+                cached_tokens: None,
+                thinking_tokens: None,
+                cost: None,
+                provider: None,
             };
             state.monitor.log_request(log).await;
 
@@ -352,6 +358,12 @@ pub async fn handle_warmup(
                 output_tokens: None,
                 protocol: Some("warmup".to_string()),
                 username: None,
+                latency: None,
+                replay_of: None,
+                cached_tokens: None,
+                thinking_tokens: None,
+                cost: None,
+                provider: None,
             };
             state.monitor.log_request(log).await;
 
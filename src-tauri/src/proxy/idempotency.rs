@@ -0,0 +1,511 @@
+// 幂等请求去重
+//
+// Agent 框架常在客户端超时后重试，而第一次请求仍在上游执行，导致配额被重复消耗。
+// 带 Idempotency-Key 的重复请求在窗口期内会挂到原请求上：
+// 原请求仍在进行时先回放已缓冲的事件再实时跟随，已完成时直接回放完整结果。
+
+use axum::http::{HeaderMap, StatusCode};
+use bytes::Bytes;
+use dashmap::DashMap;
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// 显式幂等键请求头：同一个键携带不同请求体时返回 422
+pub const IDEMPOTENCY_HEADERS: [&str; 2] = ["idempotency-key", "x-idempotency-key"];
+
+/// 客户端请求 ID (OpenAI / Anthropic SDK)：同样用于去重，但内容不一致时直接放行
+pub const REQUEST_ID_HEADERS: [&str; 2] = ["x-client-request-id", "x-request-id"];
+
+/// 回放响应上附加的标记头
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// 幂等键最大长度，超出视为无效键
+const MAX_KEY_LEN: usize = 256;
+
+/// 幂等去重配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdempotencyConfig {
+    /// 是否启用
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 去重窗口 (秒)：原请求完成后结果保留的时长
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+    /// 最多保留的条目数，超出后新请求不再参与去重
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+    /// 单个响应最多缓冲的字节数，超出后该结果不再保留给重复请求
+    #[serde(default = "default_max_response_bytes")]
+    pub max_response_bytes: usize,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_secs: default_window_secs(),
+            max_entries: default_max_entries(),
+            max_response_bytes: default_max_response_bytes(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_window_secs() -> u64 {
+    600
+}
+
+fn default_max_entries() -> usize {
+    1000
+}
+
+fn default_max_response_bytes() -> usize {
+    8 * 1024 * 1024
+}
+
+static GLOBAL_IDEMPOTENCY_CONFIG: OnceLock<RwLock<IdempotencyConfig>> = OnceLock::new();
+
+/// 获取当前幂等去重配置
+pub fn get_idempotency_config() -> IdempotencyConfig {
+    GLOBAL_IDEMPOTENCY_CONFIG
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+/// 更新全局幂等去重配置
+pub fn update_idempotency_config(config: IdempotencyConfig) {
+    if let Some(lock) = GLOBAL_IDEMPOTENCY_CONFIG.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = config.clone();
+        }
+    } else {
+        let _ = GLOBAL_IDEMPOTENCY_CONFIG.set(RwLock::new(config.clone()));
+    }
+    tracing::info!(
+        "[Idempotency] Config updated: enabled={}, window={}s, max_entries={}",
+        config.enabled,
+        config.window_secs,
+        config.max_entries
+    );
+}
+
+/// 请求携带的幂等键
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdempotencyKey {
+    pub value: String,
+    /// 是否来自显式的 Idempotency-Key (而非客户端请求 ID)
+    pub explicit: bool,
+}
+
+/// 从请求头中提取幂等键，显式键优先
+pub fn extract_idempotency_key(headers: &HeaderMap) -> Option<IdempotencyKey> {
+    let find = |names: &[&str]| {
+        names.iter().find_map(|name| {
+            headers
+                .get(*name)
+                .and_then(|v| v.to_str().ok())
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty() && s.len() <= MAX_KEY_LEN)
+                .map(|s| s.to_string())
+        })
+    };
+    find(&IDEMPOTENCY_HEADERS)
+        .map(|value| IdempotencyKey { value, explicit: true })
+        .or_else(|| find(&REQUEST_ID_HEADERS).map(|value| IdempotencyKey { value, explicit: false }))
+}
+
+/// 计算存储键：调用方身份 + 路径 + 幂等键，避免不同客户端的键互相冲突
+pub fn scoped_key(scope: &str, path: &str, key: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(scope.as_bytes());
+    hasher.update([0]);
+    hasher.update(path.as_bytes());
+    hasher.update([0]);
+    hasher.update(key.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// 请求体摘要，用于识别复用同一个键但内容不同的请求
+pub fn body_digest(body: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(body))
+}
+
+#[derive(Default)]
+struct EntryState {
+    head: Option<(StatusCode, HeaderMap)>,
+    chunks: Vec<Bytes>,
+    buffered_bytes: usize,
+    /// 响应超出缓冲上限，已丢弃缓冲内容
+    oversized: bool,
+    finished: bool,
+    finished_at: Option<Instant>,
+    error: Option<String>,
+}
+
+/// 一次原始请求的结果缓冲
+pub struct IdempotentEntry {
+    body_digest: String,
+    max_bytes: usize,
+    state: parking_lot::Mutex<EntryState>,
+    version: watch::Sender<u64>,
+}
+
+impl IdempotentEntry {
+    fn new(body_digest: String, max_bytes: usize) -> Self {
+        Self {
+            body_digest,
+            max_bytes,
+            state: parking_lot::Mutex::new(EntryState::default()),
+            version: watch::channel(0).0,
+        }
+    }
+
+    fn bump(&self) {
+        self.version.send_modify(|v| *v += 1);
+    }
+
+    pub fn set_head(&self, status: StatusCode, headers: HeaderMap) {
+        self.state.lock().head = Some((status, headers));
+        self.bump();
+    }
+
+    /// 缓冲一个响应分片；超出上限后丢弃已缓冲内容，重复请求不再回放该结果
+    pub fn push_chunk(&self, chunk: Bytes) {
+        {
+            let mut state = self.state.lock();
+            if state.oversized {
+                return;
+            }
+            state.buffered_bytes += chunk.len();
+            if state.buffered_bytes > self.max_bytes {
+                state.oversized = true;
+                state.chunks = Vec::new();
+            } else {
+                state.chunks.push(chunk);
+            }
+        }
+        self.bump();
+    }
+
+    pub fn finish(&self, error: Option<String>) {
+        {
+            let mut state = self.state.lock();
+            state.finished = true;
+            state.finished_at = Some(Instant::now());
+            state.error = error;
+        }
+        self.bump();
+    }
+
+    /// 是否为成功结果 (失败结果不缓存，重试应重新下发)
+    pub fn is_success(&self) -> bool {
+        let state = self.state.lock();
+        state.error.is_none()
+            && !state.oversized
+            && state
+                .head
+                .as_ref()
+                .map(|(status, _)| status.is_success())
+                .unwrap_or(false)
+    }
+
+    fn is_oversized(&self) -> bool {
+        self.state.lock().oversized
+    }
+
+    /// 去重窗口从原请求完成时起算，进行中的条目不会过期
+    fn is_expired(&self, window: Duration) -> bool {
+        self.state
+            .lock()
+            .finished_at
+            .is_some_and(|at| at.elapsed() > window)
+    }
+
+    /// 等待原请求返回响应头
+    pub async fn wait_head(&self) -> Option<(StatusCode, HeaderMap)> {
+        let mut rx = self.version.subscribe();
+        loop {
+            {
+                let state = self.state.lock();
+                if let Some(head) = state.head.clone() {
+                    return Some(head);
+                }
+                if state.finished {
+                    return None;
+                }
+            }
+            if rx.changed().await.is_err() {
+                return None;
+            }
+        }
+    }
+
+    /// 订阅响应体：先回放已缓冲的分片，再实时跟随直到原请求结束
+    pub fn subscribe(
+        self: &Arc<Self>,
+    ) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send + 'static {
+        let rx = self.version.subscribe();
+        futures::stream::unfold(
+            (self.clone(), 0usize, rx, false),
+            |(entry, idx, mut rx, done)| async move {
+                if done {
+                    return None;
+                }
+                loop {
+                    {
+                        let state = entry.state.lock();
+                        if state.oversized {
+                            drop(state);
+                            let error = std::io::Error::other(
+                                "Original response exceeded the idempotency buffer limit",
+                            );
+                            return Some((Err(error), (entry, idx, rx, true)));
+                        }
+                        if let Some(chunk) = state.chunks.get(idx).cloned() {
+                            drop(state);
+                            return Some((Ok(chunk), (entry, idx + 1, rx, false)));
+                        }
+                        if state.finished {
+                            let error = state.error.clone();
+                            drop(state);
+                            return error.map(|e| {
+                                (Err(std::io::Error::other(e)), (entry, idx, rx, true))
+                            });
+                        }
+                    }
+                    if rx.changed().await.is_err() {
+                        return None;
+                    }
+                }
+            },
+        )
+    }
+}
+
+/// 登记结果
+pub enum Claim {
+    /// 首个请求，负责实际下发
+    Leader(Arc<IdempotentEntry>),
+    /// 重复请求，挂到原请求上
+    Attached(Arc<IdempotentEntry>),
+    /// 同一个键被用于不同的请求体
+    Conflict,
+    /// 条目已满或原响应过大，不参与去重
+    Bypass,
+}
+
+/// 幂等条目存储
+#[derive(Default)]
+pub struct IdempotencyStore {
+    entries: DashMap<String, Arc<IdempotentEntry>>,
+}
+
+impl IdempotencyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记一个带幂等键的请求
+    pub fn claim(&self, key: &str, body_digest: &str, config: &IdempotencyConfig) -> Claim {
+        let window = Duration::from_secs(config.window_secs);
+        self.purge_expired(window);
+        if self.entries.len() >= config.max_entries {
+            return Claim::Bypass;
+        }
+
+        match self.entries.entry(key.to_string()) {
+            dashmap::mapref::entry::Entry::Occupied(mut occupied) => {
+                let existing = occupied.get().clone();
+                if existing.is_expired(window) {
+                    let entry = Arc::new(IdempotentEntry::new(
+                        body_digest.to_string(),
+                        config.max_response_bytes,
+                    ));
+                    occupied.insert(entry.clone());
+                    return Claim::Leader(entry);
+                }
+                if existing.body_digest != body_digest {
+                    return Claim::Conflict;
+                }
+                if existing.is_oversized() {
+                    return Claim::Bypass;
+                }
+                Claim::Attached(existing)
+            }
+            dashmap::mapref::entry::Entry::Vacant(vacant) => {
+                let entry = Arc::new(IdempotentEntry::new(
+                    body_digest.to_string(),
+                    config.max_response_bytes,
+                ));
+                vacant.insert(entry.clone());
+                Claim::Leader(entry)
+            }
+        }
+    }
+
+    /// 原请求结束后调用：失败结果立即移除，允许客户端重新下发
+    pub fn complete(&self, key: &str, entry: &Arc<IdempotentEntry>) {
+        if !entry.is_success() {
+            self.entries
+                .remove_if(key, |_, current| Arc::ptr_eq(current, entry));
+        }
+    }
+
+    fn purge_expired(&self, window: Duration) {
+        self.entries.retain(|_, entry| !entry.is_expired(window));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[test]
+    fn test_extract_idempotency_key() {
+        let mut headers = HeaderMap::new();
+        assert!(extract_idempotency_key(&headers).is_none());
+        headers.insert("x-request-id", "req-1".parse().unwrap());
+        let key = extract_idempotency_key(&headers).unwrap();
+        assert_eq!((key.value.as_str(), key.explicit), ("req-1", false));
+        headers.insert("idempotency-key", "idem-1".parse().unwrap());
+        let key = extract_idempotency_key(&headers).unwrap();
+        assert_eq!((key.value.as_str(), key.explicit), ("idem-1", true));
+    }
+
+    #[test]
+    fn test_claim_leader_attach_and_conflict() {
+        let store = IdempotencyStore::new();
+        let config = IdempotencyConfig::default();
+
+        assert!(matches!(store.claim("k", "body-a", &config), Claim::Leader(_)));
+        assert!(matches!(store.claim("k", "body-a", &config), Claim::Attached(_)));
+        assert!(matches!(store.claim("k", "body-b", &config), Claim::Conflict));
+    }
+
+    #[test]
+    fn test_failed_result_is_not_cached() {
+        let store = IdempotencyStore::new();
+        let config = IdempotencyConfig::default();
+
+        let Claim::Leader(entry) = store.claim("k", "body", &config) else {
+            panic!("expected leader");
+        };
+        entry.set_head(StatusCode::TOO_MANY_REQUESTS, HeaderMap::new());
+        entry.finish(None);
+        store.complete("k", &entry);
+        assert_eq!(store.len(), 0);
+
+        let Claim::Leader(entry) = store.claim("k", "body", &config) else {
+            panic!("expected leader");
+        };
+        entry.set_head(StatusCode::OK, HeaderMap::new());
+        entry.finish(None);
+        store.complete("k", &entry);
+        assert!(matches!(store.claim("k", "body", &config), Claim::Attached(_)));
+    }
+
+    #[tokio::test]
+    async fn test_subscriber_replays_then_tails() {
+        let entry = Arc::new(IdempotentEntry::new("body".to_string(), usize::MAX));
+        entry.set_head(StatusCode::OK, HeaderMap::new());
+        entry.push_chunk(Bytes::from_static(b"a"));
+
+        let mut late = Box::pin(entry.subscribe());
+        assert_eq!(late.next().await.unwrap().unwrap(), Bytes::from_static(b"a"));
+
+        let producer = entry.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            producer.push_chunk(Bytes::from_static(b"b"));
+            producer.finish(None);
+        });
+
+        assert_eq!(late.next().await.unwrap().unwrap(), Bytes::from_static(b"b"));
+        assert!(late.next().await.is_none());
+
+        // 完成后的订阅者拿到完整回放
+        let replay: Vec<Bytes> = entry.subscribe().map(|c| c.unwrap()).collect().await;
+        assert_eq!(replay, vec![Bytes::from_static(b"a"), Bytes::from_static(b"b")]);
+        assert_eq!(entry.wait_head().await.unwrap().0, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_full_store_bypasses() {
+        let store = IdempotencyStore::new();
+        let config = IdempotencyConfig {
+            max_entries: 1,
+            ..Default::default()
+        };
+        assert!(matches!(store.claim("a", "body", &config), Claim::Leader(_)));
+        assert!(matches!(store.claim("b", "body", &config), Claim::Bypass));
+    }
+
+    #[test]
+    fn test_window_starts_at_completion() {
+        let entry = IdempotentEntry::new("body".to_string(), usize::MAX);
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(!entry.is_expired(Duration::from_millis(10)));
+
+        entry.finish(None);
+        assert!(!entry.is_expired(Duration::from_millis(10)));
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(entry.is_expired(Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn test_expired_entries_purged_on_claim() {
+        let store = IdempotencyStore::new();
+        let config = IdempotencyConfig {
+            window_secs: 0,
+            ..Default::default()
+        };
+        let Claim::Leader(entry) = store.claim("a", "body", &config) else {
+            panic!("expected leader");
+        };
+        entry.set_head(StatusCode::OK, HeaderMap::new());
+        entry.finish(None);
+        store.complete("a", &entry);
+        std::thread::sleep(Duration::from_millis(5));
+
+        assert!(matches!(store.claim("b", "body", &config), Claim::Leader(_)));
+        assert_eq!(store.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_response_is_not_kept() {
+        let store = IdempotencyStore::new();
+        let config = IdempotencyConfig {
+            max_response_bytes: 4,
+            ..Default::default()
+        };
+        let Claim::Leader(entry) = store.claim("k", "body", &config) else {
+            panic!("expected leader");
+        };
+        entry.set_head(StatusCode::OK, HeaderMap::new());
+        entry.push_chunk(Bytes::from_static(b"abc"));
+        assert!(matches!(store.claim("k", "body", &config), Claim::Attached(_)));
+
+        entry.push_chunk(Bytes::from_static(b"def"));
+        assert!(matches!(store.claim("k", "body", &config), Claim::Bypass));
+        let mut follower = Box::pin(entry.subscribe());
+        assert!(follower.next().await.unwrap().is_err());
+
+        entry.finish(None);
+        store.complete("k", &entry);
+        assert_eq!(store.len(), 0);
+    }
+}
@@ -0,0 +1,554 @@
+// Prometheus 指标导出
+//
+// 请求 / 上游尝试等计数在内存中累加，账号池与并发等瞬时值在抓取时采集，
+// 以 Prometheus 文本格式 (0.0.4) 输出，供 Grafana 等直接抓取。
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{OnceLock, RwLock};
+
+use crate::proxy::monitor::ProxyRequestLog;
+
+/// Prometheus 文本格式的 Content-Type
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// 请求延迟直方图分桶 (秒)
+const LATENCY_BUCKETS_SECS: [f64; 12] = [
+    0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0,
+];
+
+/// 指标导出配置
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MetricsConfig {
+    /// 是否启用 /metrics
+    #[serde(default)]
+    pub enabled: bool,
+    /// 抓取专用 Bearer Token；主端口必须配置后才能抓取
+    #[serde(default)]
+    pub auth_token: String,
+    /// 独立监听地址 (如 "127.0.0.1:9464")；未设置 auth_token 时该地址上的抓取无需鉴权
+    #[serde(default)]
+    pub listen_address: Option<String>,
+}
+
+static GLOBAL_METRICS_CONFIG: OnceLock<RwLock<MetricsConfig>> = OnceLock::new();
+
+/// 获取当前指标导出配置
+pub fn get_metrics_config() -> MetricsConfig {
+    GLOBAL_METRICS_CONFIG
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+/// 更新全局指标导出配置
+pub fn update_metrics_config(config: MetricsConfig) {
+    if let Some(lock) = GLOBAL_METRICS_CONFIG.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = config.clone();
+        }
+    } else {
+        let _ = GLOBAL_METRICS_CONFIG.set(RwLock::new(config.clone()));
+    }
+    tracing::info!(
+        "[Metrics] Config updated: enabled={}, listen_address={:?}",
+        config.enabled,
+        config.listen_address
+    );
+}
+
+/// /metrics 独立监听 (listen_address)，配置变更时重新绑定
+#[derive(Default)]
+pub struct MetricsListener {
+    app: OnceLock<axum::Router>,
+    current: tokio::sync::Mutex<Option<(String, tokio::task::JoinHandle<()>)>>,
+}
+
+impl MetricsListener {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_app(&self, app: axum::Router) {
+        let _ = self.app.set(app);
+    }
+
+    /// 按配置启动 / 切换 / 关闭独立监听
+    pub async fn apply(&self, config: &MetricsConfig) {
+        let desired = config
+            .listen_address
+            .clone()
+            .filter(|addr| config.enabled && !addr.trim().is_empty());
+
+        let mut current = self.current.lock().await;
+        if current.as_ref().map(|(addr, _)| addr) == desired.as_ref() {
+            return;
+        }
+        if let Some((addr, handle)) = current.take() {
+            handle.abort();
+            tracing::info!("[Metrics] Stopped dedicated listener on {}", addr);
+        }
+        let (Some(addr), Some(app)) = (desired, self.app.get().cloned()) else {
+            return;
+        };
+
+        match tokio::net::TcpListener::bind(&addr).await {
+            Ok(listener) => {
+                let handle = tokio::spawn(async move {
+                    if let Err(e) = axum::serve(listener, app).await {
+                        tracing::error!("[Metrics] Dedicated listener exited: {}", e);
+                    }
+                });
+                tracing::info!("[Metrics] Serving http://{}/metrics", addr);
+                *current = Some((addr, handle));
+            }
+            Err(e) => tracing::error!("[Metrics] Failed to bind {}: {}", addr, e),
+        }
+    }
+
+    pub async fn shutdown(&self) {
+        if let Some((_, handle)) = self.current.lock().await.take() {
+            handle.abort();
+        }
+    }
+}
+
+/// 校验抓取凭证
+///
+/// - 配置了 auth_token 时必须携带该 Token (常量时间比较)
+/// - 否则仅独立监听地址放行；主端口不接受管理密码 / API Key，避免成为凭证探测入口
+pub fn is_authorized(provided: Option<&str>, config: &MetricsConfig, dedicated_listener: bool) -> bool {
+    if !config.auth_token.is_empty() {
+        return provided.is_some_and(|token| {
+            crate::utils::crypto::constant_time_eq(token.as_bytes(), config.auth_token.as_bytes())
+        });
+    }
+    dedicated_listener
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct RequestLabels {
+    protocol: String,
+    model: String,
+    mapped_model: String,
+    status: String,
+    user: String,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS_SECS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, secs: f64) {
+        for (i, bound) in LATENCY_BUCKETS_SECS.iter().enumerate() {
+            if secs <= *bound {
+                self.buckets[i] += 1;
+            }
+        }
+        self.sum += secs;
+        self.count += 1;
+    }
+}
+
+/// 正在处理中的请求计数，Drop 时自动减一
+pub struct InFlightGuard;
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        global().in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// 账号池瞬时状态 (抓取时由 TokenManager 采集)
+#[derive(Debug, Clone, Default)]
+pub struct AccountPoolMetrics {
+    pub total: usize,
+    pub available: usize,
+    pub rate_limited: usize,
+    pub validation_blocked: usize,
+    /// (账号邮箱, 模型, 剩余配额百分比)
+    pub model_quotas: Vec<(String, String, i32)>,
+}
+
+/// 抓取时采集的瞬时指标
+#[derive(Debug, Clone, Default)]
+pub struct ScrapeSnapshot {
+    pub accounts: AccountPoolMetrics,
+    pub disabled_accounts: usize,
+    /// (代理名称, 活跃连接数, 累计连接数)
+    pub proxy_connections: Vec<(String, usize, u64)>,
+    pub idempotency_entries: usize,
+}
+
+/// 累计型指标
+#[derive(Default)]
+pub struct ProxyMetrics {
+    requests: Mutex<HashMap<RequestLabels, Histogram>>,
+    /// (model, user, direction) -> tokens
+    tokens: Mutex<HashMap<(String, String, &'static str), u64>>,
+    /// (endpoint, status) -> attempts
+    upstream_attempts: Mutex<HashMap<(String, String), u64>>,
+    /// endpoint -> 从该端点降级到下一个端点的次数
+    upstream_fallbacks: Mutex<HashMap<String, u64>>,
+    in_flight: AtomicI64,
+}
+
+static GLOBAL_METRICS: OnceLock<ProxyMetrics> = OnceLock::new();
+
+/// 全局指标注册表
+pub fn global() -> &'static ProxyMetrics {
+    GLOBAL_METRICS.get_or_init(ProxyMetrics::default)
+}
+
+fn label_or_unknown(value: Option<&str>) -> String {
+    value
+        .filter(|v| !v.is_empty())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+impl ProxyMetrics {
+    /// 记录一次已完成的代理请求
+    pub fn record_request(&self, log: &ProxyRequestLog) {
+        let labels = RequestLabels {
+            protocol: label_or_unknown(log.protocol.as_deref()),
+            model: label_or_unknown(log.model.as_deref()),
+            mapped_model: label_or_unknown(log.mapped_model.as_deref()),
+            status: log.status.to_string(),
+            user: log.username.clone().unwrap_or_default(),
+        };
+        let secs = log.duration as f64 / 1000.0;
+        self.requests.lock().entry(labels.clone()).or_default().observe(secs);
+
+        let mut tokens = self.tokens.lock();
+        for (direction, count) in [("input", log.input_tokens), ("output", log.output_tokens)] {
+            if let Some(count) = count {
+                *tokens
+                    .entry((labels.model.clone(), labels.user.clone(), direction))
+                    .or_default() += count as u64;
+            }
+        }
+    }
+
+    /// 记录一次上游尝试 (status 为 None 表示网络错误)
+    pub fn record_upstream_attempt(&self, endpoint: &str, status: Option<u16>) {
+        let status = status
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "error".to_string());
+        *self
+            .upstream_attempts
+            .lock()
+            .entry((endpoint.to_string(), status))
+            .or_default() += 1;
+    }
+
+    /// 记录一次端点降级
+    pub fn record_upstream_fallback(&self, endpoint: &str) {
+        *self
+            .upstream_fallbacks
+            .lock()
+            .entry(endpoint.to_string())
+            .or_default() += 1;
+    }
+
+    pub fn track_in_flight(&self) -> InFlightGuard {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard
+    }
+
+    /// 输出 Prometheus 文本格式
+    pub fn render(&self, snapshot: &ScrapeSnapshot) -> String {
+        let mut out = MetricsWriter::default();
+
+        out.header(
+            "antigravity_requests_total",
+            "Proxied requests by protocol, model, mapped model, status and user token.",
+            "counter",
+        );
+        out.header(
+            "antigravity_request_duration_seconds",
+            "Time until response headers were returned to the client.",
+            "histogram",
+        );
+        {
+            let requests = self.requests.lock();
+            let mut entries: Vec<_> = requests.iter().collect();
+            entries.sort_by(|a, b| {
+                (&a.0.protocol, &a.0.model, &a.0.mapped_model, &a.0.status, &a.0.user).cmp(&(
+                    &b.0.protocol,
+                    &b.0.model,
+                    &b.0.mapped_model,
+                    &b.0.status,
+                    &b.0.user,
+                ))
+            });
+            for (labels, hist) in entries {
+                let base = [
+                    ("protocol", labels.protocol.as_str()),
+                    ("model", labels.model.as_str()),
+                    ("mapped_model", labels.mapped_model.as_str()),
+                    ("status", labels.status.as_str()),
+                    ("user", labels.user.as_str()),
+                ];
+                out.sample("antigravity_requests_total", &base, hist.count as f64);
+                for (bound, count) in LATENCY_BUCKETS_SECS.iter().zip(hist.buckets.iter()) {
+                    let le = bound.to_string();
+                    let mut with_le = base.to_vec();
+                    with_le.push(("le", le.as_str()));
+                    out.sample("antigravity_request_duration_seconds_bucket", &with_le, *count as f64);
+                }
+                let mut inf = base.to_vec();
+                inf.push(("le", "+Inf"));
+                out.sample("antigravity_request_duration_seconds_bucket", &inf, hist.count as f64);
+                out.sample("antigravity_request_duration_seconds_sum", &base, hist.sum);
+                out.sample("antigravity_request_duration_seconds_count", &base, hist.count as f64);
+            }
+        }
+
+        out.header(
+            "antigravity_tokens_total",
+            "Tokens processed by model, user token and direction.",
+            "counter",
+        );
+        {
+            let tokens = self.tokens.lock();
+            let mut entries: Vec<_> = tokens.iter().collect();
+            entries.sort();
+            for ((model, user, direction), count) in entries {
+                out.sample(
+                    "antigravity_tokens_total",
+                    &[("model", model), ("user", user), ("direction", direction)],
+                    *count as f64,
+                );
+            }
+        }
+
+        out.header(
+            "antigravity_upstream_attempts_total",
+            "Upstream v1internal attempts by endpoint and status (error = network failure).",
+            "counter",
+        );
+        {
+            let attempts = self.upstream_attempts.lock();
+            let mut entries: Vec<_> = attempts.iter().collect();
+            entries.sort();
+            for ((endpoint, status), count) in entries {
+                out.sample(
+                    "antigravity_upstream_attempts_total",
+                    &[("endpoint", endpoint), ("status", status)],
+                    *count as f64,
+                );
+            }
+        }
+
+        out.header(
+            "antigravity_upstream_fallbacks_total",
+            "Times an endpoint failed and the request fell back to the next endpoint.",
+            "counter",
+        );
+        {
+            let fallbacks = self.upstream_fallbacks.lock();
+            let mut entries: Vec<_> = fallbacks.iter().collect();
+            entries.sort();
+            for (endpoint, count) in entries {
+                out.sample(
+                    "antigravity_upstream_fallbacks_total",
+                    &[("endpoint", endpoint)],
+                    *count as f64,
+                );
+            }
+        }
+
+        out.header("antigravity_accounts", "Accounts in the proxy pool by state.", "gauge");
+        let accounts = &snapshot.accounts;
+        for (state, value) in [
+            ("total", accounts.total),
+            ("available", accounts.available),
+            ("rate_limited", accounts.rate_limited),
+            ("validation_blocked", accounts.validation_blocked),
+            ("disabled", snapshot.disabled_accounts),
+        ] {
+            out.sample("antigravity_accounts", &[("state", state)], value as f64);
+        }
+
+        out.header(
+            "antigravity_account_model_quota_remaining_percent",
+            "Remaining quota per account and model.",
+            "gauge",
+        );
+        let mut quotas: Vec<_> = accounts.model_quotas.iter().collect();
+        quotas.sort();
+        for (account, model, percent) in quotas {
+            out.sample(
+                "antigravity_account_model_quota_remaining_percent",
+                &[("account", account), ("model", model)],
+                *percent as f64,
+            );
+        }
+
+        out.header(
+            "antigravity_requests_in_flight",
+            "Requests currently being processed (streams count until they finish).",
+            "gauge",
+        );
+        out.sample(
+            "antigravity_requests_in_flight",
+            &[],
+            self.in_flight.load(Ordering::Relaxed).max(0) as f64,
+        );
+
+        out.header(
+            "antigravity_proxy_active_connections",
+            "Active upstream connections per proxy pool entry.",
+            "gauge",
+        );
+        out.header(
+            "antigravity_proxy_connections_total",
+            "Upstream connections opened per proxy pool entry.",
+            "counter",
+        );
+        for (proxy, active, total) in &snapshot.proxy_connections {
+            out.sample("antigravity_proxy_active_connections", &[("proxy", proxy)], *active as f64);
+            out.sample("antigravity_proxy_connections_total", &[("proxy", proxy)], *total as f64);
+        }
+
+        out.header(
+            "antigravity_idempotency_entries",
+            "Requests currently tracked for Idempotency-Key deduplication.",
+            "gauge",
+        );
+        out.sample(
+            "antigravity_idempotency_entries",
+            &[],
+            snapshot.idempotency_entries as f64,
+        );
+
+        out.finish()
+    }
+}
+
+#[derive(Default)]
+struct MetricsWriter {
+    buf: String,
+}
+
+impl MetricsWriter {
+    fn header(&mut self, name: &str, help: &str, kind: &str) {
+        let _ = writeln!(self.buf, "# HELP {} {}", name, help);
+        let _ = writeln!(self.buf, "# TYPE {} {}", name, kind);
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.buf.push_str(name);
+        if !labels.is_empty() {
+            self.buf.push('{');
+            for (i, (k, v)) in labels.iter().enumerate() {
+                if i > 0 {
+                    self.buf.push(',');
+                }
+                let _ = write!(self.buf, "{}=\"{}\"", k, escape_label(v));
+            }
+            self.buf.push('}');
+        }
+        let _ = writeln!(self.buf, " {}", value);
+    }
+
+    fn finish(self) -> String {
+        self.buf
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(status: u16, duration: u64) -> ProxyRequestLog {
+        ProxyRequestLog {
+            id: "1".to_string(),
+            timestamp: 0,
+            method: "POST".to_string(),
+            url: "/v1/messages".to_string(),
+            status,
+            duration,
+            model: Some("claude-sonnet-4-5".to_string()),
+            mapped_model: Some("gemini-3-pro".to_string()),
+            account_email: None,
+            client_ip: None,
+            error: None,
+            request_body: None,
+            response_body: None,
+            input_tokens: Some(10),
+            output_tokens: Some(5),
+            protocol: Some("anthropic".to_string()),
+            username: Some("alice".to_string()),
+            latency: None,
+            replay_of: None,
+            cached_tokens: None,
+            thinking_tokens: None,
+            cost: None,
+            provider: None,
+        }
+    }
+
+    #[test]
+    fn test_render_requests_histogram_and_tokens() {
+        let metrics = ProxyMetrics::default();
+        metrics.record_request(&log(200, 300));
+        metrics.record_request(&log(200, 1500));
+        metrics.record_upstream_attempt("sandbox", Some(429));
+        metrics.record_upstream_fallback("sandbox");
+
+        let text = metrics.render(&ScrapeSnapshot::default());
+        let labels = r#"protocol="anthropic",model="claude-sonnet-4-5",mapped_model="gemini-3-pro",status="200",user="alice""#;
+        assert!(text.contains(&format!("antigravity_requests_total{{{}}} 2", labels)));
+        assert!(text.contains(&format!(
+            "antigravity_request_duration_seconds_bucket{{{},le=\"0.5\"}} 1",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "antigravity_request_duration_seconds_bucket{{{},le=\"+Inf\"}} 2",
+            labels
+        )));
+        assert!(text.contains(
+            r#"antigravity_tokens_total{model="claude-sonnet-4-5",user="alice",direction="input"} 20"#
+        ));
+        assert!(text.contains(r#"antigravity_upstream_attempts_total{endpoint="sandbox",status="429"} 1"#));
+        assert!(text.contains(r#"antigravity_upstream_fallbacks_total{endpoint="sandbox"} 1"#));
+    }
+
+    #[test]
+    fn test_label_escaping() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+
+    #[test]
+    fn test_authorization_rules() {
+        let mut config = MetricsConfig::default();
+        // 主端口未配置 Token 时拒绝所有凭证
+        assert!(!is_authorized(Some("admin"), &config, false));
+        assert!(!is_authorized(None, &config, false));
+        // 独立监听地址未配置 Token 时放行
+        assert!(is_authorized(None, &config, true));
+
+        config.auth_token = "scrape".to_string();
+        assert!(is_authorized(Some("scrape"), &config, false));
+        assert!(!is_authorized(Some("scrapf"), &config, false));
+        assert!(!is_authorized(Some("admin"), &config, false));
+        assert!(!is_authorized(None, &config, true));
+    }
+}
@@ -0,0 +1,189 @@
+// 管理接口 (/api/*) 基于角色的权限检查
+//
+// 权限级别:
+//   View        账号、统计、代理状态等只读接口
+//   ViewLogs    请求日志、访问日志、安全统计、调试日志、事件流、告警历史
+//   Operate     账号与代理服务的日常操作、用户令牌管理、读取完整配置与安全设置
+//   Administer  保存配置、安全设置、导出账号、系统操作、清空日志、管理员管理
+//
+// 角色:
+//   owner     全部权限
+//   operator  View + ViewLogs + Operate
+//   viewer    View
+//   auditor   View + ViewLogs
+// 路径为去掉 /api 前缀后的形式 (如 /accounts)。
+
+use axum::http::Method;
+
+use crate::modules::admin_user_db::AdminRole;
+
+/// 权限级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    View,
+    ViewLogs,
+    Operate,
+    Administer,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::View => "view",
+            Permission::ViewLogs => "view_logs",
+            Permission::Operate => "operate",
+            Permission::Administer => "administer",
+        }
+    }
+}
+
+/// 角色是否拥有指定权限
+pub fn role_allows(role: AdminRole, permission: Permission) -> bool {
+    match role {
+        AdminRole::Owner => true,
+        AdminRole::Operator => permission != Permission::Administer,
+        AdminRole::Viewer => permission == Permission::View,
+        AdminRole::Auditor => matches!(permission, Permission::View | Permission::ViewLogs),
+    }
+}
+
+/// 日志 / 审计类只读路径
+fn is_log_path(path: &str) -> bool {
+    path == "/logs"
+        || path.starts_with("/logs/")
+        || matches!(
+            path,
+            "/security/logs"
+                | "/security/stats"
+                | "/security/ban-events"
+                | "/security/token-stats"
+                | "/security/dlp/stats"
+        )
+        || path.starts_with("/debug/")
+        || path == "/events"
+        || path.starts_with("/events/")
+        || path.starts_with("/alerts/history")
+        || path == "/audit"
+        || path.starts_with("/audit/")
+}
+
+/// 使用 POST 的只读接口
+fn is_read_only_post(path: &str) -> bool {
+    matches!(
+        path,
+        "/logs/search"
+            | "/proxy/cli/status"
+            | "/proxy/opencode/status"
+            | "/proxy/droid/status"
+            | "/proxy/cli/config"
+            | "/proxy/opencode/config"
+            | "/proxy/droid/config"
+    )
+}
+
+/// 请求是否不修改任何状态 (无需审计)
+pub fn is_read_only(method: &Method, path: &str) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+        || (method == Method::POST && is_read_only_post(path))
+}
+
+/// 路由所需的权限
+pub fn required_permission(method: &Method, path: &str) -> Permission {
+    // 当前登录用户的自助接口 (个人信息、登出、二次验证)
+    if path == "/admin/me" || path == "/admin/logout" || path.starts_with("/admin/totp/") {
+        return Permission::View;
+    }
+    if path.starts_with("/admin/users") {
+        return Permission::Administer;
+    }
+
+    if method == Method::GET || method == Method::HEAD {
+        return match path {
+            "/config" => Permission::Operate,
+            _ if is_log_path(path) => Permission::ViewLogs,
+            // 安全配置、黑白名单、加密状态等
+            _ if path.starts_with("/security/") => Permission::Operate,
+            _ => Permission::View,
+        };
+    }
+
+    // 使用 POST 的只读接口
+    match path {
+        "/logs/search" => return Permission::ViewLogs,
+        "/proxy/cli/status" | "/proxy/opencode/status" | "/proxy/droid/status" => {
+            return Permission::View
+        }
+        "/proxy/cli/config" | "/proxy/opencode/config" | "/proxy/droid/config" => {
+            return Permission::Operate
+        }
+        _ => {}
+    }
+
+    match path {
+        "/config"
+        | "/accounts/export"
+        | "/proxy/api-key/generate"
+        | "/security/config"
+        | "/security/reencrypt"
+        | "/logs/clear"
+        | "/security/logs/clear"
+        | "/security/dlp/clear"
+        | "/debug/enable"
+        | "/debug/disable"
+        | "/debug/logs/clear"
+        | "/stats/token/clear" => Permission::Administer,
+        _ if path.starts_with("/security/account-encryption/") => Permission::Administer,
+        "/system/updates/check" | "/system/updates/touch" => Permission::Operate,
+        _ if path.starts_with("/system/") => Permission::Administer,
+        _ => Permission::Operate,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(role: AdminRole, method: Method, path: &str) -> bool {
+        role_allows(role, required_permission(&method, path))
+    }
+
+    #[test]
+    fn test_role_permissions() {
+        // viewer 只能查看，不能删除账号或读取日志
+        assert!(allowed(AdminRole::Viewer, Method::GET, "/accounts"));
+        assert!(!allowed(AdminRole::Viewer, Method::DELETE, "/accounts/abc"));
+        assert!(!allowed(AdminRole::Viewer, Method::GET, "/logs"));
+        assert!(allowed(AdminRole::Viewer, Method::POST, "/proxy/cli/status"));
+
+        // auditor 可以查看日志，但不能修改
+        assert!(allowed(AdminRole::Auditor, Method::GET, "/logs/123"));
+        assert!(allowed(AdminRole::Auditor, Method::POST, "/logs/search"));
+        assert!(allowed(AdminRole::Auditor, Method::GET, "/security/logs"));
+        assert!(!allowed(AdminRole::Auditor, Method::POST, "/logs/clear"));
+        assert!(!allowed(AdminRole::Auditor, Method::GET, "/config"));
+        assert!(allowed(AdminRole::Auditor, Method::GET, "/audit/export"));
+        assert!(allowed(AdminRole::Auditor, Method::GET, "/security/dlp/stats"));
+        assert!(!allowed(AdminRole::Auditor, Method::GET, "/security/config"));
+        assert!(!allowed(AdminRole::Auditor, Method::GET, "/security/blacklist"));
+        assert!(!allowed(AdminRole::Viewer, Method::GET, "/security/config"));
+        assert!(allowed(AdminRole::Operator, Method::GET, "/security/config"));
+        assert!(!allowed(AdminRole::Operator, Method::POST, "/security/dlp/clear"));
+        assert!(!allowed(AdminRole::Viewer, Method::GET, "/audit"));
+
+        // operator 可以日常操作，但不能改配置或管理管理员
+        assert!(allowed(AdminRole::Operator, Method::DELETE, "/accounts/abc"));
+        assert!(allowed(AdminRole::Operator, Method::POST, "/proxy/start"));
+        assert!(allowed(AdminRole::Operator, Method::GET, "/config"));
+        assert!(!allowed(AdminRole::Operator, Method::POST, "/config"));
+        assert!(!allowed(AdminRole::Operator, Method::POST, "/accounts/export"));
+        assert!(!allowed(AdminRole::Operator, Method::GET, "/admin/users"));
+        assert!(!allowed(AdminRole::Operator, Method::POST, "/security/account-encryption/disable"));
+
+        // 自助接口对所有角色开放
+        for role in [AdminRole::Viewer, AdminRole::Auditor, AdminRole::Operator] {
+            assert!(allowed(role, Method::POST, "/admin/totp/setup"));
+            assert!(allowed(role, Method::GET, "/admin/me"));
+        }
+        assert!(allowed(AdminRole::Owner, Method::DELETE, "/admin/users/abc"));
+    }
+}
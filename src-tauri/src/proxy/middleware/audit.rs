@@ -0,0 +1,68 @@
+// 管理接口审计中间件
+//
+// 位于管理员鉴权之后：从请求扩展读取管理员身份，在审计上下文中执行处理函数，
+// 并将处理函数记录的事件连同身份、IP、状态码写入审计日志。
+// 只读请求不记录；未显式记录事件的修改类请求以 "方法 路径" 记录一条通用记录。
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::modules::admin_user_db::AdminIdentity;
+use crate::modules::audit::{self, PendingEvent};
+use crate::modules::audit_db::AuditEntry;
+use crate::proxy::middleware::{admin_rbac, client_ip};
+use crate::proxy::ProxySecurityConfig;
+
+pub async fn admin_audit_middleware(
+    State(security): State<Arc<RwLock<ProxySecurityConfig>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    if admin_rbac::is_read_only(&method, &path) {
+        return next.run(request).await;
+    }
+
+    let identity = request.extensions().get::<AdminIdentity>().cloned();
+    let client_ip = {
+        let security = security.read().await;
+        client_ip::client_ip(&request, &security.security_monitor)
+    };
+
+    let (response, mut events) = audit::web_scope(next.run(request)).await;
+    if events.is_empty() {
+        events.push(PendingEvent {
+            action: format!("{} /api{}", method, path),
+            target: None,
+            details: None,
+        });
+    }
+
+    let status = response.status();
+    let timestamp = chrono::Utc::now().timestamp();
+    for event in events {
+        audit::write(AuditEntry {
+            id: 0,
+            timestamp,
+            source: "web".to_string(),
+            actor: identity
+                .as_ref()
+                .map(|i| i.username.clone())
+                .unwrap_or_else(|| "anonymous".to_string()),
+            actor_role: identity.as_ref().map(|i| i.role.as_str().to_string()),
+            client_ip: client_ip.clone(),
+            action: event.action,
+            target: event.target,
+            details: event.details,
+            success: status.is_success(),
+            status: Some(status.as_u16()),
+        });
+    }
+    response
+}
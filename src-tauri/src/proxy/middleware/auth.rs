@@ -9,7 +9,13 @@ use axum::{
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use crate::modules::admin_user_db::{self, AdminIdentity};
 use crate::proxy::{ProxyAuthMode, ProxySecurityConfig};
+use super::{admin_rbac, client_ip, token_scope};
+use crate::proxy::auto_ban::{self, Offense};
+
+/// 管理员登录接口 (由处理函数自行校验凭据)
+pub const ADMIN_LOGIN_PATH: &str = "/admin/login";
 
 /// API Key 认证中间件 (代理接口使用，遵循 auth_mode)
 pub async fn auth_middleware(
@@ -17,16 +23,131 @@ pub async fn auth_middleware(
     request: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    auth_middleware_internal(state, request, next, false).await
+    auth_middleware_internal(state, request, next).await
 }
 
-/// 管理接口认证中间件 (管理接口使用，强制严格鉴权)
+/// 管理接口认证中间件 (强制鉴权 + 角色权限检查)
+///
+/// - 已创建管理员账号时，必须携带登录获得的会话令牌
+/// - 尚未创建管理员账号时，沿用 admin_password / api_key (视为 owner)
 pub async fn admin_auth_middleware(
-    state: State<Arc<RwLock<ProxySecurityConfig>>>,
-    request: Request,
+    State(security): State<Arc<RwLock<ProxySecurityConfig>>>,
+    mut request: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    auth_middleware_internal(state, request, next, true).await
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let is_health_check = path == "/healthz" || path == "/api/health" || path == "/health";
+    if !is_health_check {
+        tracing::info!("Request: {} {}", method, path);
+    } else {
+        tracing::trace!("Heartbeat/Health: {} {}", method, path);
+    }
+
+    // 预检请求、健康检查与登录接口放行
+    if method == axum::http::Method::OPTIONS || is_health_check || path == ADMIN_LOGIN_PATH {
+        return Ok(next.run(request).await);
+    }
+
+    let security = security.read().await.clone();
+    let Some(identity) = resolve_admin_identity(&security, &request, &path) else {
+        // [NEW] 鉴权失败计入自动封禁
+        if let Some(ip) = client_ip::client_ip(&request, &security.security_monitor) {
+            auto_ban::record(&security.security_monitor.auto_ban, &ip, Offense::AuthFailure);
+        }
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    let permission = admin_rbac::required_permission(&method, &path);
+    if !admin_rbac::role_allows(identity.role, permission) {
+        tracing::warn!(
+            "Admin '{}' ({}) denied {} {}: requires {}",
+            identity.username,
+            identity.role.as_str(),
+            method,
+            path,
+            permission.as_str()
+        );
+        let body = serde_json::json!({
+            "error": format!("Forbidden: role '{}' lacks '{}' permission", identity.role.as_str(), permission.as_str())
+        });
+        return Ok(Response::builder()
+            .status(StatusCode::FORBIDDEN)
+            .header("Content-Type", "application/json")
+            .body(axum::body::Body::from(body.to_string()))
+            .unwrap());
+    }
+
+    // 注入身份，供处理函数 (如 /admin/me) 与审计使用
+    request.extensions_mut().insert(identity);
+    Ok(next.run(request).await)
+}
+
+/// 从 Authorization / x-api-key / x-goog-api-key 请求头中提取凭据
+fn header_token(headers: &axum::http::HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer ").or(Some(s)))
+        .or_else(|| headers.get("x-api-key").and_then(|h| h.to_str().ok()))
+        .or_else(|| headers.get("x-goog-api-key").and_then(|h| h.to_str().ok()))
+}
+
+/// 旧版管理凭据: 优先使用独立的 admin_password，没有则回退使用 api_key
+pub fn legacy_password_matches(security: &ProxySecurityConfig, candidate: &str) -> bool {
+    let expected = match &security.admin_password {
+        Some(pwd) if !pwd.is_empty() => pwd,
+        _ => &security.api_key,
+    };
+    if expected.is_empty() {
+        tracing::error!("Admin auth is required but both api_key and admin_password are empty; denying request");
+        return false;
+    }
+    candidate == expected
+}
+
+/// 解析管理接口请求的身份
+fn resolve_admin_identity(security: &ProxySecurityConfig, request: &Request, path: &str) -> Option<AdminIdentity> {
+    let token = header_token(request.headers()).map(str::to_string).or_else(|| {
+        // [NEW] 事件流接口允许通过 ?token= 鉴权 (浏览器 EventSource / WebSocket 无法设置请求头)
+        if !crate::proxy::event_stream::is_stream_path(path) {
+            return None;
+        }
+        request.uri().query().and_then(|q| {
+            url::form_urlencoded::parse(q.as_bytes())
+                .find(|(k, _)| k == "token")
+                .map(|(_, v)| v.into_owned())
+        })
+    });
+
+    if let Some(token) = token.as_deref().filter(|t| t.starts_with(admin_user_db::SESSION_PREFIX)) {
+        return match admin_user_db::resolve_session(token) {
+            Ok(identity) => identity,
+            Err(e) => {
+                tracing::error!("Admin session lookup failed: {}", e);
+                None
+            }
+        };
+    }
+
+    // 已创建管理员账号后不再接受旧版管理密码
+    if admin_user_db::has_users() {
+        return None;
+    }
+    // 全局鉴权关闭时管理接口也放行
+    if matches!(security.effective_auth_mode(), ProxyAuthMode::Off) {
+        return Some(AdminIdentity::legacy());
+    }
+    token
+        .filter(|t| legacy_password_matches(security, t))
+        .map(|_| AdminIdentity::legacy())
+}
+
+/// 结束鉴权 span 后继续执行后续中间件
+async fn proceed(auth_span: tracing::Span, outcome: &'static str, next: Next, request: Request) -> Response {
+    auth_span.record("auth.outcome", outcome);
+    drop(auth_span);
+    next.run(request).await
 }
 
 /// 内部认证逻辑
@@ -34,10 +155,11 @@ async fn auth_middleware_internal(
     State(security): State<Arc<RwLock<ProxySecurityConfig>>>,
     request: Request,
     next: Next,
-    force_strict: bool,
 ) -> Result<Response, StatusCode> {
     let method = request.method().clone();
     let path = request.uri().path().to_string();
+    // [NEW] 鉴权 span，放行时结束，不包含下游处理耗时
+    let auth_span = tracing::info_span!("auth", auth.outcome = tracing::field::Empty);
 
     // 过滤心跳和健康检查请求,避免日志噪音
     let is_health_check = path == "/healthz" || path == "/api/health" || path == "/health";
@@ -50,135 +172,86 @@ async fn auth_middleware_internal(
 
     // Allow CORS preflight regardless of auth policy.
     if method == axum::http::Method::OPTIONS {
-        return Ok(next.run(request).await);
+        return Ok(proceed(auth_span, "preflight", next, request).await);
     }
 
     let security = security.read().await.clone();
     let effective_mode = security.effective_auth_mode();
 
-    // 权限检查逻辑
-    if !force_strict {
-        // AI 代理接口 (v1/chat/completions 等)
-        if matches!(effective_mode, ProxyAuthMode::Off) {
-            // [FIX] 即使 auth_mode=Off，也需要尝试识别 User Token 以记录使用情况
-            // 先检查是否携带了 User Token
-            let api_key = request
-                .headers()
-                .get(header::AUTHORIZATION)
-                .and_then(|h| h.to_str().ok())
-                .and_then(|s| s.strip_prefix("Bearer ").or(Some(s)))
-                .or_else(|| {
-                    request
-                        .headers()
-                        .get("x-api-key")
-                        .and_then(|h| h.to_str().ok())
-                });
-            
-            if let Some(token) = api_key {
-                // 尝试验证是否为 User Token（不阻止请求，只记录）
-                if let Ok(Some(user_token)) = crate::modules::user_token_db::get_token_by_value(token) {
-                    let identity = UserTokenIdentity {
-                        token_id: user_token.id,
-                        token: user_token.token,
-                        username: user_token.username,
-                    };
-                    // 注入 identity 到请求
-                    let (mut parts, body) = request.into_parts();
-                    parts.extensions.insert(identity);
-                    let request = Request::from_parts(parts, body);
-                    return Ok(next.run(request).await);
-                }
+    // 权限检查逻辑 (AI 代理接口 v1/chat/completions 等)
+    if matches!(effective_mode, ProxyAuthMode::Off) {
+        // [FIX] 即使 auth_mode=Off，也需要尝试识别 User Token 以记录使用情况
+        // 先检查是否携带了 User Token
+        let api_key = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|s| s.strip_prefix("Bearer ").or(Some(s)))
+            .or_else(|| {
+                request
+                    .headers()
+                    .get("x-api-key")
+                    .and_then(|h| h.to_str().ok())
+            });
+        
+        if let Some(token) = api_key {
+            // 尝试验证是否为 User Token（不阻止请求，只记录）
+            if let Ok(Some(user_token)) = crate::modules::user_token_db::get_token_by_value(token) {
+                // [NEW] 令牌权限范围与能力限制
+                let request = match token_scope::enforce(&user_token.restrictions, request).await {
+                    Ok(request) => request,
+                    Err(response) => {
+                        auth_span.record("auth.outcome", "forbidden");
+                        return Ok(response);
+                    }
+                };
+                let identity = UserTokenIdentity {
+                    token_id: user_token.id,
+                    token: user_token.token,
+                    username: user_token.username,
+                    response_cache: user_token.response_cache,
+                    log_metadata_only: user_token.log_metadata_only,
+                };
+                // 注入 identity 到请求
+                let (mut parts, body) = request.into_parts();
+                parts.extensions.insert(identity);
+                let request = Request::from_parts(parts, body);
+                return Ok(proceed(auth_span, "user_token", next, request).await);
             }
-            
-            return Ok(next.run(request).await);
-        }
-
-        if matches!(effective_mode, ProxyAuthMode::AllExceptHealth) && is_health_check {
-            return Ok(next.run(request).await);
         }
+        
+        return Ok(proceed(auth_span, "open", next, request).await);
+    }
 
-        // 内部端点 (/internal/*) 豁免鉴权 - 用于 warmup 等内部功能
-        if is_internal_endpoint {
-            tracing::debug!("Internal endpoint bypassed auth: {}", path);
-            return Ok(next.run(request).await);
-        }
-    } else {
-        // 管理接口 (/api/*)
-        // 1. 如果全局鉴权关闭，则管理接口也放行 (除非是强制局域网模式)
-        if matches!(effective_mode, ProxyAuthMode::Off) {
-            return Ok(next.run(request).await);
-        }
+    if matches!(effective_mode, ProxyAuthMode::AllExceptHealth) && is_health_check {
+        return Ok(proceed(auth_span, "exempt", next, request).await);
+    }
 
-        // 2. 健康检查在所有模式下对管理接口放行
-        if is_health_check {
-            return Ok(next.run(request).await);
-        }
+    // 内部端点 (/internal/*) 豁免鉴权 - 用于 warmup 等内部功能
+    if is_internal_endpoint {
+        tracing::debug!("Internal endpoint bypassed auth: {}", path);
+        return Ok(proceed(auth_span, "exempt", next, request).await);
     }
     
     // 从 header 中提取 API key
-    let api_key = request
-        .headers()
-        .get(header::AUTHORIZATION)
-        .and_then(|h| h.to_str().ok())
-        .and_then(|s| s.strip_prefix("Bearer ").or(Some(s)))
-        .or_else(|| {
-            request
-                .headers()
-                .get("x-api-key")
-                .and_then(|h| h.to_str().ok())
-        })
-        .or_else(|| {
-            request
-                .headers()
-                .get("x-goog-api-key")
-                .and_then(|h| h.to_str().ok())
-        });
+    let api_key = header_token(request.headers());
 
     if security.api_key.is_empty() && (security.admin_password.is_none() || security.admin_password.as_ref().unwrap().is_empty()) {
-        if force_strict {
-             tracing::error!("Admin auth is required but both api_key and admin_password are empty; denying request");
-             return Err(StatusCode::UNAUTHORIZED);
-        }
         tracing::error!("Proxy auth is enabled but api_key is empty; denying request");
         return Err(StatusCode::UNAUTHORIZED);
     }
 
-    // 认证逻辑
-    let authorized = if force_strict {
-        // 管理接口：优先使用独立的 admin_password，如果没有则回退使用 api_key
-        match &security.admin_password {
-            Some(pwd) if !pwd.is_empty() => {
-                api_key.map(|k| k == pwd).unwrap_or(false)
-            }
-            _ => {
-                // 回退使用 api_key
-                api_key.map(|k| k == security.api_key).unwrap_or(false)
-            }
-        }
-    } else {
-        // AI 代理接口：仅允许使用 api_key
-        api_key.map(|k| k == security.api_key).unwrap_or(false)
-    };
+    // 认证逻辑: AI 代理接口仅允许使用 api_key
+    let authorized = api_key.map(|k| k == security.api_key).unwrap_or(false);
 
     if authorized {
-        Ok(next.run(request).await)
-    } else if !force_strict && api_key.is_some() {
+        Ok(proceed(auth_span, "api_key", next, request).await)
+    } else if api_key.is_some() {
         // 尝试验证 UserToken
         let token = api_key.unwrap();
         
-        // 提取 IP (复用逻辑)
-        let client_ip = request
-            .headers()
-            .get("x-forwarded-for")
-            .and_then(|v| v.to_str().ok())
-            .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
-            .or_else(|| {
-                request
-                    .headers()
-                    .get("x-real-ip")
-                    .and_then(|v| v.to_str().ok())
-                    .map(|s| s.to_string())
-            })
+        // 提取 IP (与 IP 过滤 / 监控使用同一套受信任代理规则)
+        let client_ip = client_ip::client_ip(&request, &security.security_monitor)
             .unwrap_or_else(|| "127.0.0.1".to_string()); // Default fallback
 
         // 验证 Token
@@ -186,10 +259,20 @@ async fn auth_middleware_internal(
             Ok((true, _)) => {
                 // Token 有效，查询信息以便传递
                 if let Ok(Some(user_token)) = crate::modules::user_token_db::get_token_by_value(token) {
+                    // [NEW] 令牌权限范围与能力限制
+                    let request = match token_scope::enforce(&user_token.restrictions, request).await {
+                        Ok(request) => request,
+                        Err(response) => {
+                            auth_span.record("auth.outcome", "forbidden");
+                            return Ok(response);
+                        }
+                    };
                      let identity = UserTokenIdentity {
                         token_id: user_token.id,
                         token: user_token.token,
                         username: user_token.username,
+                        response_cache: user_token.response_cache,
+                        log_metadata_only: user_token.log_metadata_only,
                     };
                     
                     // [FIX] 将身份信息注入到请求 extensions 中，而不是响应
@@ -202,7 +285,7 @@ async fn auth_middleware_internal(
                     let request = Request::from_parts(parts, body);
                     
                     // 执行请求
-                    let response = next.run(request).await;
+                    let response = proceed(auth_span, "user_token", next, request).await;
                     
                     Ok(response)
                 } else {
@@ -210,7 +293,14 @@ async fn auth_middleware_internal(
                 }
             }
             Ok((false, reason)) => {
+                // [NEW] 既不是 API Key 也不是已有令牌: 同时计入鉴权失败与无效令牌规则
+                if reason.as_deref() == Some(crate::modules::user_token_db::INVALID_TOKEN_REASON) {
+                    let auto_ban_config = &security.security_monitor.auto_ban;
+                    auto_ban::record(auto_ban_config, &client_ip, Offense::AuthFailure);
+                    auto_ban::record(auto_ban_config, &client_ip, Offense::InvalidToken);
+                }
                 let reason_str = reason.unwrap_or_else(|| "Access denied".to_string());
+                auth_span.record("auth.outcome", "rejected");
                 tracing::warn!("UserToken rejected: {}", reason_str);
                 let body = serde_json::json!({
                     "error": {
@@ -232,6 +322,10 @@ async fn auth_middleware_internal(
             }
         }
     } else {
+        // [NEW] 鉴权失败计入自动封禁
+        if let Some(ip) = client_ip::client_ip(&request, &security.security_monitor) {
+            auto_ban::record(&security.security_monitor.auto_ban, &ip, Offense::AuthFailure);
+        }
         Err(StatusCode::UNAUTHORIZED)
     }
 }
@@ -243,6 +337,10 @@ pub struct UserTokenIdentity {
     #[allow(dead_code)] // 保留原始 token 便于审计/调试
     pub token: String,
     pub username: String,
+    /// 该令牌是否启用响应缓存
+    pub response_cache: bool,
+    /// 该令牌的请求日志是否仅记录元数据
+    pub log_metadata_only: bool,
 }
 
 #[cfg(test)]
@@ -272,6 +370,27 @@ mod tests {
         // 我们在 auth_middleware_internal 基础上做了逻辑校验即可
     }
 
+    #[test]
+    fn test_legacy_password_fallback() {
+        let mut security = ProxySecurityConfig {
+            auth_mode: ProxyAuthMode::Strict,
+            api_key: "sk-api".to_string(),
+            admin_password: Some("admin123".to_string()),
+            allow_lan_access: true,
+            port: 8045,
+            security_monitor: crate::proxy::config::SecurityMonitorConfig::default(),
+        };
+        assert!(legacy_password_matches(&security, "admin123"));
+        assert!(!legacy_password_matches(&security, "sk-api"));
+
+        // 未设置管理密码时回退使用 api_key
+        security.admin_password = Some(String::new());
+        assert!(legacy_password_matches(&security, "sk-api"));
+
+        security.api_key = String::new();
+        assert!(!legacy_password_matches(&security, ""));
+    }
+
     #[test]
     fn test_auth_placeholder() {
         assert!(true);
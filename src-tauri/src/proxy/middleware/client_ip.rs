@@ -0,0 +1,136 @@
+// 客户端 IP 解析
+//
+// 仅当 TCP 对端是受信任的反向代理时才采信转发头，防止客户端伪造 X-Forwarded-For 绕过黑名单。
+// X-Forwarded-For 从右向左遍历，跳过受信任代理，取第一个不受信任的地址。
+
+use axum::{extract::ConnectInfo, extract::Request, http::HeaderMap};
+use std::net::{IpAddr, SocketAddr};
+
+use crate::proxy::config::SecurityMonitorConfig;
+use crate::utils::ip::{matches_any, IpNet};
+
+fn parse_header_ip(value: &str) -> Option<IpAddr> {
+    let value = value.trim().trim_matches('"');
+    value
+        .parse::<IpAddr>()
+        .ok()
+        // 部分代理会附带端口 (1.2.3.4:5678 / [::1]:5678)
+        .or_else(|| value.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+        .map(|ip| ip.to_canonical())
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+/// 根据对端地址与转发头解析真实客户端 IP
+pub fn resolve_ip(headers: &HeaderMap, peer: Option<IpAddr>, config: &SecurityMonitorConfig) -> Option<IpAddr> {
+    let peer = peer?.to_canonical();
+    let trusted: Vec<IpNet> = config.trusted_proxies.iter().filter_map(|p| IpNet::parse(p)).collect();
+    let is_trusted = |ip: IpAddr| matches_any(ip, &trusted);
+
+    // 本机 cloudflared 隧道
+    if config.trust_cloudflared && peer.is_loopback() {
+        if let Some(ip) = header_str(headers, "cf-connecting-ip").and_then(parse_header_ip) {
+            return Some(ip);
+        }
+    }
+
+    if !is_trusted(peer) {
+        return Some(peer);
+    }
+
+    let forwarded: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .collect();
+    if !forwarded.is_empty() {
+        let mut client = peer;
+        for hop in forwarded.iter().rev() {
+            match parse_header_ip(hop) {
+                Some(ip) => {
+                    client = ip;
+                    if !is_trusted(ip) {
+                        break;
+                    }
+                }
+                // 无法解析的条目之前的内容不可信
+                None => break,
+            }
+        }
+        return Some(client);
+    }
+
+    header_str(headers, "x-real-ip")
+        .and_then(parse_header_ip)
+        .or(Some(peer))
+}
+
+/// 从请求中提取客户端 IP 字符串
+pub fn client_ip(request: &Request, config: &SecurityMonitorConfig) -> Option<String> {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip());
+    resolve_ip(request.headers(), peer, config).map(|ip| ip.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (k, v) in pairs {
+            map.append(*k, v.parse().unwrap());
+        }
+        map
+    }
+
+    fn config(trusted: &[&str]) -> SecurityMonitorConfig {
+        SecurityMonitorConfig {
+            trusted_proxies: trusted.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn ip(s: &str) -> Option<IpAddr> {
+        Some(s.parse().unwrap())
+    }
+
+    #[test]
+    fn test_untrusted_peer_ignores_forwarded_headers() {
+        let h = headers(&[("x-forwarded-for", "1.1.1.1"), ("x-real-ip", "2.2.2.2")]);
+        assert_eq!(resolve_ip(&h, ip("203.0.113.9"), &config(&["10.0.0.0/8"])), ip("203.0.113.9"));
+    }
+
+    #[test]
+    fn test_trusted_proxy_chain_skips_trusted_hops() {
+        let cfg = config(&["10.0.0.0/8", "fd00::/8"]);
+        // 客户端伪造的最左侧地址不会被采信
+        let h = headers(&[("x-forwarded-for", "6.6.6.6, 198.51.100.7, 10.0.0.2")]);
+        assert_eq!(resolve_ip(&h, ip("10.0.0.1"), &cfg), ip("198.51.100.7"));
+
+        let h = headers(&[("x-forwarded-for", "2001:db8::5, fd00::2")]);
+        assert_eq!(resolve_ip(&h, ip("fd00::1"), &cfg), ip("2001:db8::5"));
+
+        let h = headers(&[("x-real-ip", "198.51.100.8")]);
+        assert_eq!(resolve_ip(&h, ip("10.0.0.1"), &cfg), ip("198.51.100.8"));
+
+        // IPv4-mapped 对端归一化为 IPv4
+        assert_eq!(resolve_ip(&HeaderMap::new(), ip("::ffff:203.0.113.1"), &cfg), ip("203.0.113.1"));
+    }
+
+    #[test]
+    fn test_cloudflared_header_only_from_loopback() {
+        let h = headers(&[("cf-connecting-ip", "2001:db8::1")]);
+        let cfg = config(&[]);
+        assert_eq!(resolve_ip(&h, ip("127.0.0.1"), &cfg), ip("2001:db8::1"));
+        assert_eq!(resolve_ip(&h, ip("192.0.2.1"), &cfg), ip("192.0.2.1"));
+
+        let cfg = SecurityMonitorConfig { trust_cloudflared: false, ..cfg };
+        assert_eq!(resolve_ip(&h, ip("127.0.0.1"), &cfg), ip("127.0.0.1"));
+    }
+}
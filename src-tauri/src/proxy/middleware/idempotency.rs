@@ -0,0 +1,136 @@
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use bytes::Bytes;
+use futures::StreamExt;
+use std::sync::Arc;
+
+use crate::proxy::idempotency::{self, Claim, IdempotentEntry};
+use crate::proxy::middleware::auth::UserTokenIdentity;
+use crate::proxy::server::AppState;
+
+const MAX_IDEMPOTENT_BODY_SIZE: usize = 100 * 1024 * 1024; // 100MB
+
+/// 调用方身份：优先使用 User Token，其次使用请求携带的凭证，避免不同客户端的幂等键互相串用
+fn caller_scope(request: &Request) -> String {
+    if let Some(identity) = request.extensions().get::<UserTokenIdentity>() {
+        return format!("user-token:{}", identity.token_id);
+    }
+    let headers = request.headers();
+    [header::AUTHORIZATION.as_str(), "x-api-key", "x-goog-api-key"]
+        .iter()
+        .find_map(|name| headers.get(*name).and_then(|v| v.to_str().ok()))
+        .map(|cred| format!("credential:{}", cred))
+        .unwrap_or_else(|| "anonymous".to_string())
+}
+
+/// 基于缓冲条目构建重复请求的响应
+async fn response_from_entry(entry: &Arc<IdempotentEntry>) -> Response {
+    let Some((status, headers)) = entry.wait_head().await else {
+        return (StatusCode::BAD_GATEWAY, "Original request did not produce a response").into_response();
+    };
+
+    let mut response = Response::new(Body::from_stream(entry.subscribe()));
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    response
+        .headers_mut()
+        .insert(idempotency::REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+pub async fn idempotency_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let config = idempotency::get_idempotency_config();
+    if !config.enabled || request.method() != axum::http::Method::POST {
+        return next.run(request).await;
+    }
+
+    let path = request.uri().path().to_string();
+    if path.contains("event_logging") || path.starts_with("/internal/") {
+        return next.run(request).await;
+    }
+
+    let Some(key) = idempotency::extract_idempotency_key(request.headers()) else {
+        return next.run(request).await;
+    };
+
+    let scope = caller_scope(&request);
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_IDEMPOTENT_BODY_SIZE).await {
+        Ok(b) => b,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, format!("Failed to read request body: {}", e))
+                .into_response();
+        }
+    };
+
+    let store_key = idempotency::scoped_key(&scope, &path, &key.value);
+    let digest = idempotency::body_digest(&bytes);
+    let request = Request::from_parts(parts, Body::from(bytes));
+
+    let entry = match state.idempotency.claim(&store_key, &digest, &config) {
+        Claim::Bypass => return next.run(request).await,
+        // 客户端请求 ID 不保证唯一，内容不一致时按普通请求处理
+        Claim::Conflict if !key.explicit => return next.run(request).await,
+        Claim::Conflict => {
+            tracing::warn!("[Idempotency] Key reused with a different request body: {}", key.value);
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                axum::Json(serde_json::json!({
+                    "error": {
+                        "type": "idempotency_error",
+                        "message": "Idempotency key was already used with a different request body"
+                    }
+                })),
+            )
+                .into_response();
+        }
+        Claim::Attached(entry) => {
+            tracing::info!("[Idempotency] Duplicate request attached to original: {}", key.value);
+            return response_from_entry(&entry).await;
+        }
+        Claim::Leader(entry) => entry,
+    };
+
+    let response = next.run(request).await;
+    let (parts, body) = response.into_parts();
+    entry.set_head(parts.status, parts.headers.clone());
+
+    // 在独立任务中驱动原始响应体：即使原客户端已超时断开，上游结果也会继续缓冲给重试请求
+    // 原请求直接接收上游分片，不受缓冲上限影响
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Bytes, std::io::Error>>(64);
+    let driver = entry.clone();
+    let store = state.idempotency.clone();
+    tokio::spawn(async move {
+        let mut stream = body.into_data_stream();
+        let mut error = None;
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(bytes) => {
+                    driver.push_chunk(bytes.clone());
+                    let _ = tx.send(Ok(bytes)).await;
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(std::io::Error::other(e.to_string()))).await;
+                    error = Some(e.to_string());
+                    break;
+                }
+            }
+        }
+        driver.finish(error);
+        store.complete(&store_key, &driver);
+    });
+
+    Response::from_parts(
+        parts,
+        Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)),
+    )
+}
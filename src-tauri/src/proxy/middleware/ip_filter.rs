@@ -4,8 +4,14 @@ use axum::{
     response::{IntoResponse, Response},
     http::StatusCode,
 };
+use crate::proxy::config::SecurityMonitorConfig;
 use crate::proxy::server::AppState;
+use crate::proxy::ProxySecurityConfig;
+use std::sync::Arc;
+use tokio::sync::RwLock;
 use crate::modules::security_db;
+use super::client_ip;
+use crate::proxy::auto_ban::{self, Offense};
 
 /// IP 黑白名单过滤中间件
 pub async fn ip_filter_middleware(
@@ -13,13 +19,13 @@ pub async fn ip_filter_middleware(
     request: Request,
     next: Next,
 ) -> Response {
-    // 提取客户端 IP
-    let client_ip = extract_client_ip(&request);
+    // 读取安全配置
+    let security_config = state.security.read().await.clone();
+
+    // 提取客户端 IP (仅采信受信任代理的转发头)
+    let client_ip = client_ip::client_ip(&request, &security_config.security_monitor);
     
     if let Some(ip) = &client_ip {
-        // 读取安全配置
-        let security_config = state.security.read().await;
-        
         // 1. 检查白名单 (如果启用白名单模式,只允许白名单 IP)
         if security_config.security_monitor.whitelist.enabled {
             match security_db::is_ip_in_whitelist(ip) {
@@ -58,83 +64,14 @@ pub async fn ip_filter_middleware(
             }
         }
 
-        // 2. 检查黑名单
-        if security_config.security_monitor.blacklist.enabled {
-            match security_db::get_blacklist_entry_for_ip(ip) {
-                Ok(Some(entry)) => {
-                    tracing::warn!("[IP Filter] IP {} is in blacklist, blocking", ip);
-                    
-                    // 构建详细的封禁消息
-                    let reason = entry.reason.as_deref().unwrap_or("Malicious activity detected");
-                    let ban_type = if let Some(expires_at) = entry.expires_at {
-                        let now = chrono::Utc::now().timestamp();
-                        let remaining_seconds = expires_at - now;
-                        
-                        if remaining_seconds > 0 {
-                            let hours = remaining_seconds / 3600;
-                            let minutes = (remaining_seconds % 3600) / 60;
-                            
-                            if hours > 24 {
-                                let days = hours / 24;
-                                format!("Temporary ban. Please try again after {} day(s).", days)
-                            } else if hours > 0 {
-                                format!("Temporary ban. Please try again after {} hour(s) and {} minute(s).", hours, minutes)
-                            } else {
-                                format!("Temporary ban. Please try again after {} minute(s).", minutes)
-                            }
-                        } else {
-                            "Temporary ban (expired, will be removed soon).".to_string()
-                        }
-                    } else {
-                        "Permanent ban.".to_string()
-                    };
-                    
-                    let detailed_message = format!(
-                        "Access denied. Reason: {}. {}",
-                        reason,
-                        ban_type
-                    );
-                    
-                    // 记录被封禁的访问日志
-                    let log = security_db::IpAccessLog {
-                        id: uuid::Uuid::new_v4().to_string(),
-                        client_ip: ip.clone(),
-                        timestamp: chrono::Utc::now().timestamp(),
-                        method: Some(request.method().to_string()),
-                        path: Some(request.uri().to_string()),
-                        user_agent: request
-                            .headers()
-                            .get("user-agent")
-                            .and_then(|v| v.to_str().ok())
-                            .map(|s| s.to_string()),
-                        status: Some(403),
-                        duration: Some(0),
-                        api_key_hash: None,
-                        blocked: true,
-                        block_reason: Some(format!("IP in blacklist: {}", reason)),
-                        username: None,
-                    };
-                    
-                    tokio::spawn(async move {
-                        if let Err(e) = security_db::save_ip_access_log(&log) {
-                            tracing::error!("[IP Filter] Failed to save blocked access log: {}", e);
-                        }
-                    });
-                    
-                    return create_blocked_response(
-                        ip,
-                        &detailed_message,
-                    );
-                }
-                Ok(None) => {
-                    // 不在黑名单中,放行
-                    tracing::debug!("[IP Filter] IP {} not in blacklist, allowing", ip);
-                }
-                Err(e) => {
-                    tracing::error!("[IP Filter] Failed to check blacklist: {}", e);
-                }
-            }
+        // 2. 检查黑名单 (仅启用自动封禁时只检查自动封禁条目)
+        let monitor_config = &security_config.security_monitor;
+        if let Some(blocked) = check_blacklist(ip, monitor_config, &request) {
+            return blocked;
         }
+
+        // 3. 请求频率计入自动封禁
+        auto_ban::record(&monitor_config.auto_ban, ip, Offense::Request);
     } else {
         tracing::warn!("[IP Filter] Unable to extract client IP from request");
     }
@@ -143,30 +80,111 @@ pub async fn ip_filter_middleware(
     next.run(request).await
 }
 
-/// 从请求中提取客户端 IP
-fn extract_client_ip(request: &Request) -> Option<String> {
-    // 1. 优先从 X-Forwarded-For 提取 (取第一个 IP)
-    request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
-        .or_else(|| {
-            // 2. 备选从 X-Real-IP 提取
-            request
-                .headers()
-                .get("x-real-ip")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string())
-        })
-        .or_else(|| {
-            // 3. 最后尝试从 ConnectInfo 获取 (TCP 连接 IP)
-            // 这可以解决本地开发/测试时没有代理头导致 IP 获取失败的问题
-            request
-                .extensions()
-                .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
-                .map(|info| info.0.ip().to_string())
-        })
+/// 检查黑名单 (含自动封禁条目)，命中时记录访问日志并返回 403 响应
+fn check_blacklist(ip: &str, monitor_config: &SecurityMonitorConfig, request: &Request) -> Option<Response> {
+    // 仅启用自动封禁时只执行自动封禁条目，手动黑名单随黑名单开关停用
+    let lookup = if monitor_config.blacklist.enabled {
+        security_db::get_blacklist_entry_for_ip(ip)
+    } else if monitor_config.auto_ban.enabled {
+        security_db::get_auto_ban_entry_for_ip(ip)
+    } else {
+        return None;
+    };
+    match lookup {
+        Ok(Some(entry)) => {
+            tracing::warn!("[IP Filter] IP {} is in blacklist, blocking", ip);
+            
+            // 构建详细的封禁消息
+            let reason = entry.reason.as_deref().unwrap_or("Malicious activity detected");
+            let ban_type = if let Some(expires_at) = entry.expires_at {
+                let now = chrono::Utc::now().timestamp();
+                let remaining_seconds = expires_at - now;
+                
+                if remaining_seconds > 0 {
+                    let hours = remaining_seconds / 3600;
+                    let minutes = (remaining_seconds % 3600) / 60;
+                    
+                    if hours > 24 {
+                        let days = hours / 24;
+                        format!("Temporary ban. Please try again after {} day(s).", days)
+                    } else if hours > 0 {
+                        format!("Temporary ban. Please try again after {} hour(s) and {} minute(s).", hours, minutes)
+                    } else {
+                        format!("Temporary ban. Please try again after {} minute(s).", minutes)
+                    }
+                } else {
+                    "Temporary ban (expired, will be removed soon).".to_string()
+                }
+            } else {
+                "Permanent ban.".to_string()
+            };
+            
+            let detailed_message = format!(
+                "Access denied. Reason: {}. {}",
+                reason,
+                ban_type
+            );
+            
+            // 记录被封禁的访问日志
+            let log = security_db::IpAccessLog {
+                id: uuid::Uuid::new_v4().to_string(),
+                client_ip: ip.to_string(),
+                timestamp: chrono::Utc::now().timestamp(),
+                method: Some(request.method().to_string()),
+                path: Some(request.uri().to_string()),
+                user_agent: request
+                    .headers()
+                    .get("user-agent")
+                    .and_then(|v| v.to_str().ok())
+                    .map(|s| s.to_string()),
+                status: Some(403),
+                duration: Some(0),
+                api_key_hash: None,
+                blocked: true,
+                block_reason: Some(format!("IP in blacklist: {}", reason)),
+                username: None,
+            };
+            
+            tokio::spawn(async move {
+                if let Err(e) = security_db::save_ip_access_log(&log) {
+                    tracing::error!("[IP Filter] Failed to save blocked access log: {}", e);
+                }
+            });
+            
+            Some(create_blocked_response(ip, &detailed_message))
+        }
+        Ok(None) => {
+            // 不在黑名单中,放行
+            tracing::debug!("[IP Filter] IP {} not in blacklist, allowing", ip);
+            None
+        }
+        Err(e) => {
+            tracing::error!("[IP Filter] Failed to check blacklist: {}", e);
+            None
+        }
+    }
+}
+
+/// 管理接口 IP 过滤中间件
+///
+/// 管理登录失败会计入自动封禁，因此管理接口同样需要拦截黑名单 (含自动封禁) IP。
+/// 不启用白名单模式与请求频率计数，避免管理后台被误锁或因轮询触发封禁。
+pub async fn admin_ip_filter_middleware(
+    State(security): State<Arc<RwLock<ProxySecurityConfig>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let monitor_config = security.read().await.security_monitor.clone();
+    if let Some(ip) = client_ip::client_ip(&request, &monitor_config) {
+        let whitelisted = monitor_config.whitelist.whitelist_priority
+            && security_db::is_ip_in_whitelist(&ip).unwrap_or(false);
+        if !whitelisted {
+            if let Some(blocked) = check_blacklist(&ip, &monitor_config, &request) {
+                return blocked;
+            }
+        }
+    }
+    next.run(request).await
 }
 
 /// 创建被封禁的响应
@@ -187,3 +205,72 @@ fn create_blocked_response(ip: &str, message: &str) -> Response {
     )
         .into_response()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, extract::ConnectInfo, routing::get, Router};
+    use std::net::SocketAddr;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_admin_filter_rejects_banned_ip() {
+        security_db::init_db().unwrap();
+        let banned_ip = "203.0.113.201";
+        let entry = security_db::add_to_blacklist(banned_ip, Some("test ban"), None, "test").unwrap();
+
+        let mut config = ProxySecurityConfig {
+            auth_mode: crate::proxy::ProxyAuthMode::Strict,
+            api_key: "sk-api".to_string(),
+            admin_password: None,
+            allow_lan_access: true,
+            port: 8045,
+            security_monitor: SecurityMonitorConfig::default(),
+        };
+        config.security_monitor.blacklist.enabled = true;
+        let security = Arc::new(RwLock::new(config));
+        let app = Router::new()
+            .nest("/api", Router::new().route("/accounts", get(|| async { "ok" })))
+            .layer(axum::middleware::from_fn_with_state(security, admin_ip_filter_middleware));
+
+        let request = |ip: &str| {
+            let mut req = Request::builder().uri("/api/accounts").body(Body::empty()).unwrap();
+            let addr: SocketAddr = format!("{}:40000", ip).parse().unwrap();
+            req.extensions_mut().insert(ConnectInfo(addr));
+            req
+        };
+
+        let blocked = app.clone().oneshot(request(banned_ip)).await.unwrap();
+        let allowed = app.oneshot(request("203.0.113.202")).await.unwrap();
+        security_db::remove_from_blacklist(&entry.id).unwrap();
+
+        assert_eq!(blocked.status(), StatusCode::FORBIDDEN);
+        assert_eq!(allowed.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_auto_ban_only_ignores_manual_entries() {
+        security_db::init_db().unwrap();
+        let manual_ip = "203.0.113.211";
+        let auto_ip = "203.0.113.212";
+        let manual = security_db::add_to_blacklist(manual_ip, Some("manual"), None, "manual").unwrap();
+        let expires = chrono::Utc::now().timestamp() + 600;
+        let auto = security_db::add_to_blacklist(auto_ip, Some("auto"), Some(expires), "auto").unwrap();
+
+        let mut config = SecurityMonitorConfig::default();
+        config.blacklist.enabled = false;
+        config.auto_ban.enabled = true;
+        let request = Request::builder().uri("/v1/models").body(Body::empty()).unwrap();
+
+        let manual_blocked = check_blacklist(manual_ip, &config, &request).is_some();
+        let auto_blocked = check_blacklist(auto_ip, &config, &request).is_some();
+        config.blacklist.enabled = true;
+        let manual_enforced = check_blacklist(manual_ip, &config, &request).is_some();
+        security_db::remove_from_blacklist(&manual.id).unwrap();
+        security_db::remove_from_blacklist(&auto.id).unwrap();
+
+        assert!(!manual_blocked);
+        assert!(auto_blocked);
+        assert!(manual_enforced);
+    }
+}
@@ -5,6 +5,13 @@ pub mod cors;
 pub mod logging;
 pub mod monitor;
 pub mod ip_filter;
+pub mod idempotency;
+pub mod response_cache;
+pub mod trace_context;
+pub mod token_scope;
+pub mod client_ip;
+pub mod admin_rbac;
+pub mod audit;
 
 pub mod service_status;
 
@@ -12,4 +19,8 @@ pub use cors::cors_layer;
 pub use monitor::monitor_middleware;
 pub use service_status::service_status_middleware;
 pub use auth::{auth_middleware, admin_auth_middleware};
-pub use ip_filter::ip_filter_middleware;
+pub use audit::admin_audit_middleware;
+pub use ip_filter::{admin_ip_filter_middleware, ip_filter_middleware};
+pub use idempotency::idempotency_middleware;
+pub use response_cache::response_cache_middleware;
+pub use trace_context::trace_context_middleware;
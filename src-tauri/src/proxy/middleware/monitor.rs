@@ -3,10 +3,12 @@ use axum::{
     middleware::Next,
     response::Response,
     body::Body,
+    http::HeaderValue,
 };
 use std::time::Instant;
 use crate::proxy::server::AppState;
 use crate::proxy::monitor::ProxyRequestLog;
+use crate::proxy::request_timing::{self, RequestTiming};
 use serde_json::Value;
 use crate::proxy::middleware::auth::UserTokenIdentity;
 use futures::StreamExt;
@@ -33,6 +35,52 @@ fn record_user_token_usage(
     }
 }
 
+/// 提取缓存 / 思考 Token，并统一为「包含在 input_tokens / output_tokens 内」的口径
+fn extract_usage_details(usage: &Value, log: &mut ProxyRequestLog) {
+    let count = |pointer: &str| usage.pointer(pointer).and_then(|v| v.as_u64()).map(|v| v as u32);
+
+    // OpenAI / Gemini 的缓存 Token 已计入输入；Anthropic 的 cache_read_input_tokens 单独计数
+    if let Some(cached) = count("/prompt_tokens_details/cached_tokens").or(count("/cachedContentTokenCount")) {
+        log.cached_tokens = Some(cached);
+    } else if let Some(cached) = count("/cache_read_input_tokens") {
+        log.cached_tokens = Some(cached);
+        log.input_tokens = Some(log.input_tokens.unwrap_or(0).saturating_add(cached));
+    }
+
+    // OpenAI 的 reasoning_tokens 已计入输出；Gemini 的 thoughtsTokenCount 不计入 candidatesTokenCount
+    if let Some(thinking) = count("/completion_tokens_details/reasoning_tokens") {
+        log.thinking_tokens = Some(thinking);
+    } else if let Some(thinking) = count("/thoughtsTokenCount") {
+        log.thinking_tokens = Some(thinking);
+        log.output_tokens = Some(log.output_tokens.unwrap_or(0).saturating_add(thinking));
+    }
+}
+
+/// 按价格表计算本次请求费用 (优先使用路由后的实际模型)
+fn apply_cost(log: &mut ProxyRequestLog, provider: Option<&str>) {
+    if log.input_tokens.is_none() && log.output_tokens.is_none() {
+        return;
+    }
+    let Some(model) = log.mapped_model.as_deref().or(log.model.as_deref()) else {
+        return;
+    };
+    let usage = crate::proxy::pricing::UsageTokens {
+        input: log.input_tokens.unwrap_or(0),
+        output: log.output_tokens.unwrap_or(0),
+        cached: log.cached_tokens.unwrap_or(0),
+        thinking: log.thinking_tokens.unwrap_or(0),
+    };
+    log.cost = crate::proxy::pricing::calculate_cost(model, provider, usage);
+}
+
+/// 保存日志前按全局规则脱敏，令牌配置为仅记录元数据时丢弃请求 / 响应体
+fn redact_for_storage(user_token_identity: &Option<UserTokenIdentity>, log: &mut ProxyRequestLog) {
+    let metadata_only = user_token_identity
+        .as_ref()
+        .is_some_and(|identity| identity.log_metadata_only);
+    crate::proxy::redaction::redactor().apply_to_log(log, metadata_only);
+}
+
 pub async fn monitor_middleware(
     State(state): State<AppState>,
     request: Request,
@@ -48,22 +96,18 @@ pub async fn monitor_middleware(
     }
     
     let start = Instant::now();
+    // 并发统计：流式响应在数据发送完毕后才释放
+    let in_flight = crate::proxy::metrics::global().track_in_flight();
+    // [NEW] 延迟分解：Token 获取 / 退避 / 上游调用在请求处理过程中累加
+    let timing = RequestTiming::new();
     
-    // Extract client IP from headers (X-Forwarded-For or X-Real-IP)
+    // Extract client IP (forwarded headers are only honored from trusted proxies)
     // IMPORTANT: Extract from Request headers, not Response headers (since we want the client's IP)
     // Note: We need to do this BEFORE consuming the request body if possible, or extract it from the original request
-    let client_ip = request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
-        .or_else(|| {
-            request
-                .headers()
-                .get("x-real-ip")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string())
-        });
+    let client_ip = {
+        let security = state.security.read().await;
+        crate::proxy::middleware::client_ip::client_ip(&request, &security.security_monitor)
+    };
         
     let user_agent = request
         .headers()
@@ -85,6 +129,8 @@ pub async fn monitor_middleware(
     // [FIX] 从请求 extensions 提取 UserTokenIdentity (由 Auth 中间件注入)
     // 必须在处理 request body 之前提取，因为 into_parts() 后需要保留这个值
     let user_token_identity = request.extensions().get::<UserTokenIdentity>().cloned();
+    // [NEW] 管理端发起的请求重放 (日志 ID 由发起方预先分配)
+    let replay = crate::proxy::replay::claim(request.headers());
     
     let request = if method == "POST" {
         let (parts, body) = request.into_parts();
@@ -112,12 +158,28 @@ pub async fn monitor_middleware(
         request
     };
     
-    let response = next.run(request).await;
+    let handled = request_timing::scope(timing.clone(), next.run(request));
+    let mut response = match replay.clone() {
+        Some(ctx) => crate::proxy::replay::scope(ctx, handled).await,
+        None => handled.await,
+    };
     
     // user_token_identity 已在上面从请求 extensions 中提取
     
     let duration = start.elapsed().as_millis() as u64;
     let status = response.status().as_u16();
+
+    // 流式响应在首个数据块到达时更新 TTFT，这里先以响应头时间作为近似值
+    let latency = (!timing.is_empty()).then(|| {
+        let mut latency = timing.snapshot();
+        latency.ttft_ms = Some(duration);
+        latency
+    });
+    if let Some(latency) = &latency {
+        if let Ok(value) = HeaderValue::from_str(&latency.server_timing(duration)) {
+            response.headers_mut().insert("server-timing", value);
+        }
+    }
     
     let content_type = response.headers().get("content-type")
         .and_then(|v| v.to_str().ok())
@@ -138,6 +200,16 @@ pub async fn monitor_middleware(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
 
+    // [NEW] 上游提供方 (用于价格表按提供方覆盖)，账号池请求默认为 google
+    let provider = response
+        .headers()
+        .get("X-Upstream-Provider")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+        .or_else(|| account_email.as_ref().map(|_| "google".to_string()));
+    // [FIX] 内部头仅供监控使用，不返回给客户端
+    response.headers_mut().remove("X-Upstream-Provider");
+
     // Determine protocol from URL path
     let protocol = if uri.contains("/v1/messages") {
         Some("anthropic".to_string())
@@ -156,7 +228,10 @@ pub async fn monitor_middleware(
 
     let monitor = state.monitor.clone();
     let mut log = ProxyRequestLog {
-        id: uuid::Uuid::new_v4().to_string(),
+        id: replay
+            .as_ref()
+            .map(|ctx| ctx.replay_id.clone())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
         timestamp: chrono::Utc::now().timestamp_millis(),
         method,
         url: uri,
@@ -173,6 +248,12 @@ pub async fn monitor_middleware(
         output_tokens: None,
         protocol,
         username,
+        latency,
+        replay_of: replay.map(|ctx| ctx.original_id),
+        cached_tokens: None,
+        thinking_tokens: None,
+        cost: None,
+        provider: provider.clone(),
     };
 
 
@@ -182,11 +263,16 @@ pub async fn monitor_middleware(
         let (tx, rx) = tokio::sync::mpsc::channel(64);
         
         tokio::spawn(async move {
+            let _in_flight = in_flight;
             let mut all_stream_data = Vec::new();
             let mut last_few_bytes = Vec::new();
+            let mut first_chunk_ms: Option<u64> = None;
             
             while let Some(chunk_res) = stream.next().await {
                 if let Ok(chunk) = chunk_res {
+                    if first_chunk_ms.is_none() && !chunk.is_empty() {
+                        first_chunk_ms = Some(start.elapsed().as_millis() as u64);
+                    }
                     all_stream_data.extend_from_slice(&chunk);
                     
                     if chunk.len() > 8192 {
@@ -350,6 +436,7 @@ pub async fn monitor_middleware(
                                 .or(usage.get("candidatesTokenCount"))
                                 .and_then(|v| v.as_u64())
                                 .map(|v| v as u32);
+                            extract_usage_details(usage, &mut log);
                             
                             if log.input_tokens.is_none() && log.output_tokens.is_none() {
                                 log.output_tokens = usage.get("total_tokens")
@@ -418,6 +505,7 @@ pub async fn monitor_middleware(
                                         .or(usage.get("candidatesTokenCount"))
                                         .and_then(|v| v.as_u64())
                                         .map(|v| v as u32);
+                                    extract_usage_details(usage, &mut log);
                                     break;
                                 }
                             }
@@ -430,9 +518,21 @@ pub async fn monitor_middleware(
                 log.error = Some("Stream Error or Failed".to_string());
             }
 
+            if let Some(latency) = log.latency.as_mut() {
+                if first_chunk_ms.is_some() {
+                    latency.ttft_ms = first_chunk_ms;
+                }
+                latency.finalize(start.elapsed().as_millis() as u64, log.output_tokens);
+            }
+
+            apply_cost(&mut log, provider.as_deref());
+
+
             // Record User Token Usage
             record_user_token_usage(&user_token_identity, &log, user_agent.clone());
 
+            // [NEW] 落盘前脱敏 / 仅保留元数据
+            redact_for_storage(&user_token_identity, &mut log);
             monitor.log_request(log).await;
         });
 
@@ -455,6 +555,7 @@ pub async fn monitor_middleware(
                                 .or(usage.get("candidatesTokenCount"))
                                 .and_then(|v| v.as_u64())
                                 .map(|v| v as u32);
+                            extract_usage_details(usage, &mut log);
                                 
                             if log.input_tokens.is_none() && log.output_tokens.is_none() {
                                 log.output_tokens = usage.get("total_tokens")
@@ -472,19 +573,32 @@ pub async fn monitor_middleware(
                 if log.status >= 400 {
                     log.error = log.response_body.clone();
                 }
+                if let Some(latency) = log.latency.as_mut() {
+                    latency.finalize(log.duration, log.output_tokens);
+                }
+
+                apply_cost(&mut log, provider.as_deref());
+
 
                 // Record User Token Usage
                 record_user_token_usage(&user_token_identity, &log, user_agent.clone());
 
+                // [NEW] 落盘前脱敏 / 仅保留元数据
+                redact_for_storage(&user_token_identity, &mut log);
                 monitor.log_request(log).await;
                 Response::from_parts(parts, Body::from(bytes))
             }
             Err(_) => {
                 log.response_body = Some("[Response too large (>100MB)]".to_string());
 
+                apply_cost(&mut log, provider.as_deref());
+
+
                 // Record User Token Usage (even if too large)
                 record_user_token_usage(&user_token_identity, &log, user_agent.clone());
 
+                // [NEW] 落盘前脱敏 / 仅保留元数据
+                redact_for_storage(&user_token_identity, &mut log);
                 monitor.log_request(log).await;
                 Response::from_parts(parts, Body::empty())
             }
@@ -492,9 +606,14 @@ pub async fn monitor_middleware(
     } else {
         log.response_body = Some(format!("[{}]", content_type));
 
+        apply_cost(&mut log, provider.as_deref());
+
+
         // Record User Token Usage
         record_user_token_usage(&user_token_identity, &log, user_agent);
 
+        // [NEW] 落盘前脱敏 / 仅保留元数据
+        redact_for_storage(&user_token_identity, &mut log);
         monitor.log_request(log).await;
         response
     }
@@ -0,0 +1,51 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::proxy::middleware::auth::UserTokenIdentity;
+use crate::proxy::response_cache::{self, CacheContext, CacheStatus};
+use crate::proxy::server::AppState;
+
+/// 建立请求级缓存上下文，由上游客户端完成实际的查找与写入，这里只负责开关判断与响应头
+pub async fn response_cache_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let config = response_cache::get_response_cache_config();
+    // 重放请求需要真实的上游结果
+    if !config.enabled
+        || request.method() != axum::http::Method::POST
+        || crate::proxy::replay::is_replay()
+    {
+        return next.run(request).await;
+    }
+
+    // User Token 需单独开启缓存；未使用 User Token 的请求跟随全局开关
+    let opted_in = request
+        .extensions()
+        .get::<UserTokenIdentity>()
+        .map(|identity| identity.response_cache)
+        .unwrap_or(true);
+    if !opted_in {
+        return next.run(request).await;
+    }
+
+    let ctx = CacheContext::new(state.response_cache.clone(), config);
+    let mut response = response_cache::scope(ctx.clone(), next.run(request)).await;
+
+    if let Some(status) = ctx.status() {
+        let headers = response.headers_mut();
+        headers.insert(
+            response_cache::CACHE_STATUS_HEADER,
+            HeaderValue::from_static(status.as_header_value()),
+        );
+        if let CacheStatus::Hit { age_secs } = status {
+            headers.insert(header::AGE, HeaderValue::from(age_secs));
+        }
+    }
+    response
+}
@@ -14,7 +14,7 @@ pub async fn service_status_middleware(
     let path = request.uri().path();
     
     // Always allow Admin API and Auth callback
-    if path.starts_with("/api/") || path == "/auth/callback" || path == "/health" {
+    if path.starts_with("/api/") || path == "/auth/callback" || path == "/health" || path == "/metrics" {
         return next.run(request).await;
     }
 
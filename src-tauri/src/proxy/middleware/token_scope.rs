@@ -0,0 +1,267 @@
+// 用户令牌权限范围 (Scope) 与能力限制
+//
+// 路由 -> 权限范围映射:
+//   openai:chat           /v1/chat/completions, /v1/completions, /v1/responses
+//   anthropic:messages    /v1/messages, /v1/messages/count_tokens
+//   gemini:generate       POST /v1beta/models/{model}:* 及 countTokens
+//   images                /v1/images/*
+//   audio                 /v1/audio/*
+//   mcp:web_search        /mcp/web_search_prime/*
+//   mcp:web_reader        /mcp/web_reader/*
+//   mcp:zai               /mcp/zai-mcp-server/*
+//   models:detect         /v1/models/detect
+// 模型列表等只读接口不受 scope 限制；/internal/warmup 仅供内部调用且不经过令牌鉴权，因此没有对应 scope。
+
+use axum::{
+    body::{Body, Bytes},
+    extract::Request,
+    http::{Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde_json::{json, Value};
+
+use crate::modules::user_token_db::TokenRestrictions;
+
+/// 能力检查时读取请求体的上限
+const MAX_INSPECT_BODY_SIZE: usize = 100 * 1024 * 1024;
+
+/// 请求所属协议，决定错误响应格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Protocol {
+    OpenAI,
+    Anthropic,
+    Gemini,
+}
+
+fn protocol_for(path: &str) -> Protocol {
+    if path.starts_with("/v1/messages") {
+        Protocol::Anthropic
+    } else if path.starts_with("/v1beta/") {
+        Protocol::Gemini
+    } else {
+        Protocol::OpenAI
+    }
+}
+
+/// 路由所需的权限范围，None 表示无需授权
+pub fn required_scope(method: &Method, path: &str) -> Option<&'static str> {
+    match path {
+        "/v1/chat/completions" | "/v1/completions" | "/v1/responses" => Some("openai:chat"),
+        "/v1/messages" | "/v1/messages/count_tokens" => Some("anthropic:messages"),
+        "/v1/models/detect" => Some("models:detect"),
+        _ if path.starts_with("/v1/images/") => Some("images"),
+        _ if path.starts_with("/v1/audio/") => Some("audio"),
+        _ if path.starts_with("/mcp/web_search_prime/") => Some("mcp:web_search"),
+        _ if path.starts_with("/mcp/web_reader/") => Some("mcp:web_reader"),
+        _ if path.starts_with("/mcp/zai-mcp-server/") => Some("mcp:zai"),
+        _ if path.starts_with("/v1beta/models/") && method == Method::POST => Some("gemini:generate"),
+        _ => None,
+    }
+}
+
+/// 判断令牌的 scopes 是否覆盖所需权限 (支持 `*` 与 `namespace:*`，不区分大小写)
+pub fn scope_allows(scopes: &[String], required: &str) -> bool {
+    if scopes.is_empty() {
+        return true;
+    }
+    let required = required.to_ascii_lowercase();
+    scopes.iter().any(|scope| {
+        let scope = scope.trim().to_ascii_lowercase();
+        scope == "*"
+            || scope == required
+            || scope
+                .strip_suffix(":*")
+                .is_some_and(|ns| required.split(':').next() == Some(ns))
+    })
+}
+
+fn non_empty_array(value: Option<&Value>) -> bool {
+    value.and_then(|v| v.as_array()).is_some_and(|a| !a.is_empty())
+}
+
+/// 请求体 (或 Gemini 路径) 中的模型名
+fn request_model(path: &str, body: Option<&Value>) -> String {
+    body.and_then(|b| b.get("model"))
+        .and_then(|m| m.as_str())
+        .map(str::to_string)
+        .or_else(|| {
+            path.strip_prefix("/v1beta/models/")
+                .map(|rest| rest.split([':', '/']).next().unwrap_or(rest).to_string())
+        })
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+fn uses_tools(body: &Value) -> bool {
+    non_empty_array(body.get("tools")) || non_empty_array(body.get("functions"))
+}
+
+/// 未显式配置思考参数时默认开启思考的模型 (与映射器的默认思考策略保持一致)
+const DEFAULT_THINKING_MODELS: &[&str] = &[
+    "gemini-2.5-pro",
+    "gemini-2.5-flash",
+    "gemini-2.0-pro",
+    "gemini-3-pro",
+    "gemini-3.1-pro",
+    "gemini-3-flash",
+    "gemini-3.1-flash",
+    "opus-4-5",
+    "opus-4.5",
+    "opus-4-6",
+    "opus-4.6",
+];
+
+fn uses_thinking(model: &str, body: &Value) -> bool {
+    if model.contains("thinking") {
+        return true;
+    }
+    // Anthropic
+    if let Some(kind) = body.pointer("/thinking/type").and_then(|v| v.as_str()) {
+        return kind != "disabled";
+    }
+    // OpenAI (chat / responses)
+    let effort = body
+        .get("reasoning_effort")
+        .or_else(|| body.pointer("/reasoning/effort"))
+        .and_then(|v| v.as_str());
+    if let Some(effort) = effort {
+        return effort != "none";
+    }
+    // Gemini
+    if let Some(config) = body.pointer("/generationConfig/thinkingConfig") {
+        let budget_disabled = config.get("thinkingBudget").and_then(|v| v.as_i64()) == Some(0);
+        let include = config.get("includeThoughts").and_then(|v| v.as_bool()).unwrap_or(false);
+        return include || config.get("thinkingLevel").is_some() || !budget_disabled;
+    }
+    // 未显式配置时按模型的默认行为判断
+    DEFAULT_THINKING_MODELS.iter().any(|m| model.contains(m))
+}
+
+/// 检查能力限制，返回拒绝原因
+pub fn check_capabilities(restrictions: &TokenRestrictions, path: &str, body: Option<&Value>) -> Option<String> {
+    let model = request_model(path, body);
+
+    if restrictions.deny_image_generation
+        && (path.starts_with("/v1/images/") || model.contains("image") || model.contains("imagen"))
+    {
+        return Some("Image generation is not allowed for this token.".to_string());
+    }
+    let body = body?;
+    if restrictions.deny_tools && uses_tools(body) {
+        return Some("Tool use is not allowed for this token.".to_string());
+    }
+    if restrictions.deny_thinking && uses_thinking(&model, body) {
+        return Some("Thinking / reasoning is not allowed for this token.".to_string());
+    }
+    None
+}
+
+/// 按协议格式构造 403 错误响应
+pub fn forbidden_response(path: &str, message: &str) -> Response {
+    let body = match protocol_for(path) {
+        Protocol::Anthropic => json!({
+            "type": "error",
+            "error": { "type": "permission_error", "message": message }
+        }),
+        Protocol::Gemini => json!({
+            "error": { "code": 403, "message": message, "status": "PERMISSION_DENIED" }
+        }),
+        Protocol::OpenAI => json!({
+            "error": { "message": message, "type": "permission_error", "code": "insufficient_scope" }
+        }),
+    };
+    (StatusCode::FORBIDDEN, axum::Json(body)).into_response()
+}
+
+/// 对已识别的用户令牌执行 scope 与能力检查，通过时返回 (可能已重建的) 请求
+pub async fn enforce(restrictions: &TokenRestrictions, request: Request) -> Result<Request, Response> {
+    let path = request.uri().path().to_string();
+
+    if let Some(required) = required_scope(request.method(), &path) {
+        if !scope_allows(&restrictions.scopes, required) {
+            tracing::warn!("UserToken scope denied: {} requires '{}'", path, required);
+            return Err(forbidden_response(
+                &path,
+                &format!("This token does not have the '{}' scope.", required),
+            ));
+        }
+    }
+
+    if !(restrictions.deny_tools || restrictions.deny_image_generation || restrictions.deny_thinking) {
+        return Ok(request);
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes: Bytes = match axum::body::to_bytes(body, MAX_INSPECT_BODY_SIZE).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("UserToken capability check failed to read body: {}", e);
+            return Err(forbidden_response(&path, "Request body could not be inspected."));
+        }
+    };
+    let json = serde_json::from_slice::<Value>(&bytes).ok();
+
+    if let Some(reason) = check_capabilities(restrictions, &path, json.as_ref()) {
+        tracing::warn!("UserToken capability denied on {}: {}", path, reason);
+        return Err(forbidden_response(&path, &reason));
+    }
+    Ok(Request::from_parts(parts, Body::from(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_matching() {
+        let scopes = vec!["openai:chat".to_string(), "gemini:*".to_string(), "images".to_string()];
+        assert!(scope_allows(&scopes, "openai:chat"));
+        assert!(scope_allows(&scopes, "gemini:generate"));
+        assert!(scope_allows(&scopes, "images"));
+        assert!(!scope_allows(&scopes, "anthropic:messages"));
+        assert!(!scope_allows(&scopes, "mcp:web_search"));
+        assert!(scope_allows(&[], "audio"));
+        assert!(scope_allows(&["*".to_string()], "audio"));
+        // 精确匹配与通配符同样不区分大小写
+        assert!(scope_allows(&["OpenAI:Chat".to_string()], "openai:chat"));
+        assert!(scope_allows(&["Gemini:*".to_string()], "gemini:generate"));
+
+        assert_eq!(required_scope(&Method::POST, "/v1beta/models/gemini-2.5-pro:generateContent"), Some("gemini:generate"));
+        assert_eq!(required_scope(&Method::GET, "/v1beta/models/gemini-2.5-pro"), None);
+        assert_eq!(required_scope(&Method::POST, "/mcp/web_search_prime/mcp"), Some("mcp:web_search"));
+        assert_eq!(required_scope(&Method::GET, "/v1/models"), None);
+        assert_eq!(required_scope(&Method::POST, "/v1/models/detect"), Some("models:detect"));
+        assert_eq!(required_scope(&Method::POST, "/internal/warmup"), None);
+    }
+
+    #[test]
+    fn test_capability_checks() {
+        let restrictions = TokenRestrictions {
+            deny_tools: true,
+            deny_image_generation: true,
+            deny_thinking: true,
+            ..Default::default()
+        };
+        let plain = json!({ "model": "claude-sonnet-4-5", "messages": [] });
+        assert!(check_capabilities(&restrictions, "/v1/messages", Some(&plain)).is_none());
+
+        let tools = json!({ "model": "gpt-4o", "tools": [{ "type": "function" }] });
+        assert!(check_capabilities(&restrictions, "/v1/chat/completions", Some(&tools)).is_some());
+
+        let thinking = json!({ "model": "claude-sonnet-4-5", "thinking": { "type": "enabled", "budget_tokens": 1024 } });
+        assert!(check_capabilities(&restrictions, "/v1/messages", Some(&thinking)).is_some());
+
+        let gemini_no_thinking = json!({ "generationConfig": { "thinkingConfig": { "thinkingBudget": 0 } } });
+        assert!(check_capabilities(&restrictions, "/v1beta/models/gemini-2.5-flash:generateContent", Some(&gemini_no_thinking)).is_none());
+        assert!(check_capabilities(&restrictions, "/v1beta/models/gemini-3-pro-image:generateContent", Some(&gemini_no_thinking)).is_some());
+        assert!(check_capabilities(&restrictions, "/v1/images/generations", None).is_some());
+
+        // 默认开启思考的模型：未显式关闭即视为使用思考
+        let default_thinking = json!({ "contents": [] });
+        assert!(check_capabilities(&restrictions, "/v1beta/models/gemini-2.5-pro:generateContent", Some(&default_thinking)).is_some());
+        let opus = json!({ "model": "claude-opus-4-6", "messages": [] });
+        assert!(check_capabilities(&restrictions, "/v1/messages", Some(&opus)).is_some());
+        let opus_disabled = json!({ "model": "claude-opus-4-6", "messages": [], "thinking": { "type": "disabled" } });
+        assert!(check_capabilities(&restrictions, "/v1/messages", Some(&opus_disabled)).is_none());
+    }
+}
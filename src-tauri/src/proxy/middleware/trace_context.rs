@@ -0,0 +1,25 @@
+use axum::{extract::Request, middleware::Next, response::Response};
+use tracing::Instrument;
+
+/// 为每个代理请求建立根 span，并继承入站 W3C traceparent
+pub async fn trace_context_middleware(request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    if path.contains("event_logging") {
+        return next.run(request).await;
+    }
+
+    let method = request.method().clone();
+    let span = tracing::info_span!(
+        "proxy_request",
+        otel.name = %format!("{} {}", method, path),
+        otel.kind = "server",
+        http.request.method = %method,
+        url.path = %path,
+        http.response.status_code = tracing::field::Empty
+    );
+    crate::proxy::telemetry::set_parent_from_headers(&span, request.headers());
+
+    let response = next.run(request).instrument(span.clone()).await;
+    span.record("http.response.status_code", response.status().as_u16());
+    response
+}
@@ -8,23 +8,37 @@ pub mod server;
 pub mod token_manager;
 
 // 新架构模块
+pub mod alerts; // 告警规则与通知投递
 pub mod audio; // 音频处理模块
+pub mod auto_ban; // 自动临时封禁 (fail2ban 风格)
 pub mod cli_sync; // CLI 配置同步 (v3.3.35)
 pub mod droid_sync; // Droid (Factory CLI) 配置同步
+pub mod event_stream; // 管理端实时事件流 (SSE / WebSocket)
 pub mod common; // 公共工具
 pub mod debug_logger;
 pub mod handlers; // API 端点处理器
+pub mod idempotency; // 幂等请求去重
 pub mod mappers; // 协议转换器
+pub mod metrics; // Prometheus 指标导出
 pub mod middleware; // Axum 中间件
 pub mod monitor; // 监控
 pub mod opencode_sync; // OpenCode 配置同步
+pub mod pricing; // 模型价格表与费用计算
 pub mod providers; // Extra upstream providers (z.ai, etc.)
 pub mod proxy_pool; // 代理池管理器
 pub mod rate_limit; // 限流跟踪
+pub mod redaction; // 日志敏感信息脱敏
+pub mod dlp; // 出站数据防泄漏
+pub mod replay; // 请求重放与结果对比
+pub mod request_timing; // 请求延迟分解
+pub mod response_cache; // 精确匹配响应缓存
+pub mod runtime_state; // 调度运行时状态持久化
 pub mod model_specs; // 模型规格管理 (v4.1.29)
 pub mod session_manager; // 会话指纹管理
 pub mod signature_cache; // Signature Cache (v3.3.16)
 pub mod sticky_config; // 粘性调度配置
+pub mod telemetry; // OpenTelemetry 链路追踪导出
+pub mod tls; // 监听端 HTTPS (TLS 终止)
 pub mod upstream; // 上游客户端
 pub mod zai_vision_mcp; // Built-in Vision MCP server state
 pub mod zai_vision_tools; // Built-in Vision MCP tools (z.ai vision API) // 调试日志
@@ -23,6 +23,18 @@ pub struct ProxyRequestLog {
     pub output_tokens: Option<u32>,
     pub protocol: Option<String>,     // 协议类型: "openai", "anthropic", "gemini"
     pub username: Option<String>,     // User token username
+    #[serde(default)]
+    pub latency: Option<crate::proxy::request_timing::LatencyBreakdown>, // 延迟分解
+    #[serde(default)]
+    pub replay_of: Option<String>,    // 重放来源日志 ID
+    #[serde(default)]
+    pub cached_tokens: Option<u32>,   // 缓存命中的输入 Token (包含在 input_tokens 内)
+    #[serde(default)]
+    pub thinking_tokens: Option<u32>, // 思考 Token (包含在 output_tokens 内)
+    #[serde(default)]
+    pub cost: Option<f64>,            // 按价格表计算的费用
+    #[serde(default)]
+    pub provider: Option<String>,     // 上游提供方 (无账号时作为用量统计的账号键)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -30,6 +42,30 @@ pub struct ProxyStats {
     pub total_requests: u64,
     pub success_count: u64,
     pub error_count: u64,
+    #[serde(default)]
+    pub latency: LatencyStats, // 最近窗口内的延迟分位数
+}
+
+/// 单项指标的分位数 (无数据时为 None)
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Percentiles {
+    pub p50: Option<f64>,
+    pub p90: Option<f64>,
+    pub p99: Option<f64>,
+}
+
+/// 延迟分解聚合统计
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LatencyStats {
+    pub window_hours: i64,
+    pub duration_ms: Percentiles,
+    pub ttft_ms: Percentiles,
+    pub token_wait_ms: Percentiles,
+    pub queue_wait_ms: Percentiles,
+    pub upstream_ms: Percentiles,
+    pub output_tokens_per_sec: Percentiles,
+    /// 平均上游调度次数
+    pub avg_attempts: Option<f64>,
 }
 
 pub struct ProxyMonitor {
@@ -79,21 +115,37 @@ impl ProxyMonitor {
     }
 
     pub async fn log_request(&self, log: ProxyRequestLog) {
+        // Prometheus 指标不受监控开关影响
+        crate::proxy::metrics::global().record_request(&log);
+
+        // [FIX] Z.ai 等直连提供方没有账号邮箱，以提供方作为账号键记录用量
+        let usage_account = log.account_email.as_ref().or(log.provider.as_ref());
         if let (Some(account), Some(input), Some(output)) = (
-            &log.account_email,
+            usage_account,
             log.input_tokens,
             log.output_tokens,
         ) {
-            let model = log.model.clone().unwrap_or_else(|| "unknown".to_string());
-            let account = account.clone();
+            let record = crate::modules::token_stats::UsageRecord {
+                account_email: account.clone(),
+                model: log.model.clone().unwrap_or_else(|| "unknown".to_string()),
+                username: log.username.clone(),
+                input_tokens: input,
+                output_tokens: output,
+                cached_tokens: log.cached_tokens.unwrap_or(0),
+                thinking_tokens: log.thinking_tokens.unwrap_or(0),
+                cost: log.cost.unwrap_or(0.0),
+            };
             tokio::spawn(async move {
-                if let Err(e) = crate::modules::token_stats::record_usage(&account, &model, input, output) {
+                if let Err(e) = crate::modules::token_stats::record_usage(&record) {
                     tracing::debug!("Failed to record token stats: {}", e);
                 }
             });
         }
 
-        if !self.is_enabled() {
+        crate::proxy::replay::complete(&log);
+
+        // 重放结果需与原始日志关联保存，不受监控开关影响
+        if !self.is_enabled() && log.replay_of.is_none() {
             return;
         }
         tracing::info!("[Monitor] Logging request: {} {}", log.method, log.url);
@@ -145,22 +197,12 @@ impl ProxyMonitor {
                      tracing::error!("Failed to save security log: {}", e);
                 }
             }
-
-            // Record token stats if available
-            if let (Some(account), Some(input), Some(output)) = (
-                &log_to_save.account_email,
-                log_to_save.input_tokens,
-                log_to_save.output_tokens,
-            ) {
-                let model = log_to_save.model.clone().unwrap_or_else(|| "unknown".to_string());
-                if let Err(e) = crate::modules::token_stats::record_usage(account, &model, input, output) {
-                    tracing::debug!("Failed to record token stats: {}", e);
-                }
-            }
         });
 
         // Emit event (send summary only, without body to reduce memory)
-        if let Some(app) = &self.app_handle {
+        // [NEW] 同时推送到管理端事件流 (Headless / Web 模式)
+        let stream_subscribed = crate::proxy::event_stream::has_subscribers();
+        if self.app_handle.is_some() || stream_subscribed {
             let log_summary = ProxyRequestLog {
                 id: log.id.clone(),
                 timestamp: log.timestamp,
@@ -179,8 +221,21 @@ impl ProxyMonitor {
                 output_tokens: log.output_tokens,
                 protocol: log.protocol.clone(),
                 username: log.username.clone(),
+                latency: log.latency.clone(),
+                replay_of: log.replay_of.clone(),
+                cached_tokens: log.cached_tokens,
+                thinking_tokens: log.thinking_tokens,
+                cost: log.cost,
+                provider: log.provider.clone(),
             };
-            let _ = app.emit("proxy://request", &log_summary);
+            if let Some(app) = &self.app_handle {
+                let _ = app.emit("proxy://request", &log_summary);
+            }
+            if stream_subscribed {
+                crate::proxy::event_stream::publish(
+                    crate::proxy::event_stream::AdminEvent::Request(Box::new(log_summary)),
+                );
+            }
         }
     }
 
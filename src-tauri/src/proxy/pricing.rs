@@ -0,0 +1,227 @@
+// 模型价格表与费用计算
+//
+// 价格单位为「每百万 Token」。缓存命中的输入 Token 计入 input_tokens，按 cached_input 单价计费；
+// 思考 Token 计入 output_tokens，按 thinking 单价计费。未配置缓存 / 思考单价时沿用输入 / 输出单价。
+
+use serde::{Deserialize, Serialize};
+use std::sync::{OnceLock, RwLock};
+
+/// 单个模型的价格
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelPrice {
+    /// 模型名，支持 `*` 通配 (如 `gemini-2.5-flash*`)
+    pub model: String,
+    /// 仅对指定上游提供方生效 (如 `google`、`zai`)，为空时适用于所有提供方
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// 输入单价
+    pub input: f64,
+    /// 输出单价
+    pub output: f64,
+    /// 缓存命中输入单价
+    #[serde(default)]
+    pub cached_input: Option<f64>,
+    /// 思考 Token 单价
+    #[serde(default)]
+    pub thinking: Option<f64>,
+}
+
+/// 计费配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PricingConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 显示用货币单位
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    #[serde(default = "default_prices")]
+    pub models: Vec<ModelPrice>,
+}
+
+impl Default for PricingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            currency: default_currency(),
+            models: default_prices(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+fn price(model: &str, input: f64, output: f64, cached_input: Option<f64>) -> ModelPrice {
+    ModelPrice {
+        model: model.to_string(),
+        provider: None,
+        input,
+        output,
+        cached_input,
+        thinking: None,
+    }
+}
+
+/// 默认价格表 (参考各厂商公开标价，可在设置中修改)
+fn default_prices() -> Vec<ModelPrice> {
+    vec![
+        price("gemini-3-pro*", 2.0, 12.0, Some(0.2)),
+        price("gemini-3-flash*", 0.5, 3.0, Some(0.05)),
+        price("gemini-2.5-pro*", 1.25, 10.0, Some(0.31)),
+        price("gemini-2.5-flash-lite*", 0.1, 0.4, Some(0.025)),
+        price("gemini-2.5-flash*", 0.3, 2.5, Some(0.075)),
+        price("claude-opus-4-5*", 5.0, 25.0, Some(0.5)),
+        price("claude-opus-4*", 15.0, 75.0, Some(1.5)),
+        price("claude-sonnet-4*", 3.0, 15.0, Some(0.3)),
+        price("claude-haiku-4*", 1.0, 5.0, Some(0.1)),
+    ]
+}
+
+/// 单次请求的用量
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UsageTokens {
+    pub input: u32,
+    pub output: u32,
+    pub cached: u32,
+    pub thinking: u32,
+}
+
+/// 通配匹配，仅支持 `*`
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !text.starts_with(first) || text.len() < first.len() + last.len() || !text.ends_with(last) {
+        return false;
+    }
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+impl PricingConfig {
+    /// 查找模型价格：指定提供方的条目优先，其次精确匹配，再按通配前缀长度取最具体的一条
+    pub fn find(&self, model: &str, provider: Option<&str>) -> Option<&ModelPrice> {
+        let model = model.to_ascii_lowercase();
+        self.models
+            .iter()
+            .filter(|p| wildcard_match(&p.model.to_ascii_lowercase(), &model))
+            .filter(|p| match (&p.provider, provider) {
+                (None, _) => true,
+                (Some(expected), Some(actual)) => expected.eq_ignore_ascii_case(actual),
+                (Some(_), None) => false,
+            })
+            .max_by_key(|p| {
+                let exact = !p.model.contains('*');
+                let specificity = p.model.split('*').next().map(str::len).unwrap_or(0);
+                (p.provider.is_some(), exact, specificity)
+            })
+    }
+
+    /// 计算费用，未配置价格的模型返回 None
+    pub fn cost(&self, model: &str, provider: Option<&str>, usage: UsageTokens) -> Option<f64> {
+        if !self.enabled {
+            return None;
+        }
+        let price = self.find(model, provider)?;
+        let cached = usage.cached.min(usage.input);
+        let thinking = usage.thinking.min(usage.output);
+        let per_token = |count: u32, rate: f64| count as f64 * rate / 1_000_000.0;
+
+        Some(
+            per_token(usage.input - cached, price.input)
+                + per_token(cached, price.cached_input.unwrap_or(price.input))
+                + per_token(usage.output - thinking, price.output)
+                + per_token(thinking, price.thinking.unwrap_or(price.output)),
+        )
+    }
+}
+
+static GLOBAL_PRICING_CONFIG: OnceLock<RwLock<PricingConfig>> = OnceLock::new();
+
+/// 获取当前价格表
+pub fn get_pricing_config() -> PricingConfig {
+    GLOBAL_PRICING_CONFIG
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+/// 更新全局价格表
+pub fn update_pricing_config(config: PricingConfig) {
+    if let Some(lock) = GLOBAL_PRICING_CONFIG.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = config.clone();
+        }
+    } else {
+        let _ = GLOBAL_PRICING_CONFIG.set(RwLock::new(config.clone()));
+    }
+    tracing::info!(
+        "[Pricing] Config updated: enabled={}, models={}",
+        config.enabled,
+        config.models.len()
+    );
+}
+
+/// 按当前价格表计算单次请求费用
+pub fn calculate_cost(model: &str, provider: Option<&str>, usage: UsageTokens) -> Option<f64> {
+    get_pricing_config().cost(model, provider, usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_prefers_provider_and_specific_entries() {
+        let mut config = PricingConfig::default();
+        config.models.push(ModelPrice {
+            provider: Some("zai".to_string()),
+            ..price("claude-sonnet-4*", 1.0, 2.0, None)
+        });
+
+        assert_eq!(config.find("gemini-2.5-flash-lite", None).unwrap().input, 0.1);
+        assert_eq!(config.find("gemini-2.5-flash-thinking", None).unwrap().input, 0.3);
+        assert_eq!(config.find("claude-opus-4-5-thinking", None).unwrap().input, 5.0);
+        assert_eq!(config.find("claude-sonnet-4-5", Some("google")).unwrap().input, 3.0);
+        assert_eq!(config.find("claude-sonnet-4-5", Some("zai")).unwrap().input, 1.0);
+        assert!(config.find("unknown-model", None).is_none());
+    }
+
+    #[test]
+    fn test_cost_splits_cached_and_thinking_tokens() {
+        let config = PricingConfig {
+            enabled: true,
+            currency: "USD".to_string(),
+            models: vec![ModelPrice {
+                thinking: Some(20.0),
+                ..price("model-a", 1.0, 10.0, Some(0.1))
+            }],
+        };
+        let usage = UsageTokens {
+            input: 1_000_000,
+            output: 500_000,
+            cached: 400_000,
+            thinking: 100_000,
+        };
+        // 0.6 + 0.04 + 4.0 + 2.0
+        let cost = config.cost("model-a", None, usage).unwrap();
+        assert!((cost - 6.64).abs() < 1e-9);
+
+        let disabled = PricingConfig { enabled: false, ..config };
+        assert!(disabled.cost("model-a", None, usage).is_none());
+    }
+}
@@ -214,7 +214,9 @@ pub async fn forward_anthropic_json(
 
     let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
 
-    let mut out = Response::builder().status(status);
+    let mut out = Response::builder()
+        .status(status)
+        .header("X-Upstream-Provider", "zai");
     if let Some(ct) = resp.headers().get(header::CONTENT_TYPE) {
         out = out.header(header::CONTENT_TYPE, ct.clone());
     }
@@ -1,7 +1,9 @@
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::collections::HashMap;
 use dashmap::DashMap;
+use serde::Serialize;
 use rquest::Client;
 use futures::{stream, StreamExt};
 use std::time::Duration;
@@ -33,12 +35,60 @@ pub struct PoolProxyConfig {
     pub entry_id: String,
 }
 
+/// 单个代理的连接计数器
+#[derive(Debug, Default)]
+struct ProxyConnectionCounters {
+    /// 当前进行中的请求数 (响应体读取完毕后才释放)
+    active: AtomicUsize,
+    /// 累计请求数
+    total: AtomicU64,
+    /// 被负载均衡策略选中的次数
+    selected: AtomicU64,
+}
+
+/// 代理连接租约
+///
+/// 创建时活跃连接 +1，Drop 时 -1。由上游客户端挂在响应体上，流式响应结束后才释放。
+pub struct ProxyConnectionGuard {
+    counters: Arc<ProxyConnectionCounters>,
+}
+
+impl Drop for ProxyConnectionGuard {
+    fn drop(&mut self) {
+        self.counters.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// 代理连接统计 (Admin API 展示)
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyConnectionStats {
+    pub proxy_id: String,
+    pub name: String,
+    pub enabled: bool,
+    pub is_healthy: bool,
+    pub latency: Option<u64>,
+    pub weight: u32,
+    /// 结合延迟与健康状态后的实际权重 (WeightedRoundRobin 使用)
+    pub effective_weight: i64,
+    pub active_connections: usize,
+    pub total_connections: u64,
+    pub total_selections: u64,
+}
+
+/// 延迟参考值 (毫秒)：延迟等于该值时权重减半
+const WEIGHT_LATENCY_REFERENCE_MS: f64 = 1000.0;
+/// 不健康代理 (未开启自动故障转移时仍参与调度) 的权重系数
+const UNHEALTHY_WEIGHT_FACTOR: f64 = 0.1;
+
 /// 代理池管理器
 pub struct ProxyPoolManager {
     config: Arc<RwLock<ProxyPoolConfig>>,
     
-    /// 代理使用计数 (proxy_id -> count)
-    usage_counter: Arc<DashMap<String, usize>>,
+    /// 代理连接统计 (proxy_id -> counters)
+    connection_stats: Arc<DashMap<String, Arc<ProxyConnectionCounters>>>,
+
+    /// 平滑加权轮询的当前权重 (proxy_id -> current_weight)
+    wrr_current: Arc<parking_lot::Mutex<HashMap<String, i64>>>,
     
     /// 账号到代理的绑定 (account_id -> proxy_id)
     account_bindings: Arc<DashMap<String, String>>,
@@ -65,7 +115,8 @@ impl ProxyPoolManager {
 
         Self {
             config,
-            usage_counter: Arc::new(DashMap::new()),
+            connection_stats: Arc::new(DashMap::new()),
+            wrr_current: Arc::new(parking_lot::Mutex::new(HashMap::new())),
             account_bindings,
             round_robin_index: Arc::new(AtomicUsize::new(0)),
         }
@@ -261,7 +312,7 @@ impl ProxyPoolManager {
         
         if let Some(entry) = selected {
             // 更新计数
-            *self.usage_counter.entry(entry.id.clone()).or_insert(0) += 1;
+            self.counters(&entry.id).selected.fetch_add(1, Ordering::Relaxed);
             Ok(Some(self.build_proxy_config(entry)?))
         } else {
             Ok(None)
@@ -287,14 +338,99 @@ impl ProxyPoolManager {
     }
     
     fn select_least_connections<'a>(&self, proxies: &[&'a ProxyEntry]) -> Option<&'a ProxyEntry> {
+        // 活跃连接数最少者优先，相同时按优先级
         proxies.iter().min_by_key(|p| {
-            self.usage_counter.get(&p.id).map(|v| *v).unwrap_or(0)
+            (self.active_connections(&p.id), p.priority)
         }).copied()
     }
     
+    /// 平滑加权轮询 (Nginx SWRR)：每轮各节点当前权重加上有效权重，选出最大者后减去总权重
     fn select_weighted<'a>(&self, proxies: &[&'a ProxyEntry]) -> Option<&'a ProxyEntry> {
-        // 简单实现: 类似优先级的加权, 这里暂用 Priority 代替
-        self.select_by_priority(proxies)
+        if proxies.is_empty() { return None; }
+
+        let mut current = self.wrr_current.lock();
+        // 清理已移出候选集的节点，避免残留权重影响新一轮分配
+        current.retain(|id, _| proxies.iter().any(|p| p.id == *id));
+
+        let mut total = 0i64;
+        let mut best: Option<(&'a ProxyEntry, i64)> = None;
+        for p in proxies {
+            let weight = Self::effective_weight(p);
+            total += weight;
+            let cw = current.entry(p.id.clone()).or_insert(0);
+            *cw += weight;
+            if best.map(|(_, w)| *cw > w).unwrap_or(true) {
+                best = Some((p, *cw));
+            }
+        }
+
+        let (selected, _) = best?;
+        if let Some(cw) = current.get_mut(&selected.id) {
+            *cw -= total;
+        }
+        Some(selected)
+    }
+
+    /// 有效权重 = 配置权重 × 延迟系数 × 健康系数 (放大 100 倍取整，最小为 1)
+    fn effective_weight(entry: &ProxyEntry) -> i64 {
+        let latency_factor = entry
+            .latency
+            .map(|ms| 1.0 / (1.0 + ms as f64 / WEIGHT_LATENCY_REFERENCE_MS))
+            .unwrap_or(1.0);
+        let health_factor = if entry.is_healthy { 1.0 } else { UNHEALTHY_WEIGHT_FACTOR };
+        ((entry.weight as f64) * 100.0 * latency_factor * health_factor)
+            .round()
+            .max(1.0) as i64
+    }
+
+    fn counters(&self, proxy_id: &str) -> Arc<ProxyConnectionCounters> {
+        self.connection_stats
+            .entry(proxy_id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// 登记一次经由该代理的请求，返回的租约释放时活跃连接数自动减一
+    pub fn acquire_connection(&self, proxy_id: &str) -> ProxyConnectionGuard {
+        let counters = self.counters(proxy_id);
+        counters.active.fetch_add(1, Ordering::Relaxed);
+        counters.total.fetch_add(1, Ordering::Relaxed);
+        ProxyConnectionGuard { counters }
+    }
+
+    /// 当前活跃连接数
+    pub fn active_connections(&self, proxy_id: &str) -> usize {
+        self.connection_stats
+            .get(proxy_id)
+            .map(|c| c.active.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    /// 获取所有代理的连接统计
+    pub async fn get_connection_stats(&self) -> Vec<ProxyConnectionStats> {
+        let config = self.config.read().await;
+        config
+            .proxies
+            .iter()
+            .map(|p| {
+                let counters = self.connection_stats.get(&p.id).map(|c| c.clone());
+                let load = |f: fn(&ProxyConnectionCounters) -> u64| {
+                    counters.as_deref().map(f).unwrap_or(0)
+                };
+                ProxyConnectionStats {
+                    proxy_id: p.id.clone(),
+                    name: p.name.clone(),
+                    enabled: p.enabled,
+                    is_healthy: p.is_healthy,
+                    latency: p.latency,
+                    weight: p.weight,
+                    effective_weight: Self::effective_weight(p),
+                    active_connections: load(|c| c.active.load(Ordering::Relaxed) as u64) as usize,
+                    total_connections: load(|c| c.total.load(Ordering::Relaxed)),
+                    total_selections: load(|c| c.selected.load(Ordering::Relaxed)),
+                }
+            })
+            .collect()
     }
 
     /// 构建 reqwest::Proxy 配置
@@ -440,6 +576,14 @@ impl ProxyPoolManager {
                 proxy.is_healthy = is_healthy;
                 proxy.latency = latency;
                 proxy.last_check_time = Some(chrono::Utc::now().timestamp());
+                // [NEW] 健康检查失败时上报告警 (阈值与冷却由规则控制)
+                if !is_healthy {
+                    crate::proxy::alerts::emit(
+                        crate::proxy::alerts::AlertKind::ProxyUnhealthy,
+                        &proxy.name,
+                        &format!("Proxy {} ({}) failed its health check", proxy.name, proxy.url),
+                    );
+                }
             }
         }
         
@@ -527,3 +671,76 @@ impl ProxyPoolManager {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, weight: u32, latency: Option<u64>) -> ProxyEntry {
+        ProxyEntry {
+            id: id.to_string(),
+            name: id.to_string(),
+            url: format!("http://{}.example:8080", id),
+            auth: None,
+            enabled: true,
+            priority: 0,
+            tags: Vec::new(),
+            max_accounts: None,
+            health_check_url: None,
+            last_check_time: None,
+            is_healthy: true,
+            latency,
+            weight,
+        }
+    }
+
+    fn manager() -> ProxyPoolManager {
+        ProxyPoolManager::new(Arc::new(RwLock::new(ProxyPoolConfig::default())))
+    }
+
+    #[test]
+    fn test_least_connections_uses_live_connections() {
+        let m = manager();
+        let a = entry("a", 1, None);
+        let b = entry("b", 1, None);
+        let proxies = vec![&a, &b];
+
+        let guard = m.acquire_connection("a");
+        assert_eq!(m.select_least_connections(&proxies).unwrap().id, "b");
+
+        // 请求结束后连接释放，a 重新成为候选
+        drop(guard);
+        assert_eq!(m.active_connections("a"), 0);
+        let _g1 = m.acquire_connection("b");
+        assert_eq!(m.select_least_connections(&proxies).unwrap().id, "a");
+    }
+
+    #[test]
+    fn test_smooth_weighted_round_robin_distribution() {
+        let m = manager();
+        let a = entry("a", 5, None);
+        let b = entry("b", 1, None);
+        let c = entry("c", 1, None);
+        let proxies = vec![&a, &b, &c];
+
+        let picks: Vec<String> = (0..7)
+            .map(|_| m.select_weighted(&proxies).unwrap().id.clone())
+            .collect();
+        assert_eq!(picks.iter().filter(|id| *id == "a").count(), 5);
+        assert_eq!(picks.iter().filter(|id| *id == "b").count(), 1);
+        // 平滑：高权重节点不会连续占满整轮
+        assert_ne!(picks[..5], ["a", "a", "a", "a", "a"]);
+    }
+
+    #[test]
+    fn test_effective_weight_penalizes_latency_and_health() {
+        let fast = entry("fast", 1, Some(100));
+        let slow = entry("slow", 1, Some(3000));
+        let mut unhealthy = entry("down", 1, Some(100));
+        unhealthy.is_healthy = false;
+
+        assert!(ProxyPoolManager::effective_weight(&fast) > ProxyPoolManager::effective_weight(&slow));
+        assert!(ProxyPoolManager::effective_weight(&unhealthy) < ProxyPoolManager::effective_weight(&fast));
+        assert!(ProxyPoolManager::effective_weight(&unhealthy) >= 1);
+    }
+}
@@ -1,9 +1,10 @@
 use dashmap::DashMap;
-use std::time::{SystemTime, Duration};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, Duration, UNIX_EPOCH};
 use regex::Regex;
 
 /// 限流原因类型
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum RateLimitReason {
     /// 配额耗尽 (QUOTA_EXHAUSTED)
     QuotaExhausted,
@@ -41,6 +42,44 @@ pub struct RateLimitInfo {
 /// 失败计数过期时间：1小时（超过此时间未失败则重置计数）
 const FAILURE_COUNT_EXPIRY_SECONDS: u64 = 3600;
 
+/// 持久化用的限流记录 (时间统一为 Unix 秒)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersistedRateLimit {
+    /// 限流 Key ("account_id" 或 "account_id:model")
+    pub key: String,
+    pub reset_at: u64,
+    pub retry_after_sec: u64,
+    pub detected_at: u64,
+    pub reason: RateLimitReason,
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// 持久化用的连续失败计数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersistedFailureCount {
+    pub account_id: String,
+    pub count: u32,
+    pub last_failure_at: u64,
+}
+
+/// 限流跟踪器的可序列化快照 (用于重启后恢复)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RateLimitSnapshot {
+    #[serde(default)]
+    pub limits: Vec<PersistedRateLimit>,
+    #[serde(default)]
+    pub failure_counts: Vec<PersistedFailureCount>,
+}
+
+fn to_unix_secs(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn from_unix_secs(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
 /// 限流跟踪器
 pub struct RateLimitTracker {
     limits: DashMap<String, RateLimitInfo>,
@@ -130,6 +169,13 @@ impl RateLimitTracker {
         
         let key = self.get_limit_key(account_id, model.as_deref());
         self.limits.insert(key, info);
+        crate::proxy::event_stream::publish_account(
+            account_id,
+            None,
+            crate::proxy::event_stream::AccountState::RateLimited,
+            model.as_deref(),
+            Some(format!("{:?}, retry after {}s", reason, retry_sec)),
+        );
         
         if let Some(m) = &model {
             tracing::info!(
@@ -315,6 +361,13 @@ impl RateLimitTracker {
         };
 
         self.limits.insert(key, info.clone());
+        crate::proxy::event_stream::publish_account(
+            account_id,
+            None,
+            crate::proxy::event_stream::AccountState::RateLimited,
+            info.model.as_deref(),
+            Some(format!("{} {:?}, retry after {}s", status, reason, retry_sec)),
+        );
         
         tracing::warn!(
             "账号 {} [{}] 限流类型: {:?}, 重置延时: {}秒",
@@ -545,6 +598,79 @@ impl RateLimitTracker {
         count
     }
     
+    /// 导出当前未过期的限流记录与失败计数
+    pub fn snapshot(&self) -> RateLimitSnapshot {
+        let now = SystemTime::now();
+
+        let limits = self
+            .limits
+            .iter()
+            .filter(|e| e.value().reset_time > now)
+            .map(|e| {
+                let info = e.value();
+                PersistedRateLimit {
+                    key: e.key().clone(),
+                    reset_at: to_unix_secs(info.reset_time),
+                    retry_after_sec: info.retry_after_sec,
+                    detected_at: to_unix_secs(info.detected_at),
+                    reason: info.reason,
+                    model: info.model.clone(),
+                }
+            })
+            .collect();
+
+        let failure_counts = self
+            .failure_counts
+            .iter()
+            .map(|e| PersistedFailureCount {
+                account_id: e.key().clone(),
+                count: e.value().0,
+                last_failure_at: to_unix_secs(e.value().1),
+            })
+            .collect();
+
+        RateLimitSnapshot { limits, failure_counts }
+    }
+
+    /// 从快照恢复限流状态，已过期的记录会被丢弃
+    ///
+    /// `keep` 用于按账号 ID 过滤 (例如丢弃已删除账号的记录)。
+    /// 返回恢复的限流记录数量。
+    pub fn restore(&self, snapshot: RateLimitSnapshot, keep: impl Fn(&str) -> bool) -> usize {
+        let now = SystemTime::now();
+        let mut restored = 0;
+
+        for entry in snapshot.limits {
+            let account_id = entry.key.split(':').next().unwrap_or(&entry.key);
+            let reset_time = from_unix_secs(entry.reset_at);
+            if reset_time <= now || !keep(account_id) {
+                continue;
+            }
+            self.limits.insert(
+                entry.key,
+                RateLimitInfo {
+                    reset_time,
+                    retry_after_sec: entry.retry_after_sec,
+                    detected_at: from_unix_secs(entry.detected_at),
+                    reason: entry.reason,
+                    model: entry.model,
+                },
+            );
+            restored += 1;
+        }
+
+        for entry in snapshot.failure_counts {
+            let last_failure = from_unix_secs(entry.last_failure_at);
+            let elapsed = now.duration_since(last_failure).unwrap_or(Duration::from_secs(0)).as_secs();
+            if elapsed > FAILURE_COUNT_EXPIRY_SECONDS || !keep(&entry.account_id) {
+                continue;
+            }
+            self.failure_counts.insert(entry.account_id, (entry.count, last_failure));
+        }
+
+        restored
+    }
+
     /// 清除指定账号的限流记录
     pub fn clear(&self, account_id: &str) -> bool {
         self.limits.remove(account_id).is_some()
@@ -679,4 +805,29 @@ mod tests {
         let info = tracker.parse_from_error("acc2", 429, None, quota_body, None, &backoff_steps);
         assert_eq!(info.unwrap().retry_after_sec, 7200);
     }
+
+    #[test]
+    fn test_snapshot_restore_prunes_expired_and_unknown() {
+        let tracker = RateLimitTracker::new();
+        let backoff_steps = vec![60, 300];
+        let quota_body = r#"{"error":{"details":[{"reason":"QUOTA_EXHAUSTED"}]}}"#;
+        tracker.parse_from_error("acc1", 429, None, quota_body, Some("gemini-3-pro".to_string()), &backoff_steps);
+        tracker.parse_from_error("acc2", 429, Some("30"), "", None, &backoff_steps);
+        // 已过期的记录不应被导出
+        tracker.set_lockout_until("acc3", SystemTime::now() - Duration::from_secs(10), RateLimitReason::Unknown, None);
+
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot.limits.len(), 2);
+        let json = serde_json::to_string(&snapshot).unwrap();
+
+        let restored = RateLimitTracker::new();
+        let count = restored.restore(serde_json::from_str(&json).unwrap(), |id| id != "acc2");
+        assert_eq!(count, 1);
+        assert!(restored.is_rate_limited("acc1", Some("gemini-3-pro")));
+        assert!(!restored.is_rate_limited("acc2", None));
+
+        // 失败计数也应恢复: 下一次 QuotaExhausted 从第 2 阶梯开始
+        let info = restored.parse_from_error("acc1", 429, None, quota_body, None, &backoff_steps);
+        assert_eq!(info.unwrap().retry_after_sec, 300);
+    }
 }
@@ -0,0 +1,579 @@
+// 请求重放与结果对比
+//
+// 根据 request_logs 中保存的请求体重建原始客户端请求，经本地回环地址重新走完整代理链路
+// (鉴权、模型映射、账号调度、上游调用)。重放结果作为新日志保存，并通过 replay_of 关联原始日志。
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use crate::proxy::monitor::ProxyRequestLog;
+
+/// 重放请求标记头，值为本进程登记的重放 ID
+pub const REPLAY_HEADER: &str = "x-antigravity-replay";
+
+/// 重放请求整体超时
+const REPLAY_TIMEOUT_SECS: u64 = 600;
+/// 响应读取完毕后等待日志落定的时间
+const REPLAY_LOG_WAIT_SECS: u64 = 30;
+/// 行级 diff 的最大计算规模 (行数乘积)，超出时退化为整体替换
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// 重放时可覆盖的参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ReplayOverrides {
+    /// 替换请求模型
+    pub model: Option<String>,
+    /// 固定使用的账号邮箱
+    pub account: Option<String>,
+    /// 思考预算 (0 表示关闭思考)
+    pub thinking_budget: Option<u32>,
+}
+
+/// 重放请求在代理链路中的上下文
+#[derive(Debug, Clone)]
+pub struct ReplayContext {
+    /// 同时作为重放日志的 ID
+    pub replay_id: String,
+    pub original_id: String,
+    pub account: Option<String>,
+}
+
+struct PendingReplay {
+    context: ReplayContext,
+    tx: Option<oneshot::Sender<ProxyRequestLog>>,
+}
+
+fn pending() -> &'static DashMap<String, PendingReplay> {
+    static PENDING: OnceLock<DashMap<String, PendingReplay>> = OnceLock::new();
+    PENDING.get_or_init(DashMap::new)
+}
+
+/// 发起方退出 (完成、超时或出错) 时注销登记
+struct Registration(String);
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        pending().remove(&self.0);
+    }
+}
+
+/// 识别重放请求；只接受本进程登记过的重放 ID，外部请求携带该头不会生效
+pub fn claim(headers: &axum::http::HeaderMap) -> Option<ReplayContext> {
+    let replay_id = headers.get(REPLAY_HEADER)?.to_str().ok()?;
+    pending().get(replay_id).map(|entry| entry.context.clone())
+}
+
+/// 重放请求的日志完成后回传给发起方
+pub fn complete(log: &ProxyRequestLog) {
+    if log.replay_of.is_none() {
+        return;
+    }
+    if let Some(tx) = pending().get_mut(&log.id).and_then(|mut entry| entry.tx.take()) {
+        let _ = tx.send(log.clone());
+    }
+}
+
+tokio::task_local! {
+    static REPLAY_CONTEXT: ReplayContext;
+}
+
+/// 在重放上下文中执行请求处理
+pub async fn scope<F: std::future::Future>(ctx: ReplayContext, fut: F) -> F::Output {
+    REPLAY_CONTEXT.scope(ctx, fut).await
+}
+
+/// 当前请求是否为重放 (重放请求不读写响应缓存)
+pub fn is_replay() -> bool {
+    REPLAY_CONTEXT.try_with(|_| ()).is_ok()
+}
+
+/// 重放时指定的账号
+pub fn pinned_account() -> Option<String> {
+    REPLAY_CONTEXT
+        .try_with(|ctx| ctx.account.clone())
+        .ok()
+        .flatten()
+}
+
+/// 重建后的客户端请求
+#[derive(Debug, Clone)]
+pub struct ReplayRequest {
+    pub method: String,
+    pub path: String,
+    pub body: Value,
+}
+
+/// 从日志重建原始请求并应用覆盖参数
+pub fn build_replay_request(
+    log: &ProxyRequestLog,
+    overrides: &ReplayOverrides,
+) -> Result<ReplayRequest, String> {
+    let raw = log
+        .request_body
+        .as_deref()
+        .filter(|b| !b.is_empty())
+        .ok_or_else(|| "Log has no request body to replay".to_string())?;
+    let mut body: Value = serde_json::from_str(raw)
+        .map_err(|e| format!("Request body is not valid JSON: {}", e))?;
+    if !body.is_object() {
+        return Err("Request body is not a JSON object".to_string());
+    }
+
+    let mut path = log.url.clone();
+    let is_gemini = path.contains("/v1beta/models/");
+
+    if let Some(model) = overrides.model.as_deref().filter(|m| !m.is_empty()) {
+        if is_gemini {
+            // Gemini 原生协议的模型位于路径中: /v1beta/models/{model}:{method}
+            let start = path.find("/v1beta/models/").unwrap_or(0) + "/v1beta/models/".len();
+            let end = path[start..]
+                .find([':', '?'])
+                .map(|i| start + i)
+                .unwrap_or(path.len());
+            path.replace_range(start..end, model);
+        } else {
+            body["model"] = json!(model);
+        }
+    }
+
+    if let Some(budget) = overrides.thinking_budget {
+        if is_gemini {
+            let generation_config = body
+                .as_object_mut()
+                .map(|obj| obj.entry("generationConfig").or_insert_with(|| json!({})))
+                .filter(|v| v.is_object())
+                .ok_or_else(|| "generationConfig is not an object".to_string())?;
+            generation_config["thinkingConfig"] = json!({
+                "includeThoughts": budget > 0,
+                "thinkingBudget": budget,
+            });
+        } else if budget == 0 {
+            body["thinking"] = json!({ "type": "disabled" });
+        } else {
+            body["thinking"] = json!({ "type": "enabled", "budget_tokens": budget });
+        }
+    }
+
+    Ok(ReplayRequest {
+        method: log.method.clone(),
+        path,
+        body,
+    })
+}
+
+/// 重放结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayResult {
+    pub original: ProxyRequestLog,
+    pub replay: ProxyRequestLog,
+    pub diff: ReplayDiff,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayDiff {
+    pub status: NumberDiff,
+    pub mapped_model: FieldDiff,
+    pub account: FieldDiff,
+    pub output: TextDiff,
+    pub input_tokens: NumberDiff,
+    pub output_tokens: NumberDiff,
+    pub duration_ms: NumberDiff,
+    pub ttft_ms: NumberDiff,
+    pub upstream_ms: NumberDiff,
+    pub attempts: NumberDiff,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct NumberDiff {
+    pub original: Option<i64>,
+    pub replay: Option<i64>,
+    /// replay - original
+    pub delta: Option<i64>,
+}
+
+impl NumberDiff {
+    fn new(original: Option<i64>, replay: Option<i64>) -> Self {
+        let delta = original.zip(replay).map(|(a, b)| b - a);
+        Self {
+            original,
+            replay,
+            delta,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FieldDiff {
+    pub original: Option<String>,
+    pub replay: Option<String>,
+    pub changed: bool,
+}
+
+impl FieldDiff {
+    fn new(original: &Option<String>, replay: &Option<String>) -> Self {
+        Self {
+            original: original.clone(),
+            replay: replay.clone(),
+            changed: original != replay,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Equal,
+    Delete,
+    Insert,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DiffLine {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct TextDiff {
+    pub identical: bool,
+    /// 相同行占比 (0~1)
+    pub similarity: f64,
+    pub lines: Vec<DiffLine>,
+}
+
+/// 对比原始请求与重放请求
+pub fn diff_logs(original: &ProxyRequestLog, replay: &ProxyRequestLog) -> ReplayDiff {
+    let latency = |log: &ProxyRequestLog, f: fn(&crate::proxy::request_timing::LatencyBreakdown) -> Option<i64>| {
+        log.latency.as_ref().and_then(f)
+    };
+    let output = |log: &ProxyRequestLog| {
+        log.response_body
+            .as_deref()
+            .map(crate::modules::log_text::extract_response_text)
+            .unwrap_or_default()
+    };
+
+    ReplayDiff {
+        status: NumberDiff::new(Some(original.status as i64), Some(replay.status as i64)),
+        mapped_model: FieldDiff::new(&original.mapped_model, &replay.mapped_model),
+        account: FieldDiff::new(&original.account_email, &replay.account_email),
+        output: diff_text(&output(original), &output(replay)),
+        input_tokens: NumberDiff::new(
+            original.input_tokens.map(i64::from),
+            replay.input_tokens.map(i64::from),
+        ),
+        output_tokens: NumberDiff::new(
+            original.output_tokens.map(i64::from),
+            replay.output_tokens.map(i64::from),
+        ),
+        duration_ms: NumberDiff::new(Some(original.duration as i64), Some(replay.duration as i64)),
+        ttft_ms: NumberDiff::new(
+            latency(original, |l| l.ttft_ms.map(|v| v as i64)),
+            latency(replay, |l| l.ttft_ms.map(|v| v as i64)),
+        ),
+        upstream_ms: NumberDiff::new(
+            latency(original, |l| Some(l.upstream_ms as i64)),
+            latency(replay, |l| Some(l.upstream_ms as i64)),
+        ),
+        attempts: NumberDiff::new(
+            latency(original, |l| Some(l.attempts as i64)),
+            latency(replay, |l| Some(l.attempts as i64)),
+        ),
+    }
+}
+
+/// 行级 diff (LCS)，先去除公共前后缀以缩小计算规模
+pub fn diff_text(original: &str, replay: &str) -> TextDiff {
+    let a: Vec<&str> = original.lines().collect();
+    let b: Vec<&str> = replay.lines().collect();
+
+    let prefix = a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+
+    let line = |op: DiffOp, text: &str| DiffLine {
+        op,
+        text: text.to_string(),
+    };
+    let mut lines: Vec<DiffLine> = a[..prefix].iter().map(|t| line(DiffOp::Equal, t)).collect();
+
+    if a_mid.len().saturating_mul(b_mid.len()) > MAX_DIFF_CELLS {
+        lines.extend(a_mid.iter().map(|t| line(DiffOp::Delete, t)));
+        lines.extend(b_mid.iter().map(|t| line(DiffOp::Insert, t)));
+    } else {
+        // lcs[i][j] = a_mid[i..] 与 b_mid[j..] 的最长公共子序列长度
+        let (n, m) = (a_mid.len(), b_mid.len());
+        let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if a_mid[i] == b_mid[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if a_mid[i] == b_mid[j] {
+                lines.push(line(DiffOp::Equal, a_mid[i]));
+                i += 1;
+                j += 1;
+            } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+                lines.push(line(DiffOp::Delete, a_mid[i]));
+                i += 1;
+            } else {
+                lines.push(line(DiffOp::Insert, b_mid[j]));
+                j += 1;
+            }
+        }
+        lines.extend(a_mid[i..].iter().map(|t| line(DiffOp::Delete, t)));
+        lines.extend(b_mid[j..].iter().map(|t| line(DiffOp::Insert, t)));
+    }
+    lines.extend(a[a.len() - suffix..].iter().map(|t| line(DiffOp::Equal, t)));
+
+    let equal = lines.iter().filter(|l| l.op == DiffOp::Equal).count();
+    let total = a.len() + b.len();
+    let similarity = if total == 0 {
+        1.0
+    } else {
+        ((2 * equal) as f64 / total as f64 * 1000.0).round() / 1000.0
+    };
+
+    TextDiff {
+        identical: original == replay,
+        similarity,
+        lines,
+    }
+}
+
+/// 重放指定日志并返回与原始结果的对比
+pub async fn replay_log(
+    port: u16,
+    api_key: &str,
+    log_id: &str,
+    overrides: ReplayOverrides,
+) -> Result<ReplayResult, String> {
+    let id = log_id.to_string();
+    let original = tokio::task::spawn_blocking(move || crate::modules::proxy_db::get_log_detail(&id))
+        .await
+        .map_err(|e| e.to_string())??;
+    let request = build_replay_request(&original, &overrides)?;
+
+    let replay_id = uuid::Uuid::new_v4().to_string();
+    let (tx, rx) = oneshot::channel();
+    pending().insert(
+        replay_id.clone(),
+        PendingReplay {
+            context: ReplayContext {
+                replay_id: replay_id.clone(),
+                original_id: original.id.clone(),
+                account: overrides.account.clone().filter(|a| !a.is_empty()),
+            },
+            tx: Some(tx),
+        },
+    );
+    let _registration = Registration(replay_id.clone());
+
+    tracing::info!(
+        "[Replay] Replaying log {} as {} ({} {})",
+        original.id,
+        replay_id,
+        request.method,
+        request.path
+    );
+
+    // 本地回环请求不走系统代理 (主端口仅 HTTPS 时不校验自签名证书)
+    let client = crate::proxy::tls::local_client_builder()
+        .timeout(Duration::from_secs(REPLAY_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| format!("Failed to build replay client: {}", e))?;
+    let method = rquest::Method::from_bytes(request.method.as_bytes())
+        .map_err(|e| format!("Invalid method {}: {}", request.method, e))?;
+    let response = client
+        .request(method, format!("{}{}", crate::proxy::tls::local_base_url(port), request.path))
+        .header("Content-Type", "application/json")
+        .header("Authorization", format!("Bearer {}", api_key))
+        .header("x-api-key", api_key)
+        .header(REPLAY_HEADER, replay_id.as_str())
+        .json(&request.body)
+        .send()
+        .await
+        .map_err(|e| format!("Replay request failed: {}", e))?;
+    // 读完响应体：流式响应在结束后才生成日志
+    let _ = response.bytes().await;
+
+    let replay = tokio::time::timeout(Duration::from_secs(REPLAY_LOG_WAIT_SECS), rx)
+        .await
+        .map_err(|_| "Timed out waiting for replay log".to_string())?
+        .map_err(|_| "Replay was not recorded by the monitor".to_string())?;
+
+    let diff = diff_logs(&original, &replay);
+    Ok(ReplayResult {
+        original,
+        replay,
+        diff,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(url: &str, body: &str) -> ProxyRequestLog {
+        ProxyRequestLog {
+            id: "orig".to_string(),
+            timestamp: 0,
+            method: "POST".to_string(),
+            url: url.to_string(),
+            status: 200,
+            duration: 1000,
+            model: None,
+            mapped_model: None,
+            account_email: None,
+            client_ip: None,
+            error: None,
+            request_body: Some(body.to_string()),
+            response_body: None,
+            input_tokens: None,
+            output_tokens: None,
+            protocol: None,
+            username: None,
+            latency: None,
+            replay_of: None,
+            cached_tokens: None,
+            thinking_tokens: None,
+            cost: None,
+            provider: None,
+        }
+    }
+
+    #[test]
+    fn test_build_replay_request_applies_overrides() {
+        let overrides = ReplayOverrides {
+            model: Some("gemini-3-flash".to_string()),
+            account: None,
+            thinking_budget: Some(2048),
+        };
+
+        let openai = build_replay_request(
+            &log("/v1/chat/completions", r#"{"model":"gpt-4o","messages":[]}"#),
+            &overrides,
+        )
+        .unwrap();
+        assert_eq!(openai.path, "/v1/chat/completions");
+        assert_eq!(openai.body["model"], "gemini-3-flash");
+        assert_eq!(openai.body["thinking"]["budget_tokens"], 2048);
+
+        let gemini = build_replay_request(
+            &log(
+                "/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse",
+                r#"{"contents":[]}"#,
+            ),
+            &overrides,
+        )
+        .unwrap();
+        assert_eq!(
+            gemini.path,
+            "/v1beta/models/gemini-3-flash:streamGenerateContent?alt=sse"
+        );
+        assert!(gemini.body.get("model").is_none());
+        assert_eq!(
+            gemini.body["generationConfig"]["thinkingConfig"]["thinkingBudget"],
+            2048
+        );
+
+        let mut binary = log("/v1/messages", "[Binary Request Data]");
+        assert!(build_replay_request(&binary, &overrides).is_err());
+        binary.request_body = None;
+        assert!(build_replay_request(&binary, &overrides).is_err());
+    }
+
+    #[test]
+    fn test_diff_text_lines() {
+        let diff = diff_text("a\nb\nc\nd", "a\nx\nc\nd\ne");
+        let ops: Vec<(DiffOp, &str)> = diff.lines.iter().map(|l| (l.op, l.text.as_str())).collect();
+        assert_eq!(
+            ops,
+            vec![
+                (DiffOp::Equal, "a"),
+                (DiffOp::Delete, "b"),
+                (DiffOp::Insert, "x"),
+                (DiffOp::Equal, "c"),
+                (DiffOp::Equal, "d"),
+                (DiffOp::Insert, "e"),
+            ]
+        );
+        assert!(!diff.identical);
+        assert_eq!(diff.similarity, 0.667);
+
+        let same = diff_text("same", "same");
+        assert!(same.identical);
+        assert_eq!(same.similarity, 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_replay_over_https_only_listener() {
+        use axum::{http::HeaderMap, routing::post, Router};
+        use hyper_util::rt::TokioIo;
+        use hyper_util::service::TowerToHyperService;
+
+        crate::modules::proxy_db::init_db().unwrap();
+        let mut original = log("/v1/chat/completions", r#"{"model":"gemini-3-flash","messages":[]}"#);
+        original.id = uuid::Uuid::new_v4().to_string();
+        crate::modules::proxy_db::save_log(&original).unwrap();
+
+        // 模拟代理链路: 识别重放请求并回传重放日志
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(|headers: HeaderMap| async move {
+                if let Some(ctx) = claim(&headers) {
+                    let mut replay = log("/v1/chat/completions", "{}");
+                    replay.id = ctx.replay_id;
+                    replay.replay_of = Some(ctx.original_id);
+                    complete(&replay);
+                }
+                "ok"
+            }),
+        );
+
+        // 主端口仅 HTTPS
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let acceptor = crate::proxy::tls::test_acceptor();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (acceptor, app) = (acceptor.clone(), app.clone());
+                tokio::spawn(async move {
+                    if let Ok(tls_stream) = acceptor.accept(stream).await {
+                        let _ = hyper::server::conn::http1::Builder::new()
+                            .serve_connection(TokioIo::new(tls_stream), TowerToHyperService::new(app))
+                            .await;
+                    }
+                });
+            }
+        });
+        crate::proxy::tls::set_listener_state(port, None);
+        assert_eq!(crate::proxy::tls::local_base_url(port), format!("https://127.0.0.1:{}", port));
+
+        let result = replay_log(port, "sk-test", &original.id, ReplayOverrides::default()).await;
+        crate::proxy::tls::clear_listener_state();
+
+        let result = result.unwrap();
+        assert_eq!(result.replay.replay_of.as_deref(), Some(original.id.as_str()));
+        assert_eq!(crate::proxy::tls::local_base_url(port), format!("http://127.0.0.1:{}", port));
+    }
+}
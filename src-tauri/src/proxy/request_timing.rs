@@ -0,0 +1,209 @@
+// 请求延迟分解
+//
+// monitor 中间件为每个请求建立计时上下文，Token 获取、重试退避与上游调用
+// 分别累加各自耗时，最终写入 request_logs 并通过 Server-Timing 头返回给客户端。
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 单个请求的延迟分解 (毫秒)
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct LatencyBreakdown {
+    /// 重试退避累计等待
+    #[serde(default)]
+    pub queue_wait_ms: u64,
+    /// 账号 / Token 获取累计耗时
+    #[serde(default)]
+    pub token_wait_ms: u64,
+    /// 上游请求累计耗时 (至响应头，含端点降级)
+    #[serde(default)]
+    pub upstream_ms: u64,
+    /// 首 Token 时间 (自请求进入起)
+    #[serde(default)]
+    pub ttft_ms: Option<u64>,
+    /// 上游调度次数 (重试次数 = attempts - 1)
+    #[serde(default)]
+    pub attempts: u32,
+    /// 按顺序尝试过的账号
+    #[serde(default)]
+    pub accounts_tried: Vec<String>,
+    /// 最后一次使用的上游端点
+    #[serde(default)]
+    pub upstream_endpoint: Option<String>,
+    /// 输出速度 (tokens/s)
+    #[serde(default)]
+    pub output_tokens_per_sec: Option<f64>,
+}
+
+impl LatencyBreakdown {
+    pub fn retries(&self) -> u32 {
+        self.attempts.saturating_sub(1)
+    }
+
+    /// 账号轮换次数
+    pub fn rotations(&self) -> u32 {
+        (self.accounts_tried.len() as u32).saturating_sub(1)
+    }
+
+    /// 根据生成耗时计算输出速度；流式响应扣除首 Token 之前的等待
+    pub fn finalize(&mut self, total_ms: u64, output_tokens: Option<u32>) {
+        let generation_ms = match self.ttft_ms {
+            Some(ttft) if total_ms > ttft => total_ms - ttft,
+            _ => total_ms,
+        };
+        self.output_tokens_per_sec = match output_tokens {
+            Some(tokens) if tokens > 0 && generation_ms > 0 => {
+                let tps = tokens as f64 * 1000.0 / generation_ms as f64;
+                Some((tps * 100.0).round() / 100.0)
+            }
+            _ => None,
+        };
+    }
+
+    /// 生成 Server-Timing 头 (https://www.w3.org/TR/server-timing/)
+    pub fn server_timing(&self, total_ms: u64) -> String {
+        let mut metrics = vec![
+            format!("queue;dur={}", self.queue_wait_ms),
+            format!("token;dur={}", self.token_wait_ms),
+            format!("upstream;dur={}", self.upstream_ms),
+        ];
+        if let Some(ttft) = self.ttft_ms {
+            metrics.push(format!("ttft;dur={}", ttft));
+        }
+        metrics.push(format!("total;dur={}", total_ms));
+        if self.attempts > 1 {
+            metrics.push(format!("retry;desc=\"{}\"", self.retries()));
+        }
+        if let Some(endpoint) = &self.upstream_endpoint {
+            metrics.push(format!("endpoint;desc=\"{}\"", endpoint));
+        }
+        metrics.join(", ")
+    }
+}
+
+/// 请求级计时上下文
+#[derive(Debug, Default)]
+pub struct RequestTiming {
+    inner: Mutex<LatencyBreakdown>,
+}
+
+impl RequestTiming {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn snapshot(&self) -> LatencyBreakdown {
+        self.inner.lock().clone()
+    }
+
+    /// 是否发生过上游调度 (未调度的请求不输出延迟分解)
+    pub fn is_empty(&self) -> bool {
+        let inner = self.inner.lock();
+        inner.attempts == 0 && inner.token_wait_ms == 0
+    }
+}
+
+tokio::task_local! {
+    static REQUEST_TIMING: Arc<RequestTiming>;
+}
+
+/// 在计时上下文中执行请求处理
+pub async fn scope<F: std::future::Future>(timing: Arc<RequestTiming>, fut: F) -> F::Output {
+    REQUEST_TIMING.scope(timing, fut).await
+}
+
+fn with_current(f: impl FnOnce(&mut LatencyBreakdown)) {
+    let _ = REQUEST_TIMING.try_with(|timing| f(&mut timing.inner.lock()));
+}
+
+/// 记录一次 Token 获取
+pub fn record_token_wait(elapsed: Duration, account: Option<&str>) {
+    with_current(|latency| {
+        latency.token_wait_ms += elapsed.as_millis() as u64;
+        if let Some(account) = account {
+            if !latency.accounts_tried.iter().any(|a| a == account) {
+                latency.accounts_tried.push(account.to_string());
+            }
+        }
+    });
+}
+
+/// 记录一次重试退避
+pub fn record_queue_wait(elapsed: Duration) {
+    with_current(|latency| latency.queue_wait_ms += elapsed.as_millis() as u64);
+}
+
+/// 记录一次上游端点尝试；`new_dispatch` 为 true 表示新一轮调度 (而非端点降级)
+pub fn record_upstream_attempt(endpoint: &str, elapsed: Duration, new_dispatch: bool) {
+    with_current(|latency| {
+        latency.upstream_ms += elapsed.as_millis() as u64;
+        latency.upstream_endpoint = Some(endpoint.to_string());
+        if new_dispatch {
+            latency.attempts += 1;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_records_only_inside_scope() {
+        record_token_wait(Duration::from_millis(50), Some("a@example.com"));
+
+        let timing = RequestTiming::new();
+        scope(timing.clone(), async {
+            record_token_wait(Duration::from_millis(10), Some("a@example.com"));
+            record_upstream_attempt("daily", Duration::from_millis(100), true);
+            record_queue_wait(Duration::from_millis(200));
+            record_token_wait(Duration::from_millis(5), Some("b@example.com"));
+            record_upstream_attempt("daily", Duration::from_millis(30), true);
+            record_upstream_attempt("prod", Duration::from_millis(40), false);
+        })
+        .await;
+
+        let latency = timing.snapshot();
+        assert_eq!(latency.token_wait_ms, 15);
+        assert_eq!(latency.queue_wait_ms, 200);
+        assert_eq!(latency.upstream_ms, 170);
+        assert_eq!(latency.attempts, 2);
+        assert_eq!(latency.retries(), 1);
+        assert_eq!(latency.accounts_tried, vec!["a@example.com", "b@example.com"]);
+        assert_eq!(latency.rotations(), 1);
+        assert_eq!(latency.upstream_endpoint.as_deref(), Some("prod"));
+    }
+
+    #[test]
+    fn test_finalize_excludes_ttft_from_generation() {
+        let mut latency = LatencyBreakdown {
+            ttft_ms: Some(1000),
+            ..Default::default()
+        };
+        latency.finalize(3000, Some(100));
+        assert_eq!(latency.output_tokens_per_sec, Some(50.0));
+
+        latency.finalize(3000, Some(0));
+        assert_eq!(latency.output_tokens_per_sec, None);
+    }
+
+    #[test]
+    fn test_server_timing_header() {
+        let latency = LatencyBreakdown {
+            queue_wait_ms: 0,
+            token_wait_ms: 12,
+            upstream_ms: 340,
+            ttft_ms: Some(360),
+            attempts: 2,
+            accounts_tried: vec!["a@example.com".to_string()],
+            upstream_endpoint: Some("daily".to_string()),
+            output_tokens_per_sec: None,
+        };
+        assert_eq!(
+            latency.server_timing(365),
+            "queue;dur=0, token;dur=12, upstream;dur=340, ttft;dur=360, total;dur=365, retry;desc=\"1\", endpoint;desc=\"daily\""
+        );
+    }
+}
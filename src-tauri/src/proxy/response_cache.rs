@@ -0,0 +1,559 @@
+// 精确匹配响应缓存
+//
+// 对确定性请求 (temperature = 0) 按映射后的上游请求做规范化哈希，
+// 命中时直接回放缓存的 JSON / SSE 响应，避免重复消耗配额。
+// 缓存作用域由 response_cache 中间件通过 task-local 上下文建立，仅对已开启缓存的调用方生效。
+
+use bytes::Bytes;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::modules::response_cache_db::{self, CacheDbStats, CachedResponse};
+
+/// 缓存状态响应头
+pub const CACHE_STATUS_HEADER: &str = "x-cache";
+
+/// 参与哈希的请求字段 (不含 project / requestId 等每次请求都会变化的字段)
+const KEY_REQUEST_FIELDS: [&str; 6] = [
+    "contents",
+    "systemInstruction",
+    "tools",
+    "toolConfig",
+    "generationConfig",
+    "safetySettings",
+];
+
+/// 响应缓存配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseCacheConfig {
+    /// 是否启用 (默认关闭)
+    #[serde(default)]
+    pub enabled: bool,
+    /// 缓存有效期 (秒)
+    #[serde(default = "default_ttl_secs")]
+    pub ttl_secs: u64,
+    /// 最多保留的条目数，超出后淘汰最旧的条目
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+    /// 单条响应的最大字节数，超出则不缓存
+    #[serde(default = "default_max_entry_bytes")]
+    pub max_entry_bytes: usize,
+    /// 仅缓存 temperature = 0 的请求
+    #[serde(default = "default_true")]
+    pub require_deterministic: bool,
+    /// 带有副作用的工具 (内置工具名或函数名)，请求中包含任一工具时不缓存
+    #[serde(default = "default_excluded_tools")]
+    pub excluded_tools: Vec<String>,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: default_ttl_secs(),
+            max_entries: default_max_entries(),
+            max_entry_bytes: default_max_entry_bytes(),
+            require_deterministic: true,
+            excluded_tools: default_excluded_tools(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_max_entries() -> usize {
+    1000
+}
+
+fn default_max_entry_bytes() -> usize {
+    2 * 1024 * 1024
+}
+
+fn default_excluded_tools() -> Vec<String> {
+    vec![
+        "googleSearch".to_string(),
+        "googleSearchRetrieval".to_string(),
+        "codeExecution".to_string(),
+        "urlContext".to_string(),
+    ]
+}
+
+static GLOBAL_RESPONSE_CACHE_CONFIG: OnceLock<RwLock<ResponseCacheConfig>> = OnceLock::new();
+
+/// 获取当前响应缓存配置
+pub fn get_response_cache_config() -> ResponseCacheConfig {
+    GLOBAL_RESPONSE_CACHE_CONFIG
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+/// 更新全局响应缓存配置
+pub fn update_response_cache_config(config: ResponseCacheConfig) {
+    if let Some(lock) = GLOBAL_RESPONSE_CACHE_CONFIG.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = config.clone();
+        }
+    } else {
+        let _ = GLOBAL_RESPONSE_CACHE_CONFIG.set(RwLock::new(config.clone()));
+    }
+    tracing::info!(
+        "[ResponseCache] Config updated: enabled={}, ttl={}s, max_entries={}",
+        config.enabled,
+        config.ttl_secs,
+        config.max_entries
+    );
+}
+
+/// 按 key 排序输出 JSON，保证字段顺序不同的等价请求得到相同哈希
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(&map[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+fn is_deterministic(request: &Value) -> bool {
+    request
+        .get("generationConfig")
+        .and_then(|g| g.get("temperature"))
+        .and_then(|t| t.as_f64())
+        .map(|t| t == 0.0)
+        .unwrap_or(false)
+}
+
+/// 返回请求中命中排除列表的工具名
+fn find_excluded_tool(request: &Value, excluded: &[String]) -> Option<String> {
+    let is_excluded = |name: &str| excluded.iter().any(|e| e.eq_ignore_ascii_case(name));
+    let tools = request.get("tools")?.as_array()?;
+    for tool in tools {
+        let Some(obj) = tool.as_object() else { continue };
+        for (kind, decl) in obj {
+            if is_excluded(kind) {
+                return Some(kind.clone());
+            }
+            if kind == "functionDeclarations" {
+                let names = decl
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|f| f.get("name").and_then(|n| n.as_str()));
+                for name in names {
+                    if is_excluded(name) {
+                        return Some(name.to_string());
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// 计算上游请求的缓存键，不可缓存时返回 None
+pub fn cache_key(
+    method: &str,
+    query_string: Option<&str>,
+    body: &Value,
+    config: &ResponseCacheConfig,
+) -> Option<String> {
+    let request = body.get("request")?;
+    if config.require_deterministic && !is_deterministic(request) {
+        return None;
+    }
+    if let Some(tool) = find_excluded_tool(request, &config.excluded_tools) {
+        tracing::debug!("[ResponseCache] Bypass: request uses side-effect tool {}", tool);
+        return None;
+    }
+
+    let mut normalized = serde_json::Map::new();
+    normalized.insert("method".to_string(), Value::String(method.to_string()));
+    normalized.insert(
+        "query".to_string(),
+        Value::String(query_string.unwrap_or_default().to_string()),
+    );
+    normalized.insert(
+        "model".to_string(),
+        body.get("model").cloned().unwrap_or(Value::Null),
+    );
+    for field in KEY_REQUEST_FIELDS {
+        if let Some(v) = request.get(field) {
+            normalized.insert(field.to_string(), v.clone());
+        }
+    }
+
+    let mut canonical = String::new();
+    write_canonical(&Value::Object(normalized), &mut canonical);
+    Some(format!("{:x}", Sha256::digest(canonical.as_bytes())))
+}
+
+/// 单次请求的缓存结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CacheStatus {
+    /// 命中，附带缓存年龄 (秒)
+    Hit { age_secs: u64 },
+    Miss,
+    Bypass,
+}
+
+impl CacheStatus {
+    pub fn as_header_value(&self) -> &'static str {
+        match self {
+            CacheStatus::Hit { .. } => "HIT",
+            CacheStatus::Miss => "MISS",
+            CacheStatus::Bypass => "BYPASS",
+        }
+    }
+}
+
+/// 响应缓存统计
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponseCacheStats {
+    pub enabled: bool,
+    pub hits: u64,
+    pub misses: u64,
+    pub bypasses: u64,
+    pub stores: u64,
+    pub hit_rate: f64,
+    #[serde(flatten)]
+    pub storage: CacheDbStats,
+}
+
+/// 响应缓存 (计数器 + SQLite 存储)
+#[derive(Default)]
+pub struct ResponseCache {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    bypasses: AtomicU64,
+    stores: AtomicU64,
+}
+
+impl ResponseCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn lookup(&self, key: &str) -> Option<CachedResponse> {
+        let owned = key.to_string();
+        let result = tokio::task::spawn_blocking(move || response_cache_db::lookup(&owned))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r);
+        match result {
+            Ok(Some(cached)) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(cached)
+            }
+            Ok(None) => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            Err(e) => {
+                tracing::warn!("[ResponseCache] Lookup failed: {}", e);
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    pub fn record_bypass(&self) {
+        self.bypasses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 边转发边缓冲上游响应体，仅在响应体完整结束、内容完整且未超限时写入缓存
+    pub fn tee_body<S, E>(
+        self: &Arc<Self>,
+        key: String,
+        model: Option<String>,
+        status: u16,
+        content_type: String,
+        config: &ResponseCacheConfig,
+        stream: S,
+    ) -> impl futures::Stream<Item = Result<Bytes, E>> + Send + 'static
+    where
+        S: futures::Stream<Item = Result<Bytes, E>> + Send + 'static,
+        E: Send + 'static,
+    {
+        let buffer = Arc::new(parking_lot::Mutex::new(Some(Vec::<u8>::new())));
+        let max_bytes = config.max_entry_bytes;
+        let ttl_secs = config.ttl_secs;
+        let max_entries = config.max_entries;
+
+        let collector = buffer.clone();
+        let forwarded = stream.map(move |chunk| {
+            let mut guard = collector.lock();
+            match &chunk {
+                Ok(bytes) => {
+                    let oversized = guard
+                        .as_ref()
+                        .map(|buf| buf.len() + bytes.len() > max_bytes)
+                        .unwrap_or(false);
+                    if oversized {
+                        *guard = None;
+                    } else if let Some(buf) = guard.as_mut() {
+                        buf.extend_from_slice(bytes);
+                    }
+                }
+                // 流中断的响应不完整，放弃缓存
+                Err(_) => *guard = None,
+            }
+            chunk
+        });
+
+        let cache = self.clone();
+        let finalize = futures::stream::once(async move {
+            let Some(body) = buffer.lock().take() else {
+                return;
+            };
+            // 空响应或缺少结束事件的截断响应会被客户端重试，不能缓存
+            if !is_complete_body(&content_type, &body) {
+                tracing::debug!("[ResponseCache] Skip storing incomplete response ({} bytes)", body.len());
+                return;
+            }
+            let result = tokio::task::spawn_blocking(move || {
+                response_cache_db::store(
+                    &key,
+                    model.as_deref(),
+                    status,
+                    &content_type,
+                    &body,
+                    ttl_secs,
+                    max_entries,
+                )
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r);
+            match result {
+                Ok(()) => {
+                    cache.stores.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(e) => tracing::warn!("[ResponseCache] Store failed: {}", e),
+            }
+        })
+        .filter_map(|_| async { None });
+
+        forwarded.chain(finalize)
+    }
+
+    pub fn stats(&self) -> ResponseCacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let storage = response_cache_db::get_stats().unwrap_or_else(|e| {
+            tracing::warn!("[ResponseCache] Failed to read storage stats: {}", e);
+            CacheDbStats::default()
+        });
+        ResponseCacheStats {
+            enabled: get_response_cache_config().enabled,
+            hits,
+            misses,
+            bypasses: self.bypasses.load(Ordering::Relaxed),
+            stores: self.stores.load(Ordering::Relaxed),
+            hit_rate: if hits + misses > 0 {
+                hits as f64 / (hits + misses) as f64
+            } else {
+                0.0
+            },
+            storage,
+        }
+    }
+
+    /// 清空缓存与计数器，返回删除的条目数
+    pub fn clear(&self) -> Result<usize, String> {
+        let removed = response_cache_db::clear()?;
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+        self.bypasses.store(0, Ordering::Relaxed);
+        self.stores.store(0, Ordering::Relaxed);
+        Ok(removed)
+    }
+}
+
+/// 响应体是否完整: 流式响应需包含结束事件 (finishReason / message_stop / [DONE])，非流式响应需为有效 JSON
+fn is_complete_body(content_type: &str, body: &[u8]) -> bool {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return false;
+    }
+    if content_type.contains("text/event-stream") {
+        let text = String::from_utf8_lossy(body);
+        return ["\"finishReason\"", "message_stop", "[DONE]"]
+            .iter()
+            .any(|marker| text.contains(marker));
+    }
+    serde_json::from_slice::<Value>(body).is_ok()
+}
+
+/// 将缓存的 SSE 响应按事件切分，回放时保持逐事件下发
+pub fn replay_chunks(cached: &CachedResponse) -> Vec<Bytes> {
+    if !cached.content_type.contains("text/event-stream") {
+        return vec![Bytes::from(cached.body.clone())];
+    }
+    let mut chunks = Vec::new();
+    let mut rest = cached.body.as_slice();
+    while let Some(pos) = rest.windows(2).position(|w| w == b"\n\n") {
+        chunks.push(Bytes::copy_from_slice(&rest[..pos + 2]));
+        rest = &rest[pos + 2..];
+    }
+    if !rest.is_empty() {
+        chunks.push(Bytes::copy_from_slice(rest));
+    }
+    chunks
+}
+
+/// 中间件建立的请求级缓存上下文
+#[derive(Clone)]
+pub struct CacheContext {
+    pub cache: Arc<ResponseCache>,
+    pub config: ResponseCacheConfig,
+    status: Arc<parking_lot::Mutex<Option<CacheStatus>>>,
+}
+
+impl CacheContext {
+    pub fn new(cache: Arc<ResponseCache>, config: ResponseCacheConfig) -> Self {
+        Self {
+            cache,
+            config,
+            status: Arc::new(parking_lot::Mutex::new(None)),
+        }
+    }
+
+    /// 记录本次请求的缓存结果 (多次上游调用时以最后一次为准)
+    pub fn set_status(&self, status: CacheStatus) {
+        *self.status.lock() = Some(status);
+    }
+
+    pub fn status(&self) -> Option<CacheStatus> {
+        *self.status.lock()
+    }
+}
+
+tokio::task_local! {
+    static CACHE_CONTEXT: CacheContext;
+}
+
+/// 在缓存上下文中执行请求处理
+pub async fn scope<F: std::future::Future>(ctx: CacheContext, fut: F) -> F::Output {
+    CACHE_CONTEXT.scope(ctx, fut).await
+}
+
+/// 获取当前请求的缓存上下文 (未开启缓存时为 None)
+pub fn current_context() -> Option<CacheContext> {
+    CACHE_CONTEXT.try_with(|ctx| ctx.clone()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn body(temperature: f64, tools: Value) -> Value {
+        json!({
+            "project": "p-1",
+            "requestId": "agent-123",
+            "model": "gemini-2.5-flash",
+            "request": {
+                "contents": [{"role": "user", "parts": [{"text": "hi"}]}],
+                "generationConfig": {"temperature": temperature, "maxOutputTokens": 64},
+                "tools": tools,
+                "sessionId": "s-1"
+            }
+        })
+    }
+
+    #[test]
+    fn test_key_ignores_volatile_fields_and_key_order() {
+        let config = ResponseCacheConfig::default();
+        let a = body(0.0, json!([]));
+        let mut b = body(0.0, json!([]));
+        b["project"] = json!("p-2");
+        b["requestId"] = json!("agent-456");
+        b["request"]["sessionId"] = json!("s-2");
+        b["request"]["generationConfig"] = json!({"maxOutputTokens": 64, "temperature": 0.0});
+
+        let ka = cache_key("generateContent", None, &a, &config).unwrap();
+        let kb = cache_key("generateContent", None, &b, &config).unwrap();
+        assert_eq!(ka, kb);
+
+        // 流式与非流式不共用缓存
+        let ks = cache_key("streamGenerateContent", Some("alt=sse"), &a, &config).unwrap();
+        assert_ne!(ka, ks);
+    }
+
+    #[test]
+    fn test_non_deterministic_and_side_effect_requests_bypass() {
+        let config = ResponseCacheConfig::default();
+        assert!(cache_key("generateContent", None, &body(0.7, json!([])), &config).is_none());
+        assert!(cache_key("generateContent", None, &body(0.0, json!([{"googleSearch": {}}])), &config).is_none());
+
+        let mut custom = config.clone();
+        custom.excluded_tools.push("send_email".to_string());
+        let tools = json!([{"functionDeclarations": [{"name": "send_email"}]}]);
+        assert!(cache_key("generateContent", None, &body(0.0, tools.clone()), &config).is_some());
+        assert!(cache_key("generateContent", None, &body(0.0, tools), &custom).is_none());
+
+        custom.require_deterministic = false;
+        assert!(cache_key("generateContent", None, &body(0.7, json!([])), &custom).is_some());
+    }
+
+    #[test]
+    fn test_incomplete_bodies_are_not_cacheable() {
+        let sse = "text/event-stream";
+        assert!(!is_complete_body(sse, b""));
+        assert!(!is_complete_body(sse, b"data: {\"candidates\":[{\"content\":{}}]}\n\n"));
+        assert!(is_complete_body(sse, b"data: {\"candidates\":[{\"finishReason\":\"STOP\"}]}\n\n"));
+        assert!(is_complete_body(sse, b"event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n"));
+        assert!(is_complete_body(sse, b"data: {\"choices\":[]}\n\ndata: [DONE]\n\n"));
+
+        let json = "application/json";
+        assert!(!is_complete_body(json, b"  "));
+        assert!(!is_complete_body(json, b"{\"choices\":[{\"message\""));
+        assert!(is_complete_body(json, b"{\"choices\":[]}"));
+    }
+
+    #[test]
+    fn test_replay_chunks_split_sse_events() {
+        let cached = CachedResponse {
+            status: 200,
+            content_type: "text/event-stream".to_string(),
+            body: b"data: 1\n\ndata: 2\n\ndata: 3".to_vec(),
+            created_at: 0,
+        };
+        let chunks = replay_chunks(&cached);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(&chunks[0][..], b"data: 1\n\n");
+        assert_eq!(&chunks[2][..], b"data: 3");
+    }
+}
@@ -0,0 +1,145 @@
+// 调度运行时状态持久化
+//
+// 限流锁定、连续失败计数、会话粘性绑定和账号健康分原本只存在于内存 DashMap 中，
+// 重启反代 (修改配置 / 容器重新调度) 后会全部丢失。这里将它们定期快照到数据目录，
+// 并在启动时恢复 (过期条目会被裁剪)。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::proxy::rate_limit::RateLimitSnapshot;
+
+/// 快照文件名 (位于数据目录下)
+pub const RUNTIME_STATE_FILE: &str = "proxy_runtime_state.json";
+
+/// 当前快照格式版本
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// 会话绑定的最大恢复年龄 (秒)
+/// 会话映射本身没有时间戳，快照过旧时上游的 Prompt Cache 早已失效，恢复绑定没有意义
+pub const SESSION_BINDING_MAX_AGE_SECS: i64 = 3600;
+
+/// 持久化的调度运行时状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuntimeStateSnapshot {
+    #[serde(default)]
+    pub version: u32,
+    /// 快照保存时间 (Unix 秒)
+    #[serde(default)]
+    pub saved_at: i64,
+    #[serde(default)]
+    pub rate_limits: RateLimitSnapshot,
+    /// SessionID -> AccountID
+    #[serde(default)]
+    pub session_accounts: HashMap<String, String>,
+    /// AccountID -> 健康分
+    #[serde(default)]
+    pub health_scores: HashMap<String, f32>,
+}
+
+impl RuntimeStateSnapshot {
+    pub fn new(
+        rate_limits: RateLimitSnapshot,
+        session_accounts: HashMap<String, String>,
+        health_scores: HashMap<String, f32>,
+    ) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            saved_at: chrono::Utc::now().timestamp(),
+            rate_limits,
+            session_accounts,
+            health_scores,
+        }
+    }
+
+    /// 快照中的会话绑定是否仍然值得恢复
+    pub fn sessions_fresh(&self, now: i64) -> bool {
+        now - self.saved_at <= SESSION_BINDING_MAX_AGE_SECS
+    }
+}
+
+/// 读取快照，文件不存在或损坏时返回 None
+pub fn load_snapshot(data_dir: &Path) -> Option<RuntimeStateSnapshot> {
+    let path = data_dir.join(RUNTIME_STATE_FILE);
+    if !path.exists() {
+        return None;
+    }
+
+    let content = match std::fs::read_to_string(&path) {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!("[RuntimeState] Failed to read {:?}: {}", path, e);
+            return None;
+        }
+    };
+
+    match serde_json::from_str::<RuntimeStateSnapshot>(&content) {
+        Ok(snapshot) if snapshot.version <= SNAPSHOT_VERSION => Some(snapshot),
+        Ok(snapshot) => {
+            tracing::warn!(
+                "[RuntimeState] Ignoring snapshot with unsupported version {}",
+                snapshot.version
+            );
+            None
+        }
+        Err(e) => {
+            tracing::warn!("[RuntimeState] Ignoring corrupted snapshot {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// 原子写入快照 (临时文件 + rename)，避免进程中途退出留下半截文件
+pub fn save_snapshot(data_dir: &Path, snapshot: &RuntimeStateSnapshot) -> Result<(), String> {
+    let path = data_dir.join(RUNTIME_STATE_FILE);
+    let temp_path = data_dir.join(format!("{}.tmp.{}", RUNTIME_STATE_FILE, uuid::Uuid::new_v4()));
+
+    let content = serde_json::to_string(snapshot)
+        .map_err(|e| format!("failed_to_serialize_runtime_state: {}", e))?;
+
+    if let Err(e) = std::fs::write(&temp_path, content) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(format!("failed_to_write_runtime_state: {}", e));
+    }
+
+    if let Err(e) = std::fs::rename(&temp_path, &path) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(format!("failed_to_replace_runtime_state: {}", e));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("antigravity-runtime-state-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        assert!(load_snapshot(&dir).is_none());
+
+        let mut sessions = HashMap::new();
+        sessions.insert("sid1".to_string(), "acc1".to_string());
+        let mut health = HashMap::new();
+        health.insert("acc1".to_string(), 0.6);
+        let snapshot = RuntimeStateSnapshot::new(RateLimitSnapshot::default(), sessions, health);
+        save_snapshot(&dir, &snapshot).unwrap();
+
+        let loaded = load_snapshot(&dir).unwrap();
+        assert_eq!(loaded.version, SNAPSHOT_VERSION);
+        assert_eq!(loaded.session_accounts.get("sid1").map(String::as_str), Some("acc1"));
+        assert_eq!(loaded.health_scores.get("acc1"), Some(&0.6));
+        assert!(loaded.sessions_fresh(loaded.saved_at + 10));
+        assert!(!loaded.sessions_fresh(loaded.saved_at + SESSION_BINDING_MAX_AGE_SECS + 1));
+
+        // 损坏的文件应被忽略而不是 panic
+        std::fs::write(dir.join(RUNTIME_STATE_FILE), "{not json").unwrap();
+        assert!(load_snapshot(&dir).is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
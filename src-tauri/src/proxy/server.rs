@@ -3,7 +3,7 @@ use crate::modules::{account, config, logger, migration, proxy_db, security_db,
 use crate::proxy::TokenManager;
 use axum::{
     extract::{DefaultBodyLimit, Path, Query, State},
-    http::{HeaderMap, StatusCode},
+    http::{header, HeaderMap, StatusCode},
     response::{Html, IntoResponse, Json, Response},
     routing::{any, delete, get, post},
     Router,
@@ -115,6 +115,9 @@ pub struct AppState {
     pub port: u16,                     // [NEW] 本地监听端口 (v4.0.8 修复)
     pub proxy_pool_state: Arc<tokio::sync::RwLock<crate::proxy::config::ProxyPoolConfig>>, // [FIX Web Mode]
     pub proxy_pool_manager: Arc<crate::proxy::proxy_pool::ProxyPoolManager>, // [FIX Web Mode]
+    pub idempotency: Arc<crate::proxy::idempotency::IdempotencyStore>, // [NEW] 幂等请求去重
+    pub response_cache: Arc<crate::proxy::response_cache::ResponseCache>, // [NEW] 精确匹配响应缓存
+    pub metrics_listener: Arc<crate::proxy::metrics::MetricsListener>, // [NEW] /metrics 独立监听
 }
 
 // 为 AppState 实现 FromRef，以便中间件提取 security 状态
@@ -227,6 +230,8 @@ pub struct AxumServer {
     pub token_manager: Arc<TokenManager>, // [NEW] 暴露出 TokenManager 供反代服务复用
     pub proxy_pool_state: Arc<tokio::sync::RwLock<crate::proxy::config::ProxyPoolConfig>>, // [NEW] 代理池配置状态
     pub proxy_pool_manager: Arc<crate::proxy::proxy_pool::ProxyPoolManager>, // [NEW] 暴露代理池管理器供命令调用
+    pub response_cache: Arc<crate::proxy::response_cache::ResponseCache>, // [NEW] 响应缓存统计与清理
+    metrics_listener: Arc<crate::proxy::metrics::MetricsListener>, // [NEW] /metrics 独立监听
 }
 
 impl AxumServer {
@@ -283,6 +288,28 @@ impl AxumServer {
         tracing::info!("User-Agent 配置已热更新: {:?}", config.user_agent_override);
     }
 
+    /// 上游端点健康跟踪器
+    pub fn upstream_endpoint_health(
+        &self,
+    ) -> Arc<crate::proxy::upstream::endpoint_health::EndpointHealthTracker> {
+        self.upstream.endpoint_health()
+    }
+
+    /// 更新上游端点健康跟踪配置
+    pub async fn update_upstream_endpoints(&self, config: &crate::proxy::config::ProxyConfig) {
+        self.upstream
+            .endpoint_health()
+            .update_config(config.upstream_endpoints.clone());
+        tracing::info!("上游端点配置已热更新");
+    }
+
+    /// 按当前配置启动 / 切换 / 关闭 /metrics 独立监听
+    pub async fn update_metrics_listener(&self) {
+        self.metrics_listener
+            .apply(&crate::proxy::metrics::get_metrics_config())
+            .await;
+    }
+
     pub async fn set_running(&self, running: bool) {
         let mut r = self.is_running.write().await;
         *r = running;
@@ -296,7 +323,8 @@ impl AxumServer {
         token_manager: Arc<TokenManager>,
         custom_mapping: std::collections::HashMap<String, String>,
         _request_timeout: u64,
-        upstream_proxy: crate::proxy::config::UpstreamProxyConfig,
+        // 上游代理与端点健康跟踪配置
+        proxy_config: &crate::proxy::config::ProxyConfig,
         user_agent_override: Option<String>,
         security_config: crate::proxy::ProxySecurityConfig,
         zai_config: crate::proxy::ZaiConfig,
@@ -308,6 +336,7 @@ impl AxumServer {
         cloudflared_state: Arc<crate::commands::cloudflared::CloudflaredState>,
         proxy_pool_config: crate::proxy::config::ProxyPoolConfig, // [NEW]
     ) -> Result<(Self, tokio::task::JoinHandle<()>), String> {
+        let upstream_proxy = proxy_config.upstream_proxy.clone();
         let custom_mapping_state = Arc::new(tokio::sync::RwLock::new(custom_mapping));
         let proxy_state = Arc::new(tokio::sync::RwLock::new(upstream_proxy.clone()));
         let proxy_pool_state = Arc::new(tokio::sync::RwLock::new(proxy_pool_config));
@@ -340,6 +369,9 @@ impl AxumServer {
                 if user_agent_override.is_some() {
                     u.set_user_agent_override(user_agent_override).await;
                 }
+                // [NEW] 端点健康跟踪配置 + 后台探测
+                u.endpoint_health().update_config(proxy_config.upstream_endpoints.clone());
+                u.start_endpoint_probe_loop();
                 u
             },
             zai: zai_state.clone(),
@@ -359,13 +391,22 @@ impl AxumServer {
             port,
             proxy_pool_state: proxy_pool_state.clone(),
             proxy_pool_manager: proxy_pool_manager.clone(),
+            idempotency: Arc::new(crate::proxy::idempotency::IdempotencyStore::new()),
+            response_cache: Arc::new(crate::proxy::response_cache::ResponseCache::new()),
+            metrics_listener: Arc::new(crate::proxy::metrics::MetricsListener::new()),
         };
+        state.metrics_listener.set_app(
+            Router::new()
+                .route("/metrics", get(dedicated_metrics_handler))
+                .with_state(state.clone()),
+        );
 
         // 构建路由 - 使用新架构的 handlers！
         use crate::proxy::handlers;
         use crate::proxy::middleware::{
-            admin_auth_middleware, auth_middleware, cors_layer, ip_filter_middleware,
-            monitor_middleware, service_status_middleware,
+            admin_audit_middleware, admin_auth_middleware, auth_middleware, cors_layer, idempotency_middleware,
+            admin_ip_filter_middleware, ip_filter_middleware, monitor_middleware, response_cache_middleware,
+            service_status_middleware, trace_context_middleware,
         };
 
         // 1. 构建主 AI 代理路由 (遵循 auth_mode 配置)
@@ -435,13 +476,24 @@ impl AxumServer {
             .route("/v1/api/event_logging", post(silent_ok_handler))
             // 应用 AI 服务特定的层
             // 注意：Axum layer 执行顺序是从下往上（洋葱模型）
-            // 请求: ip_filter -> auth -> monitor -> handler
-            // 响应: handler -> monitor -> auth -> ip_filter
+            // 请求: trace_context -> ip_filter -> auth -> idempotency -> monitor -> response_cache -> handler
+            // 响应: handler -> response_cache -> monitor -> idempotency -> auth -> ip_filter -> trace_context
+            // trace_context 位于最外层，整个请求 (含鉴权) 都归属同一条链路
             // monitor 需要在 auth 之后执行才能获取 UserTokenIdentity
+            // idempotency 位于 monitor 之外，重复请求直接回放，不会重复计入日志与 Token 统计
+            // response_cache 位于 monitor 之内，缓存命中仍会记录日志
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                response_cache_middleware,
+            ))
             .layer(axum::middleware::from_fn_with_state(
                 state.clone(),
                 monitor_middleware,
             ))
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                idempotency_middleware,
+            ))
             .layer(axum::middleware::from_fn_with_state(
                 state.clone(),
                 auth_middleware,
@@ -449,7 +501,8 @@ impl AxumServer {
             .layer(axum::middleware::from_fn_with_state(
                 state.clone(),
                 ip_filter_middleware,
-            ));
+            ))
+            .layer(axum::middleware::from_fn(trace_context_middleware));
 
         // 2. 构建管理 API (强制鉴权)
         let admin_routes = Router::new()
@@ -518,10 +571,21 @@ impl AxumServer {
             .route("/proxy/status", get(admin_get_proxy_status))
             .route("/proxy/pool/config", get(admin_get_proxy_pool_config))
             .route("/proxy/pool/bindings", get(admin_get_all_account_bindings))
+            .route("/proxy/pool/stats", get(admin_get_proxy_pool_stats))
             .route("/proxy/pool/bind", post(admin_bind_account_proxy))
             .route("/proxy/pool/unbind", post(admin_unbind_account_proxy))
             .route("/proxy/pool/binding/:accountId", get(admin_get_account_proxy_binding))
             .route("/proxy/health-check/trigger", post(admin_trigger_proxy_health_check))
+            .route(
+                "/proxy/upstream/endpoints",
+                get(admin_get_upstream_endpoints).delete(admin_reset_upstream_endpoints),
+            )
+            .route(
+                "/proxy/upstream/endpoints/pin",
+                post(admin_pin_upstream_endpoints).delete(admin_unpin_upstream_endpoints),
+            )
+            .route("/proxy/cache", delete(admin_clear_response_cache))
+            .route("/proxy/cache/stats", get(admin_get_response_cache_stats))
             .route("/proxy/start", post(admin_start_proxy_service))
             .route("/proxy/stop", post(admin_stop_proxy_service))
             .route("/proxy/mapping", post(admin_update_model_mapping))
@@ -569,7 +633,20 @@ impl AxumServer {
             .route("/logs", get(admin_get_proxy_logs_filtered))
             .route("/logs/count", get(admin_get_proxy_logs_count_filtered))
             .route("/logs/clear", post(admin_clear_proxy_logs))
+            .route("/logs/export", get(admin_export_proxy_logs))
+            .route("/logs/search", post(admin_search_proxy_logs))
             .route("/logs/:logId", get(admin_get_proxy_log_detail))
+            .route("/logs/:logId/replay", post(admin_replay_proxy_log))
+            .route("/logs/:logId/replays", get(admin_get_proxy_log_replays))
+            // 告警
+            .route("/alerts/test", post(admin_test_alert_channel))
+            .route(
+                "/alerts/history",
+                get(admin_get_alert_history).delete(admin_clear_alert_history),
+            )
+            // 实时事件流 (请求日志 / 账号状态 / 调试日志)
+            .route("/events", get(admin_event_stream_sse))
+            .route("/events/ws", get(admin_event_stream_ws))
             // Debug Console (Log Bridge)
             .route("/debug/enable", post(admin_enable_debug_console))
             .route("/debug/disable", post(admin_disable_debug_console))
@@ -602,6 +679,7 @@ impl AxumServer {
                 "/stats/token/account-trend/daily",
                 get(admin_get_token_stats_account_trend_daily),
             )
+            .route("/stats/token/cost", get(admin_get_token_cost_breakdown))
             .route("/accounts/bulk-delete", post(admin_delete_accounts))
             .route("/accounts/export", post(admin_export_accounts))
             .route("/accounts/reorder", post(admin_reorder_accounts))
@@ -642,6 +720,11 @@ impl AxumServer {
             .route("/security/logs", get(admin_get_ip_access_logs))
             .route("/security/logs/clear", post(admin_clear_ip_access_logs))
             .route("/security/stats", get(admin_get_ip_stats))
+            .route("/security/ban-events", get(admin_get_ip_ban_events))
+            .route("/security/dlp/stats", get(admin_get_dlp_stats))
+            .route("/security/dlp/clear", post(admin_clear_dlp_stats))
+            .route("/proxy/tls", get(admin_get_tls_status))
+            .route("/proxy/tls/reload", post(admin_reload_tls_certificate))
             .route("/security/token-stats", get(admin_get_ip_token_stats)) // For IP Token usage
             .route("/security/blacklist", get(admin_get_ip_blacklist).post(admin_add_ip_to_blacklist).delete(admin_remove_ip_from_blacklist))
             .route("/security/blacklist/clear", post(admin_clear_ip_blacklist))
@@ -650,6 +733,12 @@ impl AxumServer {
             .route("/security/whitelist/clear", post(admin_clear_ip_whitelist))
             .route("/security/whitelist/check", get(admin_check_ip_in_whitelist))
             .route("/security/config", get(admin_get_security_config).post(admin_update_security_config))
+            .route("/security/reencrypt", post(admin_reencrypt_secrets))
+            .route("/security/account-encryption", get(admin_get_account_encryption_status))
+            .route("/security/account-encryption/unlock", post(admin_unlock_account_encryption))
+            .route("/security/account-encryption/enable", post(admin_enable_account_encryption))
+            .route("/security/account-encryption/rotate", post(admin_rotate_account_encryption))
+            .route("/security/account-encryption/disable", post(admin_disable_account_encryption))
             // User Tokens
             .route("/user-tokens", get(admin_list_user_tokens).post(admin_create_user_token))
             .route("/user-tokens/summary", get(admin_get_user_token_summary))
@@ -657,10 +746,33 @@ impl AxumServer {
             .route("/user-tokens/:id", delete(admin_delete_user_token).patch(admin_update_user_token))
             // OAuth (Web) - Admin 接口
             .route("/auth/url", get(admin_prepare_oauth_url_web))
+            // Admin Users / Sessions
+            .route("/admin/login", post(admin_login))
+            .route("/admin/logout", post(admin_logout))
+            .route("/admin/me", get(admin_me))
+            .route("/admin/totp/setup", post(admin_totp_setup))
+            .route("/admin/totp/enable", post(admin_totp_enable))
+            .route("/admin/totp/disable", post(admin_totp_disable))
+            .route("/admin/users", get(admin_list_admin_users).post(admin_create_admin_user))
+            .route("/admin/users/:id", delete(admin_delete_admin_user).patch(admin_update_admin_user))
+            .route("/admin/users/:id/reset-totp", post(admin_reset_admin_user_totp))
+            // Audit Log
+            .route("/audit", get(admin_get_audit_logs))
+            .route("/audit/export", get(admin_export_audit_logs))
+            // 审计层 (位于鉴权层之内，可读取管理员身份)
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                admin_audit_middleware,
+            ))
             // 应用管理特定鉴权层 (强制校验)
             .layer(axum::middleware::from_fn_with_state(
                 state.clone(),
                 admin_auth_middleware,
+            ))
+            // 黑名单 / 自动封禁 IP 在鉴权之前拒绝，防止被封禁后继续暴力尝试管理登录
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                admin_ip_filter_middleware,
             ));
 
         // 3. 整合并应用全局层
@@ -671,9 +783,18 @@ impl AxumServer {
             .unwrap_or(100 * 1024 * 1024); // 默认 100MB
         tracing::info!("请求体大小限制: {} MB", max_body_size / 1024 / 1024);
 
+        // Prometheus 指标 (独立鉴权，见 metrics::is_authorized)，同样经过黑白名单与自动封禁检查
+        let metrics_routes = Router::new()
+            .route("/metrics", get(metrics_handler))
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                ip_filter_middleware,
+            ));
+
         let app = Router::new()
             .nest("/api", admin_routes)
             .merge(proxy_routes)
+            .merge(metrics_routes)
             // 公开路由 (无需鉴权)
             .route("/auth/callback", get(handle_oauth_callback))
             // 应用全局监控与状态层 (外层)
@@ -702,7 +823,37 @@ impl AxumServer {
             .await
             .map_err(|e| format!("地址 {} 绑定失败: {}", addr, e))?;
 
-        tracing::info!("反代服务器启动在 http://{}", addr);
+        // [NEW] HTTPS: 主端口仅 HTTPS，或主端口 HTTP + 独立 HTTPS 端口
+        use crate::proxy::tls::{self, ListenerMode};
+        let tls_mode = tls::listener_mode(&tls::get_tls_config(), port);
+        let tls_acceptor = match tls_mode {
+            ListenerMode::Http => None,
+            _ => Some(tls::acceptor().map_err(|e| format!("HTTPS 证书加载失败: {}", e))?),
+        };
+        let (primary_acceptor, https_listener) = match tls_mode {
+            ListenerMode::Http => {
+                tls::clear_listener_state();
+                tracing::info!("反代服务器启动在 http://{}", addr);
+                (None, None)
+            }
+            ListenerMode::Https => {
+                tls::set_listener_state(port, None);
+                tracing::info!("反代服务器启动在 https://{}", addr);
+                (tls_acceptor, None)
+            }
+            ListenerMode::Both { https_port } => {
+                let https_addr = format!("{}:{}", host, https_port);
+                let https_listener = tokio::net::TcpListener::bind(&https_addr)
+                    .await
+                    .map_err(|e| format!("HTTPS 地址 {} 绑定失败: {}", https_addr, e))?;
+                tls::set_listener_state(https_port, Some(port));
+                tracing::info!("反代服务器启动在 http://{} 与 https://{}", addr, https_addr);
+                (None, tls_acceptor.map(|acceptor| (https_listener, acceptor)))
+            }
+        };
+        if !matches!(tls_mode, ListenerMode::Http) {
+            tls::spawn_watcher();
+        }
 
         // 创建关闭通道
         let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
@@ -721,46 +872,35 @@ impl AxumServer {
             token_manager: token_manager.clone(),
             proxy_pool_state,
             proxy_pool_manager,
+            response_cache: state.response_cache.clone(),
+            metrics_listener: state.metrics_listener.clone(),
         };
 
         // 在新任务中启动服务器
         let handle = tokio::spawn(async move {
-            use hyper::server::conn::http1;
-            use hyper_util::rt::TokioIo;
-            use hyper_util::service::TowerToHyperService;
-
             loop {
                 tokio::select! {
                     res = listener.accept() => {
                         match res {
                             Ok((stream, remote_addr)) => {
-                                let io = TokioIo::new(stream);
-                                
-                                // 注入 ConnectInfo (用于获取真实 IP)
-                                use tower::ServiceExt;
-                                use hyper::body::Incoming;
-                                let app_with_info = app.clone().map_request(move |mut req: axum::http::Request<Incoming>| {
-                                    req.extensions_mut().insert(axum::extract::ConnectInfo(remote_addr));
-                                    req
-                                });
-
-                                let service = TowerToHyperService::new(app_with_info);
-
-                                tokio::task::spawn(async move {
-                                    if let Err(err) = http1::Builder::new()
-                                        .serve_connection(io, service)
-                                        .with_upgrades() // 支持 WebSocket (如果以后需要)
-                                        .await
-                                    {
-                                        debug!("连接处理结束或出错: {:?}", err);
-                                    }
-                                });
+                                accept_connection(app.clone(), stream, remote_addr, primary_acceptor.clone());
                             }
                             Err(e) => {
                                 error!("接收连接失败: {:?}", e);
                             }
                         }
                     }
+                    res = accept_optional(https_listener.as_ref().map(|(l, _)| l)) => {
+                        match res {
+                            Ok((stream, remote_addr)) => {
+                                let acceptor = https_listener.as_ref().map(|(_, a)| a.clone());
+                                accept_connection(app.clone(), stream, remote_addr, acceptor);
+                            }
+                            Err(e) => {
+                                error!("接收 HTTPS 连接失败: {:?}", e);
+                            }
+                        }
+                    }
                     _ = &mut shutdown_rx => {
                         tracing::info!("反代服务器停止监听");
                         break;
@@ -775,7 +915,9 @@ impl AxumServer {
     /// 停止服务器
     pub fn stop(&self) {
         let tx_mutex = self.shutdown_tx.clone();
+        let metrics_listener = self.metrics_listener.clone();
         tokio::spawn(async move {
+            metrics_listener.shutdown().await;
             let mut lock = tx_mutex.lock().await;
             if let Some(tx) = lock.take() {
                 let _ = tx.send(());
@@ -785,6 +927,61 @@ impl AxumServer {
     }
 }
 
+/// 可选监听器的 accept；未配置时永远挂起
+async fn accept_optional(
+    listener: Option<&tokio::net::TcpListener>,
+) -> std::io::Result<(tokio::net::TcpStream, std::net::SocketAddr)> {
+    match listener {
+        Some(listener) => listener.accept().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// 处理单个连接: 可选 TLS 握手后交给 hyper
+fn accept_connection(
+    app: Router,
+    stream: tokio::net::TcpStream,
+    remote_addr: std::net::SocketAddr,
+    acceptor: Option<tokio_rustls::TlsAcceptor>,
+) {
+    tokio::task::spawn(async move {
+        match acceptor {
+            Some(acceptor) => match tokio::time::timeout(crate::proxy::tls::HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(tls_stream)) => serve_connection(app, tls_stream, remote_addr).await,
+                Ok(Err(e)) => debug!("TLS 握手失败 ({}): {}", remote_addr, e),
+                Err(_) => debug!("TLS 握手超时 ({})", remote_addr),
+            },
+            None => serve_connection(app, stream, remote_addr).await,
+        }
+    });
+}
+
+async fn serve_connection<I>(app: Router, io: I, remote_addr: std::net::SocketAddr)
+where
+    I: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+{
+    use hyper::body::Incoming;
+    use hyper::server::conn::http1;
+    use hyper_util::rt::TokioIo;
+    use hyper_util::service::TowerToHyperService;
+    use tower::ServiceExt;
+
+    // 注入 ConnectInfo (用于获取真实 IP)
+    let app_with_info = app.map_request(move |mut req: axum::http::Request<Incoming>| {
+        req.extensions_mut().insert(axum::extract::ConnectInfo(remote_addr));
+        req
+    });
+    let service = TowerToHyperService::new(app_with_info);
+
+    if let Err(err) = http1::Builder::new()
+        .serve_connection(TokioIo::new(io), service)
+        .with_upgrades() // 支持 WebSocket (如果以后需要)
+        .await
+    {
+        debug!("连接处理结束或出错: {:?}", err);
+    }
+}
+
 // ===== API 处理器 (旧代码已移除，由 src/proxy/handlers/* 接管) =====
 
 /// 健康检查处理器
@@ -796,6 +993,92 @@ async fn health_check_handler() -> Response {
     .into_response()
 }
 
+/// Prometheus 指标 (主端口)
+async fn metrics_handler(
+    State(state): State<AppState>,
+    connect_info: Option<axum::extract::ConnectInfo<std::net::SocketAddr>>,
+    headers: HeaderMap,
+) -> Response {
+    let ip = connect_info.map(|info| info.0.ip());
+    render_metrics(&state, &headers, ip, false).await
+}
+
+/// Prometheus 指标 (独立监听地址)
+async fn dedicated_metrics_handler(
+    State(state): State<AppState>,
+    connect_info: Option<axum::extract::ConnectInfo<std::net::SocketAddr>>,
+    headers: HeaderMap,
+) -> Response {
+    let ip = connect_info.map(|info| info.0.ip());
+    render_metrics(&state, &headers, ip, true).await
+}
+
+async fn render_metrics(
+    state: &AppState,
+    headers: &HeaderMap,
+    peer_ip: Option<std::net::IpAddr>,
+    dedicated: bool,
+) -> Response {
+    let config = crate::proxy::metrics::get_metrics_config();
+    if !config.enabled {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.strip_prefix("Bearer ").unwrap_or(s))
+        .or_else(|| headers.get("x-api-key").and_then(|h| h.to_str().ok()));
+    if !crate::proxy::metrics::is_authorized(provided, &config, dedicated) {
+        // 携带了错误凭证的抓取计入自动封禁
+        if provided.is_some() {
+            let monitor_config = state.security.read().await.security_monitor.clone();
+            if let Some(ip) =
+                crate::proxy::middleware::client_ip::resolve_ip(headers, peer_ip, &monitor_config)
+            {
+                crate::proxy::auto_ban::record(
+                    &monitor_config.auto_ban,
+                    &ip.to_string(),
+                    crate::proxy::auto_ban::Offense::AuthFailure,
+                );
+            }
+        }
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    let disabled_accounts = tokio::task::spawn_blocking(|| {
+        crate::modules::account::list_accounts()
+            .map(|accounts| {
+                accounts
+                    .iter()
+                    .filter(|a| a.disabled || a.proxy_disabled)
+                    .count()
+            })
+            .unwrap_or(0)
+    })
+    .await
+    .unwrap_or(0);
+
+    let snapshot = crate::proxy::metrics::ScrapeSnapshot {
+        accounts: state.token_manager.pool_metrics().await,
+        disabled_accounts,
+        proxy_connections: state
+            .proxy_pool_manager
+            .get_connection_stats()
+            .await
+            .into_iter()
+            .map(|s| (s.name, s.active_connections, s.total_connections))
+            .collect(),
+        idempotency_entries: state.idempotency.len(),
+    };
+
+    (
+        [(header::CONTENT_TYPE, crate::proxy::metrics::CONTENT_TYPE)],
+        crate::proxy::metrics::global().render(&snapshot),
+    )
+        .into_response()
+}
+
 /// 静默成功处理器 (用于拦截遥测日志等)
 async fn silent_ok_handler() -> Response {
     StatusCode::OK.into_response()
@@ -1302,6 +1585,11 @@ async fn admin_save_config(
     Json(payload): Json<SaveConfigWrapper>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let new_config = payload.config;
+    // 拒绝无效的自定义脱敏规则
+    crate::proxy::redaction::validate_config(&new_config.proxy.redaction)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    crate::proxy::dlp::validate_config(&new_config.proxy.dlp)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
     // 1. 持久化
     config::save_app_config(&new_config).map_err(|e| {
         (
@@ -1354,6 +1642,34 @@ async fn admin_save_config(
         *pool = new_config.clone().proxy.proxy_pool;
     }
 
+    // 更新幂等去重配置
+    crate::proxy::idempotency::update_idempotency_config(new_config.proxy.idempotency.clone());
+    // 更新响应缓存配置
+    crate::proxy::response_cache::update_response_cache_config(new_config.proxy.response_cache.clone());
+    // 更新指标导出配置
+    crate::proxy::metrics::update_metrics_config(new_config.proxy.metrics.clone());
+    state.metrics_listener.apply(&new_config.proxy.metrics).await;
+    // 更新 HTTPS 配置 (证书热重载，开关与端口需重启生效)
+    crate::proxy::tls::update_tls_config(new_config.proxy.tls.clone());
+    // 更新链路追踪导出配置
+    if let Err(e) = crate::proxy::telemetry::apply_telemetry_config(&new_config.proxy.telemetry) {
+        tracing::error!("[Telemetry] {}", e);
+    }
+    // 更新告警配置
+    crate::proxy::alerts::update_alerts_config(new_config.proxy.alerts.clone());
+    // 更新脱敏配置
+    crate::proxy::redaction::update_redaction_config(new_config.proxy.redaction.clone());
+    // 更新 DLP 规则
+    crate::proxy::dlp::update_dlp_config(new_config.proxy.dlp.clone());
+    // 更新价格表
+    crate::proxy::pricing::update_pricing_config(new_config.proxy.pricing.clone());
+
+    // 更新上游端点配置
+    state
+        .upstream
+        .endpoint_health()
+        .update_config(new_config.proxy.upstream_endpoints.clone());
+
     Ok(StatusCode::OK)
 }
 
@@ -1365,6 +1681,11 @@ async fn admin_get_proxy_pool_config(
     Ok(Json(config.clone()))
 }
 
+// [NEW] Get per-proxy live/total connection counters
+async fn admin_get_proxy_pool_stats(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.proxy_pool_manager.get_connection_stats().await)
+}
+
 // [FIX Web Mode] Get all account proxy bindings
 async fn admin_get_all_account_bindings(
     State(state): State<AppState>,
@@ -1446,7 +1767,7 @@ async fn admin_get_proxy_status(
     Ok(Json(serde_json::json!({
         "running": is_running,
         "port": state.port,
-        "base_url": format!("http://127.0.0.1:{}", state.port),
+        "base_url": crate::proxy::tls::local_base_url(state.port),
         "active_accounts": active_accounts,
     })))
 }
@@ -1466,6 +1787,7 @@ async fn admin_start_proxy_service(State(state): State<AppState>) -> impl IntoRe
     let mut running = state.is_running.write().await;
     *running = true;
     logger::log_info("[API] 反代服务功能已启用 (持久化已同步)");
+    crate::modules::audit::record("proxy.start", None, None);
     StatusCode::OK
 }
 
@@ -1479,6 +1801,7 @@ async fn admin_stop_proxy_service(State(state): State<AppState>) -> impl IntoRes
     let mut running = state.is_running.write().await;
     *running = false;
     logger::log_info("[API] 反代服务功能已禁用 (Axum 模式 / 持久化已同步)");
+    crate::modules::audit::record("proxy.stop", None, None);
     StatusCode::OK
 }
 
@@ -1522,6 +1845,92 @@ async fn admin_update_model_mapping(
     Ok(StatusCode::OK)
 }
 
+// [NEW] 上游端点健康状态
+async fn admin_get_upstream_endpoints(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.upstream.endpoint_health().report())
+}
+
+// [NEW] 清空端点统计与熔断状态
+async fn admin_reset_upstream_endpoints(State(state): State<AppState>) -> impl IntoResponse {
+    state.upstream.endpoint_health().reset();
+    logger::log_info("[API] 已重置上游端点健康统计");
+    StatusCode::OK
+}
+
+// [NEW] 响应缓存统计
+async fn admin_get_response_cache_stats(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.response_cache.stats())
+}
+
+// [NEW] 清空响应缓存
+async fn admin_clear_response_cache(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let removed = state.response_cache.clear().map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
+    logger::log_info(&format!("[API] 已清空响应缓存 ({} 条)", removed));
+    Ok(Json(serde_json::json!({ "removed": removed })))
+}
+
+#[derive(Deserialize)]
+struct PinUpstreamEndpointsRequest {
+    /// 端点名称 (sandbox / daily / prod) 或 URL，未列出的端点按默认顺序追加
+    order: Vec<String>,
+}
+
+/// 持久化端点固定顺序并热更新
+async fn save_pinned_endpoint_order(
+    state: &AppState,
+    order: Vec<String>,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let mut app_config = config::load_app_config().map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
+    app_config.proxy.upstream_endpoints.pinned_order = order;
+    config::save_app_config(&app_config).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
+    state
+        .upstream
+        .endpoint_health()
+        .update_config(app_config.proxy.upstream_endpoints);
+    Ok(())
+}
+
+// [NEW] 手动固定端点顺序
+async fn admin_pin_upstream_endpoints(
+    State(state): State<AppState>,
+    Json(payload): Json<PinUpstreamEndpointsRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let tracker = state.upstream.endpoint_health();
+    let order = tracker
+        .normalize_pinned_order(&payload.order)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+
+    save_pinned_endpoint_order(&state, order.clone()).await?;
+    logger::log_info(&format!("[API] 上游端点顺序已固定: {:?}", order));
+    Ok(Json(tracker.report()))
+}
+
+// [NEW] 取消固定，恢复自适应排序
+async fn admin_unpin_upstream_endpoints(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    save_pinned_endpoint_order(&state, Vec::new()).await?;
+    logger::log_info("[API] 已取消上游端点固定顺序");
+    Ok(Json(state.upstream.endpoint_health().report()))
+}
+
 async fn admin_generate_api_key() -> impl IntoResponse {
     let new_key = format!("sk-{}", uuid::Uuid::new_v4().to_string().replace("-", ""));
     Json(new_key)
@@ -1715,6 +2124,175 @@ async fn admin_get_proxy_log_detail(
     }
 }
 
+/// [NEW] 全文搜索日志
+async fn admin_test_alert_channel(
+    Json(channel): Json<crate::proxy::alerts::AlertChannel>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    crate::proxy::alerts::send_test(&channel)
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, Json(ErrorResponse { error: e })))?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+struct AlertHistoryQuery {
+    #[serde(default = "default_alert_history_limit")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+}
+
+fn default_alert_history_limit() -> usize {
+    100
+}
+
+async fn admin_get_alert_history(
+    Query(params): Query<AlertHistoryQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let res = tokio::task::spawn_blocking(move || {
+        crate::modules::alert_db::get_history(params.limit, params.offset)
+    })
+    .await;
+
+    match res {
+        Ok(Ok(history)) => Ok(Json(history)),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+async fn admin_clear_alert_history() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let removed = tokio::task::spawn_blocking(crate::modules::alert_db::clear_history)
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: e }),
+            )
+        })?;
+    logger::log_info(&format!("[API] 已清空告警投递记录 ({} 条)", removed));
+    Ok(Json(serde_json::json!({ "removed": removed })))
+}
+
+/// SSE 事件流：每条消息的 data 为 {"type": ..., "data": ...}
+async fn admin_event_stream_sse(
+    Query(filter): Query<crate::proxy::event_stream::EventStreamFilter>,
+) -> impl IntoResponse {
+    use axum::response::sse::{Event, KeepAlive, Sse};
+
+    let mut subscription = crate::proxy::event_stream::Subscription::new(filter);
+    let stream = async_stream::stream! {
+        while let Some(event) = subscription.next().await {
+            match Event::default().json_data(&event) {
+                Ok(sse_event) => yield Ok::<_, std::convert::Infallible>(sse_event),
+                Err(_) => continue,
+            }
+        }
+    };
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// WebSocket 事件流：服务端推送 JSON 文本帧，客户端可随时发送新的筛选条件 (JSON) 替换当前条件
+async fn admin_event_stream_ws(
+    ws: axum::extract::ws::WebSocketUpgrade,
+    Query(filter): Query<crate::proxy::event_stream::EventStreamFilter>,
+) -> Response {
+    use axum::extract::ws::Message;
+
+    ws.on_upgrade(move |mut socket| async move {
+        let mut subscription = crate::proxy::event_stream::Subscription::new(filter);
+        loop {
+            tokio::select! {
+                event = subscription.next() => {
+                    let Some(event) = event else { break };
+                    let Ok(text) = serde_json::to_string(&event) else { continue };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                incoming = socket.recv() => match incoming {
+                    Some(Ok(Message::Text(text))) => {
+                        if let Ok(filter) = serde_json::from_str(&text) {
+                            subscription.set_filter(filter);
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+    })
+}
+
+async fn admin_search_proxy_logs(
+    Json(query): Json<crate::modules::proxy_db::LogSearchQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let res =
+        tokio::task::spawn_blocking(move || crate::modules::proxy_db::search_logs(&query)).await;
+
+    match res {
+        Ok(Ok(result)) => Ok(Json(result)),
+        Ok(Err(e)) => Err((StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e }))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct ReplayLogRequest {
+    #[serde(default)]
+    overrides: crate::proxy::replay::ReplayOverrides,
+}
+
+/// [NEW] 重放日志中的请求并返回与原始结果的对比
+async fn admin_replay_proxy_log(
+    State(state): State<AppState>,
+    Path(log_id): Path<String>,
+    Json(payload): Json<ReplayLogRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let api_key = state.security.read().await.api_key.clone();
+    crate::proxy::replay::replay_log(state.port, &api_key, &log_id, payload.overrides)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))
+}
+
+async fn admin_get_proxy_log_replays(
+    Path(log_id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let res =
+        tokio::task::spawn_blocking(move || crate::modules::proxy_db::get_log_replays(&log_id))
+            .await;
+
+    match res {
+        Ok(Ok(logs)) => Ok(Json(logs)),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
 #[derive(Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 struct LogsFilterQuery {
@@ -1756,6 +2334,73 @@ async fn admin_get_proxy_logs_filtered(
     }
 }
 
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct LogsExportQuery {
+    #[serde(default)]
+    format: crate::modules::log_export::LogExportFormat,
+    #[serde(default)]
+    redact_bodies: bool,
+    #[serde(default)]
+    filter: String,
+    #[serde(default)]
+    errors_only: bool,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    model: Option<String>,
+    account: Option<String>,
+    user: Option<String>,
+    status: Option<u16>,
+}
+
+/// [NEW] 流式导出日志 (JSONL / CSV / HAR)
+async fn admin_export_proxy_logs(
+    State(state): State<AppState>,
+    Query(params): Query<LogsExportQuery>,
+) -> Response {
+    use crate::modules::log_export::{export_logs, ChannelWriter, LogExportOptions};
+
+    let format = params.format;
+    let filter = crate::modules::proxy_db::LogFilter {
+        filter: params.filter,
+        errors_only: params.errors_only,
+        start_time: params.start_time,
+        end_time: params.end_time,
+        model: params.model,
+        account: params.account,
+        user: params.user,
+        status: params.status,
+    };
+    let options = LogExportOptions {
+        format,
+        redact_bodies: params.redact_bodies,
+        base_url: crate::proxy::tls::local_base_url(state.port),
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<bytes::Bytes, std::io::Error>>(8);
+    tokio::task::spawn_blocking(move || {
+        let err_tx = tx.clone();
+        if let Err(e) = export_logs(ChannelWriter::new(tx), &filter, &options) {
+            tracing::warn!("[Logs] Export aborted: {}", e);
+            let _ = err_tx.blocking_send(Err(std::io::Error::other(e)));
+        }
+    });
+
+    let filename = format!(
+        "proxy-logs-{}.{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        format.extension()
+    );
+    Response::builder()
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        )
+        .body(axum::body::Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
 async fn admin_get_proxy_stats(
     State(state): State<AppState>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
@@ -1848,6 +2493,292 @@ async fn admin_update_user_token(
     Ok(StatusCode::OK)
 }
 
+// --- 管理员账号与会话 ---
+
+#[derive(Deserialize)]
+struct AdminLoginRequest {
+    #[serde(default)]
+    username: Option<String>,
+    password: String,
+    #[serde(default)]
+    totp_code: Option<String>,
+}
+
+/// 管理员登录: 用户名 + 密码 (+ TOTP)；尚未创建管理员时可仅使用旧版管理密码
+async fn admin_login(
+    State(state): State<AppState>,
+    connect_info: Option<axum::extract::ConnectInfo<std::net::SocketAddr>>,
+    headers: HeaderMap,
+    Json(payload): Json<AdminLoginRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    use crate::modules::admin_user_db::{self, LoginError};
+
+    let security = state.security.read().await.clone();
+    let client_ip = crate::proxy::middleware::client_ip::resolve_ip(
+        &headers,
+        connect_info.map(|info| info.0.ip()),
+        &security.security_monitor,
+    )
+    .map(|ip| ip.to_string());
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    let username = payload.username.as_deref().map(str::trim).unwrap_or_default().to_string();
+    // Argon2 校验与 SQLite 读写放到阻塞线程，避免登录请求占用异步工作线程
+    let result = {
+        let (username, security) = (username.clone(), security.clone());
+        tokio::task::spawn_blocking(move || {
+            if !username.is_empty() {
+                admin_user_db::authenticate(&username, &payload.password, payload.totp_code.as_deref())
+                    .map(Some)
+            } else if !admin_user_db::has_users()
+                && crate::proxy::middleware::auth::legacy_password_matches(&security, &payload.password)
+            {
+                Ok(None)
+            } else {
+                Err(LoginError::InvalidCredentials)
+            }
+        })
+        .await
+        .unwrap_or_else(|e| Err(LoginError::Internal(e.to_string())))
+    };
+
+    let login_target = if username.is_empty() { "legacy".to_string() } else { username.clone() };
+    let user = result.map_err(|e| {
+        crate::modules::audit::record(
+            "admin.login",
+            Some(login_target.clone()),
+            Some(serde_json::json!({ "error": e.code() })),
+        );
+        if matches!(e, LoginError::InvalidCredentials | LoginError::InvalidTotp) {
+            if let Some(ip) = &client_ip {
+                crate::proxy::auto_ban::record(
+                    &security.security_monitor.auto_ban,
+                    ip,
+                    crate::proxy::auto_ban::Offense::AuthFailure,
+                );
+            }
+            tracing::warn!("Admin login failed for '{}': {}", username, e.code());
+        }
+        let status = match &e {
+            LoginError::Internal(detail) => {
+                tracing::error!("[AdminAuth] Login failed with internal error: {}", detail);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            _ => StatusCode::UNAUTHORIZED,
+        };
+        (status, Json(ErrorResponse { error: e.code().to_string() }))
+    })?;
+
+    let session_ip = client_ip.clone();
+    let session = tokio::task::spawn_blocking(move || {
+        admin_user_db::create_session(user.as_ref(), session_ip.as_deref(), user_agent.as_deref())
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r)
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    tracing::info!(
+        "Admin '{}' ({}) logged in from {}",
+        session.identity.username,
+        session.identity.role.as_str(),
+        client_ip.as_deref().unwrap_or("unknown")
+    );
+    crate::modules::audit::record(
+        "admin.login",
+        Some(login_target),
+        Some(serde_json::json!({ "role": session.identity.role.as_str() })),
+    );
+    Ok(Json(session))
+}
+
+/// 注销当前会话
+async fn admin_logout(
+    axum::Extension(identity): axum::Extension<crate::modules::admin_user_db::AdminIdentity>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    if let Some(session_id) = identity.session_id.as_deref() {
+        crate::modules::admin_user_db::delete_session(session_id)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    }
+    crate::modules::audit::record("admin.logout", Some(identity.username.clone()), None);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Serialize)]
+struct AdminMeResponse {
+    #[serde(flatten)]
+    identity: crate::modules::admin_user_db::AdminIdentity,
+    /// 是否已启用多管理员 (否则为旧版管理密码登录)
+    admin_users_enabled: bool,
+}
+
+/// 当前登录身份
+async fn admin_me(
+    axum::Extension(identity): axum::Extension<crate::modules::admin_user_db::AdminIdentity>,
+) -> impl IntoResponse {
+    Json(AdminMeResponse {
+        identity,
+        admin_users_enabled: crate::modules::admin_user_db::has_users(),
+    })
+}
+
+fn require_admin_user(
+    identity: &crate::modules::admin_user_db::AdminIdentity,
+) -> Result<&str, (StatusCode, Json<ErrorResponse>)> {
+    identity.user_id.as_deref().ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Two-factor authentication requires an admin user account".to_string(),
+            }),
+        )
+    })
+}
+
+#[derive(Serialize)]
+struct TotpSetupResponse {
+    secret: String,
+    otpauth_url: String,
+}
+
+/// 开始绑定 TOTP (返回密钥与 otpauth 链接)
+async fn admin_totp_setup(
+    axum::Extension(identity): axum::Extension<crate::modules::admin_user_db::AdminIdentity>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let user_id = require_admin_user(&identity)?;
+    let (secret, otpauth_url) = crate::modules::admin_user_db::begin_totp_setup(user_id)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    Ok(Json(TotpSetupResponse { secret, otpauth_url }))
+}
+
+#[derive(Deserialize)]
+struct TotpCodeRequest {
+    code: String,
+}
+
+async fn admin_totp_enable(
+    axum::Extension(identity): axum::Extension<crate::modules::admin_user_db::AdminIdentity>,
+    Json(payload): Json<TotpCodeRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let user_id = require_admin_user(&identity)?;
+    crate::modules::admin_user_db::set_totp_enabled(user_id, &payload.code, true)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    crate::modules::audit::record("admin.totp_enable", Some(identity.username.clone()), None);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn admin_totp_disable(
+    axum::Extension(identity): axum::Extension<crate::modules::admin_user_db::AdminIdentity>,
+    Json(payload): Json<TotpCodeRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let user_id = require_admin_user(&identity)?;
+    crate::modules::admin_user_db::set_totp_enabled(user_id, &payload.code, false)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    crate::modules::audit::record("admin.totp_disable", Some(identity.username.clone()), None);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn admin_list_admin_users() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let users = crate::commands::admin_user::list_admin_users()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    Ok(Json(users))
+}
+
+async fn admin_create_admin_user(
+    Json(payload): Json<crate::commands::admin_user::CreateAdminUserRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let user = crate::commands::admin_user::create_admin_user(payload)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    Ok(Json(user))
+}
+
+async fn admin_update_admin_user(
+    Path(id): Path<String>,
+    Json(payload): Json<crate::modules::admin_user_db::UpdateAdminUser>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let user = crate::commands::admin_user::update_admin_user(id, payload)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    Ok(Json(user))
+}
+
+async fn admin_delete_admin_user(
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    crate::commands::admin_user::delete_admin_user(id)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn admin_reset_admin_user_totp(
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    crate::commands::admin_user::reset_admin_user_totp(id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// --- 审计日志 ---
+
+#[derive(Deserialize)]
+struct AuditPageParams {
+    #[serde(default = "default_audit_limit")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+}
+
+fn default_audit_limit() -> usize { 50 }
+
+async fn admin_get_audit_logs(
+    Query(filter): Query<crate::modules::audit_db::AuditFilter>,
+    Query(page): Query<AuditPageParams>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let result = crate::modules::audit_db::query(&filter, page.limit.min(500), page.offset)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    Ok(Json(result))
+}
+
+#[derive(Deserialize)]
+struct AuditExportParams {
+    #[serde(default)]
+    format: crate::modules::audit_db::AuditExportFormat,
+}
+
+async fn admin_export_audit_logs(
+    Query(filter): Query<crate::modules::audit_db::AuditFilter>,
+    Query(params): Query<AuditExportParams>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let format = params.format;
+    let (content, _) = tokio::task::spawn_blocking(move || {
+        crate::modules::audit_db::export(&filter, format)
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r)
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+
+    let filename = format!(
+        "audit-log-{}.{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        format.extension()
+    );
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        )
+        .body(axum::body::Body::from(content))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response()))
+}
+
 async fn admin_should_check_updates() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)>
 {
     let settings = crate::modules::update_checker::load_update_settings().map_err(|e| {
@@ -2141,6 +3072,51 @@ async fn admin_get_token_stats_account_trend_daily(
     }
 }
 
+#[derive(Deserialize)]
+struct CostBreakdownQuery {
+    hours: Option<i64>,
+    #[serde(default, alias = "groupBy")]
+    group_by: token_stats::CostGroupBy,
+    /// "csv" 时以附件形式导出
+    format: Option<String>,
+}
+
+/// [NEW] 费用明细 (按用户令牌 / 账号 / 模型 / 日期汇总)
+async fn admin_get_token_cost_breakdown(
+    Query(p): Query<CostBreakdownQuery>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let hours = p.hours.unwrap_or(24 * 30);
+    let group_by = p.group_by;
+    let rows = tokio::task::spawn_blocking(move || token_stats::get_cost_breakdown(hours, group_by))
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: e.to_string() }),
+            )
+        })?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+
+    if !p.format.as_deref().is_some_and(|f| f.eq_ignore_ascii_case("csv")) {
+        return Ok(Json(rows).into_response());
+    }
+
+    let currency = crate::proxy::pricing::get_pricing_config().currency;
+    let csv = token_stats::cost_breakdown_csv(&rows, group_by, &currency);
+    let filename = format!("token-cost-{}.csv", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        csv,
+    )
+        .into_response())
+}
+
 async fn admin_clear_token_stats() -> impl IntoResponse {
     let res = tokio::task::spawn_blocking(|| {
         // Clear databases (brute force)
@@ -3164,6 +4140,8 @@ struct IpStatsResponse {
     unique_ips: usize,
     blocked_requests: usize,
     top_ips: Vec<crate::modules::security_db::IpRanking>,
+    auto_ban_count: usize,
+    ban_events_24h: usize,
 }
 
 async fn admin_get_ip_stats() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
@@ -3177,10 +4155,55 @@ async fn admin_get_ip_stats() -> Result<impl IntoResponse, (StatusCode, Json<Err
         unique_ips: stats.unique_ips as usize,
         blocked_requests: stats.blocked_count as usize,
         top_ips,
+        auto_ban_count: stats.auto_ban_count as usize,
+        ban_events_24h: stats.ban_events_24h as usize,
     };
     Ok(Json(response))
 }
 
+async fn admin_get_tls_status() -> impl IntoResponse {
+    Json(crate::proxy::tls::status())
+}
+
+async fn admin_reload_tls_certificate() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let info = crate::proxy::tls::reload()
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    Ok(Json(info))
+}
+
+#[derive(Deserialize)]
+struct BanEventsQuery {
+    limit: Option<usize>,
+}
+
+async fn admin_get_ip_ban_events(
+    Query(q): Query<BanEventsQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let events = security_db::get_ban_events(q.limit.unwrap_or(100))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    Ok(Json(events))
+}
+
+#[derive(Deserialize)]
+struct DlpStatsQuery {
+    hours: Option<i64>,
+}
+
+async fn admin_get_dlp_stats(
+    Query(q): Query<DlpStatsQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let since = chrono::Utc::now().timestamp() - q.hours.unwrap_or(24).max(1) * 3600;
+    let stats = security_db::get_dlp_stats(since)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    Ok(Json(stats))
+}
+
+async fn admin_clear_dlp_stats() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    security_db::clear_dlp_hits()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    Ok(StatusCode::OK)
+}
+
 #[derive(Deserialize)]
 struct IpTokenStatsQuery {
     limit: Option<usize>,
@@ -3220,6 +4243,11 @@ async fn admin_add_ip_to_blacklist(
         req.expires_at,
         "manual",
     ).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    crate::modules::audit::record(
+        "blacklist.add",
+        Some(req.ip_pattern.clone()),
+        Some(serde_json::json!({ "reason": req.reason, "expires_at": req.expires_at })),
+    );
 
     Ok(StatusCode::CREATED)
 }
@@ -3239,6 +4267,7 @@ async fn admin_remove_ip_from_blacklist(
     if let Some(entry) = entries.iter().find(|e| e.ip_pattern == q.ip_pattern) {
         security_db::remove_from_blacklist(&entry.id)
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+        crate::modules::audit::record("blacklist.remove", Some(q.ip_pattern.clone()), None);
     } else {
         return Err((StatusCode::NOT_FOUND, Json(ErrorResponse { error: format!("IP pattern {} not found", q.ip_pattern) })));
     }
@@ -3249,10 +4278,12 @@ async fn admin_remove_ip_from_blacklist(
 async fn admin_clear_ip_blacklist() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let entries = security_db::get_blacklist()
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    let count = entries.len();
     for entry in entries {
         security_db::remove_from_blacklist(&entry.id)
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
     }
+    crate::modules::audit::record("blacklist.clear", None, Some(serde_json::json!({ "count": count })));
     Ok(StatusCode::OK)
 }
 
@@ -3290,6 +4321,11 @@ async fn admin_add_ip_to_whitelist(
         &req.ip_pattern,
         req.description.as_deref(),
     ).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    crate::modules::audit::record(
+        "whitelist.add",
+        Some(req.ip_pattern.clone()),
+        Some(serde_json::json!({ "description": req.description })),
+    );
     Ok(StatusCode::CREATED)
 }
 
@@ -3302,6 +4338,7 @@ async fn admin_remove_ip_from_whitelist(
     if let Some(entry) = entries.iter().find(|e| e.ip_pattern == q.ip_pattern) {
         security_db::remove_from_whitelist(&entry.id)
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+        crate::modules::audit::record("whitelist.remove", Some(q.ip_pattern.clone()), None);
     } else {
         return Err((StatusCode::NOT_FOUND, Json(ErrorResponse { error: format!("IP pattern {} not found", q.ip_pattern) })));
     }
@@ -3311,10 +4348,12 @@ async fn admin_remove_ip_from_whitelist(
 async fn admin_clear_ip_whitelist() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let entries = security_db::get_whitelist()
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    let count = entries.len();
     for entry in entries {
         security_db::remove_from_whitelist(&entry.ip_pattern)
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
     }
+    crate::modules::audit::record("whitelist.clear", None, Some(serde_json::json!({ "count": count })));
     Ok(StatusCode::OK)
 }
 
@@ -3362,6 +4401,84 @@ async fn admin_update_security_config(
     Ok(StatusCode::OK)
 }
 
+/// 将已保存的密文重新加密为当前密钥下的 v2 格式
+async fn admin_reencrypt_secrets() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let count = crate::commands::security::reencrypt_secrets()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    Ok(Json(serde_json::json!({ "reencrypted": count })))
+}
+
+#[derive(Deserialize)]
+struct MasterPasswordRequest {
+    password: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RotateMasterPasswordRequest {
+    old_password: String,
+    new_password: String,
+}
+
+async fn run_account_crypto<T, F>(f: F) -> Result<T, (StatusCode, Json<ErrorResponse>)>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) if e == "invalid_master_password" => {
+            Err((StatusCode::UNAUTHORIZED, Json(ErrorResponse { error: e })))
+        }
+        Ok(Err(e)) => Err((StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e }))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e.to_string() }),
+        )),
+    }
+}
+
+async fn admin_get_account_encryption_status() -> impl IntoResponse {
+    Json(crate::modules::account_crypto::status())
+}
+
+/// 使用主密码解锁账号令牌，并重新加载账号池
+async fn admin_unlock_account_encryption(
+    State(state): State<AppState>,
+    Json(payload): Json<MasterPasswordRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    run_account_crypto(move || crate::modules::account_crypto::unlock(&payload.password)).await?;
+    if let Err(e) = state.token_manager.load_accounts().await {
+        logger::log_error(&format!("[API] 解锁后重新加载账号失败: {}", e));
+    }
+    Ok(Json(crate::modules::account_crypto::status()))
+}
+
+async fn admin_enable_account_encryption(
+    Json(payload): Json<MasterPasswordRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let count = run_account_crypto(move || crate::modules::account_crypto::enable(&payload.password)).await?;
+    Ok(Json(serde_json::json!({ "accounts": count })))
+}
+
+async fn admin_rotate_account_encryption(
+    Json(payload): Json<RotateMasterPasswordRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let count = run_account_crypto(move || {
+        crate::modules::account_crypto::rotate(&payload.old_password, &payload.new_password)
+    })
+    .await?;
+    Ok(Json(serde_json::json!({ "accounts": count })))
+}
+
+async fn admin_disable_account_encryption(
+    Json(payload): Json<MasterPasswordRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let count = run_account_crypto(move || crate::modules::account_crypto::disable(&payload.password)).await?;
+    Ok(Json(serde_json::json!({ "accounts": count })))
+}
+
 // --- Debug Console Handlers ---
 
 async fn admin_enable_debug_console() -> impl IntoResponse {
@@ -0,0 +1,422 @@
+// OpenTelemetry 链路追踪导出
+//
+// 追踪层在日志初始化时常驻安装，导出器 (OTLP gRPC / HTTP) 按配置热切换；
+// 未启用时采样器直接丢弃，所有 span 仅在本地 tracing 中存在。
+// 入站请求携带 W3C `traceparent` 时，请求 span 会挂到调用方的链路下。
+
+use opentelemetry::propagation::{Extractor, TextMapPropagator};
+use opentelemetry::trace::{Link, SamplingResult, SpanKind, TraceContextExt, TraceId, TracerProvider as _};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_otlp::{WithExportConfig, WithHttpConfig, WithTonicConfig};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{
+    BatchSpanProcessor, Sampler, SdkTracer, SdkTracerProvider, ShouldSample, Span, SpanData,
+    SpanProcessor,
+};
+use opentelemetry_sdk::Resource;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+const SERVICE_NAME: &str = "antigravity-manager";
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// OTLP 传输协议
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OtlpProtocol {
+    /// gRPC (默认端口 4317)
+    #[default]
+    Grpc,
+    /// HTTP/protobuf (默认端口 4318)
+    Http,
+}
+
+/// 链路追踪导出配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TelemetryConfig {
+    /// 是否导出链路追踪
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub protocol: OtlpProtocol,
+    /// Collector 地址；为空时按协议使用本机默认端口
+    #[serde(default)]
+    pub endpoint: String,
+    /// 附加到导出请求的头 (如 Collector 鉴权)
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// 根 span 采样率 (0.0 - 1.0)；带 traceparent 的请求跟随上游采样决定
+    #[serde(default = "default_sample_ratio")]
+    pub sample_ratio: f64,
+}
+
+fn default_sample_ratio() -> f64 {
+    1.0
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            protocol: OtlpProtocol::Grpc,
+            endpoint: String::new(),
+            headers: HashMap::new(),
+            sample_ratio: default_sample_ratio(),
+        }
+    }
+}
+
+impl TelemetryConfig {
+    /// 实际使用的 Collector 地址 (HTTP 协议需带 /v1/traces 路径)
+    pub fn resolved_endpoint(&self) -> String {
+        let endpoint = self.endpoint.trim().trim_end_matches('/');
+        match self.protocol {
+            OtlpProtocol::Grpc if endpoint.is_empty() => "http://127.0.0.1:4317".to_string(),
+            OtlpProtocol::Grpc => endpoint.to_string(),
+            OtlpProtocol::Http if endpoint.is_empty() => {
+                "http://127.0.0.1:4318/v1/traces".to_string()
+            }
+            OtlpProtocol::Http if endpoint.ends_with("/v1/traces") => endpoint.to_string(),
+            OtlpProtocol::Http => format!("{}/v1/traces", endpoint),
+        }
+    }
+}
+
+static GLOBAL_TELEMETRY_CONFIG: OnceLock<RwLock<TelemetryConfig>> = OnceLock::new();
+
+/// 获取当前链路追踪配置
+pub fn get_telemetry_config() -> TelemetryConfig {
+    GLOBAL_TELEMETRY_CONFIG
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+fn store_telemetry_config(config: TelemetryConfig) {
+    if let Some(lock) = GLOBAL_TELEMETRY_CONFIG.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = config;
+        }
+    } else {
+        let _ = GLOBAL_TELEMETRY_CONFIG.set(RwLock::new(config));
+    }
+}
+
+/// 导出器与 Provider 共享的可替换状态
+#[derive(Debug, Default)]
+struct ExportSlot {
+    active: RwLock<Option<BatchSpanProcessor>>,
+    resource: RwLock<Option<Resource>>,
+}
+
+/// 转发到当前导出器的 SpanProcessor，配置变更时只替换内部的 BatchSpanProcessor
+#[derive(Debug, Clone)]
+struct SwitchableSpanProcessor(Arc<ExportSlot>);
+
+impl SpanProcessor for SwitchableSpanProcessor {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        if let Ok(active) = self.0.active.read() {
+            if let Some(processor) = active.as_ref() {
+                processor.on_start(span, cx);
+            }
+        }
+    }
+
+    fn on_end(&self, span: SpanData) {
+        if let Ok(active) = self.0.active.read() {
+            if let Some(processor) = active.as_ref() {
+                processor.on_end(span);
+            }
+        }
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        match self.0.active.read() {
+            Ok(active) => active.as_ref().map(|p| p.force_flush()).unwrap_or(Ok(())),
+            Err(_) => Ok(()),
+        }
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        let taken = self.0.active.write().ok().and_then(|mut active| active.take());
+        taken.map(|p| p.shutdown_with_timeout(timeout)).unwrap_or(Ok(()))
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        if let Ok(mut slot) = self.0.resource.write() {
+            *slot = Some(resource.clone());
+        }
+    }
+}
+
+/// 按全局配置决定是否采样：未启用时全部丢弃，启用时遵循父 span 决定，根 span 按比例采样
+#[derive(Debug, Clone)]
+struct ConfigurableSampler;
+
+impl ShouldSample for ConfigurableSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        sampler_for(&get_telemetry_config()).should_sample(
+            parent_context,
+            trace_id,
+            name,
+            span_kind,
+            attributes,
+            links,
+        )
+    }
+}
+
+fn sampler_for(config: &TelemetryConfig) -> Sampler {
+    if !config.enabled {
+        return Sampler::AlwaysOff;
+    }
+    Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+        config.sample_ratio.clamp(0.0, 1.0),
+    )))
+}
+
+struct Telemetry {
+    slot: Arc<ExportSlot>,
+    provider: SdkTracerProvider,
+}
+
+static TELEMETRY: OnceLock<Telemetry> = OnceLock::new();
+
+fn telemetry() -> &'static Telemetry {
+    TELEMETRY.get_or_init(|| {
+        let slot = Arc::new(ExportSlot::default());
+        let provider = SdkTracerProvider::builder()
+            .with_span_processor(SwitchableSpanProcessor(slot.clone()))
+            .with_sampler(ConfigurableSampler)
+            .with_resource(Resource::builder().with_service_name(SERVICE_NAME).build())
+            .build();
+        Telemetry { slot, provider }
+    })
+}
+
+/// 供日志系统安装的 tracing -> OpenTelemetry 桥接层
+pub fn layer<S>() -> tracing_opentelemetry::OpenTelemetryLayer<S, SdkTracer>
+where
+    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    tracing_opentelemetry::layer()
+        .with_tracer(telemetry().provider.tracer(SERVICE_NAME))
+        .with_threads(false)
+}
+
+fn build_processor(config: &TelemetryConfig) -> Result<BatchSpanProcessor, String> {
+    let endpoint = config.resolved_endpoint();
+    let exporter = match config.protocol {
+        OtlpProtocol::Grpc => {
+            let mut headers = axum::http::HeaderMap::new();
+            for (name, value) in &config.headers {
+                let name = axum::http::HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|e| format!("Invalid telemetry header name '{}': {}", name, e))?;
+                let value = axum::http::HeaderValue::from_str(value)
+                    .map_err(|e| format!("Invalid telemetry header value for '{}': {}", name, e))?;
+                headers.insert(name, value);
+            }
+            opentelemetry_otlp::SpanExporter::builder()
+                .with_tonic()
+                .with_endpoint(endpoint)
+                .with_timeout(EXPORT_TIMEOUT)
+                .with_metadata(opentelemetry_otlp::tonic_types::metadata::MetadataMap::from_headers(headers))
+                .build()
+        }
+        OtlpProtocol::Http => opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_protocol(opentelemetry_otlp::Protocol::HttpBinary)
+            .with_endpoint(endpoint)
+            .with_timeout(EXPORT_TIMEOUT)
+            .with_headers(config.headers.clone())
+            .build(),
+    }
+    .map_err(|e| format!("Failed to build OTLP exporter: {}", e))?;
+
+    let mut processor = BatchSpanProcessor::builder(exporter).build();
+    if let Some(resource) = telemetry().slot.resource.read().ok().and_then(|r| r.clone()) {
+        processor.set_resource(&resource);
+    }
+    Ok(processor)
+}
+
+/// 应用链路追踪配置：更新采样开关并按需重建导出器 (需在 tokio 运行时内调用)
+pub fn apply_telemetry_config(config: &TelemetryConfig) -> Result<(), String> {
+    let previous = get_telemetry_config();
+    let exporter_changed = previous.enabled != config.enabled
+        || previous.protocol != config.protocol
+        || previous.resolved_endpoint() != config.resolved_endpoint()
+        || previous.headers != config.headers;
+    let has_exporter = telemetry()
+        .slot
+        .active
+        .read()
+        .map(|active| active.is_some())
+        .unwrap_or(false);
+
+    let next = if config.enabled && (exporter_changed || !has_exporter) {
+        Some(build_processor(config)?)
+    } else {
+        None
+    };
+
+    store_telemetry_config(config.clone());
+
+    if exporter_changed || next.is_some() {
+        let old = telemetry()
+            .slot
+            .active
+            .write()
+            .ok()
+            .and_then(|mut active| std::mem::replace(&mut *active, next));
+        // 旧导出器的关闭会阻塞等待最后一批发送完成，放到独立线程避免卡住运行时
+        if let Some(old) = old {
+            std::thread::spawn(move || {
+                let _ = old.shutdown_with_timeout(EXPORT_TIMEOUT);
+            });
+        }
+    }
+
+    tracing::info!(
+        "[Telemetry] Config updated: enabled={}, protocol={:?}, endpoint={}, sample_ratio={}",
+        config.enabled,
+        config.protocol,
+        config.resolved_endpoint(),
+        config.sample_ratio
+    );
+    Ok(())
+}
+
+/// 退出前发送剩余 span
+pub fn shutdown() {
+    if let Some(telemetry) = TELEMETRY.get() {
+        let _ = telemetry.provider.force_flush();
+        let _ = telemetry.provider.shutdown();
+    }
+}
+
+struct HeaderExtractor<'a>(&'a axum::http::HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+/// 解析入站请求的 W3C traceparent / tracestate
+pub fn extract_remote_context(headers: &axum::http::HeaderMap) -> Option<Context> {
+    let cx = TraceContextPropagator::new().extract(&HeaderExtractor(headers));
+    cx.span().span_context().is_valid().then_some(cx)
+}
+
+/// 将 span 挂到入站请求携带的远端链路下 (必须在 span 首次进入前调用)
+pub fn set_parent_from_headers(span: &tracing::Span, headers: &axum::http::HeaderMap) {
+    if let Some(cx) = extract_remote_context(headers) {
+        let _ = span.set_parent(cx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::SamplingDecision;
+
+    fn decision(config: &TelemetryConfig, parent: Option<&Context>) -> SamplingDecision {
+        sampler_for(config)
+            .should_sample(
+                parent,
+                TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+                "proxy_request",
+                &SpanKind::Server,
+                &[],
+                &[],
+            )
+            .decision
+    }
+
+    fn headers(traceparent: &str) -> axum::http::HeaderMap {
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert("traceparent", traceparent.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_resolved_endpoint_defaults_per_protocol() {
+        let mut config = TelemetryConfig::default();
+        assert_eq!(config.resolved_endpoint(), "http://127.0.0.1:4317");
+
+        config.protocol = OtlpProtocol::Http;
+        assert_eq!(config.resolved_endpoint(), "http://127.0.0.1:4318/v1/traces");
+
+        config.endpoint = "http://collector:4318/".to_string();
+        assert_eq!(config.resolved_endpoint(), "http://collector:4318/v1/traces");
+
+        config.endpoint = "http://collector:4318/v1/traces".to_string();
+        assert_eq!(config.resolved_endpoint(), "http://collector:4318/v1/traces");
+    }
+
+    #[test]
+    fn test_extract_remote_context() {
+        let cx = extract_remote_context(&headers(
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        ))
+        .expect("valid traceparent");
+        let span = cx.span();
+        let span_context = span.span_context();
+        assert!(span_context.is_remote());
+        assert!(span_context.is_sampled());
+        assert_eq!(
+            span_context.trace_id().to_string(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+
+        assert!(extract_remote_context(&headers("garbage")).is_none());
+        assert!(extract_remote_context(&axum::http::HeaderMap::new()).is_none());
+    }
+
+    #[test]
+    fn test_sampler_follows_config_and_parent() {
+        let mut config = TelemetryConfig::default();
+        assert_eq!(decision(&config, None), SamplingDecision::Drop);
+
+        config.enabled = true;
+        assert_eq!(decision(&config, None), SamplingDecision::RecordAndSample);
+
+        config.sample_ratio = 0.0;
+        assert_eq!(decision(&config, None), SamplingDecision::Drop);
+
+        // 上游已采样的链路不受本地采样率影响
+        let sampled = extract_remote_context(&headers(
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        ))
+        .unwrap();
+        assert_eq!(
+            decision(&config, Some(&sampled)),
+            SamplingDecision::RecordAndSample
+        );
+
+        config.sample_ratio = 1.0;
+        let unsampled = extract_remote_context(&headers(
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00",
+        ))
+        .unwrap();
+        assert_eq!(decision(&config, Some(&unsampled)), SamplingDecision::Drop);
+    }
+}
@@ -211,6 +211,20 @@ mod security_db_tests {
         cleanup_test_data();
     }
 
+    #[test]
+    fn test_cidr_matching_ipv6() {
+        let _ = init_db();
+        cleanup_test_data();
+
+        let _ = add_to_blacklist("2001:db8:abcd::/48", Some("Block IPv6 /48"), None, "test");
+
+        assert!(is_ip_in_blacklist("2001:db8:abcd::1").unwrap(), "Should match IPv6 /48");
+        assert!(is_ip_in_blacklist("2001:db8:abcd:ffff::1").unwrap(), "Should match IPv6 /48");
+        assert!(!is_ip_in_blacklist("2001:db8:abce::1").unwrap(), "Should not match IPv6 /48");
+
+        cleanup_test_data();
+    }
+
     // ============================================================================
     // 测试类别 4: 过期时间处理
     // ============================================================================
@@ -0,0 +1,589 @@
+// 反代监听 HTTPS (TLS 终止)
+//
+// 证书来源:
+//   - cert_path + key_path: 加载 PEM 证书链与私钥，文件变更后自动热重载
+//   - 未配置路径: 生成并持久化自签名证书 (数据目录 tls/)，SAN 覆盖 localhost 与本机所有网卡 IP，
+//     有效期 397 天，到期前 30 天自动重新生成
+// 证书通过自定义 ResolvesServerCert 提供，重载只影响新建连接，无需重启监听。
+// 开关与端口变更需重启反代服务后生效。
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio_rustls::TlsAcceptor;
+
+/// 证书文件变更检查间隔
+const WATCH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 自签名证书有效期 (天，不超过浏览器接受的 398 天上限)
+const SELF_SIGNED_VALIDITY_DAYS: u64 = 397;
+
+/// 自签名证书在到期前多少天重新生成
+const SELF_SIGNED_RENEW_BEFORE_DAYS: u64 = 30;
+
+/// TLS 握手超时，防止未完成握手的连接长期占用任务与套接字
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+const SELF_SIGNED_CERT_FILE: &str = "self_signed.crt";
+const SELF_SIGNED_KEY_FILE: &str = "self_signed.key";
+const SELF_SIGNED_SANS_FILE: &str = "self_signed.sans";
+
+/// HTTPS 配置
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TlsConfig {
+    /// 是否启用 HTTPS
+    #[serde(default)]
+    pub enabled: bool,
+    /// PEM 证书 (可包含中间证书链)；与 key_path 均为空时使用自签名证书
+    #[serde(default)]
+    pub cert_path: Option<String>,
+    /// PEM 私钥 (PKCS#8 / PKCS#1 / SEC1)
+    #[serde(default)]
+    pub key_path: Option<String>,
+    /// 独立 HTTPS 端口: 设置且不同于主端口时主端口继续提供 HTTP；未设置时主端口仅提供 HTTPS
+    #[serde(default)]
+    pub https_port: Option<u16>,
+    /// 自签名证书额外的 SAN (域名或 IP)
+    #[serde(default)]
+    pub extra_sans: Vec<String>,
+}
+
+impl TlsConfig {
+    /// 是否使用用户提供的证书文件
+    fn file_paths(&self) -> Option<(&str, &str)> {
+        let cert = self.cert_path.as_deref().map(str::trim).filter(|p| !p.is_empty())?;
+        let key = self.key_path.as_deref().map(str::trim).filter(|p| !p.is_empty())?;
+        Some((cert, key))
+    }
+}
+
+/// 监听模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListenerMode {
+    /// 仅 HTTP
+    Http,
+    /// 主端口仅 HTTPS
+    Https,
+    /// 主端口 HTTP + 独立端口 HTTPS
+    Both { https_port: u16 },
+}
+
+/// 根据配置与主端口计算监听模式
+pub fn listener_mode(config: &TlsConfig, port: u16) -> ListenerMode {
+    if !config.enabled {
+        return ListenerMode::Http;
+    }
+    match config.https_port {
+        Some(https_port) if https_port != 0 && https_port != port => ListenerMode::Both { https_port },
+        _ => ListenerMode::Https,
+    }
+}
+
+/// 当前证书信息 (供管理 API 展示)
+#[derive(Debug, Clone, Serialize)]
+pub struct CertificateInfo {
+    /// "file" | "self_signed"
+    pub source: String,
+    /// 叶子证书 DER 的 SHA-256 指纹 (AA:BB:...)
+    pub fingerprint_sha256: String,
+    /// 自签名证书的 SAN 列表 (文件证书为空)
+    pub subject_alt_names: Vec<String>,
+    pub cert_path: String,
+    pub key_path: String,
+    pub loaded_at: i64,
+}
+
+/// HTTPS 运行状态
+#[derive(Debug, Clone, Serialize)]
+pub struct TlsStatus {
+    /// 配置中是否启用
+    pub enabled: bool,
+    /// 当前监听器是否已提供 HTTPS
+    pub active: bool,
+    /// 提供 HTTPS 的端口
+    pub https_port: Option<u16>,
+    /// 同时提供 HTTP 的端口
+    pub http_port: Option<u16>,
+    pub certificate: Option<CertificateInfo>,
+    /// 最近一次加载失败原因 (失败时继续使用旧证书)
+    pub last_error: Option<String>,
+}
+
+struct LoadedCertificate {
+    key: Arc<CertifiedKey>,
+    info: CertificateInfo,
+    /// 文件证书的 (证书, 私钥) 修改时间，用于热重载
+    modified: Option<(SystemTime, SystemTime)>,
+}
+
+#[derive(Default)]
+struct TlsState {
+    config: TlsConfig,
+    current: Option<Arc<LoadedCertificate>>,
+    last_error: Option<String>,
+    /// (HTTPS 端口, HTTP 端口)
+    listener: Option<(u16, Option<u16>)>,
+}
+
+static TLS_STATE: Lazy<RwLock<TlsState>> = Lazy::new(|| RwLock::new(TlsState::default()));
+static WATCHER_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// 获取当前 HTTPS 配置
+pub fn get_tls_config() -> TlsConfig {
+    TLS_STATE.read().config.clone()
+}
+
+/// 更新全局 HTTPS 配置；HTTPS 已生效时立即按新配置重载证书
+pub fn update_tls_config(config: TlsConfig) {
+    let active = {
+        let mut state = TLS_STATE.write();
+        state.config = config.clone();
+        state.listener.is_some()
+    };
+    tracing::info!(
+        "[TLS] Config updated: enabled={}, https_port={:?}, cert_path={:?}",
+        config.enabled,
+        config.https_port,
+        config.cert_path
+    );
+    if active {
+        if let Err(e) = reload() {
+            tracing::error!("[TLS] Certificate reload failed: {}", e);
+        }
+    }
+}
+
+/// 记录监听器已启用 HTTPS
+pub fn set_listener_state(https_port: u16, http_port: Option<u16>) {
+    TLS_STATE.write().listener = Some((https_port, http_port));
+}
+
+/// 记录监听器未启用 HTTPS
+pub fn clear_listener_state() {
+    TLS_STATE.write().listener = None;
+}
+
+/// 主端口是否仅接受 HTTPS
+fn local_port_is_https(port: u16) -> bool {
+    matches!(TLS_STATE.read().listener, Some((https_port, None)) if https_port == port)
+}
+
+/// 进程内回环访问反代服务的基础地址 (重放、预热、cloudflared 源站、展示的 base_url)
+///
+/// 主端口仅 HTTPS 时返回 https://，请求需使用 `local_client_builder` 关闭证书校验；
+/// 主端口 HTTP + 独立 HTTPS 端口时仍使用主端口的 HTTP。
+pub fn local_base_url(port: u16) -> String {
+    let scheme = if local_port_is_https(port) { "https" } else { "http" };
+    format!("{}://127.0.0.1:{}", scheme, port)
+}
+
+/// 回环请求客户端: 不走系统代理，不校验证书 (仅用于 `local_base_url`)
+pub fn local_client_builder() -> rquest::ClientBuilder {
+    rquest::Client::builder().no_proxy().cert_verification(false)
+}
+
+/// 当前 HTTPS 状态
+pub fn status() -> TlsStatus {
+    let state = TLS_STATE.read();
+    TlsStatus {
+        enabled: state.config.enabled,
+        active: state.listener.is_some(),
+        https_port: state.listener.map(|(https, _)| https),
+        http_port: state.listener.and_then(|(_, http)| http),
+        certificate: state.current.as_ref().map(|c| c.info.clone()),
+        last_error: state.last_error.clone(),
+    }
+}
+
+/// SHA-256 指纹，冒号分隔的大写十六进制
+pub fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn tls_dir() -> Result<PathBuf, String> {
+    let dir = crate::modules::account::get_data_dir()?.join("tls");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create TLS directory: {}", e))?;
+    Ok(dir)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// 本机出口地址 (UDP connect 不会发送数据包)
+fn outbound_ip(target: &str, bind: &str) -> Option<std::net::IpAddr> {
+    let socket = std::net::UdpSocket::bind(bind).ok()?;
+    socket.connect(target).ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_loopback() && !ip.is_unspecified()).then_some(ip)
+}
+
+/// 是否为需要写入 SAN 的网卡地址 (排除回环、未指定与 IPv6 链路本地地址)
+fn is_lan_ip(ip: &std::net::IpAddr) -> bool {
+    let link_local_v6 = matches!(ip, std::net::IpAddr::V6(v6) if (v6.segments()[0] & 0xffc0) == 0xfe80);
+    !ip.is_loopback() && !ip.is_unspecified() && !link_local_v6
+}
+
+/// 本机所有网卡地址
+#[cfg(unix)]
+fn interface_ips() -> Vec<std::net::IpAddr> {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    let mut ips = Vec::new();
+    let mut addrs: *mut libc::ifaddrs = std::ptr::null_mut();
+    // SAFETY: getifaddrs 成功后链表在 freeifaddrs 之前一直有效，按地址族读取对应的 sockaddr 结构
+    unsafe {
+        if libc::getifaddrs(&mut addrs) != 0 {
+            return ips;
+        }
+        let mut cur = addrs;
+        while !cur.is_null() {
+            let addr = (*cur).ifa_addr;
+            if !addr.is_null() {
+                match (*addr).sa_family as i32 {
+                    libc::AF_INET => {
+                        let sin = &*(addr as *const libc::sockaddr_in);
+                        ips.push(IpAddr::V4(Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr))));
+                    }
+                    libc::AF_INET6 => {
+                        let sin6 = &*(addr as *const libc::sockaddr_in6);
+                        ips.push(IpAddr::V6(Ipv6Addr::from(sin6.sin6_addr.s6_addr)));
+                    }
+                    _ => {}
+                }
+            }
+            cur = (*cur).ifa_next;
+        }
+        libc::freeifaddrs(addrs);
+    }
+    ips.retain(is_lan_ip);
+    ips
+}
+
+/// 本机所有网卡地址 (Windows 下解析本机计算机名即可得到全部网卡地址)
+#[cfg(not(unix))]
+fn interface_ips() -> Vec<std::net::IpAddr> {
+    use std::net::ToSocketAddrs;
+
+    let Ok(host) = std::env::var("COMPUTERNAME") else {
+        return Vec::new();
+    };
+    (host.as_str(), 0)
+        .to_socket_addrs()
+        .map(|addrs| addrs.map(|a| a.ip()).filter(is_lan_ip).collect())
+        .unwrap_or_default()
+}
+
+/// 自签名证书需要覆盖的地址: localhost、回环地址与本机所有网卡 IP
+fn self_signed_sans(config: &TlsConfig) -> Vec<String> {
+    let mut sans = vec!["localhost".to_string(), "127.0.0.1".to_string(), "::1".to_string()];
+    sans.extend(interface_ips().iter().map(|ip| ip.to_string()));
+    sans.extend(outbound_ip("8.8.8.8:80", "0.0.0.0:0").map(|ip| ip.to_string()));
+    sans.extend(outbound_ip("[2001:4860:4860::8888]:80", "[::]:0").map(|ip| ip.to_string()));
+    sans.extend(
+        config
+            .extra_sans
+            .iter()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty()),
+    );
+    sans.sort();
+    sans.dedup();
+    sans
+}
+
+/// 生成自签名证书，返回 (证书 PEM, 私钥 PEM)
+pub fn generate_self_signed(sans: &[String]) -> Result<(String, String), String> {
+    use chrono::Datelike;
+
+    let mut params = rcgen::CertificateParams::new(sans.to_vec())
+        .map_err(|e| format!("Invalid subject alternative name: {}", e))?;
+    params
+        .distinguished_name
+        .push(rcgen::DnType::CommonName, "Antigravity Tools");
+    let today = chrono::Utc::now().date_naive();
+    let expires = today + chrono::Days::new(SELF_SIGNED_VALIDITY_DAYS);
+    params.not_before = rcgen::date_time_ymd(today.year(), today.month() as u8, today.day() as u8);
+    params.not_after = rcgen::date_time_ymd(expires.year(), expires.month() as u8, expires.day() as u8);
+
+    let key_pair = rcgen::KeyPair::generate().map_err(|e| format!("Failed to generate key: {}", e))?;
+    let cert = params
+        .self_signed(&key_pair)
+        .map_err(|e| format!("Failed to sign certificate: {}", e))?;
+    Ok((cert.pem(), key_pair.serialize_pem()))
+}
+
+fn write_private_key(path: &Path, pem: &str) -> Result<(), String> {
+    std::fs::write(path, pem).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600));
+    }
+    Ok(())
+}
+
+fn parse_pem(cert_pem: &[u8], key_pem: &[u8]) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), String> {
+    let certs = rustls_pemfile::certs(&mut &cert_pem[..])
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid certificate PEM: {}", e))?;
+    if certs.is_empty() {
+        return Err("No certificate found in PEM".to_string());
+    }
+    let key = rustls_pemfile::private_key(&mut &key_pem[..])
+        .map_err(|e| format!("Invalid private key PEM: {}", e))?
+        .ok_or_else(|| "No private key found in PEM".to_string())?;
+    Ok((certs, key))
+}
+
+fn certified_key(certs: Vec<CertificateDer<'static>>, key: &PrivateKeyDer<'static>) -> Result<Arc<CertifiedKey>, String> {
+    let signing_key = rustls::crypto::ring::sign::any_supported_type(key)
+        .map_err(|e| format!("Unsupported private key: {}", e))?;
+    let certified = CertifiedKey::new(certs, signing_key);
+    certified
+        .keys_match()
+        .map_err(|e| format!("Certificate does not match private key: {}", e))?;
+    Ok(Arc::new(certified))
+}
+
+fn load_from_files(cert_path: &str, key_path: &str) -> Result<LoadedCertificate, String> {
+    let (cert_file, key_file) = (Path::new(cert_path), Path::new(key_path));
+    let modified = modified(cert_file).zip(modified(key_file));
+    let cert_pem = std::fs::read(cert_file).map_err(|e| format!("Failed to read {}: {}", cert_path, e))?;
+    let key_pem = std::fs::read(key_file).map_err(|e| format!("Failed to read {}: {}", key_path, e))?;
+    let (certs, key) = parse_pem(&cert_pem, &key_pem)?;
+    let fingerprint = fingerprint(&certs[0]);
+
+    Ok(LoadedCertificate {
+        key: certified_key(certs, &key)?,
+        info: CertificateInfo {
+            source: "file".to_string(),
+            fingerprint_sha256: fingerprint,
+            subject_alt_names: Vec::new(),
+            cert_path: cert_path.to_string(),
+            key_path: key_path.to_string(),
+            loaded_at: chrono::Utc::now().timestamp(),
+        },
+        modified,
+    })
+}
+
+/// 自签名证书是否临近到期 (按证书文件生成时间判断)
+fn self_signed_expiring(generated_at: SystemTime, now: SystemTime) -> bool {
+    let renew_after = Duration::from_secs((SELF_SIGNED_VALIDITY_DAYS - SELF_SIGNED_RENEW_BEFORE_DAYS) * 86400);
+    now.duration_since(generated_at).is_ok_and(|age| age >= renew_after)
+}
+
+fn self_signed_cert_expiring() -> bool {
+    tls_dir()
+        .ok()
+        .and_then(|dir| modified(&dir.join(SELF_SIGNED_CERT_FILE)))
+        .is_some_and(|generated_at| self_signed_expiring(generated_at, SystemTime::now()))
+}
+
+/// 加载自签名证书；缺失、临近到期或 SAN 未覆盖当前网卡地址时重新生成
+fn load_self_signed(config: &TlsConfig) -> Result<LoadedCertificate, String> {
+    let dir = tls_dir()?;
+    let (cert_file, key_file, sans_file) = (
+        dir.join(SELF_SIGNED_CERT_FILE),
+        dir.join(SELF_SIGNED_KEY_FILE),
+        dir.join(SELF_SIGNED_SANS_FILE),
+    );
+
+    let desired = self_signed_sans(config);
+    let stored: Vec<String> = std::fs::read_to_string(&sans_file)
+        .map(|s| s.lines().map(str::to_string).collect())
+        .unwrap_or_default();
+    let existing = std::fs::read(&cert_file).ok().zip(std::fs::read(&key_file).ok());
+
+    let (cert_pem, key_pem, sans) = match existing {
+        Some((cert, key))
+            if desired.iter().all(|san| stored.contains(san)) && !self_signed_cert_expiring() =>
+        {
+            (cert, key, stored)
+        }
+        _ => {
+            let (cert, key) = generate_self_signed(&desired)?;
+            std::fs::write(&cert_file, &cert).map_err(|e| format!("Failed to write certificate: {}", e))?;
+            write_private_key(&key_file, &key)?;
+            let _ = std::fs::write(&sans_file, desired.join("\n"));
+            tracing::warn!("[TLS] Generated self-signed certificate for {}", desired.join(", "));
+            (cert.into_bytes(), key.into_bytes(), desired)
+        }
+    };
+
+    let (certs, key) = parse_pem(&cert_pem, &key_pem)?;
+    let fingerprint = fingerprint(&certs[0]);
+    Ok(LoadedCertificate {
+        key: certified_key(certs, &key)?,
+        info: CertificateInfo {
+            source: "self_signed".to_string(),
+            fingerprint_sha256: fingerprint,
+            subject_alt_names: sans,
+            cert_path: cert_file.to_string_lossy().to_string(),
+            key_path: key_file.to_string_lossy().to_string(),
+            loaded_at: chrono::Utc::now().timestamp(),
+        },
+        modified: None,
+    })
+}
+
+/// 按当前配置 (重新) 加载证书；失败时保留旧证书
+pub fn reload() -> Result<CertificateInfo, String> {
+    let config = get_tls_config();
+    let result = match config.file_paths() {
+        Some((cert, key)) => load_from_files(cert, key),
+        None => load_self_signed(&config),
+    };
+
+    let mut state = TLS_STATE.write();
+    match result {
+        Ok(loaded) => {
+            let info = loaded.info.clone();
+            tracing::info!(
+                "[TLS] Loaded {} certificate, SHA-256 {}",
+                info.source,
+                info.fingerprint_sha256
+            );
+            state.current = Some(Arc::new(loaded));
+            state.last_error = None;
+            Ok(info)
+        }
+        Err(e) => {
+            state.last_error = Some(e.clone());
+            Err(e)
+        }
+    }
+}
+
+/// 热重载证书解析器: 每次握手读取当前证书
+#[derive(Debug)]
+struct ReloadingResolver;
+
+impl ResolvesServerCert for ReloadingResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        TLS_STATE.read().current.as_ref().map(|c| c.key.clone())
+    }
+}
+
+/// 构建 TLS Acceptor (必要时先加载证书)
+pub fn acceptor() -> Result<TlsAcceptor, String> {
+    if TLS_STATE.read().current.is_none() {
+        reload()?;
+    }
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut server_config = rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("Failed to build TLS config: {}", e))?
+        .with_no_client_auth()
+        .with_cert_resolver(Arc::new(ReloadingResolver));
+    server_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+/// 文件证书变更或自签名证书临近到期时自动重载 (仅启动一次)
+pub fn spawn_watcher() {
+    if WATCHER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+        loop {
+            interval.tick().await;
+            let config = get_tls_config();
+            let Some((cert, key)) = config.file_paths() else {
+                if config.enabled && self_signed_cert_expiring() {
+                    if let Err(e) = reload() {
+                        tracing::error!("[TLS] Self-signed certificate renewal failed: {}", e);
+                    }
+                }
+                continue;
+            };
+            let current = TLS_STATE.read().current.as_ref().and_then(|c| c.modified);
+            let latest = modified(Path::new(cert)).zip(modified(Path::new(key)));
+            if latest.is_some() && latest != current {
+                if let Err(e) = reload() {
+                    tracing::error!("[TLS] Certificate reload failed: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// 测试用: 临时自签名证书的 Acceptor (不读写全局状态与数据目录)
+#[cfg(test)]
+pub(crate) fn test_acceptor() -> TlsAcceptor {
+    let sans = vec!["localhost".to_string(), "127.0.0.1".to_string()];
+    let (cert_pem, key_pem) = generate_self_signed(&sans).unwrap();
+    let (certs, key) = parse_pem(cert_pem.as_bytes(), key_pem.as_bytes()).unwrap();
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let server_config = rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .unwrap();
+    TlsAcceptor::from(Arc::new(server_config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listener_mode() {
+        let mut config = TlsConfig::default();
+        assert_eq!(listener_mode(&config, 8045), ListenerMode::Http);
+
+        config.enabled = true;
+        assert_eq!(listener_mode(&config, 8045), ListenerMode::Https);
+        config.https_port = Some(8045);
+        assert_eq!(listener_mode(&config, 8045), ListenerMode::Https);
+        config.https_port = Some(8443);
+        assert_eq!(listener_mode(&config, 8045), ListenerMode::Both { https_port: 8443 });
+    }
+
+    #[test]
+    fn test_self_signed_certificate_loads() {
+        let sans = vec!["localhost".to_string(), "192.168.1.10".to_string(), "::1".to_string()];
+        let (cert_pem, key_pem) = generate_self_signed(&sans).unwrap();
+        let (certs, key) = parse_pem(cert_pem.as_bytes(), key_pem.as_bytes()).unwrap();
+        assert!(certified_key(certs.clone(), &key).is_ok());
+
+        let fp = fingerprint(&certs[0]);
+        assert_eq!(fp.len(), 32 * 3 - 1);
+        assert!(fp.chars().all(|c| c == ':' || c.is_ascii_hexdigit()));
+
+        // 证书与私钥不匹配时拒绝
+        let (_, other_key_pem) = generate_self_signed(&sans).unwrap();
+        let (_, other_key) = parse_pem(cert_pem.as_bytes(), other_key_pem.as_bytes()).unwrap();
+        assert!(certified_key(certs, &other_key).is_err());
+    }
+
+    #[test]
+    fn test_self_signed_renewal_and_sans() {
+        let now = SystemTime::now();
+        let days = |d: u64| Duration::from_secs(d * 86400);
+        assert!(!self_signed_expiring(now - days(300), now));
+        assert!(self_signed_expiring(now - days(SELF_SIGNED_VALIDITY_DAYS - SELF_SIGNED_RENEW_BEFORE_DAYS), now));
+        assert!(!self_signed_expiring(now + days(1), now));
+
+        assert!(!is_lan_ip(&"127.0.0.1".parse().unwrap()));
+        assert!(!is_lan_ip(&"fe80::1".parse().unwrap()));
+        assert!(is_lan_ip(&"192.168.1.10".parse().unwrap()));
+        assert!(is_lan_ip(&"fd00::10".parse().unwrap()));
+        assert!(interface_ips().iter().all(is_lan_ip));
+    }
+}
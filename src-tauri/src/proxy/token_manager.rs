@@ -7,6 +7,7 @@ use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
 
 use crate::proxy::rate_limit::RateLimitTracker;
+use crate::proxy::runtime_state::{self, RuntimeStateSnapshot};
 use crate::proxy::sticky_config::StickySessionConfig;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -76,12 +77,18 @@ impl TokenManager {
     }
 
     /// 启动限流记录自动清理后台任务（每15秒检查并清除过期记录）
+    /// 同时每 60 秒将调度运行时状态快照到数据目录
     pub async fn start_auto_cleanup(&self) {
         let tracker = self.rate_limit_tracker.clone();
+        let session_accounts = self.session_accounts.clone();
+        let health_scores = self.health_scores.clone();
+        let data_dir = self.data_dir.clone();
         let cancel = self.cancel_token.child_token();
 
         let handle = tokio::spawn(async move {
             let mut interval = tokio::time::interval(std::time::Duration::from_secs(15));
+            let mut persist_interval = tokio::time::interval(std::time::Duration::from_secs(60));
+            persist_interval.tick().await; // 跳过立即触发的第一次
             loop {
                 tokio::select! {
                     _ = cancel.cancelled() => {
@@ -97,6 +104,11 @@ impl TokenManager {
                             );
                         }
                     }
+                    _ = persist_interval.tick() => {
+                        if let Err(e) = Self::write_runtime_state(&data_dir, &tracker, &session_accounts, &health_scores) {
+                            tracing::warn!("[RuntimeState] Periodic snapshot failed: {}", e);
+                        }
+                    }
                 }
             }
         });
@@ -434,13 +446,14 @@ impl TokenManager {
         let token_obj = account["token"].as_object()
             .ok_or("缺少 token 字段")?;
 
-        let access_token = token_obj["access_token"].as_str()
-            .ok_or("缺少 access_token")?
-            .to_string();
+        // [NEW] 启用静态加密时解密令牌
+        let access_token = crate::modules::account_crypto::decrypt_field(
+            token_obj["access_token"].as_str().ok_or("缺少 access_token")?,
+        )?;
 
-        let refresh_token = token_obj["refresh_token"].as_str()
-            .ok_or("缺少 refresh_token")?
-            .to_string();
+        let refresh_token = crate::modules::account_crypto::decrypt_field(
+            token_obj["refresh_token"].as_str().ok_or("缺少 refresh_token")?,
+        )?;
 
         let expires_in = token_obj["expires_in"].as_i64()
             .ok_or("缺少 expires_in")?;
@@ -865,6 +878,20 @@ impl TokenManager {
             // [FIX] 触发 TokenManager 的账号重新加载信号，确保内存中的 protected_models 同步
             crate::proxy::server::trigger_account_reload(account_id);
 
+            // [NEW] 配额保护告警
+            let email = account_json
+                .get("email")
+                .and_then(|v| v.as_str())
+                .unwrap_or(account_id);
+            crate::proxy::alerts::emit(
+                crate::proxy::alerts::AlertKind::QuotaProtection,
+                email,
+                &format!(
+                    "Model {} on account {} is protected: quota {}% <= {}%",
+                    model_name, email, current_val, threshold
+                ),
+            );
+
             return Ok(true);
         }
 
@@ -1019,6 +1046,11 @@ impl TokenManager {
     pub async fn graceful_shutdown(&self, timeout: std::time::Duration) {
         tracing::info!("Initiating graceful shutdown of background tasks...");
 
+        // 关闭前保存一次调度状态，重启后可恢复
+        if let Err(e) = self.persist_runtime_state() {
+            tracing::warn!("[RuntimeState] Failed to save snapshot on shutdown: {}", e);
+        }
+
         // 发送取消信号给所有后台任务
         self.cancel_token.cancel();
 
@@ -1029,6 +1061,77 @@ impl TokenManager {
         }
     }
 
+    /// 将限流/失败计数/会话绑定/健康分快照写入数据目录
+    pub fn persist_runtime_state(&self) -> Result<(), String> {
+        Self::write_runtime_state(
+            &self.data_dir,
+            &self.rate_limit_tracker,
+            &self.session_accounts,
+            &self.health_scores,
+        )
+    }
+
+    fn write_runtime_state(
+        data_dir: &std::path::Path,
+        tracker: &RateLimitTracker,
+        session_accounts: &DashMap<String, String>,
+        health_scores: &DashMap<String, f32>,
+    ) -> Result<(), String> {
+        let snapshot = RuntimeStateSnapshot::new(
+            tracker.snapshot(),
+            session_accounts
+                .iter()
+                .map(|e| (e.key().clone(), e.value().clone()))
+                .collect(),
+            health_scores.iter().map(|e| (e.key().clone(), *e.value())).collect(),
+        );
+        runtime_state::save_snapshot(data_dir, &snapshot)
+    }
+
+    /// 从数据目录恢复调度状态 (需在 load_accounts 之后调用)
+    ///
+    /// 过期的限流记录、已不存在账号的条目以及过旧的会话绑定会被丢弃。
+    /// 返回恢复的限流记录数量。
+    pub fn restore_runtime_state(&self) -> usize {
+        let Some(snapshot) = runtime_state::load_snapshot(&self.data_dir) else {
+            return 0;
+        };
+
+        let known = |account_id: &str| self.tokens.contains_key(account_id);
+        let now = chrono::Utc::now().timestamp();
+        let sessions_fresh = snapshot.sessions_fresh(now);
+
+        let restored_limits = self.rate_limit_tracker.restore(snapshot.rate_limits, known);
+
+        let mut restored_sessions = 0;
+        if sessions_fresh {
+            for (session_id, account_id) in snapshot.session_accounts {
+                if known(&account_id) {
+                    self.session_accounts.insert(session_id, account_id);
+                    restored_sessions += 1;
+                }
+            }
+        }
+
+        for (account_id, score) in snapshot.health_scores {
+            if let Some(mut token) = self.tokens.get_mut(&account_id) {
+                // 账号已在 load_accounts 时读取过健康分，这里同步更新
+                let score = score.clamp(0.0, 1.0);
+                token.health_score = score;
+                drop(token);
+                self.health_scores.insert(account_id, score);
+            }
+        }
+
+        tracing::info!(
+            "[RuntimeState] Restored {} rate limit record(s), {} session binding(s), {} health score(s)",
+            restored_limits,
+            restored_sessions,
+            self.health_scores.len()
+        );
+        restored_limits
+    }
+
     /// 中止并等待所有后台任务完成
     /// abort() 仅设置取消标志，必须 await 确认清理完成
     pub async fn abort_background_tasks(&self) {
@@ -1061,6 +1164,11 @@ impl TokenManager {
     /// 参数 `force_rotate` 为 true 时将忽略锁定，强制切换账号
     /// 参数 `session_id` 用于跨请求维持会话粘性
     /// 参数 `target_model` 用于检查配额保护 (Issue #621)
+    #[tracing::instrument(
+        name = "get_token",
+        skip(self, session_id),
+        fields(sticky = session_id.is_some(), account = tracing::field::Empty)
+    )]
     pub async fn get_token(
         &self,
         quota_group: &str,
@@ -1093,17 +1201,45 @@ impl TokenManager {
 
         // 【优化 Issue #284】添加 5 秒超时，防止死锁
         let timeout_duration = std::time::Duration::from_secs(5);
-        match tokio::time::timeout(
-            timeout_duration,
-            self.get_token_internal(quota_group, force_rotate, session_id, target_model),
-        )
+        let started = std::time::Instant::now();
+        let result = match tokio::time::timeout(timeout_duration, async {
+            // [NEW] 请求重放可指定账号
+            match crate::proxy::replay::pinned_account() {
+                Some(email) => self.get_token_by_email(&email).await,
+                None => {
+                    self.get_token_internal(quota_group, force_rotate, session_id, target_model)
+                        .await
+                }
+            }
+        })
         .await
         {
-            Ok(result) => result,
+            Ok(result) => {
+                if let Ok((_, _, email, _, _)) = &result {
+                    tracing::Span::current().record("account", email.as_str());
+                }
+                result
+            }
             Err(_) => Err(
                 "Token acquisition timeout (5s) - system too busy or deadlock detected".to_string(),
             ),
+        };
+        // [NEW] 模型无可用账号时上报告警 (重放指定账号的失败不计入)
+        if let Err(e) = &result {
+            if crate::proxy::replay::pinned_account().is_none() {
+                crate::proxy::alerts::emit(
+                    crate::proxy::alerts::AlertKind::AccountsExhausted,
+                    target_model,
+                    &format!("No account could serve model {}: {}", target_model, e),
+                );
+            }
         }
+        // [NEW] 计入请求延迟分解
+        crate::proxy::request_timing::record_token_wait(
+            started.elapsed(),
+            result.as_ref().ok().map(|(_, _, email, _, _)| email.as_str()),
+        );
+        result
     }
 
     /// 内部实现：获取 Token 的核心逻辑
@@ -1668,6 +1804,15 @@ impl TokenManager {
                                     &format!("invalid_grant: {}", e),
                                 )
                                 .await;
+                            // [NEW] 账号禁用告警
+                            crate::proxy::alerts::emit(
+                                crate::proxy::alerts::AlertKind::AccountDisabled,
+                                &token.email,
+                                &format!(
+                                    "Account {} was disabled: refresh token rejected (invalid_grant)",
+                                    token.email
+                                ),
+                            );
                             self.tokens.remove(&token.account_id);
                         }
                         // Avoid leaking account emails to API clients; details are still in logs.
@@ -1762,6 +1907,13 @@ impl TokenManager {
         self.tokens.remove(account_id);
 
         tracing::warn!("Account disabled: {} ({:?})", account_id, path);
+        crate::proxy::event_stream::publish_account(
+            account_id,
+            content.get("email").and_then(|v| v.as_str()),
+            crate::proxy::event_stream::AccountState::Disabled,
+            None,
+            Some(truncate_reason(reason, 800)),
+        );
         Ok(())
     }
 
@@ -1772,6 +1924,8 @@ impl TokenManager {
 
         let path = &entry.account_path;
 
+        // 持有写入锁直到落盘，避免与密钥轮换交错
+        let _guard = crate::modules::account_crypto::write_lock();
         let mut content: serde_json::Value = serde_json::from_str(
             &std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?
         ).map_err(|e| format!("解析 JSON 失败: {}", e))?;
@@ -1792,13 +1946,17 @@ impl TokenManager {
 
         let path = &entry.account_path;
 
+        // 持有写入锁直到落盘，避免与密钥轮换交错
+        let _guard = crate::modules::account_crypto::write_lock();
         let mut content: serde_json::Value = serde_json::from_str(
             &std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?
         ).map_err(|e| format!("解析 JSON 失败: {}", e))?;
 
         let now = chrono::Utc::now().timestamp();
 
-        content["token"]["access_token"] = serde_json::Value::String(token_response.access_token.clone());
+        content["token"]["access_token"] = serde_json::Value::String(
+            crate::modules::account_crypto::encrypt_field(&token_response.access_token)?,
+        );
         content["token"]["expires_in"] = serde_json::Value::Number(token_response.expires_in.into());
         content["token"]["expiry_timestamp"] = serde_json::Value::Number((now + token_response.expires_in).into());
 
@@ -1813,6 +1971,33 @@ impl TokenManager {
         self.tokens.len()
     }
 
+    /// 账号池状态快照 (供 /metrics 导出)
+    pub async fn pool_metrics(&self) -> crate::proxy::metrics::AccountPoolMetrics {
+        let breaker_enabled = self.circuit_breaker_config.read().await.enabled;
+        let now = chrono::Utc::now().timestamp();
+        let mut metrics = crate::proxy::metrics::AccountPoolMetrics::default();
+
+        for entry in self.tokens.iter() {
+            let token = entry.value();
+            metrics.total += 1;
+            if token.validation_blocked && token.validation_blocked_until > now {
+                metrics.validation_blocked += 1;
+            } else if breaker_enabled
+                && self.rate_limit_tracker.is_rate_limited(&token.account_id, None)
+            {
+                metrics.rate_limited += 1;
+            } else {
+                metrics.available += 1;
+            }
+            for (model, remaining) in &token.model_quotas {
+                metrics
+                    .model_quotas
+                    .push((token.email.clone(), model.clone(), *remaining));
+            }
+        }
+        metrics
+    }
+
     /// 通过 email 获取指定账号的 Token（用于预热等需要指定账号的场景）
     /// 此方法会自动刷新过期的 token
     pub async fn get_token_by_email(
@@ -2598,6 +2783,30 @@ impl TokenManager {
              reason
         );
 
+        // [NEW] 验证拦截告警与事件流推送
+        let email = account
+            .get("email")
+            .and_then(|v| v.as_str())
+            .unwrap_or(account_id);
+        crate::proxy::event_stream::publish_account(
+            account_id,
+            Some(email),
+            crate::proxy::event_stream::AccountState::ValidationBlocked,
+            None,
+            Some(format!("blocked until {}", block_until)),
+        );
+        crate::proxy::alerts::emit(
+            crate::proxy::alerts::AlertKind::ValidationBlocked,
+            email,
+            &format!(
+                "Account {} was blocked by upstream validation (403) until {}",
+                email,
+                chrono::DateTime::from_timestamp(block_until, 0)
+                    .map(|t| t.to_rfc3339())
+                    .unwrap_or_else(|| block_until.to_string())
+            ),
+        );
+
         Ok(())
     }
 
@@ -2707,6 +2916,68 @@ mod tests {
         let _ = std::fs::remove_dir_all(&tmp_root);
     }
 
+    #[tokio::test]
+    async fn test_runtime_state_survives_restart() {
+        let tmp_root = std::env::temp_dir().join(format!(
+            "antigravity-token-manager-test-runtime-state-{}",
+            uuid::Uuid::new_v4()
+        ));
+        let accounts_dir = tmp_root.join("accounts");
+        std::fs::create_dir_all(&accounts_dir).unwrap();
+
+        let now = chrono::Utc::now().timestamp();
+        for id in ["acc1", "acc2"] {
+            let json = serde_json::json!({
+                "id": id,
+                "email": format!("{}@test.com", id),
+                "token": {
+                    "access_token": "atk",
+                    "refresh_token": "rtk",
+                    "expires_in": 3600,
+                    "expiry_timestamp": now + 3600
+                },
+                "disabled": false,
+                "proxy_disabled": false,
+                "created_at": now,
+                "last_used": now
+            });
+            std::fs::write(
+                accounts_dir.join(format!("{}.json", id)),
+                serde_json::to_string_pretty(&json).unwrap(),
+            )
+            .unwrap();
+        }
+
+        let manager = TokenManager::new(tmp_root.clone());
+        manager.load_accounts().await.unwrap();
+        manager.rate_limit_tracker.set_lockout_until(
+            "acc1",
+            std::time::SystemTime::now() + std::time::Duration::from_secs(600),
+            crate::proxy::rate_limit::RateLimitReason::QuotaExhausted,
+            None,
+        );
+        manager.session_accounts.insert("sid1".to_string(), "acc2".to_string());
+        manager.session_accounts.insert("sid-gone".to_string(), "deleted".to_string());
+        manager.record_failure("acc2");
+        manager.persist_runtime_state().unwrap();
+
+        // 模拟重启
+        let restarted = TokenManager::new(tmp_root.clone());
+        restarted.load_accounts().await.unwrap();
+        assert_eq!(restarted.restore_runtime_state(), 1);
+
+        assert!(restarted.rate_limit_tracker.is_rate_limited("acc1", None));
+        assert_eq!(
+            restarted.session_accounts.get("sid1").map(|v| v.clone()),
+            Some("acc2".to_string())
+        );
+        assert!(restarted.session_accounts.get("sid-gone").is_none());
+        let score = restarted.tokens.get("acc2").unwrap().health_score;
+        assert!((score - 0.8).abs() < f32::EPSILON);
+
+        let _ = std::fs::remove_dir_all(&tmp_root);
+    }
+
     #[tokio::test]
     async fn test_fixed_account_mode_skips_preferred_when_disabled_on_disk_without_reload() {
         let tmp_root = std::env::temp_dir().join(format!(
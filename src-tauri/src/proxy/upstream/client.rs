@@ -2,11 +2,17 @@
 // 基于高性能通讯接口封装
 
 use dashmap::DashMap;
-use rquest::{header, Client, Response, StatusCode};
+use futures::StreamExt;
+use rquest::{header, Client, Response, ResponseBuilderExt, StatusCode};
 use serde_json::Value;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tokio::time::Duration;
+use tokio::time::{Duration, Instant};
+use tracing::Instrument;
+
+use super::endpoint_health::{EndpointHealthTracker, EndpointOutcome};
+use crate::proxy::proxy_pool::ProxyConnectionGuard;
+use crate::proxy::response_cache::{self, CacheContext, CacheStatus};
 
 /// 端点降级尝试的记录信息
 #[derive(Debug, Clone)]
@@ -50,17 +56,22 @@ const V1_INTERNAL_BASE_URL_DAILY: &str = "https://daily-cloudcode-pa.googleapis.
 const V1_INTERNAL_BASE_URL_SANDBOX: &str =
     "https://daily-cloudcode-pa.sandbox.googleapis.com/v1internal";
 
-const V1_INTERNAL_BASE_URL_FALLBACKS: [&str; 3] = [
-    V1_INTERNAL_BASE_URL_SANDBOX, // 优先级 1: Sandbox (已知有效且稳定)
-    V1_INTERNAL_BASE_URL_DAILY,   // 优先级 2: Daily (备用)
-    V1_INTERNAL_BASE_URL_PROD,    // 优先级 3: Prod (仅作为兜底)
+// 默认降级顺序，实际顺序由 EndpointHealthTracker 根据健康度 / 手动固定顺序决定
+const V1_INTERNAL_BASE_URL_FALLBACKS: [(&str, &str); 3] = [
+    ("sandbox", V1_INTERNAL_BASE_URL_SANDBOX), // 优先级 1: Sandbox (已知有效且稳定)
+    ("daily", V1_INTERNAL_BASE_URL_DAILY),     // 优先级 2: Daily (备用)
+    ("prod", V1_INTERNAL_BASE_URL_PROD),       // 优先级 3: Prod (仅作为兜底)
 ];
 
+/// 端点探测请求超时
+const ENDPOINT_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub struct UpstreamClient {
     default_client: Client,
     proxy_pool: Option<Arc<crate::proxy::proxy_pool::ProxyPoolManager>>,
     client_cache: DashMap<String, Client>, // proxy_id -> Client
     user_agent_override: RwLock<Option<String>>,
+    endpoint_health: Arc<EndpointHealthTracker>, // [NEW] 端点健康跟踪
 }
 
 impl UpstreamClient {
@@ -93,9 +104,18 @@ impl UpstreamClient {
             proxy_pool,
             client_cache: DashMap::new(),
             user_agent_override: RwLock::new(None),
+            endpoint_health: Arc::new(EndpointHealthTracker::new(
+                &V1_INTERNAL_BASE_URL_FALLBACKS,
+                crate::proxy::config::UpstreamEndpointConfig::default(),
+            )),
         }
     }
 
+    /// 端点健康跟踪器 (Admin API / 配置热更新使用)
+    pub fn endpoint_health(&self) -> Arc<EndpointHealthTracker> {
+        self.endpoint_health.clone()
+    }
+
     /// Internal helper to build a client with optional upstream proxy config
     fn build_client_internal(
         proxy_config: Option<crate::proxy::config::UpstreamProxyConfig>,
@@ -173,6 +193,11 @@ impl UpstreamClient {
 
     /// Get client for a specific account (or default if no proxy bound)
     pub async fn get_client(&self, account_id: Option<&str>) -> Client {
+        self.get_client_with_proxy(account_id).await.0
+    }
+
+    /// 获取客户端及其使用的代理池节点 ID (未走代理池时为 None)
+    async fn get_client_with_proxy(&self, account_id: Option<&str>) -> (Client, Option<String>) {
         if let Some(pool) = &self.proxy_pool {
             if let Some(acc_id) = account_id {
                 // Try to get per-account proxy
@@ -180,7 +205,7 @@ impl UpstreamClient {
                     Ok(Some(proxy_cfg)) => {
                         // Check cache
                         if let Some(client) = self.client_cache.get(&proxy_cfg.entry_id) {
-                            return client.clone();
+                            return (client.clone(), Some(proxy_cfg.entry_id));
                         }
                         // Build new client and cache it
                         match self.build_client_with_proxy(proxy_cfg.clone()) {
@@ -192,7 +217,7 @@ impl UpstreamClient {
                                     proxy_cfg.entry_id,
                                     acc_id
                                 );
-                                return client;
+                                return (client, Some(proxy_cfg.entry_id));
                             }
                             Err(e) => {
                                 tracing::error!("Failed to build client for proxy {}: {}, falling back to default", proxy_cfg.entry_id, e);
@@ -213,7 +238,78 @@ impl UpstreamClient {
             }
         }
         // Fallback to default client
-        self.default_client.clone()
+        (self.default_client.clone(), None)
+    }
+
+    /// 将代理连接租约挂到响应体上，响应体读取完毕 (或被丢弃) 时才释放活跃连接计数
+    fn attach_connection_guard(resp: Response, guard: Option<ProxyConnectionGuard>) -> Response {
+        let Some(guard) = guard else {
+            return resp;
+        };
+
+        Self::map_body(resp, move |stream| {
+            stream.map(move |chunk| {
+                let _ = &guard;
+                chunk
+            })
+        })
+    }
+
+    /// 原地替换响应体：状态码与头部保持不变，仅补回 http::Response 转换时丢失的上游 URL
+    fn map_body<S, E>(resp: Response, f: impl FnOnce(axum::body::BodyDataStream) -> S) -> Response
+    where
+        S: futures::Stream<Item = Result<bytes::Bytes, E>> + Send + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let url = resp.url().clone();
+        let mut response = axum::http::Response::<rquest::Body>::from(resp).map(|body| {
+            rquest::Body::wrap_stream(f(axum::body::Body::new(body).into_data_stream()))
+        });
+        if let Ok(marker) = axum::http::Response::builder().url(url).body(()) {
+            response
+                .extensions_mut()
+                .extend(marker.into_parts().0.extensions);
+        }
+        Response::from(response)
+    }
+
+    /// 将响应体写入响应缓存 (边转发边缓冲，完整结束后落库)
+    fn attach_cache_store(
+        resp: Response,
+        ctx: &CacheContext,
+        key: String,
+        model: Option<String>,
+    ) -> Response {
+        let status = resp.status().as_u16();
+        let content_type = resp
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("application/json")
+            .to_string();
+        Self::map_body(resp, |stream| {
+            ctx.cache
+                .tee_body(key, model, status, content_type, &ctx.config, stream)
+        })
+    }
+
+    /// 基于缓存条目构建上游响应，SSE 按事件逐条回放
+    fn cached_response(
+        cached: &crate::modules::response_cache_db::CachedResponse,
+        url: &str,
+    ) -> Result<Response, String> {
+        let url = rquest::Url::parse(url).map_err(|e| e.to_string())?;
+        let chunks = response_cache::replay_chunks(cached);
+        let body = rquest::Body::wrap_stream(futures::stream::iter(
+            chunks.into_iter().map(Ok::<_, std::io::Error>),
+        ));
+        axum::http::Response::builder()
+            .status(cached.status)
+            .header(header::CONTENT_TYPE, cached.content_type.as_str())
+            .url(url)
+            .body(body)
+            .map(Response::from)
+            .map_err(|e| e.to_string())
     }
 
     /// Build v1internal URL
@@ -267,8 +363,45 @@ impl UpstreamClient {
         extra_headers: std::collections::HashMap<String, String>,
         account_id: Option<&str>, // [NEW] Account ID
     ) -> Result<UpstreamCallResult, String> {
+        // [NEW] 响应缓存：仅在中间件建立了缓存上下文 (调用方已开启缓存) 时生效
+        let cache_ctx = response_cache::current_context();
+        let cache_key = cache_ctx.as_ref().and_then(|ctx| {
+            let key = response_cache::cache_key(method, query_string, &body, &ctx.config);
+            if key.is_none() {
+                ctx.cache.record_bypass();
+                ctx.set_status(CacheStatus::Bypass);
+            }
+            key
+        });
+        if let (Some(ctx), Some(key)) = (&cache_ctx, &cache_key) {
+            if let Some(cached) = ctx.cache.lookup(key).await {
+                let age_secs = (chrono::Utc::now().timestamp() - cached.created_at).max(0) as u64;
+                tracing::info!(
+                    "[ResponseCache] Hit | method={} | age={}s | {} bytes",
+                    method,
+                    age_secs,
+                    cached.body.len()
+                );
+                ctx.set_status(CacheStatus::Hit { age_secs });
+                let base_url = V1_INTERNAL_BASE_URL_FALLBACKS[0].1;
+                return Ok(UpstreamCallResult {
+                    response: Self::cached_response(
+                        &cached,
+                        &Self::build_url(base_url, method, query_string),
+                    )?,
+                    fallback_attempts: Vec::new(),
+                });
+            }
+            ctx.set_status(CacheStatus::Miss);
+        }
+
         // [NEW] Get client based on account (cached in proxy pool manager)
-        let client = self.get_client(account_id).await;
+        let (client, proxy_id) = self.get_client_with_proxy(account_id).await;
+        // [NEW] 登记代理池活跃连接 (LeastConnections 策略依赖)
+        let mut connection_guard = match (&self.proxy_pool, proxy_id) {
+            (Some(pool), Some(id)) => Some(pool.acquire_connection(&id)),
+            _ => None,
+        };
 
         // 构建 Headers (所有端点复用)
         let mut headers = header::HeaderMap::new();
@@ -332,28 +465,53 @@ impl UpstreamClient {
         // [NEW] 收集降级尝试记录
         let mut fallback_attempts: Vec<FallbackAttemptLog> = Vec::new();
 
+        // [NEW] 按健康度排序并跳过熔断中的端点
+        let endpoints = self.endpoint_health.ordered_endpoints();
+
         // 遍历所有端点，失败时自动切换
-        for (idx, base_url) in V1_INTERNAL_BASE_URL_FALLBACKS.iter().enumerate() {
+        for (idx, base_url) in endpoints.iter().copied().enumerate() {
             let url = Self::build_url(base_url, method, query_string);
-            let has_next = idx + 1 < V1_INTERNAL_BASE_URL_FALLBACKS.len();
+            let has_next = idx + 1 < endpoints.len();
+            let endpoint_name = self.endpoint_health.name_of(base_url);
+            // [NEW] 每个端点尝试一个 span，降级时标记 upstream.fallback
+            let attempt_span = tracing::info_span!(
+                "upstream_attempt",
+                upstream.endpoint = endpoint_name,
+                upstream.method = method,
+                upstream.attempt = idx,
+                http.response.status_code = tracing::field::Empty,
+                upstream.fallback = false,
+                error = tracing::field::Empty
+            );
 
+            let started = Instant::now();
             let response = client
                 .post(&url)
                 .headers(headers.clone())
                 .json(&body)
                 .send()
+                .instrument(attempt_span.clone())
                 .await;
 
             match response {
                 Ok(resp) => {
                     let status = resp.status();
+                    attempt_span.record("http.response.status_code", status.as_u16());
+                    crate::proxy::request_timing::record_upstream_attempt(endpoint_name, started.elapsed(), idx == 0);
+                    crate::proxy::metrics::global()
+                        .record_upstream_attempt(endpoint_name, Some(status.as_u16()));
+                    self.endpoint_health.record(
+                        base_url,
+                        EndpointOutcome::from_status(status.as_u16()),
+                        started.elapsed(),
+                    );
                     if status.is_success() {
                         if idx > 0 {
                             tracing::info!(
                                 "✓ Upstream fallback succeeded | Endpoint: {} | Status: {} | Next endpoints available: {}",
                                 base_url,
                                 status,
-                                V1_INTERNAL_BASE_URL_FALLBACKS.len() - idx - 1
+                                endpoints.len() - idx - 1
                             );
                         } else {
                             tracing::debug!(
@@ -362,8 +520,14 @@ impl UpstreamClient {
                                 status
                             );
                         }
+                        let mut response =
+                            Self::attach_connection_guard(resp, connection_guard.take());
+                        if let (Some(ctx), Some(key)) = (&cache_ctx, cache_key) {
+                            let model = body.get("model").and_then(|m| m.as_str()).map(String::from);
+                            response = Self::attach_cache_store(response, ctx, key, model);
+                        }
                         return Ok(UpstreamCallResult {
-                            response: resp,
+                            response,
                             fallback_attempts,
                         });
                     }
@@ -378,6 +542,8 @@ impl UpstreamClient {
                             method
                         );
                         // [NEW] 记录降级尝试
+                        attempt_span.record("upstream.fallback", true);
+                        crate::proxy::metrics::global().record_upstream_fallback(endpoint_name);
                         fallback_attempts.push(FallbackAttemptLog {
                             endpoint_url: url.clone(),
                             status: Some(status.as_u16()),
@@ -389,13 +555,25 @@ impl UpstreamClient {
 
                     // 不可重试的错误或已是最后一个端点，直接返回
                     return Ok(UpstreamCallResult {
-                        response: resp,
+                        response: Self::attach_connection_guard(resp, connection_guard.take()),
                         fallback_attempts,
                     });
                 }
                 Err(e) => {
                     let msg = format!("HTTP request failed at {}: {}", base_url, e);
                     tracing::debug!("{}", msg);
+                    attempt_span.record("error", e.to_string().as_str());
+                    crate::proxy::request_timing::record_upstream_attempt(endpoint_name, started.elapsed(), idx == 0);
+                    crate::proxy::metrics::global().record_upstream_attempt(endpoint_name, None);
+                    if has_next {
+                        attempt_span.record("upstream.fallback", true);
+                        crate::proxy::metrics::global().record_upstream_fallback(endpoint_name);
+                    }
+                    self.endpoint_health.record(
+                        base_url,
+                        EndpointOutcome::Failure(None, e.to_string()),
+                        started.elapsed(),
+                    );
                     // [NEW] 记录网络错误的降级尝试
                     fallback_attempts.push(FallbackAttemptLog {
                         endpoint_url: url.clone(),
@@ -416,6 +594,64 @@ impl UpstreamClient {
         Err(last_err.unwrap_or_else(|| "All endpoints failed".to_string()))
     }
 
+    /// 对所有端点执行一次轻量探测
+    ///
+    /// 不携带凭证，只确认端点可达：返回 401/403 等非 5xx 状态即视为可用。
+    pub async fn probe_endpoints(&self) {
+        for (_, base_url) in V1_INTERNAL_BASE_URL_FALLBACKS {
+            let url = Self::build_url(base_url, "loadCodeAssist", None);
+            let started = Instant::now();
+            let result = self
+                .default_client
+                .post(&url)
+                .timeout(ENDPOINT_PROBE_TIMEOUT)
+                .json(&serde_json::json!({}))
+                .send()
+                .await;
+
+            match result {
+                Ok(resp) if !resp.status().is_server_error() => {
+                    self.endpoint_health
+                        .record_probe(base_url, true, Some(started.elapsed()), None);
+                }
+                Ok(resp) => {
+                    let err = format!("Probe returned {}", resp.status());
+                    tracing::debug!("[Upstream-Health] {} at {}", err, base_url);
+                    self.endpoint_health
+                        .record_probe(base_url, false, Some(started.elapsed()), Some(err));
+                }
+                Err(e) => {
+                    tracing::debug!("[Upstream-Health] Probe failed at {}: {}", base_url, e);
+                    self.endpoint_health
+                        .record_probe(base_url, false, None, Some(e.to_string()));
+                }
+            }
+        }
+    }
+
+    /// 启动端点探测循环，客户端被释放 (服务器停止) 后自动退出
+    pub fn start_endpoint_probe_loop(self: &Arc<Self>) {
+        let weak = Arc::downgrade(self);
+        tokio::spawn(async move {
+            tracing::info!("Starting upstream endpoint probe loop...");
+            loop {
+                let interval_secs = match weak.upgrade() {
+                    Some(client) => {
+                        let interval = client.endpoint_health.config().probe_interval_secs;
+                        if interval > 0 {
+                            client.probe_endpoints().await;
+                            interval.max(10)
+                        } else {
+                            60 // 探测关闭时每分钟检查一次配置
+                        }
+                    }
+                    None => break,
+                };
+                tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+            }
+        });
+    }
+
     /// 调用 v1internal API（带 429 重试,支持闭包）
     ///
     /// 带容错和重试的核心请求逻辑
@@ -483,4 +719,25 @@ mod tests {
             "https://cloudcode-pa.googleapis.com/v1internal:streamGenerateContent?alt=sse"
         );
     }
+
+    #[tokio::test]
+    async fn test_map_body_keeps_response_parts() {
+        let url = rquest::Url::parse("https://upstream.example/v1internal:generateContent").unwrap();
+        let resp = Response::from(
+            axum::http::Response::builder()
+                .status(StatusCode::CREATED)
+                .header("x-upstream", "1")
+                .url(url.clone())
+                .body("hello")
+                .unwrap(),
+        );
+
+        let mapped = UpstreamClient::map_body(resp, |stream| {
+            stream.map(|chunk| chunk.map(|b| bytes::Bytes::from(b.to_ascii_uppercase())))
+        });
+        assert_eq!(mapped.status(), StatusCode::CREATED);
+        assert_eq!(mapped.headers()["x-upstream"], "1");
+        assert_eq!(mapped.url(), &url);
+        assert_eq!(mapped.text().await.unwrap(), "HELLO");
+    }
 }
@@ -0,0 +1,505 @@
+// 上游端点健康跟踪
+//
+// 记录每个 v1internal 端点的成功率 / 延迟 / 错误分布，
+// 为端点提供独立熔断，并根据观测到的健康度调整降级顺序。
+
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::proxy::config::UpstreamEndpointConfig;
+
+/// EWMA 平滑系数 (越大越敏感)
+const EWMA_ALPHA: f64 = 0.2;
+/// 延迟评分参考值：延迟等于该值时延迟因子为 0.5
+const LATENCY_REFERENCE_MS: f64 = 5000.0;
+/// 熔断时长上限 (指数增长封顶)
+const MAX_OPEN_DURATION_SECS: u64 = 600;
+/// 健康分分桶数量，同一桶内保持默认优先级，避免轻微波动导致顺序频繁翻转
+const SCORE_BUCKETS: f64 = 5.0;
+
+/// 单次请求对端点的结果分类
+#[derive(Debug, Clone, PartialEq)]
+pub enum EndpointOutcome {
+    /// 2xx
+    Success,
+    /// 与端点本身无关的错误 (如 429 账号配额、4xx 请求错误)，计入统计但不触发熔断
+    SoftError(u16),
+    /// 端点故障 (网络错误 / 5xx / 408)，累计到阈值后触发熔断
+    Failure(Option<u16>, String),
+}
+
+impl EndpointOutcome {
+    /// 根据 HTTP 状态码分类
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            200..=299 => Self::Success,
+            408 | 500..=599 => Self::Failure(Some(status), format!("HTTP {}", status)),
+            _ => Self::SoftError(status),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct EndpointStats {
+    total_requests: u64,
+    successes: u64,
+    failures: u64,
+    soft_errors: u64,
+    consecutive_failures: u32,
+    /// 连续熔断次数 (用于熔断时长指数退避)
+    trip_count: u32,
+    success_ewma: f64,
+    latency_ewma_ms: Option<f64>,
+    status_counts: HashMap<u16, u64>,
+    last_status: Option<u16>,
+    last_error: Option<String>,
+    last_success_at: Option<i64>,
+    last_failure_at: Option<i64>,
+    /// 熔断截止时间 (毫秒时间戳)
+    open_until_ms: Option<i64>,
+    last_probe_at: Option<i64>,
+    last_probe_ok: Option<bool>,
+    last_probe_latency_ms: Option<u64>,
+}
+
+impl Default for EndpointStats {
+    fn default() -> Self {
+        Self {
+            total_requests: 0,
+            successes: 0,
+            failures: 0,
+            soft_errors: 0,
+            consecutive_failures: 0,
+            trip_count: 0,
+            success_ewma: 1.0,
+            latency_ewma_ms: None,
+            status_counts: HashMap::new(),
+            last_status: None,
+            last_error: None,
+            last_success_at: None,
+            last_failure_at: None,
+            open_until_ms: None,
+            last_probe_at: None,
+            last_probe_ok: None,
+            last_probe_latency_ms: None,
+        }
+    }
+}
+
+impl EndpointStats {
+    fn is_open(&self, now_ms: i64) -> bool {
+        self.open_until_ms.map(|t| t > now_ms).unwrap_or(false)
+    }
+
+    /// 健康分 (0.0 - 1.0)：成功率 EWMA × 延迟因子
+    fn score(&self) -> f64 {
+        let latency_factor = match self.latency_ewma_ms {
+            Some(ms) => 1.0 / (1.0 + ms / LATENCY_REFERENCE_MS),
+            None => 1.0,
+        };
+        self.success_ewma * latency_factor
+    }
+
+    fn observe_latency(&mut self, latency: Duration) {
+        let ms = latency.as_millis() as f64;
+        self.latency_ewma_ms = Some(match self.latency_ewma_ms {
+            Some(prev) => prev + EWMA_ALPHA * (ms - prev),
+            None => ms,
+        });
+    }
+
+    fn mark_healthy(&mut self) {
+        self.consecutive_failures = 0;
+        self.trip_count = 0;
+        self.open_until_ms = None;
+    }
+
+    fn mark_failed(&mut self, now_ms: i64, config: &UpstreamEndpointConfig) -> bool {
+        self.consecutive_failures += 1;
+        if self.consecutive_failures < config.failure_threshold.max(1) || self.is_open(now_ms) {
+            return false;
+        }
+        self.trip_count += 1;
+        let secs = config
+            .open_duration_secs
+            .max(1)
+            .saturating_mul(1u64 << (self.trip_count - 1).min(10))
+            .min(MAX_OPEN_DURATION_SECS);
+        self.open_until_ms = Some(now_ms + (secs as i64) * 1000);
+        true
+    }
+}
+
+/// 端点健康状态 (Admin API 展示)
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointHealthView {
+    pub name: String,
+    pub base_url: String,
+    /// 在当前生效顺序中的位置 (从 0 开始)
+    pub position: usize,
+    pub total_requests: u64,
+    pub successes: u64,
+    pub failures: u64,
+    pub soft_errors: u64,
+    pub success_rate: Option<f64>,
+    pub avg_latency_ms: Option<u64>,
+    pub health_score: f64,
+    pub consecutive_failures: u32,
+    pub circuit_open: bool,
+    pub circuit_open_until: Option<i64>,
+    pub status_counts: HashMap<u16, u64>,
+    pub last_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub last_success_at: Option<i64>,
+    pub last_failure_at: Option<i64>,
+    pub last_probe_at: Option<i64>,
+    pub last_probe_ok: Option<bool>,
+    pub last_probe_latency_ms: Option<u64>,
+}
+
+/// 端点健康汇总 (Admin API 返回值)
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointHealthReport {
+    pub adaptive_ordering: bool,
+    pub pinned_order: Vec<String>,
+    pub effective_order: Vec<String>,
+    pub endpoints: Vec<EndpointHealthView>,
+}
+
+/// 上游端点健康跟踪器
+pub struct EndpointHealthTracker {
+    /// (名称, base_url)，按默认优先级排列
+    endpoints: Vec<(&'static str, &'static str)>,
+    stats: RwLock<HashMap<&'static str, EndpointStats>>,
+    config: RwLock<UpstreamEndpointConfig>,
+}
+
+impl EndpointHealthTracker {
+    pub fn new(endpoints: &[(&'static str, &'static str)], config: UpstreamEndpointConfig) -> Self {
+        Self {
+            endpoints: endpoints.to_vec(),
+            stats: RwLock::new(HashMap::new()),
+            config: RwLock::new(config),
+        }
+    }
+
+    pub fn config(&self) -> UpstreamEndpointConfig {
+        self.config.read().clone()
+    }
+
+    pub fn update_config(&self, config: UpstreamEndpointConfig) {
+        *self.config.write() = config;
+    }
+
+    /// 将名称或 URL 解析为已知端点的 base_url
+    fn resolve(&self, key: &str) -> Option<&'static str> {
+        let key = key.trim().trim_end_matches('/');
+        self.endpoints
+            .iter()
+            .find(|(name, url)| name.eq_ignore_ascii_case(key) || *url == key)
+            .map(|(_, url)| *url)
+    }
+
+    /// 端点 URL 对应的名称 (sandbox / daily / prod)
+    pub fn name_of(&self, base_url: &str) -> &'static str {
+        self.endpoints
+            .iter()
+            .find(|(_, url)| *url == base_url)
+            .map(|(name, _)| *name)
+            .unwrap_or("unknown")
+    }
+
+    /// 校验固定顺序并补全未列出的端点，返回规范化后的名称列表
+    pub fn normalize_pinned_order(&self, order: &[String]) -> Result<Vec<String>, String> {
+        let mut result: Vec<&'static str> = Vec::new();
+        for key in order {
+            let url = self
+                .resolve(key)
+                .ok_or_else(|| format!("Unknown upstream endpoint: {}", key))?;
+            if !result.contains(&url) {
+                result.push(url);
+            }
+        }
+        for (_, url) in &self.endpoints {
+            if !result.contains(url) {
+                result.push(url);
+            }
+        }
+        Ok(result.into_iter().map(|url| self.name_of(url).to_string()).collect())
+    }
+
+    /// 不考虑熔断的基础顺序：固定顺序 > 健康度排序 > 默认顺序
+    fn base_order(&self, config: &UpstreamEndpointConfig) -> Vec<&'static str> {
+        if !config.pinned_order.is_empty() {
+            let mut order: Vec<&'static str> = config
+                .pinned_order
+                .iter()
+                .filter_map(|key| self.resolve(key))
+                .collect();
+            for (_, url) in &self.endpoints {
+                if !order.contains(url) {
+                    order.push(url);
+                }
+            }
+            return order;
+        }
+
+        let mut order: Vec<&'static str> = self.endpoints.iter().map(|(_, url)| *url).collect();
+        if config.adaptive_ordering {
+            let stats = self.stats.read();
+            // 稳定排序：同一分桶内保持默认优先级 (无数据的端点视为满分)
+            let max_bucket = SCORE_BUCKETS as i64 - 1;
+            order.sort_by_key(|url| {
+                let bucket = stats
+                    .get(url)
+                    .map(|s| ((s.score() * SCORE_BUCKETS).floor() as i64).min(max_bucket))
+                    .unwrap_or(max_bucket);
+                -bucket
+            });
+        }
+        order
+    }
+
+    /// 本次请求应依次尝试的端点
+    ///
+    /// 处于熔断中的端点会被跳过；若全部熔断，则按基础顺序全部尝试，避免请求直接失败。
+    pub fn ordered_endpoints(&self) -> Vec<&'static str> {
+        let config = self.config();
+        let order = self.base_order(&config);
+        let now_ms = chrono::Utc::now().timestamp_millis();
+
+        let stats = self.stats.read();
+        let available: Vec<&'static str> = order
+            .iter()
+            .copied()
+            .filter(|url| !stats.get(url).map(|s| s.is_open(now_ms)).unwrap_or(false))
+            .collect();
+
+        if available.is_empty() {
+            order
+        } else {
+            available
+        }
+    }
+
+    /// 记录一次真实请求的结果
+    pub fn record(&self, base_url: &'static str, outcome: EndpointOutcome, latency: Duration) {
+        let config = self.config();
+        let now = chrono::Utc::now();
+        let now_ms = now.timestamp_millis();
+
+        let mut stats = self.stats.write();
+        let entry = stats.entry(base_url).or_default();
+        entry.total_requests += 1;
+
+        match outcome {
+            EndpointOutcome::Success => {
+                entry.successes += 1;
+                entry.success_ewma += EWMA_ALPHA * (1.0 - entry.success_ewma);
+                entry.observe_latency(latency);
+                *entry.status_counts.entry(200).or_insert(0) += 1;
+                entry.last_status = Some(200);
+                entry.last_success_at = Some(now.timestamp());
+                entry.mark_healthy();
+            }
+            EndpointOutcome::SoftError(status) => {
+                // 端点本身可达，仅说明账号/请求侧的问题
+                entry.soft_errors += 1;
+                *entry.status_counts.entry(status).or_insert(0) += 1;
+                entry.last_status = Some(status);
+            }
+            EndpointOutcome::Failure(status, error) => {
+                entry.failures += 1;
+                entry.success_ewma -= EWMA_ALPHA * entry.success_ewma;
+                if let Some(s) = status {
+                    *entry.status_counts.entry(s).or_insert(0) += 1;
+                }
+                entry.last_status = status;
+                entry.last_error = Some(error);
+                entry.last_failure_at = Some(now.timestamp());
+                if entry.mark_failed(now_ms, &config) {
+                    tracing::warn!(
+                        "[Upstream-Health] Circuit opened for {} after {} consecutive failure(s)",
+                        self.name_of(base_url),
+                        entry.consecutive_failures
+                    );
+                }
+            }
+        }
+    }
+
+    /// 记录一次探测结果
+    ///
+    /// 探测成功会提前关闭熔断 (视为半开试探成功)，探测失败按端点故障计数。
+    pub fn record_probe(&self, base_url: &'static str, ok: bool, latency: Option<Duration>, error: Option<String>) {
+        let config = self.config();
+        let now = chrono::Utc::now();
+        let now_ms = now.timestamp_millis();
+
+        let mut stats = self.stats.write();
+        let entry = stats.entry(base_url).or_default();
+        entry.last_probe_at = Some(now.timestamp());
+        entry.last_probe_ok = Some(ok);
+        entry.last_probe_latency_ms = latency.map(|d| d.as_millis() as u64);
+
+        if ok {
+            if entry.is_open(now_ms) {
+                tracing::info!(
+                    "[Upstream-Health] Probe succeeded, closing circuit for {}",
+                    self.name_of(base_url)
+                );
+            }
+            entry.mark_healthy();
+        } else {
+            entry.last_error = error;
+            entry.last_failure_at = Some(now.timestamp());
+            entry.mark_failed(now_ms, &config);
+        }
+    }
+
+    /// 清空统计数据与熔断状态
+    pub fn reset(&self) {
+        self.stats.write().clear();
+    }
+
+    /// 生成健康报告
+    pub fn report(&self) -> EndpointHealthReport {
+        let config = self.config();
+        let effective: Vec<&'static str> = self.ordered_endpoints();
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let stats = self.stats.read();
+
+        let endpoints = self
+            .base_order(&config)
+            .into_iter()
+            .map(|url| {
+                let s = stats.get(url).cloned().unwrap_or_default();
+                let counted = s.successes + s.failures;
+                EndpointHealthView {
+                    name: self.name_of(url).to_string(),
+                    base_url: url.to_string(),
+                    position: effective.iter().position(|u| *u == url).unwrap_or(effective.len()),
+                    total_requests: s.total_requests,
+                    successes: s.successes,
+                    failures: s.failures,
+                    soft_errors: s.soft_errors,
+                    success_rate: (counted > 0).then(|| s.successes as f64 / counted as f64),
+                    avg_latency_ms: s.latency_ewma_ms.map(|ms| ms.round() as u64),
+                    health_score: s.score(),
+                    consecutive_failures: s.consecutive_failures,
+                    circuit_open: s.is_open(now_ms),
+                    circuit_open_until: s.open_until_ms.filter(|t| *t > now_ms),
+                    status_counts: s.status_counts.clone(),
+                    last_status: s.last_status,
+                    last_error: s.last_error.clone(),
+                    last_success_at: s.last_success_at,
+                    last_failure_at: s.last_failure_at,
+                    last_probe_at: s.last_probe_at,
+                    last_probe_ok: s.last_probe_ok,
+                    last_probe_latency_ms: s.last_probe_latency_ms,
+                }
+            })
+            .collect();
+
+        EndpointHealthReport {
+            adaptive_ordering: config.adaptive_ordering,
+            pinned_order: config.pinned_order.clone(),
+            effective_order: effective.iter().map(|url| self.name_of(url).to_string()).collect(),
+            endpoints,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENDPOINTS: [(&str, &str); 3] = [
+        ("sandbox", "https://sandbox.example/v1internal"),
+        ("daily", "https://daily.example/v1internal"),
+        ("prod", "https://prod.example/v1internal"),
+    ];
+
+    fn tracker() -> EndpointHealthTracker {
+        EndpointHealthTracker::new(&ENDPOINTS, UpstreamEndpointConfig::default())
+    }
+
+    #[test]
+    fn test_default_order_without_data() {
+        let t = tracker();
+        assert_eq!(
+            t.ordered_endpoints(),
+            vec![ENDPOINTS[0].1, ENDPOINTS[1].1, ENDPOINTS[2].1]
+        );
+    }
+
+    #[test]
+    fn test_circuit_opens_after_threshold_and_skips_endpoint() {
+        let t = tracker();
+        let sandbox = ENDPOINTS[0].1;
+        for _ in 0..3 {
+            t.record(sandbox, EndpointOutcome::Failure(Some(503), "503".into()), Duration::ZERO);
+        }
+        let order = t.ordered_endpoints();
+        assert!(!order.contains(&sandbox));
+        assert_eq!(order[0], ENDPOINTS[1].1);
+
+        // 探测成功后熔断关闭
+        t.record_probe(sandbox, true, Some(Duration::from_millis(50)), None);
+        assert!(t.ordered_endpoints().contains(&sandbox));
+    }
+
+    #[test]
+    fn test_soft_errors_do_not_trip_circuit() {
+        let t = tracker();
+        let sandbox = ENDPOINTS[0].1;
+        for _ in 0..10 {
+            t.record(sandbox, EndpointOutcome::from_status(429), Duration::ZERO);
+        }
+        assert_eq!(t.ordered_endpoints()[0], sandbox);
+        let report = t.report();
+        assert_eq!(report.endpoints[0].soft_errors, 10);
+        assert!(!report.endpoints[0].circuit_open);
+    }
+
+    #[test]
+    fn test_unhealthy_endpoint_is_demoted() {
+        let t = tracker();
+        let sandbox = ENDPOINTS[0].1;
+        // 失败但未达到熔断阈值 (中间穿插成功)
+        for _ in 0..5 {
+            t.record(sandbox, EndpointOutcome::Failure(None, "timeout".into()), Duration::ZERO);
+            t.record(sandbox, EndpointOutcome::Failure(None, "timeout".into()), Duration::ZERO);
+            t.record(sandbox, EndpointOutcome::Success, Duration::from_millis(200));
+        }
+        t.record(ENDPOINTS[1].1, EndpointOutcome::Success, Duration::from_millis(200));
+        let order = t.ordered_endpoints();
+        assert_eq!(order[0], ENDPOINTS[1].1);
+    }
+
+    #[test]
+    fn test_all_open_falls_back_to_full_order() {
+        let t = tracker();
+        for (_, url) in ENDPOINTS {
+            for _ in 0..3 {
+                t.record(url, EndpointOutcome::Failure(None, "down".into()), Duration::ZERO);
+            }
+        }
+        assert_eq!(t.ordered_endpoints().len(), 3);
+    }
+
+    #[test]
+    fn test_pinned_order() {
+        let t = tracker();
+        let pinned = t.normalize_pinned_order(&["prod".to_string()]).unwrap();
+        assert_eq!(pinned, vec!["prod", "sandbox", "daily"]);
+        assert!(t.normalize_pinned_order(&["staging".to_string()]).is_err());
+
+        let mut config = t.config();
+        config.pinned_order = pinned;
+        t.update_config(config);
+        assert_eq!(t.ordered_endpoints()[0], ENDPOINTS[2].1);
+    }
+}
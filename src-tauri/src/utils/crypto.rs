@@ -1,15 +1,49 @@
 use aes_gcm::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     Aes256Gcm, Nonce,
 };
 use base64::{engine::general_purpose, Engine as _};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Deserializer, Serializer};
 use sha2::Digest;
+use lru::LruCache;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::{Mutex, RwLock};
 
+/// 旧版固定 nonce，仅用于解密历史数据
 const FIXED_NONCE: &[u8; 12] = b"antigravsalt";
 const ENCRYPTED_PREFIX: &str = "ag_enc_";
+/// v2 信封格式：
+/// - 设备密钥: `ag_enc_v2:m:<base64(nonce || 密文)>`
+/// - 口令密钥: `ag_enc_v2:p:<base64(salt)>:<base64(nonce || 密文)>`
+/// - 账号主密码: `ag_enc_v2:k:<base64(nonce || 密文)>`
+const ENVELOPE_V2_PREFIX: &str = "ag_enc_v2:";
+const KEY_ID_MACHINE: &str = "m";
+const KEY_ID_PASSPHRASE: &str = "p";
+const KEY_ID_MASTER: &str = "k";
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
 
-/// 生成加密密钥 (基于设备 ID)
+/// 用户口令 (ABV_ENCRYPTION_PASSPHRASE；设置后新密文使用 Argon2id 派生的密钥，否则使用设备密钥)
+static PASSPHRASE: Lazy<RwLock<Option<String>>> = Lazy::new(|| {
+    let passphrase = std::env::var("ABV_ENCRYPTION_PASSPHRASE")
+        .or_else(|_| std::env::var("ENCRYPTION_PASSPHRASE"))
+        .ok()
+        .filter(|p| !p.is_empty());
+    RwLock::new(passphrase)
+});
+
+/// 本进程加密使用的口令 salt：每次启动生成一次，同一进程内的新密文共用一次 Argon2 派生
+/// (salt 随密文保存，解密不依赖本值；AES-GCM nonce 仍为每条密文随机生成)
+static ENCRYPT_SALT: Lazy<[u8; SALT_LEN]> = Lazy::new(rand::random);
+
+/// Argon2 派生结果缓存 (sha256(口令) || salt -> key)，容量有限，仅需覆盖少量不同的 salt
+const DERIVED_KEY_CACHE_SIZE: usize = 16;
+static DERIVED_KEYS: Lazy<Mutex<LruCache<Vec<u8>, [u8; 32]>>> =
+    Lazy::new(|| Mutex::new(LruCache::new(NonZeroUsize::new(DERIVED_KEY_CACHE_SIZE).unwrap())));
+
+/// 生成加密密钥 (基于设备 ID，旧版格式使用)
 fn get_encryption_key() -> [u8; 32] {
     // 使用设备唯一标识生成密钥
     let device_id = machine_uid::get().unwrap_or_else(|_| "default".to_string());
@@ -19,6 +53,179 @@ fn get_encryption_key() -> [u8; 32] {
     key
 }
 
+/// v2 设备密钥 (与旧版密钥做域分离)
+fn get_machine_key_v2() -> [u8; 32] {
+    let device_id = machine_uid::get().unwrap_or_else(|_| "default".to_string());
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(b"antigravity-manager/crypto/v2:");
+    hasher.update(device_id.as_bytes());
+    hasher.finalize().into()
+}
+
+/// 由口令与 salt 派生 256 位密钥 (Argon2id)
+pub fn derive_passphrase_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+fn cached_passphrase_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let mut cache_key = sha2::Sha256::digest(passphrase.as_bytes()).to_vec();
+    cache_key.extend_from_slice(salt);
+    if let Some(key) = DERIVED_KEYS.lock().ok().and_then(|mut cache| cache.get(&cache_key).copied()) {
+        return Ok(key);
+    }
+    let key = derive_passphrase_key(passphrase, salt)?;
+    if let Ok(mut cache) = DERIVED_KEYS.lock() {
+        cache.put(cache_key, key);
+    }
+    Ok(key)
+}
+
+pub fn has_passphrase() -> bool {
+    PASSPHRASE.read().map(|p| p.is_some()).unwrap_or(false)
+}
+
+fn current_passphrase() -> Option<String> {
+    PASSPHRASE.read().ok().and_then(|p| p.clone())
+}
+
+/// 加密所用密钥
+enum KeySpec<'a> {
+    Machine,
+    Passphrase(&'a str),
+}
+
+fn seal(key: &[u8; 32], aad: &[u8], plaintext: &[u8]) -> Result<String, String> {
+    let cipher = Aes256Gcm::new(key.into());
+    let nonce_bytes: [u8; NONCE_LEN] = rand::random();
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce_bytes), Payload { msg: plaintext, aad })
+        .map_err(|e| format!("Encryption failed: {}", e))?;
+
+    let mut sealed = nonce_bytes.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(general_purpose::STANDARD.encode(sealed))
+}
+
+fn open(key: &[u8; 32], aad: &[u8], sealed_base64: &str) -> Result<String, String> {
+    let sealed = general_purpose::STANDARD
+        .decode(sealed_base64)
+        .map_err(|e| format!("Base64 decode failed: {}", e))?;
+    if sealed.len() <= NONCE_LEN {
+        return Err("Ciphertext too short".to_string());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let plaintext = Aes256Gcm::new(key.into())
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|e| format!("Decryption failed: {}", e))?;
+    String::from_utf8(plaintext).map_err(|e| format!("UTF-8 conversion failed: {}", e))
+}
+
+fn encrypt_with(plaintext: &str, spec: &KeySpec) -> Result<String, String> {
+    match spec {
+        KeySpec::Machine => {
+            let aad = format!("{}{}", ENVELOPE_V2_PREFIX, KEY_ID_MACHINE);
+            let sealed = seal(&get_machine_key_v2(), aad.as_bytes(), plaintext.as_bytes())?;
+            Ok(format!("{}:{}", aad, sealed))
+        }
+        KeySpec::Passphrase(passphrase) => {
+            let salt = *ENCRYPT_SALT;
+            let key = cached_passphrase_key(passphrase, &salt)?;
+            let aad = format!("{}{}", ENVELOPE_V2_PREFIX, KEY_ID_PASSPHRASE);
+            let sealed = seal(&key, aad.as_bytes(), plaintext.as_bytes())?;
+            Ok(format!("{}:{}:{}", aad, general_purpose::STANDARD.encode(salt), sealed))
+        }
+    }
+}
+
+fn decrypt_v2(body: &str, passphrase: Option<&str>) -> Result<String, String> {
+    let (key_id, rest) = body.split_once(':').ok_or("Malformed envelope")?;
+    let aad = format!("{}{}", ENVELOPE_V2_PREFIX, key_id);
+    match key_id {
+        KEY_ID_MACHINE => open(&get_machine_key_v2(), aad.as_bytes(), rest),
+        KEY_ID_PASSPHRASE => {
+            let passphrase = passphrase.ok_or("Encryption passphrase is not set")?;
+            let (salt, sealed) = rest.split_once(':').ok_or("Malformed envelope")?;
+            let salt = general_purpose::STANDARD
+                .decode(salt)
+                .map_err(|e| format!("Base64 decode failed: {}", e))?;
+            let key = cached_passphrase_key(passphrase, &salt)?;
+            open(&key, aad.as_bytes(), sealed)
+        }
+        other => Err(format!("Unknown key id: {}", other)),
+    }
+}
+
+fn decrypt_with(encrypted: &str, passphrase: Option<&str>) -> Result<String, String> {
+    if let Some(body) = encrypted.strip_prefix(ENVELOPE_V2_PREFIX) {
+        decrypt_v2(body, passphrase)
+    } else if let Some(legacy) = encrypted.strip_prefix(ENCRYPTED_PREFIX) {
+        decrypt_string_internal(legacy)
+    } else {
+        decrypt_string_internal(encrypted)
+    }
+}
+
+/// 是否为 v2 之前的旧格式 (带 ag_enc_ 前缀的固定 nonce 密文)
+pub fn is_legacy_encrypted(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_PREFIX) && !value.starts_with(ENVELOPE_V2_PREFIX)
+}
+
+/// 是否需要重新加密：旧格式，或 v2 密文使用的不是当前密钥 (如设置口令后的设备密钥密文)
+pub fn needs_reencryption(value: &str) -> bool {
+    if is_legacy_encrypted(value) {
+        return true;
+    }
+    let active = if has_passphrase() { KEY_ID_PASSPHRASE } else { KEY_ID_MACHINE };
+    value
+        .strip_prefix(ENVELOPE_V2_PREFIX)
+        .and_then(|body| body.split_once(':'))
+        .is_some_and(|(key_id, _)| key_id != active && key_id != KEY_ID_MASTER)
+}
+
+/// 常量时间比较 (用于校验 Token 等凭证，仅长度不同时提前返回)
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    std::hint::black_box(diff) == 0
+}
+
+/// 使用账号主密码派生的密钥加密
+pub fn encrypt_with_master_key(plaintext: &str, key: &[u8; 32]) -> Result<String, String> {
+    let aad = format!("{}{}", ENVELOPE_V2_PREFIX, KEY_ID_MASTER);
+    let sealed = seal(key, aad.as_bytes(), plaintext.as_bytes())?;
+    Ok(format!("{}:{}", aad, sealed))
+}
+
+/// 解密账号主密码密文
+pub fn decrypt_with_master_key(encrypted: &str, key: &[u8; 32]) -> Result<String, String> {
+    let aad = format!("{}{}", ENVELOPE_V2_PREFIX, KEY_ID_MASTER);
+    let sealed = encrypted
+        .strip_prefix(aad.as_str())
+        .and_then(|rest| rest.strip_prefix(':'))
+        .ok_or("Not a master-key envelope")?;
+    open(key, aad.as_bytes(), sealed)
+}
+
+/// 是否为账号主密码密文
+pub fn is_master_key_encrypted(value: &str) -> bool {
+    value
+        .strip_prefix(ENVELOPE_V2_PREFIX)
+        .and_then(|body| body.split_once(':'))
+        .is_some_and(|(key_id, _)| key_id == KEY_ID_MASTER)
+}
+
+/// 解密后用当前密钥重新加密，无法解密时返回错误
+pub fn reencrypt_string(encrypted: &str) -> Result<String, String> {
+    let plaintext = decrypt_string(encrypted)?;
+    encrypt_string(&plaintext)
+}
+
 pub fn serialize_password<S>(password: &str, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -37,19 +244,23 @@ where
     D: Deserializer<'de>,
 {
     let raw = String::deserialize(deserializer)?;
+    Ok(decode_password(raw))
+}
+
+/// 解密配置中的密文；无法解密时返回原值
+fn decode_password(raw: String) -> String {
     if raw.is_empty() {
-        return Ok(raw);
+        return raw;
     }
 
     // [FIX #1738] 检查魔术前缀
     if raw.starts_with(ENCRYPTED_PREFIX) {
-        // 新版格式：去前缀后解密
-        let ciphertext = &raw[ENCRYPTED_PREFIX.len()..];
-        match decrypt_string_internal(ciphertext) {
-            Ok(plaintext) => Ok(plaintext),
+        // v2 信封或带前缀的旧版密文
+        match decrypt_string(&raw) {
+            Ok(plaintext) => plaintext,
             Err(_) => {
                 // 解密失败（如密钥变更），返回原始密文以防止数据丢失
-                Ok(raw)
+                raw
             }
         }
     } else {
@@ -59,34 +270,71 @@ where
                 // 只有当解密出有效的 UTF-8 且看起来像合理个字符串时才认为是旧版密文
                 // 这里 decrypt_string_internal 已经保证了 UTF-8，
                 // 如果是用户输入的明文，通常解密会失败（Base64 错误或 Tag 校验错误）。
-                Ok(plaintext)
+                plaintext
             }
             Err(_) => {
                 // 解密失败，认为是普通明文（用户输入的无前缀密码）
-                Ok(raw)
+                raw
             }
         }
     }
 }
 
-pub fn encrypt_string(password: &str) -> Result<String, String> {
-    let key = get_encryption_key();
-    let cipher = Aes256Gcm::new(&key.into());
-    // In production, we should use a random nonce and prepend it to the ciphertext
-    // For simplicity in this demo, we use a fixed nonce (NOT SECURE for repeats)
-    // improving security: use random nonce
-    let nonce = Nonce::from_slice(FIXED_NONCE);
+/// [NEW] 可选密钥 (如告警通道 Token)，None 与空字符串原样保存
+pub fn serialize_optional_password<S>(password: &Option<String>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match password {
+        Some(p) if !p.is_empty() => serialize_password(p, serializer),
+        Some(p) => serializer.serialize_str(p),
+        None => serializer.serialize_none(),
+    }
+}
 
-    let ciphertext = cipher
-        .encrypt(nonce, password.as_bytes())
-        .map_err(|e| format!("Encryption failed: {}", e))?;
+pub fn deserialize_optional_password<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<String>::deserialize(deserializer)?.map(decode_password))
+}
+
+/// [NEW] 值为密钥的键值对 (如 Webhook 鉴权请求头)，仅加密值
+pub fn serialize_password_map<S>(map: &HashMap<String, String>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    use serde::ser::SerializeMap;
+
+    let mut out = serializer.serialize_map(Some(map.len()))?;
+    for (key, value) in map {
+        if value.is_empty() || value.starts_with(ENCRYPTED_PREFIX) {
+            out.serialize_entry(key, value)?;
+        } else {
+            let encrypted = encrypt_string(value).map_err(serde::ser::Error::custom)?;
+            out.serialize_entry(key, &encrypted)?;
+        }
+    }
+    out.end()
+}
+
+pub fn deserialize_password_map<'de, D>(deserializer: D) -> Result<HashMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let map = HashMap::<String, String>::deserialize(deserializer)?;
+    Ok(map.into_iter().map(|(key, value)| (key, decode_password(value))).collect())
+}
 
-    let base64_ciphertext = general_purpose::STANDARD.encode(ciphertext);
-    // [FIX #1738] 添加魔术前缀
-    Ok(format!("{}{}", ENCRYPTED_PREFIX, base64_ciphertext))
+pub fn encrypt_string(password: &str) -> Result<String, String> {
+    // [FIX] 每个值使用随机 nonce，并写入带版本的信封格式
+    match current_passphrase() {
+        Some(passphrase) => encrypt_with(password, &KeySpec::Passphrase(&passphrase)),
+        None => encrypt_with(password, &KeySpec::Machine),
+    }
 }
 
-/// 内部解密函数 (输入必须是纯 Base64 密文，不含前缀)
+/// 旧版解密函数 (输入必须是纯 Base64 密文，不含前缀)
 fn decrypt_string_internal(encrypted_base64: &str) -> Result<String, String> {
     let key = get_encryption_key();
     let cipher = Aes256Gcm::new(&key.into());
@@ -104,11 +352,7 @@ fn decrypt_string_internal(encrypted_base64: &str) -> Result<String, String> {
 }
 
 pub fn decrypt_string(encrypted: &str) -> Result<String, String> {
-    if encrypted.starts_with(ENCRYPTED_PREFIX) {
-        decrypt_string_internal(&encrypted[ENCRYPTED_PREFIX.len()..])
-    } else {
-        decrypt_string_internal(encrypted)
-    }
+    decrypt_with(encrypted, current_passphrase().as_deref())
 }
 
 #[cfg(test)]
@@ -143,4 +387,49 @@ mod tests {
         let decrypted = decrypt_string(&legacy_encrypted).unwrap();
         assert_eq!(password, decrypted);
     }
+
+    #[test]
+    fn test_v2_uses_random_nonce() {
+        let first = encrypt_with("same_secret", &KeySpec::Machine).unwrap();
+        let second = encrypt_with("same_secret", &KeySpec::Machine).unwrap();
+
+        assert!(first.starts_with("ag_enc_v2:m:"));
+        assert_ne!(first, second);
+        assert_eq!(decrypt_with(&first, None).unwrap(), "same_secret");
+        assert_eq!(decrypt_with(&second, None).unwrap(), "same_secret");
+    }
+
+    #[test]
+    fn test_prefixed_legacy_is_decrypted_and_flagged() {
+        let cipher = Aes256Gcm::new(&get_encryption_key().into());
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(FIXED_NONCE), b"proxy_password".as_ref())
+            .unwrap();
+        let legacy = format!("{}{}", ENCRYPTED_PREFIX, general_purpose::STANDARD.encode(ciphertext));
+
+        assert!(is_legacy_encrypted(&legacy));
+        assert!(needs_reencryption(&legacy));
+        assert_eq!(decrypt_with(&legacy, None).unwrap(), "proxy_password");
+    }
+
+    #[test]
+    fn test_passphrase_envelope() {
+        let encrypted = encrypt_with("token", &KeySpec::Passphrase("correct horse")).unwrap();
+
+        assert!(encrypted.starts_with("ag_enc_v2:p:"));
+        assert!(!is_legacy_encrypted(&encrypted));
+        assert_eq!(decrypt_with(&encrypted, Some("correct horse")).unwrap(), "token");
+        assert!(decrypt_with(&encrypted, Some("wrong")).is_err());
+        assert!(decrypt_with(&encrypted, None).is_err());
+
+        // 同一进程内复用 salt (只派生一次密钥)，nonce 仍随机
+        let again = encrypt_with("token", &KeySpec::Passphrase("correct horse")).unwrap();
+        assert_ne!(encrypted, again);
+        let salt_of = |s: &str| s.split(':').nth(2).unwrap().to_string();
+        assert_eq!(salt_of(&encrypted), salt_of(&again));
+
+        // 篡改密钥标识后认证失败
+        let tampered = encrypted.replacen("ag_enc_v2:p:", "ag_enc_v2:m:", 1);
+        assert!(decrypt_with(&tampered, Some("correct horse")).is_err());
+    }
 }
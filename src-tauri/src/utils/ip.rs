@@ -0,0 +1,173 @@
+//! IP 地址与 CIDR 匹配工具 (IPv4 / IPv6)
+//!
+//! IPv4 地址统一映射到 IPv4-mapped IPv6 (`::ffff:a.b.c.d`) 的 128 位空间，
+//! 因此 `10.0.0.0/8` 与 `::ffff:10.0.0.0/104` 等价，双栈监听时也能正确匹配。
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+
+/// IPv4 在 IPv4-mapped IPv6 中的前缀长度偏移
+const V4_MAPPED_OFFSET: u8 = 96;
+
+/// 网段 (已归一化为 128 位)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNet {
+    network: u128,
+    prefix_len: u8,
+}
+
+fn mask(prefix_len: u8) -> u128 {
+    if prefix_len == 0 {
+        0
+    } else {
+        !0u128 << (128 - prefix_len as u32)
+    }
+}
+
+/// 解析 IP 地址，IPv4 映射到 128 位空间
+pub fn parse_ip(ip: &str) -> Option<u128> {
+    let ip = ip.trim().trim_start_matches('[').trim_end_matches(']');
+    // 去掉 IPv6 zone id (fe80::1%eth0)
+    let ip = ip.split('%').next().unwrap_or(ip);
+    ip.parse::<IpAddr>().ok().map(ip_to_u128)
+}
+
+pub fn ip_to_u128(ip: IpAddr) -> u128 {
+    match ip {
+        IpAddr::V4(v4) => u128::from(v4.to_ipv6_mapped()),
+        IpAddr::V6(v6) => u128::from(v6),
+    }
+}
+
+impl IpNet {
+    /// 解析 `ip` 或 `ip/prefix`，IPv4 前缀自动换算到 128 位空间
+    pub fn parse(pattern: &str) -> Option<Self> {
+        let pattern = pattern.trim();
+        let (addr, prefix) = match pattern.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix.trim().parse::<u8>().ok()?)),
+            None => (pattern, None),
+        };
+        let addr = addr.trim().trim_start_matches('[').trim_end_matches(']');
+        let parsed: IpAddr = addr.parse().ok()?;
+        let (max, offset) = match parsed {
+            IpAddr::V4(_) => (32, V4_MAPPED_OFFSET),
+            IpAddr::V6(_) => (128, 0),
+        };
+        let prefix = prefix.unwrap_or(max);
+        if prefix > max {
+            return None;
+        }
+        let prefix_len = prefix + offset;
+        Some(Self {
+            network: ip_to_u128(parsed) & mask(prefix_len),
+            prefix_len,
+        })
+    }
+
+    pub fn contains(&self, ip: u128) -> bool {
+        ip & mask(self.prefix_len) == self.network
+    }
+}
+
+/// 判断 IP 是否命中任一网段
+pub fn matches_any(ip: IpAddr, nets: &[IpNet]) -> bool {
+    let ip = ip_to_u128(ip);
+    nets.iter().any(|net| net.contains(ip))
+}
+
+/// 内存前缀索引: 按前缀长度分桶，查找代价与不同前缀长度的数量成正比，与条目数无关
+#[derive(Debug, Clone)]
+pub struct IpPrefixIndex<T> {
+    /// 非 IP 格式的模式按原始字符串精确匹配
+    exact: HashMap<String, T>,
+    /// 前缀长度 -> (网络地址 -> 值)
+    buckets: BTreeMap<u8, HashMap<u128, T>>,
+}
+
+impl<T> Default for IpPrefixIndex<T> {
+    fn default() -> Self {
+        Self {
+            exact: HashMap::new(),
+            buckets: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> IpPrefixIndex<T> {
+    /// 插入模式；同一网段重复插入时保留先插入的值
+    pub fn insert(&mut self, pattern: &str, value: T) {
+        match IpNet::parse(pattern) {
+            Some(net) => {
+                self.buckets
+                    .entry(net.prefix_len)
+                    .or_default()
+                    .entry(net.network)
+                    .or_insert(value);
+            }
+            None => {
+                self.exact.entry(pattern.trim().to_string()).or_insert(value);
+            }
+        }
+    }
+
+    /// 查找匹配项: 先按原始字符串精确匹配，再按最长前缀匹配
+    pub fn lookup(&self, ip: &str) -> Option<&T> {
+        if let Some(value) = self.exact.get(ip.trim()) {
+            return Some(value);
+        }
+        let addr = parse_ip(ip)?;
+        self.buckets
+            .iter()
+            .rev()
+            .find_map(|(prefix_len, nets)| nets.get(&(addr & mask(*prefix_len))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cidr_match(ip: &str, cidr: &str) -> bool {
+        match (parse_ip(ip), IpNet::parse(cidr)) {
+            (Some(ip), Some(net)) => net.contains(ip),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn test_cidr_match_ipv4_and_ipv6() {
+        assert!(cidr_match("192.168.1.20", "192.168.1.0/24"));
+        assert!(!cidr_match("192.168.2.1", "192.168.1.0/24"));
+        assert!(cidr_match("1.2.3.4", "0.0.0.0/0"));
+        assert!(!cidr_match("::1", "0.0.0.0/0"));
+        assert!(cidr_match("8.8.8.8", "8.8.8.8"));
+
+        assert!(cidr_match("2001:db8::1", "2001:db8::/32"));
+        assert!(cidr_match("[2001:db8:ffff::1]", "2001:db8::/32"));
+        assert!(!cidr_match("2001:db9::1", "2001:db8::/32"));
+        assert!(cidr_match("fe80::1%eth0", "fe80::/10"));
+
+        // IPv4-mapped IPv6 与 IPv4 规则互通
+        assert!(cidr_match("::ffff:10.1.2.3", "10.0.0.0/8"));
+        assert!(cidr_match("10.1.2.3", "::ffff:10.0.0.0/104"));
+
+        assert!(!cidr_match("10.0.0.1", "10.0.0.0/33"));
+        assert!(!cidr_match("not-an-ip", "10.0.0.0/8"));
+    }
+
+    #[test]
+    fn test_prefix_index_prefers_longest_prefix() {
+        let mut index = IpPrefixIndex::default();
+        index.insert("10.0.0.0/8", "wide");
+        index.insert("10.1.0.0/16", "narrow");
+        index.insert("2001:db8::/48", "v6");
+        index.insert("legacy.host", "exact");
+
+        assert_eq!(index.lookup("10.1.2.3"), Some(&"narrow"));
+        assert_eq!(index.lookup("10.2.0.1"), Some(&"wide"));
+        assert_eq!(index.lookup("2001:db8:0:1::5"), Some(&"v6"));
+        assert_eq!(index.lookup("2001:db8:1::5"), None);
+        assert_eq!(index.lookup("legacy.host"), Some(&"exact"));
+        assert_eq!(index.lookup("11.0.0.1"), None);
+    }
+}
@@ -2,3 +2,5 @@ pub mod http;
 pub mod protobuf;
 pub mod crypto;
 pub mod command;
+pub mod ip;
+pub mod totp;
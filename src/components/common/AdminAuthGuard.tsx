@@ -1,17 +1,22 @@
 import React, { useState, useEffect } from 'react';
-import { Lock, Key, Globe, AlertCircle, Loader2 } from 'lucide-react';
+import { Lock, Key, Globe, AlertCircle, Loader2, User, ShieldCheck } from 'lucide-react';
 import { useTranslation } from 'react-i18next';
 import { isTauri } from '../../utils/env';
+import { adminLogin } from '../../utils/adminSession';
 
 /**
  * AdminAuthGuard
  * 针对 Docker/Web 模式的强制鉴权保护层。
- * 如果检测到没有存储的 API Key 或后端返回 401，将拦截 UI 并要求输入 Key。
+ * 如果检测到没有存储的会话令牌或后端返回 401，将拦截 UI 并要求登录。
+ * 已创建管理员账号时使用 用户名 + 密码 (+ TOTP)；否则仅需管理密码 / API Key。
  */
 export const AdminAuthGuard: React.FC<{ children: React.ReactNode }> = ({ children }) => {
     const { t, i18n } = useTranslation();
     const [isAuthenticated, setIsAuthenticated] = useState(isTauri());
     const [apiKey, setApiKey] = useState('');
+    const [username, setUsername] = useState('');
+    const [totpCode, setTotpCode] = useState('');
+    const [needTotp, setNeedTotp] = useState(false);
     const [showLangMenu, setShowLangMenu] = useState(false);
     const [isLoading, setIsLoading] = useState(false);
     const [error, setError] = useState('');
@@ -57,37 +62,26 @@ export const AdminAuthGuard: React.FC<{ children: React.ReactNode }> = ({ childr
         setError('');
 
         try {
-            // 先临时存储 key，用于验证请求
-            sessionStorage.setItem('abv_admin_api_key', trimmedKey);
-
-            // 调用一个需要认证的 API 来验证密码是否正确
-            const response = await fetch('/api/accounts', {
-                method: 'GET',
-                headers: {
-                    'Content-Type': 'application/json',
-                    'Authorization': `Bearer ${trimmedKey}`,
-                    'x-api-key': trimmedKey
-                }
-            });
-
-            if (response.ok || response.status === 204) {
-                // 验证成功
-                localStorage.removeItem('abv_admin_api_key');
-                setIsAuthenticated(true);
-                window.location.reload();
-            } else if (response.status === 401) {
-                // 密码错误
-                sessionStorage.removeItem('abv_admin_api_key');
+            // 登录换取会话令牌，后续请求使用令牌而不是密码
+            const session = await adminLogin(username.trim(), trimmedKey, needTotp ? totpCode.trim() : undefined);
+            sessionStorage.setItem('abv_admin_api_key', session.token);
+            localStorage.removeItem('abv_admin_api_key');
+            setIsAuthenticated(true);
+            window.location.reload();
+        } catch (err) {
+            sessionStorage.removeItem('abv_admin_api_key');
+            if (err === 'totp_required') {
+                setNeedTotp(true);
+                setError(t('login.error_totp_required'));
+            } else if (err === 'invalid_totp') {
+                setNeedTotp(true);
+                setError(t('login.error_invalid_totp'));
+            } else if (err === 'invalid_credentials') {
                 setError(t('login.error_invalid_key'));
             } else {
-                // 其他错误，但可能密码是对的
-                setIsAuthenticated(true);
-                window.location.reload();
+                // 网络错误等
+                setError(t('login.error_network'));
             }
-        } catch (err) {
-            // 网络错误等
-            sessionStorage.removeItem('abv_admin_api_key');
-            setError(t('login.error_network'));
         } finally {
             setIsLoading(false);
         }
@@ -156,6 +150,19 @@ export const AdminAuthGuard: React.FC<{ children: React.ReactNode }> = ({ childr
                     <p className="text-center text-slate-500 dark:text-slate-400 mb-8 text-sm">{t('login.desc')}</p>
 
                     <form onSubmit={handleLogin} className="space-y-6">
+                        <div className="relative">
+                            <User className="absolute left-4 top-1/2 -translate-y-1/2 w-5 h-5 text-slate-400" />
+                            <input
+                                type="text"
+                                autoComplete="username"
+                                placeholder={t('login.username_placeholder')}
+                                className="w-full pl-12 pr-4 py-4 bg-slate-50 dark:bg-base-200 border-2 border-transparent rounded-2xl focus:ring-2 focus:ring-blue-500 transition-all outline-none text-slate-900 dark:text-white"
+                                value={username}
+                                onChange={(e) => { setUsername(e.target.value); setError(''); }}
+                                autoFocus
+                                disabled={isLoading}
+                            />
+                        </div>
                         <div className="relative">
                             <Key className="absolute left-4 top-1/2 -translate-y-1/2 w-5 h-5 text-slate-400" />
                             <input
@@ -164,10 +171,27 @@ export const AdminAuthGuard: React.FC<{ children: React.ReactNode }> = ({ childr
                                 className={`w-full pl-12 pr-4 py-4 bg-slate-50 dark:bg-base-200 border-2 rounded-2xl focus:ring-2 focus:ring-blue-500 transition-all outline-none text-slate-900 dark:text-white ${error ? 'border-red-400' : 'border-transparent'}`}
                                 value={apiKey}
                                 onChange={(e) => { setApiKey(e.target.value); setError(''); }}
-                                autoFocus
+                                autoComplete="current-password"
                                 disabled={isLoading}
                             />
                         </div>
+                        {needTotp && (
+                            <div className="relative">
+                                <ShieldCheck className="absolute left-4 top-1/2 -translate-y-1/2 w-5 h-5 text-slate-400" />
+                                <input
+                                    type="text"
+                                    inputMode="numeric"
+                                    autoComplete="one-time-code"
+                                    maxLength={6}
+                                    placeholder={t('login.totp_placeholder')}
+                                    className="w-full pl-12 pr-4 py-4 bg-slate-50 dark:bg-base-200 border-2 border-transparent rounded-2xl focus:ring-2 focus:ring-blue-500 transition-all outline-none text-slate-900 dark:text-white font-mono tracking-widest"
+                                    value={totpCode}
+                                    onChange={(e) => { setTotpCode(e.target.value.replace(/\D/g, '')); setError(''); }}
+                                    autoFocus
+                                    disabled={isLoading}
+                                />
+                            </div>
+                        )}
                         {error && (
                             <div className="flex items-center gap-2 text-red-500 text-sm">
                                 <AlertCircle className="w-4 h-4" />
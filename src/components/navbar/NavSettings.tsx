@@ -4,6 +4,7 @@ import { LanguageDropdown, MoreDropdown } from './NavDropdowns';
 import { LANGUAGES } from './constants';
 import { isTauri } from '../../utils/env';
 import { useViewStore } from '../../stores/useViewStore';
+import { adminLogout } from '../../utils/adminSession';
 
 interface NavSettingsProps {
     theme: 'light' | 'dark';
@@ -29,9 +30,7 @@ export function NavSettings({
     const { setMiniView } = useViewStore();
 
     const handleLogout = () => {
-        sessionStorage.removeItem('abv_admin_api_key');
-        localStorage.removeItem('abv_admin_api_key');
-        window.location.reload();
+        adminLogout();
     };
 
     return (
@@ -28,12 +28,40 @@ interface ProxyRequestLog {
     output_tokens?: number;
     account_email?: string;
     protocol?: string;  // "openai" | "anthropic" | "gemini"
+    latency?: LatencyBreakdown | null;
+}
+
+interface LatencyBreakdown {
+    queue_wait_ms: number;
+    token_wait_ms: number;
+    upstream_ms: number;
+    ttft_ms?: number | null;
+    attempts: number;
+    accounts_tried: string[];
+    upstream_endpoint?: string | null;
+    output_tokens_per_sec?: number | null;
+}
+
+interface Percentiles {
+    p50?: number | null;
+    p90?: number | null;
+    p99?: number | null;
 }
 
 interface ProxyStats {
     total_requests: number;
     success_count: number;
     error_count: number;
+    latency?: {
+        window_hours: number;
+        duration_ms: Percentiles;
+        ttft_ms: Percentiles;
+        token_wait_ms: Percentiles;
+        queue_wait_ms: Percentiles;
+        upstream_ms: Percentiles;
+        output_tokens_per_sec: Percentiles;
+        avg_attempts?: number | null;
+    };
 }
 
 interface ProxyMonitorProps {
@@ -495,6 +523,18 @@ export const ProxyMonitor: React.FC<ProxyMonitorProps> = ({ className }) => {
                         <span className="text-blue-500">{formatCompactNumber(stats.total_requests)} {t('monitor.stats.total')}</span>
                         <span className="text-green-500">{formatCompactNumber(stats.success_count)} {t('monitor.stats.ok')}</span>
                         <span className="text-red-500">{formatCompactNumber(stats.error_count)} {t('monitor.stats.err')}</span>
+                        {stats.latency?.ttft_ms.p50 != null && (
+                            <span
+                                className="text-purple-500"
+                                title={t('monitor.stats.latency_hint', {
+                                    hours: stats.latency.window_hours,
+                                    p90: stats.latency.ttft_ms.p90 ?? '-',
+                                    p99: stats.latency.ttft_ms.p99 ?? '-',
+                                })}
+                            >
+                                {t('monitor.stats.ttft_p50')} {Math.round(stats.latency.ttft_ms.p50)}ms
+                            </span>
+                        )}
                     </div>
 
                     <button onClick={() => loadData(currentPage, filter)} className="btn btn-sm btn-ghost text-gray-400" title={t('common.refresh')}>
@@ -641,6 +681,33 @@ export const ProxyMonitor: React.FC<ProxyMonitorProps> = ({ className }) => {
                                         <span className="font-mono font-semibold text-gray-900 dark:text-base-content text-xs">{selectedLog.account_email}</span>
                                     </div>
                                 )}
+                                {selectedLog.latency && (
+                                    <div className="mt-5 pt-5 border-t border-gray-200 dark:border-base-300">
+                                        <span className="block text-gray-500 dark:text-gray-400 uppercase font-black text-[10px] tracking-widest mb-2">{t('monitor.details.latency')}</span>
+                                        <div className="grid grid-cols-2 sm:grid-cols-4 gap-3 font-mono text-xs">
+                                            {[
+                                                [t('monitor.details.ttft'), selectedLog.latency.ttft_ms != null ? `${selectedLog.latency.ttft_ms}ms` : '-'],
+                                                [t('monitor.details.token_wait'), `${selectedLog.latency.token_wait_ms}ms`],
+                                                [t('monitor.details.queue_wait'), `${selectedLog.latency.queue_wait_ms}ms`],
+                                                [t('monitor.details.upstream_time'), `${selectedLog.latency.upstream_ms}ms`],
+                                                [t('monitor.details.retries'), String(Math.max(selectedLog.latency.attempts - 1, 0))],
+                                                [t('monitor.details.upstream_endpoint'), selectedLog.latency.upstream_endpoint || '-'],
+                                                [t('monitor.details.output_speed'), selectedLog.latency.output_tokens_per_sec != null ? `${selectedLog.latency.output_tokens_per_sec} tok/s` : '-'],
+                                            ].map(([label, value]) => (
+                                                <div key={label} className="space-y-1">
+                                                    <span className="block text-gray-400 text-[10px]">{label}</span>
+                                                    <span className="font-semibold text-gray-900 dark:text-base-content">{value}</span>
+                                                </div>
+                                            ))}
+                                        </div>
+                                        {selectedLog.latency.accounts_tried.length > 1 && (
+                                            <div className="mt-3 text-xs">
+                                                <span className="text-gray-400 text-[10px] mr-2">{t('monitor.details.accounts_tried')}</span>
+                                                <span className="font-mono text-gray-900 dark:text-base-content">{selectedLog.latency.accounts_tried.join(' → ')}</span>
+                                            </div>
+                                        )}
+                                    </div>
+                                )}
                             </div>
 
                             {/* Payloads */}
@@ -0,0 +1,207 @@
+import { useState, useEffect, useCallback } from 'react';
+import { useTranslation } from 'react-i18next';
+import { Lock, RefreshCw, Copy } from 'lucide-react';
+import { request as invoke } from '../../utils/request';
+import { copyToClipboard } from '../../utils/clipboard';
+import { showToast } from '../common/ToastContainer';
+import HelpTooltip from '../common/HelpTooltip';
+import { TlsConfig } from '../../types/config';
+
+interface CertificateInfo {
+    source: 'file' | 'self_signed';
+    fingerprint_sha256: string;
+    subject_alt_names: string[];
+    cert_path: string;
+    key_path: string;
+    loaded_at: number;
+}
+
+interface TlsStatus {
+    enabled: boolean;
+    active: boolean;
+    https_port?: number | null;
+    http_port?: number | null;
+    certificate?: CertificateInfo | null;
+    last_error?: string | null;
+}
+
+interface TlsSettingsProps {
+    config?: TlsConfig;
+    onChange: (config: TlsConfig) => void;
+}
+
+const DEFAULT_TLS: TlsConfig = {
+    enabled: false,
+    cert_path: null,
+    key_path: null,
+    https_port: null,
+    extra_sans: [],
+};
+
+export const TlsSettings = ({ config, onChange }: TlsSettingsProps) => {
+    const { t } = useTranslation();
+    const tls = { ...DEFAULT_TLS, ...config };
+    const [draft, setDraft] = useState({
+        cert_path: tls.cert_path ?? '',
+        key_path: tls.key_path ?? '',
+        https_port: tls.https_port ? String(tls.https_port) : '',
+        extra_sans: tls.extra_sans.join(', '),
+    });
+    const [status, setStatus] = useState<TlsStatus | null>(null);
+    const [reloading, setReloading] = useState(false);
+
+    useEffect(() => {
+        setDraft({
+            cert_path: tls.cert_path ?? '',
+            key_path: tls.key_path ?? '',
+            https_port: tls.https_port ? String(tls.https_port) : '',
+            extra_sans: tls.extra_sans.join(', '),
+        });
+    }, [config]);
+
+    const loadStatus = useCallback(async () => {
+        try {
+            setStatus(await invoke<TlsStatus>('get_tls_status'));
+        } catch (e) {
+            console.error('Failed to load TLS status', e);
+        }
+    }, []);
+
+    useEffect(() => {
+        loadStatus();
+    }, [loadStatus, config]);
+
+    const commit = (updates: Partial<TlsConfig>) => onChange({ ...tls, ...updates });
+
+    const commitDraft = () => {
+        const port = parseInt(draft.https_port, 10);
+        commit({
+            cert_path: draft.cert_path.trim() || null,
+            key_path: draft.key_path.trim() || null,
+            https_port: Number.isFinite(port) && port > 0 && port <= 65535 ? port : null,
+            extra_sans: draft.extra_sans.split(',').map(s => s.trim()).filter(Boolean),
+        });
+    };
+
+    const handleReload = async () => {
+        setReloading(true);
+        try {
+            await invoke('reload_tls_certificate');
+            showToast(t('proxy.config.tls.reload_success'), 'success');
+        } catch (e) {
+            showToast(`${t('proxy.config.tls.reload_failed')}: ${e}`, 'error');
+        } finally {
+            setReloading(false);
+            loadStatus();
+        }
+    };
+
+    const cert = status?.certificate;
+    const inputClass = 'input input-xs input-bordered w-full font-mono';
+
+    return (
+        <div className="space-y-2 pt-2 border-t border-gray-100 dark:border-base-300">
+            <div className="flex items-center justify-between">
+                <span className="text-xs font-medium text-gray-700 dark:text-gray-300 inline-flex items-center gap-1">
+                    <Lock size={12} />
+                    {t('proxy.config.tls.title')}
+                    <HelpTooltip
+                        text={t('proxy.config.tls.tooltip')}
+                        ariaLabel={t('proxy.config.tls.title')}
+                        placement="right"
+                    />
+                </span>
+                <input
+                    type="checkbox"
+                    className="toggle toggle-sm bg-gray-200 dark:bg-gray-700 border-gray-300 dark:border-gray-600 checked:bg-blue-500 checked:border-blue-500"
+                    checked={tls.enabled}
+                    onChange={(e) => commit({ enabled: e.target.checked })}
+                />
+            </div>
+
+            {tls.enabled && (
+                <div className="space-y-2">
+                    <div className="grid grid-cols-2 gap-2">
+                        <input
+                            className={inputClass}
+                            placeholder={t('proxy.config.tls.cert_path')}
+                            value={draft.cert_path}
+                            onChange={(e) => setDraft({ ...draft, cert_path: e.target.value })}
+                            onBlur={commitDraft}
+                        />
+                        <input
+                            className={inputClass}
+                            placeholder={t('proxy.config.tls.key_path')}
+                            value={draft.key_path}
+                            onChange={(e) => setDraft({ ...draft, key_path: e.target.value })}
+                            onBlur={commitDraft}
+                        />
+                        <input
+                            className={inputClass}
+                            type="number"
+                            placeholder={t('proxy.config.tls.https_port')}
+                            value={draft.https_port}
+                            onChange={(e) => setDraft({ ...draft, https_port: e.target.value })}
+                            onBlur={commitDraft}
+                        />
+                        <input
+                            className={inputClass}
+                            placeholder={t('proxy.config.tls.extra_sans')}
+                            value={draft.extra_sans}
+                            onChange={(e) => setDraft({ ...draft, extra_sans: e.target.value })}
+                            onBlur={commitDraft}
+                        />
+                    </div>
+                    <p className="text-[10px] text-gray-500 dark:text-gray-400">
+                        {draft.cert_path || draft.key_path
+                            ? t('proxy.config.tls.file_hint')
+                            : t('proxy.config.tls.self_signed_hint')}
+                    </p>
+
+                    {status && !status.active && (
+                        <p className="text-[10px] text-blue-600 dark:text-blue-400">
+                            {t('proxy.config.tls.restart_hint')}
+                        </p>
+                    )}
+                    {status?.last_error && (
+                        <p className="text-[10px] text-red-500 break-all">{status.last_error}</p>
+                    )}
+
+                    {cert && (
+                        <div className="text-[10px] text-gray-500 dark:text-gray-400 space-y-1">
+                            <div className="flex items-center justify-between gap-2">
+                                <span>
+                                    {cert.source === 'self_signed'
+                                        ? t('proxy.config.tls.source_self_signed')
+                                        : t('proxy.config.tls.source_file')}
+                                    {status?.https_port ? ` · HTTPS :${status.https_port}` : ''}
+                                </span>
+                                <button
+                                    className="btn btn-ghost btn-xs gap-1"
+                                    onClick={handleReload}
+                                    disabled={reloading}
+                                >
+                                    <RefreshCw size={12} className={reloading ? 'animate-spin' : ''} />
+                                    {t('proxy.config.tls.reload')}
+                                </button>
+                            </div>
+                            <div className="flex items-center gap-1">
+                                <span className="shrink-0">SHA-256</span>
+                                <code className="font-mono break-all">{cert.fingerprint_sha256}</code>
+                                <button
+                                    className="btn btn-ghost btn-xs px-1"
+                                    onClick={() => copyToClipboard(cert.fingerprint_sha256)}
+                                >
+                                    <Copy size={10} />
+                                </button>
+                            </div>
+                            {cert.subject_alt_names.length > 0 && (
+                                <div className="break-all">SAN: {cert.subject_alt_names.join(', ')}</div>
+                            )}
+                        </div>
+                    )}
+                </div>
+            )}
+        </div>
+    );
+};
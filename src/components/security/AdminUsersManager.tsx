@@ -0,0 +1,367 @@
+import React, { useEffect, useState } from 'react';
+import { useTranslation } from 'react-i18next';
+import { request as invoke } from '../../utils/request';
+import { copyToClipboard } from '../../utils/clipboard';
+import { isTauri } from '../../utils/env';
+import { AdminIdentity, AdminRole, getAdminIdentity } from '../../utils/adminSession';
+import { showToast } from '../common/ToastContainer';
+import { Plus, Trash2, X, KeyRound, ShieldCheck, ShieldOff, UserCog, Copy } from 'lucide-react';
+
+interface AdminUser {
+    id: string;
+    username: string;
+    role: AdminRole;
+    disabled: boolean;
+    totp_enabled: boolean;
+    created_at: number;
+    updated_at: number;
+    last_login_at?: number | null;
+}
+
+interface TotpSetup {
+    secret: string;
+    otpauth_url: string;
+}
+
+const ROLES: AdminRole[] = ['owner', 'operator', 'viewer', 'auditor'];
+
+interface Props {
+    refreshKey?: number;
+}
+
+export const AdminUsersManager: React.FC<Props> = ({ refreshKey }) => {
+    const { t } = useTranslation();
+    const [users, setUsers] = useState<AdminUser[]>([]);
+    const [me, setMe] = useState<AdminIdentity | null>(null);
+    const [loading, setLoading] = useState(false);
+
+    // Add Modal State
+    const [isAddOpen, setIsAddOpen] = useState(false);
+    const [newUsername, setNewUsername] = useState('');
+    const [newPassword, setNewPassword] = useState('');
+    const [newRole, setNewRole] = useState<AdminRole>('owner');
+
+    // TOTP State (当前登录用户)
+    const [totpSetup, setTotpSetup] = useState<TotpSetup | null>(null);
+    const [totpCode, setTotpCode] = useState('');
+
+    const loadUsers = async () => {
+        setLoading(true);
+        try {
+            // 非 owner 无权查看管理员列表，但仍可管理自己的二次验证
+            const identity = await getAdminIdentity();
+            setMe(identity);
+            if (identity.role === 'owner') {
+                setUsers(await invoke<AdminUser[]>('list_admin_users'));
+            }
+        } catch (e) {
+            console.error('Failed to load admin users', e);
+        } finally {
+            setLoading(false);
+        }
+    };
+
+    useEffect(() => {
+        loadUsers();
+    }, [refreshKey]);
+
+    const handleAdd = async () => {
+        try {
+            await invoke('create_admin_user', {
+                request: { username: newUsername.trim(), password: newPassword, role: newRole },
+            });
+            setIsAddOpen(false);
+            setNewUsername('');
+            setNewPassword('');
+            showToast(t('security.admin_users.created'), 'success');
+            loadUsers();
+        } catch (e) {
+            showToast(`${t('security.admin_users.action_failed')}: ${e}`, 'error');
+        }
+    };
+
+    const handleUpdate = async (id: string, request: Partial<{ role: AdminRole; password: string; disabled: boolean }>) => {
+        try {
+            await invoke('update_admin_user', { id, request });
+            loadUsers();
+        } catch (e) {
+            showToast(`${t('security.admin_users.action_failed')}: ${e}`, 'error');
+        }
+    };
+
+    const handleResetPassword = (user: AdminUser) => {
+        const password = prompt(t('security.admin_users.new_password_prompt', { username: user.username }));
+        if (password) {
+            handleUpdate(user.id, { password });
+        }
+    };
+
+    const handleDelete = async (user: AdminUser) => {
+        if (!confirm(t('security.admin_users.confirm_delete', { username: user.username }))) return;
+        try {
+            await invoke('delete_admin_user', { id: user.id });
+            loadUsers();
+        } catch (e) {
+            showToast(`${t('security.admin_users.action_failed')}: ${e}`, 'error');
+        }
+    };
+
+    const handleResetTotp = async (user: AdminUser) => {
+        if (!confirm(t('security.admin_users.confirm_reset_totp', { username: user.username }))) return;
+        try {
+            await invoke('reset_admin_user_totp', { id: user.id });
+            loadUsers();
+        } catch (e) {
+            showToast(`${t('security.admin_users.action_failed')}: ${e}`, 'error');
+        }
+    };
+
+    const handleTotpSetup = async () => {
+        try {
+            setTotpSetup(await invoke<TotpSetup>('setup_admin_totp'));
+            setTotpCode('');
+        } catch (e) {
+            showToast(`${t('security.admin_users.action_failed')}: ${e}`, 'error');
+        }
+    };
+
+    const handleTotpConfirm = async (enable: boolean) => {
+        try {
+            await invoke(enable ? 'enable_admin_totp' : 'disable_admin_totp', { code: totpCode });
+            setTotpSetup(null);
+            setTotpCode('');
+            showToast(t(enable ? 'security.admin_users.totp_enabled' : 'security.admin_users.totp_disabled'), 'success');
+            loadUsers();
+        } catch (e) {
+            showToast(e === 'invalid_totp' ? t('login.error_invalid_totp') : `${t('security.admin_users.action_failed')}: ${e}`, 'error');
+        }
+    };
+
+    const formatTime = (ts?: number | null) => (ts ? new Date(ts * 1000).toLocaleString() : '-');
+    const isOwner = me?.role === 'owner';
+
+    return (
+        <div className="flex flex-col h-full bg-white dark:bg-base-100 rounded-xl">
+            {isOwner && (
+                <div className="p-5 border-b border-gray-100 dark:border-base-200 flex items-center gap-4">
+                    <button
+                        onClick={() => {
+                            setNewRole(users.length === 0 ? 'owner' : 'operator');
+                            setIsAddOpen(true);
+                        }}
+                        className="px-4 py-2 bg-white dark:bg-base-100 text-gray-700 dark:text-gray-300 text-sm font-medium rounded-lg hover:bg-gray-50 dark:hover:bg-base-200 transition-colors flex items-center gap-2 shadow-sm border border-gray-200/50 dark:border-base-300"
+                    >
+                        <Plus size={16} /> {t('security.admin_users.add')}
+                    </button>
+                    <p className="text-xs text-gray-500 dark:text-gray-400 flex-1">
+                        {users.length === 0 ? t('security.admin_users.legacy_hint') : t('security.admin_users.roles_hint')}
+                    </p>
+                </div>
+            )}
+
+            {/* 当前登录用户的二次验证 (仅 Web 模式下的管理员账号) */}
+            {!isTauri() && me?.user_id && (
+                <div className="mx-4 mt-4 p-4 rounded-lg border border-gray-100 dark:border-base-200 space-y-3">
+                    <div className="flex items-center justify-between">
+                        <span className="text-sm font-medium text-gray-700 dark:text-gray-300 flex items-center gap-2">
+                            <ShieldCheck size={16} className={me.totp_enabled ? 'text-green-500' : 'text-gray-400'} />
+                            {t('security.admin_users.totp_title', { username: me.username })}
+                        </span>
+                        {!me.totp_enabled && !totpSetup && (
+                            <button className="btn btn-xs" onClick={handleTotpSetup}>
+                                {t('security.admin_users.totp_setup')}
+                            </button>
+                        )}
+                    </div>
+                    {totpSetup && (
+                        <div className="text-xs text-gray-500 dark:text-gray-400 space-y-1">
+                            <p>{t('security.admin_users.totp_setup_hint')}</p>
+                            <div className="flex items-center gap-1">
+                                <code className="font-mono break-all">{totpSetup.secret}</code>
+                                <button className="btn btn-ghost btn-xs px-1" onClick={() => copyToClipboard(totpSetup.secret)}>
+                                    <Copy size={10} />
+                                </button>
+                            </div>
+                            <div className="flex items-center gap-1">
+                                <code className="font-mono break-all">{totpSetup.otpauth_url}</code>
+                                <button className="btn btn-ghost btn-xs px-1" onClick={() => copyToClipboard(totpSetup.otpauth_url)}>
+                                    <Copy size={10} />
+                                </button>
+                            </div>
+                        </div>
+                    )}
+                    {(totpSetup || me.totp_enabled) && (
+                        <div className="flex items-center gap-2">
+                            <input
+                                type="text"
+                                inputMode="numeric"
+                                maxLength={6}
+                                className="input input-sm input-bordered w-32 font-mono tracking-widest"
+                                placeholder={t('login.totp_placeholder')}
+                                value={totpCode}
+                                onChange={(e) => setTotpCode(e.target.value.replace(/\D/g, ''))}
+                            />
+                            <button
+                                className="btn btn-sm"
+                                disabled={totpCode.length !== 6}
+                                onClick={() => handleTotpConfirm(!me.totp_enabled)}
+                            >
+                                {me.totp_enabled ? t('security.admin_users.totp_disable') : t('security.admin_users.totp_enable')}
+                            </button>
+                        </div>
+                    )}
+                </div>
+            )}
+
+            {isOwner && (
+                <div className="flex-1 overflow-auto p-4">
+                    <table className="table table-sm w-full">
+                        <thead>
+                            <tr>
+                                <th>{t('security.admin_users.username')}</th>
+                                <th>{t('security.admin_users.role')}</th>
+                                <th>{t('security.admin_users.status')}</th>
+                                <th>TOTP</th>
+                                <th>{t('security.admin_users.last_login')}</th>
+                                <th></th>
+                            </tr>
+                        </thead>
+                        <tbody>
+                            {users.map(user => (
+                                <tr key={user.id} className={user.disabled ? 'opacity-50' : ''}>
+                                    <td className="font-medium">
+                                        {user.username}
+                                        {me?.user_id === user.id && (
+                                            <span className="ml-2 text-[10px] text-blue-500">{t('security.admin_users.you')}</span>
+                                        )}
+                                    </td>
+                                    <td>
+                                        <select
+                                            className="select select-xs select-bordered"
+                                            value={user.role}
+                                            onChange={(e) => handleUpdate(user.id, { role: e.target.value as AdminRole })}
+                                        >
+                                            {ROLES.map(role => (
+                                                <option key={role} value={role}>{t(`security.admin_users.role_${role}`)}</option>
+                                            ))}
+                                        </select>
+                                    </td>
+                                    <td>
+                                        <input
+                                            type="checkbox"
+                                            className="toggle toggle-xs"
+                                            checked={!user.disabled}
+                                            onChange={(e) => handleUpdate(user.id, { disabled: !e.target.checked })}
+                                        />
+                                    </td>
+                                    <td>
+                                        {user.totp_enabled ? (
+                                            <button
+                                                className="btn btn-ghost btn-xs text-green-600 gap-1"
+                                                title={t('security.admin_users.reset_totp')}
+                                                onClick={() => handleResetTotp(user)}
+                                            >
+                                                <ShieldCheck size={14} />
+                                            </button>
+                                        ) : (
+                                            <ShieldOff size={14} className="text-gray-300 ml-2" />
+                                        )}
+                                    </td>
+                                    <td className="text-xs text-gray-500">{formatTime(user.last_login_at)}</td>
+                                    <td className="text-right whitespace-nowrap">
+                                        <button
+                                            className="btn btn-ghost btn-xs"
+                                            title={t('security.admin_users.reset_password')}
+                                            onClick={() => handleResetPassword(user)}
+                                        >
+                                            <KeyRound size={14} />
+                                        </button>
+                                        <button
+                                            className="btn btn-ghost btn-xs text-red-500"
+                                            title={t('security.admin_users.delete')}
+                                            onClick={() => handleDelete(user)}
+                                        >
+                                            <Trash2 size={14} />
+                                        </button>
+                                    </td>
+                                </tr>
+                            ))}
+                        </tbody>
+                    </table>
+                    {!loading && users.length === 0 && (
+                        <div className="text-center py-10 text-gray-400 flex flex-col items-center gap-2">
+                            <UserCog size={32} />
+                            {t('security.admin_users.no_data')}
+                        </div>
+                    )}
+                </div>
+            )}
+
+            {/* Add Modal */}
+            {isAddOpen && (
+                <div className="fixed inset-0 z-50 flex items-center justify-center bg-black/50 backdrop-blur-sm">
+                    <div className="bg-white dark:bg-base-100 rounded-lg shadow-xl w-full max-w-md p-6">
+                        <div className="flex justify-between items-center mb-4">
+                            <h3 className="text-lg font-bold">{t('security.admin_users.add_title')}</h3>
+                            <button onClick={() => setIsAddOpen(false)} className="btn btn-ghost btn-sm btn-circle">
+                                <X size={18} />
+                            </button>
+                        </div>
+
+                        <div className="space-y-4">
+                            <div>
+                                <label className="label">{t('security.admin_users.username')}</label>
+                                <input
+                                    type="text"
+                                    className="input input-bordered w-full"
+                                    value={newUsername}
+                                    onChange={e => setNewUsername(e.target.value)}
+                                />
+                            </div>
+                            <div>
+                                <label className="label">{t('security.admin_users.password')}</label>
+                                <input
+                                    type="password"
+                                    autoComplete="new-password"
+                                    className="input input-bordered w-full"
+                                    placeholder={t('security.admin_users.password_placeholder')}
+                                    value={newPassword}
+                                    onChange={e => setNewPassword(e.target.value)}
+                                />
+                            </div>
+                            <div>
+                                <label className="label">{t('security.admin_users.role')}</label>
+                                <select
+                                    className="select select-bordered w-full"
+                                    value={newRole}
+                                    disabled={users.length === 0}
+                                    onChange={e => setNewRole(e.target.value as AdminRole)}
+                                >
+                                    {ROLES.map(role => (
+                                        <option key={role} value={role}>{t(`security.admin_users.role_${role}`)}</option>
+                                    ))}
+                                </select>
+                            </div>
+
+                            <div className="flex justify-end gap-3 mt-6">
+                                <button
+                                    className="px-4 py-2 bg-gray-100 dark:bg-base-200 text-gray-700 dark:text-gray-300 text-sm font-medium rounded-lg hover:bg-gray-200 dark:hover:bg-base-300 transition-colors"
+                                    onClick={() => setIsAddOpen(false)}
+                                >
+                                    {t('security.whitelist.cancel')}
+                                </button>
+                                <button
+                                    className="px-4 py-2 bg-blue-500 hover:bg-blue-600 text-white text-sm font-medium rounded-lg shadow-lg shadow-blue-500/20 transition-all disabled:opacity-50 disabled:cursor-not-allowed"
+                                    onClick={handleAdd}
+                                    disabled={!newUsername.trim() || newPassword.length < 8}
+                                >
+                                    {t('security.admin_users.add_btn')}
+                                </button>
+                            </div>
+                        </div>
+                    </div>
+                </div>
+            )}
+        </div>
+    );
+};
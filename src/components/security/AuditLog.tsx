@@ -0,0 +1,312 @@
+import React, { Fragment, useEffect, useState } from 'react';
+import { useTranslation } from 'react-i18next';
+import { request as invoke } from '../../utils/request';
+import { isTauri } from '../../utils/env';
+import { showToast } from '../common/ToastContainer';
+import { Search, Download, ChevronDown, ChevronRight } from 'lucide-react';
+
+interface AuditEntry {
+    id: number;
+    timestamp: number;
+    source: 'web' | 'desktop';
+    actor: string;
+    actor_role?: string;
+    client_ip?: string;
+    action: string;
+    target?: string;
+    details?: any;
+    success: boolean;
+    status?: number;
+}
+
+interface AuditPage {
+    total: number;
+    entries: AuditEntry[];
+}
+
+interface AuditFilter {
+    actor?: string;
+    action?: string;
+    source?: string;
+    search?: string;
+}
+
+interface ConfigChange {
+    path: string;
+    before: any;
+    after: any;
+}
+
+interface Props {
+    refreshKey?: number;
+}
+
+const formatValue = (value: any) => (value === null || value === undefined ? '-' : typeof value === 'string' ? value : JSON.stringify(value));
+
+export const AuditLog: React.FC<Props> = ({ refreshKey }) => {
+    const { t } = useTranslation();
+    const [entries, setEntries] = useState<AuditEntry[]>([]);
+    const [total, setTotal] = useState(0);
+    const [loading, setLoading] = useState(false);
+    const [page, setPage] = useState(1);
+    const [pageSize, setPageSize] = useState(50);
+    const [search, setSearch] = useState('');
+    const [actor, setActor] = useState('');
+    const [source, setSource] = useState('');
+    const [expanded, setExpanded] = useState<number | null>(null);
+    const [exportFormat, setExportFormat] = useState<'jsonl' | 'csv'>('jsonl');
+
+    const buildFilter = (): AuditFilter => ({
+        actor: actor.trim() || undefined,
+        source: source || undefined,
+        search: search.trim() || undefined,
+    });
+
+    const loadEntries = async () => {
+        setLoading(true);
+        try {
+            const filter = buildFilter();
+            const limit = pageSize;
+            const offset = (page - 1) * pageSize;
+            // 桌面端命令接收 filter 对象；Web 模式下作为查询参数平铺
+            const res = await invoke<AuditPage>(
+                'get_audit_logs',
+                isTauri() ? { filter, limit, offset } : { ...filter, limit, offset },
+            );
+            setEntries(res.entries);
+            setTotal(res.total);
+        } catch (e) {
+            console.error('Failed to load audit log', e);
+        } finally {
+            setLoading(false);
+        }
+    };
+
+    useEffect(() => {
+        loadEntries();
+    }, [page, pageSize, source, refreshKey]);
+
+    const handleSearch = () => {
+        setPage(1);
+        loadEntries();
+    };
+
+    const handleExport = async () => {
+        const filter = buildFilter();
+        const fileName = `audit-log-${new Date().toISOString().split('T')[0]}.${exportFormat}`;
+        try {
+            if (isTauri()) {
+                const { save } = await import('@tauri-apps/plugin-dialog');
+                const path = await save({
+                    filters: [{ name: exportFormat.toUpperCase(), extensions: [exportFormat] }],
+                    defaultPath: fileName,
+                });
+                if (!path) return;
+                const count = await invoke<number>('export_audit_logs', { filePath: path, filter, format: exportFormat });
+                showToast(t('security.audit.export_success', { count }), 'success');
+            } else {
+                const params = new URLSearchParams({ format: exportFormat });
+                Object.entries(filter).forEach(([key, value]) => value && params.append(key, value));
+                const apiKey = sessionStorage.getItem('abv_admin_api_key');
+                const response = await fetch(`/api/audit/export?${params.toString()}`, {
+                    headers: apiKey ? { Authorization: `Bearer ${apiKey}` } : {},
+                });
+                if (!response.ok) throw `HTTP Error ${response.status}`;
+                const url = URL.createObjectURL(await response.blob());
+                const a = document.createElement('a');
+                a.href = url;
+                a.download = fileName;
+                document.body.appendChild(a);
+                a.click();
+                document.body.removeChild(a);
+                URL.revokeObjectURL(url);
+            }
+        } catch (e) {
+            showToast(`${t('common.error')}: ${e}`, 'error');
+        }
+    };
+
+    const renderDetails = (entry: AuditEntry) => {
+        const changes: ConfigChange[] | undefined = entry.details?.changes;
+        if (changes) {
+            return (
+                <table className="table table-xs w-full">
+                    <thead>
+                        <tr>
+                            <th>{t('security.audit.field')}</th>
+                            <th>{t('security.audit.before')}</th>
+                            <th>{t('security.audit.after')}</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {changes.map((change) => (
+                            <tr key={change.path}>
+                                <td className="font-mono">{change.path}</td>
+                                <td className="font-mono text-red-500 break-all">{formatValue(change.before)}</td>
+                                <td className="font-mono text-green-600 break-all">{formatValue(change.after)}</td>
+                            </tr>
+                        ))}
+                        {entry.details?.truncated && (
+                            <tr>
+                                <td colSpan={3} className="text-gray-400">{t('security.audit.truncated')}</td>
+                            </tr>
+                        )}
+                    </tbody>
+                </table>
+            );
+        }
+        return (
+            <pre className="text-xs font-mono whitespace-pre-wrap break-all text-gray-600 dark:text-gray-400">
+                {JSON.stringify(entry.details, null, 2)}
+            </pre>
+        );
+    };
+
+    return (
+        <div className="flex flex-col h-full bg-white dark:bg-base-100 rounded-xl">
+            {/* Toolbar */}
+            <div className="p-5 border-b border-gray-100 dark:border-base-200 flex flex-wrap items-center gap-4">
+                <div className="relative flex-1 min-w-[200px] max-w-md">
+                    <Search className="absolute left-3 top-2.5 text-gray-400" size={16} />
+                    <input
+                        type="text"
+                        placeholder={t('security.audit.search_placeholder')}
+                        className="input input-sm input-bordered w-full pl-9"
+                        value={search}
+                        onChange={(e) => setSearch(e.target.value)}
+                        onKeyDown={(e) => e.key === 'Enter' && handleSearch()}
+                        onBlur={handleSearch}
+                    />
+                </div>
+
+                <input
+                    type="text"
+                    placeholder={t('security.audit.actor_placeholder')}
+                    className="input input-sm input-bordered w-36"
+                    value={actor}
+                    onChange={(e) => setActor(e.target.value)}
+                    onKeyDown={(e) => e.key === 'Enter' && handleSearch()}
+                    onBlur={handleSearch}
+                />
+
+                <select
+                    className="select select-sm select-bordered"
+                    value={source}
+                    onChange={(e) => { setSource(e.target.value); setPage(1); }}
+                >
+                    <option value="">{t('security.audit.source_all')}</option>
+                    <option value="web">{t('security.audit.source_web')}</option>
+                    <option value="desktop">{t('security.audit.source_desktop')}</option>
+                </select>
+
+                <div className="flex-1"></div>
+
+                <div className="flex items-center gap-2 shrink-0">
+                    <select
+                        className="select select-sm select-bordered"
+                        value={exportFormat}
+                        onChange={(e) => setExportFormat(e.target.value as 'jsonl' | 'csv')}
+                    >
+                        <option value="jsonl">JSONL</option>
+                        <option value="csv">CSV</option>
+                    </select>
+                    <button className="btn btn-sm gap-2" onClick={handleExport}>
+                        <Download size={14} />
+                        {t('security.audit.export')}
+                    </button>
+                    <select
+                        className="select select-sm select-bordered min-w-[100px]"
+                        value={pageSize}
+                        onChange={(e) => { setPageSize(Number(e.target.value)); setPage(1); }}
+                    >
+                        <option value="20">20{t('security.logs.per_page_suffix')}</option>
+                        <option value="50">50{t('security.logs.per_page_suffix')}</option>
+                        <option value="100">100{t('security.logs.per_page_suffix')}</option>
+                    </select>
+                </div>
+            </div>
+
+            {/* Table */}
+            <div className="flex-1 overflow-auto">
+                <table className="table table-xs w-full">
+                    <thead className="sticky top-0 bg-gray-100 dark:bg-base-200 z-10 shadow-sm text-gray-600 dark:text-gray-400">
+                        <tr>
+                            <th className="w-6"></th>
+                            <th className="w-36">{t('security.logs.time')}</th>
+                            <th className="w-32">{t('security.audit.actor')}</th>
+                            <th className="w-32">{t('security.logs.ip_address')}</th>
+                            <th className="">{t('security.audit.action')}</th>
+                            <th className="">{t('security.audit.target')}</th>
+                            <th className="w-20">{t('security.logs.status')}</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {entries.map((entry) => (
+                            <Fragment key={entry.id}>
+                                <tr
+                                    className={`hover:bg-gray-50 dark:hover:bg-base-200 ${entry.details ? 'cursor-pointer' : ''}`}
+                                    onClick={() => entry.details && setExpanded(expanded === entry.id ? null : entry.id)}
+                                >
+                                    <td className="text-gray-400">
+                                        {entry.details && (expanded === entry.id ? <ChevronDown size={12} /> : <ChevronRight size={12} />)}
+                                    </td>
+                                    <td className="text-xs text-gray-500">{new Date(entry.timestamp * 1000).toLocaleString()}</td>
+                                    <td>
+                                        <div className="font-medium text-blue-600 dark:text-blue-400">{entry.actor}</div>
+                                        <div className="text-[10px] text-gray-400">
+                                            {entry.source === 'web' ? t('security.audit.source_web') : t('security.audit.source_desktop')}
+                                            {entry.actor_role ? ` · ${entry.actor_role}` : ''}
+                                        </div>
+                                    </td>
+                                    <td className="font-mono">{entry.client_ip || '-'}</td>
+                                    <td className="font-mono text-xs">{entry.action}</td>
+                                    <td className="max-w-xs truncate text-gray-600 dark:text-gray-400" title={entry.target}>{entry.target || '-'}</td>
+                                    <td>
+                                        <span className={`badge badge-xs text-white border-none ${entry.success ? 'badge-success' : 'badge-error'}`}>
+                                            {entry.status ?? (entry.success ? 'OK' : '-')}
+                                        </span>
+                                    </td>
+                                </tr>
+                                {expanded === entry.id && (
+                                    <tr className="bg-gray-50 dark:bg-base-200">
+                                        <td></td>
+                                        <td colSpan={6} className="py-2">{renderDetails(entry)}</td>
+                                    </tr>
+                                )}
+                            </Fragment>
+                        ))}
+                        {!loading && entries.length === 0 && (
+                            <tr>
+                                <td colSpan={7} className="text-center py-10 text-gray-400">
+                                    {t('security.audit.empty')}
+                                </td>
+                            </tr>
+                        )}
+                    </tbody>
+                </table>
+            </div>
+
+            {/* Pagination */}
+            <div className="p-3 border-t border-gray-100 dark:border-base-200 flex items-center justify-between text-xs text-gray-500 bg-gray-50 dark:bg-base-200">
+                <span>{t('security.logs.total_records', { total })}</span>
+                <div className="flex gap-2">
+                    <button
+                        className="btn btn-xs"
+                        disabled={page <= 1}
+                        onClick={() => setPage(p => p - 1)}
+                    >
+                        {t('security.logs.prev_page')}
+                    </button>
+                    <button className="btn btn-xs btn-active">{t('security.logs.page_num', { page })}</button>
+                    <button
+                        className="btn btn-xs"
+                        disabled={page * pageSize >= total}
+                        onClick={() => setPage(p => p + 1)}
+                    >
+                        {t('security.logs.next_page')}
+                    </button>
+                </div>
+            </div>
+        </div>
+    );
+};
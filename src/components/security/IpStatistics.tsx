@@ -16,6 +16,8 @@ interface IpStatsResponse {
     unique_ips: number;
     blocked_requests: number;
     top_ips: IpRanking[];
+    auto_ban_count?: number;
+    ban_events_24h?: number;
 }
 
 interface IpTokenStats {
@@ -109,6 +111,11 @@ export const IpStatistics: React.FC<Props> = ({ refreshKey }) => {
                         <div className="stat-title">{t('security.stats.blocked_requests')}</div>
                         <div className="stat-value text-red-500">{formatCompactNumber(stats.blocked_requests)}</div>
                         <div className="stat-desc">{t('security.stats.blocked_requests_desc')}</div>
+                        {(stats.auto_ban_count ?? 0) + (stats.ban_events_24h ?? 0) > 0 && (
+                            <div className="stat-desc text-orange-500">
+                                {t('security.stats.auto_bans', { active: stats.auto_ban_count ?? 0, recent: stats.ban_events_24h ?? 0 })}
+                            </div>
+                        )}
                     </div>
                 </div>
 
@@ -1,7 +1,7 @@
 import React, { useEffect, useState } from 'react';
 import { useTranslation } from 'react-i18next';
 import { request as invoke } from '../../utils/request';
-import { Save, AlertTriangle, Shield, ShieldCheck } from 'lucide-react';
+import { Save, AlertTriangle, Shield, ShieldCheck, Network, Ban, ClipboardList } from 'lucide-react';
 import { showToast } from '../common/ToastContainer';
 
 interface IpBlacklistConfig {
@@ -14,9 +14,35 @@ interface IpWhitelistConfig {
     whitelist_priority: boolean;
 }
 
+interface AutoBanRule {
+    threshold: number;
+    window_secs: number;
+}
+
+interface AutoBanConfig {
+    enabled: boolean;
+    auth_failures: AutoBanRule;
+    invalid_tokens: AutoBanRule;
+    request_rate: AutoBanRule;
+    ban_duration_secs: number;
+    escalation_factor: number;
+    max_ban_duration_secs: number;
+    history_secs: number;
+}
+
+interface AuditLogConfig {
+    retention_days: number;
+}
+
+type AutoBanRuleKey = 'auth_failures' | 'invalid_tokens' | 'request_rate';
+
 interface SecurityMonitorConfig {
     blacklist: IpBlacklistConfig;
     whitelist: IpWhitelistConfig;
+    trusted_proxies: string[];
+    trust_cloudflared: boolean;
+    auto_ban: AutoBanConfig;
+    audit_log: AuditLogConfig;
 }
 
 export const SecurityConfig: React.FC = () => {
@@ -42,6 +68,16 @@ export const SecurityConfig: React.FC = () => {
         }
     };
 
+    const updateAutoBan = (patch: Partial<AutoBanConfig>) => {
+        if (!config) return;
+        setConfig({ ...config, auto_ban: { ...config.auto_ban, ...patch } });
+    };
+
+    const updateAutoBanRule = (key: AutoBanRuleKey, patch: Partial<AutoBanRule>) => {
+        if (!config) return;
+        updateAutoBan({ [key]: { ...config.auto_ban[key], ...patch } });
+    };
+
     const handleSave = async () => {
         if (!config) return;
         setSaving(true);
@@ -170,6 +206,179 @@ export const SecurityConfig: React.FC = () => {
                     </div>
                 </div>
             </div>
+
+            {/* Trusted Proxy Settings */}
+            <div className="card bg-base-100 border border-gray-200 dark:border-base-300 shadow-sm">
+                <div className="card-body">
+                    <h3 className="card-title flex items-center gap-2 text-blue-500">
+                        <Network size={24} />
+                        {t('security.config.trusted_proxies_title')}
+                    </h3>
+                    <p className="text-sm text-gray-500 mb-4">{t('security.config.trusted_proxies_desc')}</p>
+
+                    <div className="form-control w-full">
+                        <textarea
+                            className="textarea textarea-bordered w-full font-mono text-sm"
+                            rows={4}
+                            value={(config.trusted_proxies ?? []).join('\n')}
+                            placeholder={'127.0.0.1/8\n::1/128\n172.16.0.0/12'}
+                            onChange={(e) => setConfig({
+                                ...config,
+                                trusted_proxies: e.target.value.split('\n').map(s => s.trim()).filter(Boolean)
+                            })}
+                        />
+                    </div>
+
+                    <div className="form-control mt-4">
+                        <label className="label cursor-pointer justify-start gap-4">
+                            <input
+                                type="checkbox"
+                                className="checkbox checkbox-info"
+                                checked={config.trust_cloudflared ?? true}
+                                onChange={(e) => setConfig({ ...config, trust_cloudflared: e.target.checked })}
+                            />
+                            <span className="label-text font-medium">{t('security.config.trust_cloudflared')}</span>
+                        </label>
+                        <label className="label ml-8 pt-0">
+                            <span className="label-text-alt text-gray-400">{t('security.config.trust_cloudflared_desc')}</span>
+                        </label>
+                    </div>
+                </div>
+            </div>
+
+            {/* Auto Ban Settings */}
+            <div className="card bg-base-100 border border-gray-200 dark:border-base-300 shadow-sm">
+                <div className="card-body">
+                    <h3 className="card-title flex items-center gap-2 text-orange-500">
+                        <Ban size={24} />
+                        {t('security.config.auto_ban_title')}
+                    </h3>
+                    <p className="text-sm text-gray-500 mb-4">{t('security.config.auto_ban_desc')}</p>
+
+                    <div className="form-control">
+                        <label className="label cursor-pointer justify-start gap-4">
+                            <input
+                                type="checkbox"
+                                className="toggle toggle-warning"
+                                checked={config.auto_ban.enabled}
+                                onChange={(e) => updateAutoBan({ enabled: e.target.checked })}
+                            />
+                            <span className="label-text font-medium">{t('security.config.enable_auto_ban')}</span>
+                        </label>
+                    </div>
+
+                    <div className="overflow-x-auto mt-4">
+                        <table className="table table-sm">
+                            <thead>
+                                <tr>
+                                    <th>{t('security.config.auto_ban_rule')}</th>
+                                    <th>{t('security.config.auto_ban_threshold')}</th>
+                                    <th>{t('security.config.auto_ban_window')}</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {(['auth_failures', 'invalid_tokens', 'request_rate'] as AutoBanRuleKey[]).map(key => (
+                                    <tr key={key}>
+                                        <td>{t(`security.config.auto_ban_rules.${key}`)}</td>
+                                        <td>
+                                            <input
+                                                type="number"
+                                                min={0}
+                                                className="input input-bordered input-sm w-24"
+                                                value={config.auto_ban[key].threshold}
+                                                disabled={!config.auto_ban.enabled}
+                                                onChange={(e) => updateAutoBanRule(key, { threshold: Math.max(0, Number(e.target.value) || 0) })}
+                                            />
+                                        </td>
+                                        <td>
+                                            <input
+                                                type="number"
+                                                min={1}
+                                                className="input input-bordered input-sm w-24"
+                                                value={config.auto_ban[key].window_secs}
+                                                disabled={!config.auto_ban.enabled}
+                                                onChange={(e) => updateAutoBanRule(key, { window_secs: Math.max(1, Number(e.target.value) || 1) })}
+                                            />
+                                        </td>
+                                    </tr>
+                                ))}
+                            </tbody>
+                        </table>
+                        <label className="label">
+                            <span className="label-text-alt text-gray-400">{t('security.config.auto_ban_threshold_hint')}</span>
+                        </label>
+                    </div>
+
+                    <div className="grid grid-cols-1 md:grid-cols-3 gap-4 mt-2">
+                        <div className="form-control">
+                            <label className="label">
+                                <span className="label-text">{t('security.config.auto_ban_duration')}</span>
+                            </label>
+                            <input
+                                type="number"
+                                min={1}
+                                className="input input-bordered input-sm"
+                                value={Math.round(config.auto_ban.ban_duration_secs / 60)}
+                                disabled={!config.auto_ban.enabled}
+                                onChange={(e) => updateAutoBan({ ban_duration_secs: Math.max(1, Number(e.target.value) || 1) * 60 })}
+                            />
+                        </div>
+                        <div className="form-control">
+                            <label className="label">
+                                <span className="label-text">{t('security.config.auto_ban_escalation')}</span>
+                            </label>
+                            <input
+                                type="number"
+                                min={1}
+                                className="input input-bordered input-sm"
+                                value={config.auto_ban.escalation_factor}
+                                disabled={!config.auto_ban.enabled}
+                                onChange={(e) => updateAutoBan({ escalation_factor: Math.max(1, Number(e.target.value) || 1) })}
+                            />
+                        </div>
+                        <div className="form-control">
+                            <label className="label">
+                                <span className="label-text">{t('security.config.auto_ban_max_duration')}</span>
+                            </label>
+                            <input
+                                type="number"
+                                min={1}
+                                className="input input-bordered input-sm"
+                                value={Math.round(config.auto_ban.max_ban_duration_secs / 3600)}
+                                disabled={!config.auto_ban.enabled}
+                                onChange={(e) => updateAutoBan({ max_ban_duration_secs: Math.max(1, Number(e.target.value) || 1) * 3600 })}
+                            />
+                        </div>
+                    </div>
+                </div>
+            </div>
+
+            {/* Audit Log Settings */}
+            <div className="card bg-base-100 border border-gray-200 dark:border-base-300 shadow-sm">
+                <div className="card-body">
+                    <h3 className="card-title flex items-center gap-2 text-indigo-500">
+                        <ClipboardList size={24} />
+                        {t('security.config.audit_title')}
+                    </h3>
+                    <p className="text-sm text-gray-500 mb-4">{t('security.config.audit_desc')}</p>
+
+                    <div className="form-control max-w-xs">
+                        <label className="label">
+                            <span className="label-text">{t('security.config.audit_retention_days')}</span>
+                        </label>
+                        <input
+                            type="number"
+                            min={0}
+                            className="input input-bordered input-sm"
+                            value={config.audit_log.retention_days}
+                            onChange={(e) => setConfig({ ...config, audit_log: { retention_days: Math.max(0, Number(e.target.value) || 0) } })}
+                        />
+                        <label className="label">
+                            <span className="label-text-alt text-gray-400">{t('security.config.audit_retention_hint')}</span>
+                        </label>
+                    </div>
+                </div>
+            </div>
         </div>
     );
 };
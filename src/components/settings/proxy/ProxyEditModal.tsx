@@ -28,6 +28,7 @@ export default function ProxyEditModal({ isOpen, onClose, onSave, initialData, i
             password: ''
         },
         max_accounts: 0,
+        weight: 1,
         is_healthy: true,
         health_check_url: ''
     });
@@ -48,6 +49,7 @@ export default function ProxyEditModal({ isOpen, onClose, onSave, initialData, i
                     tags: [],
                     auth: { username: '', password: '' },
                     max_accounts: 0,
+                    weight: 1,
                     is_healthy: true,
                     health_check_url: ''
                 });
@@ -185,6 +187,18 @@ export default function ProxyEditModal({ isOpen, onClose, onSave, initialData, i
                                 className="w-full px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-700 text-gray-900 dark:text-white"
                             />
                         </div>
+                        <div>
+                            <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+                                {t('settings.proxy_pool.weight', 'Weight')} ({t('settings.proxy_pool.weight_hint', 'Weighted round-robin')})
+                            </label>
+                            <input
+                                type="number"
+                                min={1}
+                                value={formData.weight ?? 1}
+                                onChange={e => setFormData({ ...formData, weight: Math.max(1, parseInt(e.target.value) || 1) })}
+                                className="w-full px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-700 text-gray-900 dark:text-white"
+                            />
+                        </div>
                         <div className="col-span-2">
                             <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
                                 {t('settings.proxy_pool.health_check_url', 'Health Check URL')}
@@ -32,6 +32,7 @@ import { cn } from '../utils/cn';
 import { useProxyModels } from '../hooks/useProxyModels';
 import GroupedSelect, { SelectOption } from '../components/common/GroupedSelect';
 import { CliSyncCard } from '../components/proxy/CliSyncCard';
+import { TlsSettings } from '../components/proxy/TlsSettings';
 import DebouncedSlider from '../components/common/DebouncedSlider';
 import { listAccounts } from '../services/accountService';
 import CircuitBreaker from '../components/settings/CircuitBreaker';
@@ -1237,6 +1238,10 @@ print(response.choices[0].message.content)`;
                                                 {t('proxy.config.allow_lan_access_restart_hint')}
                                             </p>
                                         )}
+                                        <TlsSettings
+                                            config={appConfig.proxy.tls}
+                                            onChange={(tls) => updateProxyConfig({ tls })}
+                                        />
                                     </div>
 
                                     {/* 访问授权 */}
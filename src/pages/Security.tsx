@@ -1,15 +1,17 @@
 import React, { useState } from 'react';
 import { useTranslation } from 'react-i18next';
-import { Shield, Lock, FileText, Settings, Activity, RefreshCw } from 'lucide-react';
+import { Shield, Lock, FileText, Settings, Activity, RefreshCw, Users, ClipboardList } from 'lucide-react';
 import { IpAccessLogs } from '../components/security/IpAccessLogs';
 import { BlacklistManager } from '../components/security/BlacklistManager';
 import { WhitelistManager } from '../components/security/WhitelistManager';
 import { SecurityConfig } from '../components/security/SecurityConfig';
 import { IpStatistics } from '../components/security/IpStatistics';
+import { AdminUsersManager } from '../components/security/AdminUsersManager';
+import { AuditLog } from '../components/security/AuditLog';
 
 const Security: React.FC = () => {
     const { t } = useTranslation();
-    const [activeTab, setActiveTab] = useState<'logs' | 'stats' | 'blacklist' | 'whitelist' | 'config'>('logs');
+    const [activeTab, setActiveTab] = useState<'logs' | 'stats' | 'blacklist' | 'whitelist' | 'config' | 'admins' | 'audit'>('logs');
     const [refreshKey, setRefreshKey] = useState(0);
 
     const handleRefresh = () => {
@@ -28,6 +30,10 @@ const Security: React.FC = () => {
                 return <WhitelistManager refreshKey={refreshKey} />;
             case 'config':
                 return <SecurityConfig />;
+            case 'admins':
+                return <AdminUsersManager refreshKey={refreshKey} />;
+            case 'audit':
+                return <AuditLog refreshKey={refreshKey} />;
             default:
                 return <IpAccessLogs refreshKey={refreshKey} />;
         }
@@ -39,6 +45,8 @@ const Security: React.FC = () => {
         { id: 'blacklist', label: t('security.tab_blacklist'), icon: Shield },
         { id: 'whitelist', label: t('security.tab_whitelist'), icon: Lock },
         { id: 'config', label: t('security.tab_config'), icon: Settings },
+        { id: 'admins', label: t('security.tab_admins'), icon: Users },
+        { id: 'audit', label: t('security.tab_audit'), icon: ClipboardList },
     ];
 
     return (
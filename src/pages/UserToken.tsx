@@ -22,6 +22,12 @@ interface UserToken {
     last_used_at?: number;
     total_requests: number;
     total_tokens_used: number;
+    response_cache?: boolean;
+    log_metadata_only?: boolean;
+    scopes?: string[];
+    deny_tools?: boolean;
+    deny_image_generation?: boolean;
+    deny_thinking?: boolean;
 }
 
 interface UserTokenStats {
@@ -40,6 +46,8 @@ const UserToken: React.FC = () => {
     const [loading, setLoading] = useState(false);
     const [showCreateModal, setShowCreateModal] = useState(false);
     const [creating, setCreating] = useState(false);
+    // 新创建的完整令牌，仅展示一次
+    const [createdToken, setCreatedToken] = useState<string | null>(null);
 
     // Edit State
     const [showEditModal, setShowEditModal] = useState(false);
@@ -49,6 +57,12 @@ const UserToken: React.FC = () => {
     const [editMaxIps, setEditMaxIps] = useState(0);
     const [editCurfewStart, setEditCurfewStart] = useState('');
     const [editCurfewEnd, setEditCurfewEnd] = useState('');
+    const [editResponseCache, setEditResponseCache] = useState(false);
+    const [editLogMetadataOnly, setEditLogMetadataOnly] = useState(false);
+    const [editScopes, setEditScopes] = useState('');
+    const [editDenyTools, setEditDenyTools] = useState(false);
+    const [editDenyImageGeneration, setEditDenyImageGeneration] = useState(false);
+    const [editDenyThinking, setEditDenyThinking] = useState(false);
     const [updating, setUpdating] = useState(false);
 
     // Create Form State
@@ -100,7 +114,7 @@ const UserToken: React.FC = () => {
                 ? Math.floor(new Date(newCustomExpires).getTime() / 1000)
                 : undefined;
 
-            await invoke('create_user_token', {
+            const created = await invoke<UserToken>('create_user_token', {
                 request: {
                     username: newUsername,
                     expires_type: newExpiresType,
@@ -113,6 +127,7 @@ const UserToken: React.FC = () => {
             });
             showToast(t('common.create_success') || 'Created successfully', 'success');
             setShowCreateModal(false);
+            setCreatedToken(created.token);
             setNewUsername('');
             setNewDesc('');
             setNewExpiresType('month');
@@ -147,6 +162,12 @@ const UserToken: React.FC = () => {
         setEditMaxIps(token.max_ips ?? 0);  // 使用 ?? 确保 null/undefined 变为 0
         setEditCurfewStart(token.curfew_start ?? '');
         setEditCurfewEnd(token.curfew_end ?? '');
+        setEditResponseCache(token.response_cache ?? false);
+        setEditLogMetadataOnly(token.log_metadata_only ?? false);
+        setEditScopes((token.scopes ?? []).join(', '));
+        setEditDenyTools(token.deny_tools ?? false);
+        setEditDenyImageGeneration(token.deny_image_generation ?? false);
+        setEditDenyThinking(token.deny_thinking ?? false);
         setShowEditModal(true);
     };
 
@@ -167,7 +188,15 @@ const UserToken: React.FC = () => {
                     max_ips: editMaxIps,
                     // 使用双层包装: undefined = 不更新, null = 清空, string = 设置值
                     curfew_start: editCurfewStart === '' ? null : editCurfewStart,
-                    curfew_end: editCurfewEnd === '' ? null : editCurfewEnd
+                    curfew_end: editCurfewEnd === '' ? null : editCurfewEnd,
+                    response_cache: editResponseCache,
+                    log_metadata_only: editLogMetadataOnly,
+                    restrictions: {
+                        scopes: editScopes.split(',').map(s => s.trim()).filter(Boolean),
+                        deny_tools: editDenyTools,
+                        deny_image_generation: editDenyImageGeneration,
+                        deny_thinking: editDenyThinking
+                    }
                 }
             });
             showToast(t('common.update_success') || 'Updated successfully', 'success');
@@ -353,14 +382,8 @@ const UserToken: React.FC = () => {
                                     <td>
                                         <div className="flex items-center gap-2 group/token">
                                             <code className="bg-gray-50 dark:bg-base-200 px-2 py-1 rounded border border-gray-100 dark:border-base-300 text-[11px] font-mono text-gray-600 dark:text-gray-400">
-                                                {token.token.substring(0, 8)}••••••••
+                                                {token.token}••••••••
                                             </code>
-                                            <button
-                                                onClick={() => handleCopyToken(token.token)}
-                                                className="p-1.5 hover:bg-gray-200 dark:hover:bg-base-300 rounded-md transition-all text-gray-400 hover:text-gray-600 dark:hover:text-white"
-                                            >
-                                                <Copy size={13} />
-                                            </button>
                                         </div>
                                     </td>
                                     <td>
@@ -583,6 +606,32 @@ const UserToken: React.FC = () => {
                 </div>
             )}
 
+            {/* Created Token Modal */}
+            {createdToken && (
+                <div className="modal modal-open">
+                    <div className="modal-box">
+                        <h3 className="font-bold text-lg mb-2">{t('user_token.created_title', { defaultValue: 'Token Created' })}</h3>
+                        <p className="text-sm text-gray-500 mb-4">{t('user_token.created_hint', { defaultValue: 'Copy this token now. It is stored hashed and will not be shown again.' })}</p>
+                        <div className="flex items-center gap-2">
+                            <code className="flex-1 bg-gray-50 dark:bg-base-200 px-3 py-2 rounded border border-gray-100 dark:border-base-300 text-xs font-mono text-gray-700 dark:text-gray-300 break-all">
+                                {createdToken}
+                            </code>
+                            <button
+                                onClick={() => handleCopyToken(createdToken)}
+                                className="p-2 hover:bg-gray-200 dark:hover:bg-base-300 rounded-md transition-all text-gray-400 hover:text-gray-600 dark:hover:text-white"
+                            >
+                                <Copy size={15} />
+                            </button>
+                        </div>
+                        <div className="modal-action">
+                            <button className="px-4 py-2 bg-blue-500 hover:bg-blue-600 text-white text-sm font-medium rounded-lg transition-all" onClick={() => setCreatedToken(null)}>
+                                {t('common.close', { defaultValue: 'Close' })}
+                            </button>
+                        </div>
+                    </div>
+                </div>
+            )}
+
             {/* Edit Modal */}
             {showEditModal && editingToken && (
                 <div className="modal modal-open">
@@ -656,6 +705,97 @@ const UserToken: React.FC = () => {
                             </label>
                         </div>
 
+                        <div className="form-control w-full mb-3">
+                            <label className="label cursor-pointer justify-start gap-3">
+                                <input
+                                    type="checkbox"
+                                    className="checkbox checkbox-sm"
+                                    checked={editResponseCache}
+                                    onChange={e => setEditResponseCache(e.target.checked)}
+                                />
+                                <span className="label-text">{t('user_token.response_cache', { defaultValue: 'Response Cache' })}</span>
+                            </label>
+                            <label className="label">
+                                <span className="label-text-alt text-gray-500">{t('user_token.hint_response_cache', { defaultValue: 'Replay cached responses for identical deterministic requests (temperature = 0). Requires the global response cache to be enabled.' })}</span>
+                            </label>
+                        </div>
+
+                        <div className="form-control w-full mb-3">
+                            <label className="label cursor-pointer justify-start gap-3">
+                                <input
+                                    type="checkbox"
+                                    className="checkbox checkbox-sm"
+                                    checked={editLogMetadataOnly}
+                                    onChange={e => setEditLogMetadataOnly(e.target.checked)}
+                                />
+                                <span className="label-text">{t('user_token.log_metadata_only', { defaultValue: 'Log Metadata Only' })}</span>
+                            </label>
+                            <label className="label">
+                                <span className="label-text-alt text-gray-500">{t('user_token.hint_log_metadata_only', { defaultValue: 'Never store request or response bodies for this token; only status, model, tokens and timing are logged.' })}</span>
+                            </label>
+                        </div>
+
+                        <div className="form-control w-full mb-3">
+                            <label className="label">
+                                <span className="label-text">{t('user_token.scopes', { defaultValue: 'Scopes' })}</span>
+                            </label>
+                            <input
+                                type="text"
+                                className="input input-bordered w-full font-mono text-sm"
+                                value={editScopes}
+                                onChange={e => setEditScopes(e.target.value)}
+                                placeholder="openai:chat, anthropic:messages, gemini:*, images, audio, mcp:web_search, models:detect"
+                            />
+                            <label className="label">
+                                <span className="label-text-alt text-gray-500">{t('user_token.hint_scopes', { defaultValue: 'Comma-separated. Leave empty to allow every endpoint.' })}</span>
+                            </label>
+                        </div>
+
+                        <div className="form-control w-full mb-3">
+                            <label className="label cursor-pointer justify-start gap-3">
+                                <input
+                                    type="checkbox"
+                                    className="checkbox checkbox-sm"
+                                    checked={editDenyTools}
+                                    onChange={e => setEditDenyTools(e.target.checked)}
+                                />
+                                <span className="label-text">{t('user_token.deny_tools', { defaultValue: 'Deny Tool Use' })}</span>
+                            </label>
+                            <label className="label">
+                                <span className="label-text-alt text-gray-500">{t('user_token.hint_deny_tools', { defaultValue: 'Reject requests that declare tools or functions.' })}</span>
+                            </label>
+                        </div>
+
+                        <div className="form-control w-full mb-3">
+                            <label className="label cursor-pointer justify-start gap-3">
+                                <input
+                                    type="checkbox"
+                                    className="checkbox checkbox-sm"
+                                    checked={editDenyImageGeneration}
+                                    onChange={e => setEditDenyImageGeneration(e.target.checked)}
+                                />
+                                <span className="label-text">{t('user_token.deny_image_generation', { defaultValue: 'Deny Image Generation' })}</span>
+                            </label>
+                            <label className="label">
+                                <span className="label-text-alt text-gray-500">{t('user_token.hint_deny_image_generation', { defaultValue: 'Reject image endpoints and image generation models.' })}</span>
+                            </label>
+                        </div>
+
+                        <div className="form-control w-full mb-3">
+                            <label className="label cursor-pointer justify-start gap-3">
+                                <input
+                                    type="checkbox"
+                                    className="checkbox checkbox-sm"
+                                    checked={editDenyThinking}
+                                    onChange={e => setEditDenyThinking(e.target.checked)}
+                                />
+                                <span className="label-text">{t('user_token.deny_thinking', { defaultValue: 'Deny Thinking' })}</span>
+                            </label>
+                            <label className="label">
+                                <span className="label-text-alt text-gray-500">{t('user_token.hint_deny_thinking', { defaultValue: 'Reject requests that enable thinking / reasoning or use thinking models.' })}</span>
+                            </label>
+                        </div>
+
                         <div className="modal-action">
                             <button className="px-4 py-2 hover:bg-gray-100 dark:hover:bg-base-200 rounded-lg text-sm transition-colors" onClick={() => setShowEditModal(false)}>
                                 {t('common.cancel', { defaultValue: 'Cancel' })}
@@ -25,6 +25,213 @@ export interface ProxyConfig {
     global_system_prompt?: GlobalSystemPromptConfig;
     image_thinking_mode?: 'enabled' | 'disabled'; // [NEW] 图像思维模式开关
     proxy_pool?: ProxyPoolConfig;
+    upstream_endpoints?: UpstreamEndpointConfig;
+    idempotency?: IdempotencyConfig;
+    response_cache?: ResponseCacheConfig;
+    metrics?: MetricsConfig;
+    tls?: TlsConfig;
+    telemetry?: TelemetryConfig;
+    alerts?: AlertsConfig;
+    redaction?: RedactionConfig;
+    dlp?: DlpConfig;
+    pricing?: PricingConfig;
+}
+
+export type AlertKind =
+    | 'account_disabled'
+    | 'validation_blocked'
+    | 'quota_protection'
+    | 'accounts_exhausted'
+    | 'proxy_unhealthy';
+
+export type AlertChannelTarget =
+    | { type: 'webhook'; url: string; template: 'generic' | 'slack' | 'discord' | 'feishu'; headers: Record<string, string> }
+    | { type: 'ntfy'; server: string; topic: string; token?: string | null }
+    | { type: 'gotify'; server: string; token: string }
+    | {
+          type: 'smtp';
+          host: string;
+          port: number;
+          security: 'starttls' | 'tls' | 'none';
+          username?: string | null;
+          password?: string | null;
+          from: string;
+          to: string[];
+      };
+
+/** 告警通知通道 */
+export type AlertChannel = {
+    id: string;
+    name: string;
+    enabled: boolean;
+} & AlertChannelTarget;
+
+/** 告警规则：同一对象在 window_secs 内发生 threshold 次事件时触发 */
+export interface AlertRule {
+    id: string;
+    kind: AlertKind;
+    enabled: boolean;
+    threshold: number;
+    window_secs: number;
+    cooldown_secs: number;
+    /** 投递的通道 ID，为空时投递到所有启用的通道 */
+    channels: string[];
+}
+
+/** 模型价格 (每百万 Token) */
+export interface ModelPrice {
+    /** 支持 * 通配 */
+    model: string;
+    provider?: string | null;
+    input: number;
+    output: number;
+    cached_input?: number | null;
+    thinking?: number | null;
+}
+
+/** 价格表配置 */
+export interface PricingConfig {
+    enabled: boolean;
+    currency: string;
+    models: ModelPrice[];
+}
+
+/** 日志脱敏配置 */
+export interface RedactionConfig {
+    enabled: boolean;
+    detectors: {
+        api_keys: boolean;
+        bearer_tokens: boolean;
+        emails: boolean;
+        credit_cards: boolean;
+        private_keys: boolean;
+    };
+    custom_patterns: Array<{ name: string; pattern: string; enabled: boolean }>;
+}
+
+/** 出站数据防泄漏 (DLP) 规则 */
+export interface DlpRule {
+    name: string;
+    enabled: boolean;
+    detector: 'api_key' | 'bearer_token' | 'email' | 'credit_card' | 'private_key' | 'regex';
+    pattern?: string;
+    action: 'block' | 'mask' | 'log';
+    restore_response?: boolean;
+    user_tokens?: string[];
+}
+
+/** 出站数据防泄漏 (DLP) 配置 */
+export interface DlpConfig {
+    enabled: boolean;
+    rules: DlpRule[];
+}
+
+/** 告警配置 */
+export interface AlertsConfig {
+    enabled: boolean;
+    channels: AlertChannel[];
+    rules: AlertRule[];
+}
+
+/** 告警投递记录 */
+export interface AlertDelivery {
+    id: number;
+    timestamp: number;
+    rule_id: string | null;
+    kind: AlertKind;
+    subject: string;
+    message: string;
+    channel_id: string;
+    channel_name: string;
+    success: boolean;
+    error: string | null;
+}
+
+/** OpenTelemetry 链路追踪导出配置 (OTLP) */
+export interface TelemetryConfig {
+    enabled: boolean;
+    protocol: 'grpc' | 'http';
+    /** Collector 地址，为空时使用本机默认端口 (gRPC 4317 / HTTP 4318) */
+    endpoint: string;
+    /** 附加到导出请求的头 */
+    headers: Record<string, string>;
+    /** 根 span 采样率 (0.0 - 1.0) */
+    sample_ratio: number;
+}
+
+/** Prometheus 指标导出配置 */
+export interface MetricsConfig {
+    enabled: boolean;
+    /** 抓取专用 Bearer Token，主端口必须配置后才能抓取 */
+    auth_token: string;
+    /** 独立监听地址 (如 127.0.0.1:9464) */
+    listen_address?: string | null;
+}
+
+/** 监听端 HTTPS 配置 */
+export interface TlsConfig {
+    enabled: boolean;
+    /** PEM 证书路径，与 key_path 均为空时使用自签名证书 */
+    cert_path?: string | null;
+    key_path?: string | null;
+    /** 独立 HTTPS 端口，未设置时主端口仅提供 HTTPS */
+    https_port?: number | null;
+    /** 自签名证书额外的 SAN */
+    extra_sans: string[];
+}
+
+/** 精确匹配响应缓存配置 */
+export interface ResponseCacheConfig {
+    enabled: boolean;
+    /** 缓存有效期 (秒) */
+    ttl_secs: number;
+    /** 最多保留条目数 */
+    max_entries: number;
+    /** 单条响应最大字节数 */
+    max_entry_bytes: number;
+    /** 仅缓存 temperature = 0 的请求 */
+    require_deterministic: boolean;
+    /** 带副作用的工具，请求包含时不缓存 */
+    excluded_tools: string[];
+}
+
+/** 响应缓存统计 */
+export interface ResponseCacheStats {
+    enabled: boolean;
+    hits: number;
+    misses: number;
+    bypasses: number;
+    stores: number;
+    hit_rate: number;
+    entries: number;
+    total_bytes: number;
+    total_hits: number;
+    oldest_entry_at?: number | null;
+}
+
+/** 幂等请求去重配置 (Idempotency-Key) */
+export interface IdempotencyConfig {
+    enabled: boolean;
+    /** 去重窗口 (秒) */
+    window_secs: number;
+    /** 最多保留条目数 */
+    max_entries: number;
+    /** 单个响应最多缓冲字节数 */
+    max_response_bytes?: number;
+}
+
+/** 上游端点健康跟踪配置 */
+export interface UpstreamEndpointConfig {
+    /** 根据健康度调整降级顺序 */
+    adaptive_ordering: boolean;
+    /** 手动固定的端点顺序 (sandbox / daily / prod) */
+    pinned_order: string[];
+    /** 连续失败熔断阈值 */
+    failure_threshold: number;
+    /** 首次熔断时长 (秒) */
+    open_duration_secs: number;
+    /** 探测间隔 (秒)，0 为关闭 */
+    probe_interval_secs: number;
 }
 
 // ============================================================================
@@ -190,6 +397,21 @@ export interface ProxyEntry {
     last_check_time?: number;
     is_healthy: boolean;
     latency?: number; // [NEW] 延迟 (毫秒)
+    weight?: number; // [NEW] 权重 (加权轮询策略)
+}
+
+/** 代理连接统计 */
+export interface ProxyConnectionStats {
+    proxy_id: string;
+    name: string;
+    enabled: boolean;
+    is_healthy: boolean;
+    latency?: number;
+    weight: number;
+    effective_weight: number;
+    active_connections: number;
+    total_connections: number;
+    total_selections: number;
 }
 
 // export type ProxyPoolMode = 'global' | 'per_account' | 'hybrid'; // [REMOVED]
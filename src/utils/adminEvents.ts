@@ -0,0 +1,92 @@
+// 管理端实时事件流 (Web / Headless 模式)
+// 桌面端仍使用 Tauri 事件，这里通过 /api/events (SSE) 订阅同样的数据
+
+/** 请求日志摘要 (不含请求 / 响应体) */
+export interface RequestLogSummary {
+  id: string;
+  timestamp: number;
+  method: string;
+  url: string;
+  status: number;
+  duration: number;
+  model?: string;
+  mapped_model?: string;
+  account_email?: string;
+  client_ip?: string;
+  error?: string;
+  input_tokens?: number;
+  output_tokens?: number;
+  protocol?: string;
+  username?: string;
+  replay_of?: string;
+}
+
+export type AccountState =
+  | 'rate_limited'
+  | 'disabled'
+  | 'validation_blocked'
+  | 'forbidden'
+  | 'quota_refreshed';
+
+export interface AccountEvent {
+  accountId: string;
+  email: string | null;
+  state: AccountState;
+  model: string | null;
+  detail: string | null;
+  timestamp: number;
+}
+
+export interface StreamLogEntry {
+  id: number;
+  timestamp: number;
+  level: string;
+  target: string;
+  message: string;
+  fields: Record<string, string>;
+}
+
+export type AdminEvent =
+  | { type: 'request'; data: RequestLogSummary }
+  | { type: 'account'; data: AccountEvent }
+  | { type: 'log'; data: StreamLogEntry }
+  | { type: 'lagged'; data: { skipped: number } };
+
+/** 服务端筛选条件 */
+export interface AdminEventFilter {
+  /** 逗号分隔：request,account,log；为空时订阅全部 */
+  types?: string;
+  errorsOnly?: boolean;
+  model?: string;
+  account?: string;
+  minLevel?: 'TRACE' | 'DEBUG' | 'INFO' | 'WARN' | 'ERROR';
+  filter?: string;
+}
+
+/** 订阅事件流，返回取消订阅函数。EventSource 断线后会自动重连 */
+export function subscribeAdminEvents(
+  filter: AdminEventFilter,
+  onEvent: (event: AdminEvent) => void,
+): () => void {
+  const params = new URLSearchParams();
+  Object.entries(filter).forEach(([key, value]) => {
+    if (value !== undefined && value !== null && value !== '') {
+      params.append(key, String(value));
+    }
+  });
+  // EventSource 无法设置请求头，管理密钥通过查询参数传递
+  const apiKey = sessionStorage.getItem('abv_admin_api_key');
+  if (apiKey) {
+    params.append('token', apiKey);
+  }
+
+  const source = new EventSource(`/api/events?${params.toString()}`);
+  source.onmessage = (message) => {
+    try {
+      onEvent(JSON.parse(message.data) as AdminEvent);
+    } catch (e) {
+      console.warn('Failed to parse admin event:', message.data);
+    }
+  };
+  return () => source.close();
+}
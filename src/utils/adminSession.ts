@@ -0,0 +1,61 @@
+import { request } from './request';
+import { isTauri } from './env';
+
+export type AdminRole = 'owner' | 'operator' | 'viewer' | 'auditor';
+
+export interface AdminIdentity {
+  user_id: string | null;
+  username: string;
+  role: AdminRole;
+  totp_enabled: boolean;
+  /** 是否已启用多管理员 (否则为旧版管理密码登录) */
+  admin_users_enabled: boolean;
+}
+
+export interface AdminLoginResponse {
+  token: string;
+  expires_at: number;
+  identity: Omit<AdminIdentity, 'admin_users_enabled'>;
+}
+
+/** 会话令牌 (或旧版管理密码) 的存储键 */
+export const ADMIN_KEY_STORAGE = 'abv_admin_api_key';
+
+/**
+ * 管理员登录 (Web 模式)
+ * 失败时抛出错误码: invalid_credentials / totp_required / invalid_totp
+ */
+export async function adminLogin(username: string, password: string, totpCode?: string): Promise<AdminLoginResponse> {
+  const response = await fetch('/api/admin/login', {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({ username, password, totp_code: totpCode || null }),
+  });
+  const data = await response.json().catch(() => ({}));
+  if (!response.ok) {
+    throw data.error || `HTTP Error ${response.status}`;
+  }
+  return data as AdminLoginResponse;
+}
+
+/** 当前登录身份 (桌面端始终为 owner) */
+export async function getAdminIdentity(): Promise<AdminIdentity> {
+  if (isTauri()) {
+    return { user_id: null, username: 'admin', role: 'owner', totp_enabled: false, admin_users_enabled: false };
+  }
+  return request<AdminIdentity>('get_admin_me');
+}
+
+/** 注销当前会话并返回登录页 */
+export async function adminLogout() {
+  if (!isTauri() && sessionStorage.getItem(ADMIN_KEY_STORAGE)) {
+    try {
+      await request('admin_logout');
+    } catch (e) {
+      console.warn('Failed to revoke admin session', e);
+    }
+  }
+  sessionStorage.removeItem(ADMIN_KEY_STORAGE);
+  localStorage.removeItem(ADMIN_KEY_STORAGE);
+  window.location.reload();
+}
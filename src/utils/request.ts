@@ -40,6 +40,12 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
   'clear_proxy_rate_limit': { url: '/api/proxy/rate-limits/:accountId', method: 'DELETE' },
   'clear_all_proxy_rate_limits': { url: '/api/proxy/rate-limits', method: 'DELETE' },
   'check_proxy_health': { url: '/api/proxy/health-check/trigger', method: 'POST' },
+  'get_upstream_endpoint_health': { url: '/api/proxy/upstream/endpoints', method: 'GET' },
+  'reset_upstream_endpoint_health': { url: '/api/proxy/upstream/endpoints', method: 'DELETE' },
+  'get_response_cache_stats': { url: '/api/proxy/cache/stats', method: 'GET' },
+  'clear_response_cache': { url: '/api/proxy/cache', method: 'DELETE' },
+  'get_tls_status': { url: '/api/proxy/tls', method: 'GET' },
+  'reload_tls_certificate': { url: '/api/proxy/tls/reload', method: 'POST' },
   'get_preferred_account': { url: '/api/proxy/preferred-account', method: 'GET' },
   'set_preferred_account': { url: '/api/proxy/preferred-account', method: 'POST' },
   'fetch_zai_models': { url: '/api/zai/models/fetch', method: 'POST' },
@@ -53,6 +59,14 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
   'get_proxy_logs_count_filtered': { url: '/api/logs/count', method: 'GET' },
   'clear_proxy_logs': { url: '/api/logs/clear', method: 'POST' },
   'get_proxy_log_detail': { url: '/api/logs/:logId', method: 'GET' },
+  'search_proxy_logs': { url: '/api/logs/search', method: 'POST' },
+  'replay_proxy_log': { url: '/api/logs/:logId/replay', method: 'POST' },
+  'get_proxy_log_replays': { url: '/api/logs/:logId/replays', method: 'GET' },
+
+  // Alerts
+  'test_alert_channel': { url: '/api/alerts/test', method: 'POST' },
+  'get_alert_history': { url: '/api/alerts/history', method: 'GET' },
+  'clear_alert_history': { url: '/api/alerts/history', method: 'DELETE' },
 
   // Debug Console
   'enable_debug_console': { url: '/api/debug/enable', method: 'POST' },
@@ -86,6 +100,7 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
   'get_token_stats_account_trend_hourly': { url: '/api/stats/token/account-trend/hourly', method: 'GET' },
   'get_token_stats_account_trend_daily': { url: '/api/stats/token/account-trend/daily', method: 'GET' },
   'clear_token_stats': { url: '/api/stats/token/clear', method: 'POST' },
+  'get_token_cost_breakdown': { url: '/api/stats/token/cost', method: 'GET' },
 
   // System
   'get_data_dir_path': { url: '/api/system/data-dir', method: 'GET' },
@@ -135,6 +150,9 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
   'get_ip_access_logs': { url: '/api/security/logs', method: 'GET' },
   'clear_ip_access_logs': { url: '/api/security/logs/clear', method: 'POST' },
   'get_ip_stats': { url: '/api/security/stats', method: 'GET' },
+  'get_ip_ban_events': { url: '/api/security/ban-events', method: 'GET' },
+  'get_dlp_stats': { url: '/api/security/dlp/stats', method: 'GET' },
+  'clear_dlp_stats': { url: '/api/security/dlp/clear', method: 'POST' },
   'get_ip_token_stats': { url: '/api/security/token-stats', method: 'GET' },
   'get_ip_blacklist': { url: '/api/security/blacklist', method: 'GET' },
   'add_ip_to_blacklist': { url: '/api/security/blacklist', method: 'POST' },
@@ -148,6 +166,12 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
   'check_ip_in_whitelist': { url: '/api/security/whitelist/check', method: 'GET' },
   'get_security_config': { url: '/api/security/config', method: 'GET' },
   'update_security_config': { url: '/api/security/config', method: 'POST' },
+  'reencrypt_secrets': { url: '/api/security/reencrypt', method: 'POST' },
+  'get_account_encryption_status': { url: '/api/security/account-encryption', method: 'GET' },
+  'unlock_account_encryption': { url: '/api/security/account-encryption/unlock', method: 'POST' },
+  'enable_account_encryption': { url: '/api/security/account-encryption/enable', method: 'POST' },
+  'rotate_account_encryption': { url: '/api/security/account-encryption/rotate', method: 'POST' },
+  'disable_account_encryption': { url: '/api/security/account-encryption/disable', method: 'POST' },
   // User Tokens
   'list_user_tokens': { url: '/api/user-tokens', method: 'GET' },
   'get_user_token_summary': { url: '/api/user-tokens/summary', method: 'GET' },
@@ -155,10 +179,24 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
   'renew_user_token': { url: '/api/user-tokens/:id/renew', method: 'POST' },
   'delete_user_token': { url: '/api/user-tokens/:id', method: 'DELETE' },
   'update_user_token': { url: '/api/user-tokens/:id', method: 'PATCH' },
+  // Admin Users / Sessions
+  'get_admin_me': { url: '/api/admin/me', method: 'GET' },
+  'admin_logout': { url: '/api/admin/logout', method: 'POST' },
+  'setup_admin_totp': { url: '/api/admin/totp/setup', method: 'POST' },
+  'enable_admin_totp': { url: '/api/admin/totp/enable', method: 'POST' },
+  'disable_admin_totp': { url: '/api/admin/totp/disable', method: 'POST' },
+  'list_admin_users': { url: '/api/admin/users', method: 'GET' },
+  'create_admin_user': { url: '/api/admin/users', method: 'POST' },
+  'update_admin_user': { url: '/api/admin/users/:id', method: 'PATCH' },
+  'delete_admin_user': { url: '/api/admin/users/:id', method: 'DELETE' },
+  'reset_admin_user_totp': { url: '/api/admin/users/:id/reset-totp', method: 'POST' },
+  // Audit Log
+  'get_audit_logs': { url: '/api/audit', method: 'GET' },
 
   // Proxy Pool (Web Mode Fix)
   'get_proxy_pool_config': { url: '/api/proxy/pool/config', method: 'GET' },
   'get_all_account_bindings': { url: '/api/proxy/pool/bindings', method: 'GET' },
+  'get_proxy_pool_stats': { url: '/api/proxy/pool/stats', method: 'GET' },
   'bind_account_proxy': { url: '/api/proxy/pool/bind', method: 'POST' },
   'unbind_account_proxy': { url: '/api/proxy/pool/unbind', method: 'POST' },
   'get_account_proxy_binding': { url: '/api/proxy/pool/binding/:accountId', method: 'GET' },